@@ -0,0 +1,300 @@
+use crate::crawler::CrawlResult;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use url::Url;
+
+/// Pages a section, host or depth level needs before its rates mean anything.
+const MIN_PAGES: usize = 5;
+/// A section's 404 rate must be this many times the rest of the crawl's...
+const SPIKE_FACTOR: f64 = 2.0;
+/// ...and at least this high to count as a spike.
+const MIN_NOT_FOUND_RATE: f64 = 0.2;
+/// Share of a host's pages returning 4xx/5xx that flags the host.
+const HOST_ERROR_RATE: f64 = 0.25;
+/// A section's median response time must be this many times the rest's...
+const SLOW_FACTOR: f64 = 3.0;
+/// ...and at least this long.
+const MIN_SLOW_MEDIAN: Duration = Duration::from_millis(300);
+/// A depth level's duplicate rate must be this many times the other levels'...
+const DUPLICATE_FACTOR: f64 = 2.0;
+/// ...and at least this high.
+const MIN_DUPLICATE_RATE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Many more 404s under one path than elsewhere.
+    NotFoundSpike,
+    /// A host answering a large share of requests with errors.
+    HostErrors,
+    /// A path responding much slower than the rest of the site.
+    SlowSection,
+    /// A depth level where many pages duplicate earlier ones.
+    DuplicateDepth,
+}
+
+impl AnomalyKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AnomalyKind::NotFoundSpike => "404 spike",
+            AnomalyKind::HostErrors => "host errors",
+            AnomalyKind::SlowSection => "slow section",
+            AnomalyKind::DuplicateDepth => "duplicates",
+        }
+    }
+}
+
+/// Something in a finished crawl that looks off and is worth investigating.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    /// What it's about: a section like `example.com/blog/`, a host or `depth 3`.
+    pub subject: String,
+    pub detail: String,
+    /// Roughly how many pages are affected beyond what the rest of the crawl predicts;
+    /// anomalies are listed highest first.
+    pub score: f64,
+}
+
+impl Anomaly {
+    pub fn format_output(&self) -> String {
+        format!("[{}] {}: {}", self.kind.label(), self.subject, self.detail)
+    }
+}
+
+/// Look for 404 spikes under a path, hosts with high error rates, unusually slow
+/// sections and depth levels with many duplicates. Pages with status 0 (not fetched)
+/// are ignored. Sorted by score, highest first.
+pub fn detect_anomalies(results: &[CrawlResult]) -> Vec<Anomaly> {
+    let results: Vec<&CrawlResult> = results.iter().filter(|r| r.status_code != 0).collect();
+    let mut anomalies = Vec::new();
+
+    let mut sections: BTreeMap<String, Vec<&CrawlResult>> = BTreeMap::new();
+    let mut hosts: BTreeMap<String, Vec<&CrawlResult>> = BTreeMap::new();
+    for result in &results {
+        let Ok(url) = Url::parse(&result.url) else {
+            continue;
+        };
+        let host = url.host_str().unwrap_or_default().to_string();
+        let first_segment = url
+            .path_segments()
+            .and_then(|mut segments| segments.next())
+            .filter(|segment| !segment.is_empty());
+        // Only directories make sections; `/about` lives in the host's root section
+        let section = match first_segment {
+            Some(segment) if url.path().len() > segment.len() + 1 => {
+                format!("{}/{}/", host, segment)
+            }
+            _ => format!("{}/", host),
+        };
+        sections.entry(section).or_default().push(result);
+        hosts.entry(host).or_default().push(result);
+    }
+
+    let total = results.len();
+    let total_not_found = results.iter().filter(|r| r.status_code == 404).count();
+    let mut total_times: Vec<Duration> = results.iter().map(|r| r.crawl_time).collect();
+    total_times.sort_unstable();
+    for (section, pages) in &sections {
+        if pages.len() < MIN_PAGES || pages.len() == total {
+            continue;
+        }
+        let not_found = pages.iter().filter(|r| r.status_code == 404).count();
+        let rate = not_found as f64 / pages.len() as f64;
+        let rest_rate = (total_not_found - not_found) as f64 / (total - pages.len()) as f64;
+        if rate >= MIN_NOT_FOUND_RATE && rate >= rest_rate * SPIKE_FACTOR {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::NotFoundSpike,
+                subject: section.clone(),
+                detail: format!(
+                    "{} of {} pages returned 404 ({}, vs {} elsewhere)",
+                    not_found,
+                    pages.len(),
+                    percent(rate),
+                    percent(rest_rate)
+                ),
+                score: not_found as f64 - rest_rate * pages.len() as f64,
+            });
+        }
+
+        let mut times: Vec<Duration> = pages.iter().map(|r| r.crawl_time).collect();
+        times.sort_unstable();
+        let (median, rest_median) = (median(&times), median(&without(&total_times, &times)));
+        if median >= MIN_SLOW_MEDIAN
+            && median.as_secs_f64() >= rest_median.as_secs_f64() * SLOW_FACTOR
+        {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::SlowSection,
+                subject: section.clone(),
+                detail: format!(
+                    "median response {}ms over {} pages, vs {}ms elsewhere",
+                    median.as_millis(),
+                    pages.len(),
+                    rest_median.as_millis()
+                ),
+                score: pages.len() as f64
+                    * (1.0 - rest_median.as_secs_f64() / median.as_secs_f64()),
+            });
+        }
+    }
+
+    for (host, pages) in &hosts {
+        if pages.len() < MIN_PAGES {
+            continue;
+        }
+        let errors = pages.iter().filter(|r| r.status_code >= 400).count();
+        let rate = errors as f64 / pages.len() as f64;
+        if rate > HOST_ERROR_RATE {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::HostErrors,
+                subject: host.clone(),
+                detail: format!(
+                    "{} of {} pages returned 4xx/5xx ({})",
+                    errors,
+                    pages.len(),
+                    percent(rate)
+                ),
+                score: errors as f64 - HOST_ERROR_RATE * pages.len() as f64,
+            });
+        }
+    }
+
+    anomalies.extend(duplicate_depths(&results));
+    anomalies.sort_by(|a, b| b.score.total_cmp(&a.score));
+    anomalies
+}
+
+/// Depth levels where pages duplicate an earlier page: same body, same canonical URL,
+/// or redirected to a URL another page already landed on.
+fn duplicate_depths(results: &[&CrawlResult]) -> Vec<Anomaly> {
+    let mut seen = HashSet::new();
+    // (pages, duplicates) per depth
+    let mut levels: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for result in results {
+        let key = result
+            .canonical
+            .as_deref()
+            .or(Some(result.final_url.as_str()).filter(|url| !url.is_empty()))
+            .unwrap_or(&result.url);
+        let level = levels.entry(result.depth).or_default();
+        level.0 += 1;
+        if !seen.insert(key) || result.duplicate_of.is_some() {
+            level.1 += 1;
+        }
+    }
+
+    let total_pages: usize = levels.values().map(|(pages, _)| pages).sum();
+    let total_duplicates: usize = levels.values().map(|(_, duplicates)| duplicates).sum();
+    let mut anomalies = Vec::new();
+    for (depth, &(pages, duplicates)) in &levels {
+        if pages < MIN_PAGES || pages == total_pages {
+            continue;
+        }
+        let rate = duplicates as f64 / pages as f64;
+        let rest_rate = (total_duplicates - duplicates) as f64 / (total_pages - pages) as f64;
+        if rate >= MIN_DUPLICATE_RATE && rate >= rest_rate * DUPLICATE_FACTOR {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::DuplicateDepth,
+                subject: format!("depth {}", depth),
+                detail: format!(
+                    "{} of {} pages duplicate an earlier page ({}, vs {} at other depths)",
+                    duplicates,
+                    pages,
+                    percent(rate),
+                    percent(rest_rate)
+                ),
+                score: duplicates as f64 - rest_rate * pages as f64,
+            });
+        }
+    }
+    anomalies
+}
+
+/// `all` minus the elements of `part`, both sorted, `part` a sub-multiset of `all`.
+fn without(all: &[Duration], part: &[Duration]) -> Vec<Duration> {
+    let mut rest = Vec::with_capacity(all.len() - part.len());
+    let mut part = part.iter().peekable();
+    for time in all {
+        if part.peek() == Some(&time) {
+            part.next();
+        } else {
+            rest.push(*time);
+        }
+    }
+    rest
+}
+
+fn median(sorted: &[Duration]) -> Duration {
+    sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, status_code: u16, millis: u64, depth: usize) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code,
+            depth,
+            crawl_time: Duration::from_millis(millis),
+            final_url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_spikes_slow_sections_and_duplicates() {
+        let mut results = Vec::new();
+        for i in 0..10 {
+            results.push(page(&format!("https://a.com/blog/{}", i), 200, 50, 1));
+        }
+        // Half of /shop/ is gone
+        for i in 0..6 {
+            let status = if i % 2 == 0 { 404 } else { 200 };
+            results.push(page(&format!("https://a.com/shop/{}", i), status, 40, 1));
+        }
+        // /search/ is slow, and every page there is the same canonical page
+        for i in 0..5 {
+            let mut result = page(&format!("https://a.com/search/?q={}", i), 200, 900, 2);
+            result.canonical = Some("https://a.com/search/".to_string());
+            results.push(result);
+        }
+        // Too few pages on b.com to judge
+        results.push(page("https://b.com/x", 500, 50, 1));
+
+        let anomalies = detect_anomalies(&results);
+        let found: Vec<(AnomalyKind, &str)> = anomalies
+            .iter()
+            .map(|a| (a.kind, a.subject.as_str()))
+            .collect();
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(&(AnomalyKind::NotFoundSpike, "a.com/shop/")));
+        assert!(found.contains(&(AnomalyKind::SlowSection, "a.com/search/")));
+        assert!(found.contains(&(AnomalyKind::DuplicateDepth, "depth 2")));
+        let spike = &anomalies
+            .iter()
+            .find(|a| a.kind == AnomalyKind::NotFoundSpike)
+            .unwrap();
+        assert_eq!(
+            spike.detail,
+            "3 of 6 pages returned 404 (50%, vs 0% elsewhere)"
+        );
+        assert!(anomalies.windows(2).all(|w| w[0].score >= w[1].score));
+
+        let host_down: Vec<CrawlResult> = (0..5)
+            .map(|i| page(&format!("https://c.com/{}", i), 503, 10, 0))
+            .collect();
+        let anomalies = detect_anomalies(&host_down);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(
+            anomalies[0].format_output(),
+            "[host errors] c.com: 5 of 5 pages returned 4xx/5xx (100%)"
+        );
+    }
+}
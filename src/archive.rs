@@ -0,0 +1,220 @@
+use crate::crawler::FetchResponse;
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Writes fetched responses as WARC 1.1 records, the format of web archives like the
+/// Internet Archive's. A path ending in `.gz` gets one gzip member per record, which
+/// is what `.warc.gz` readers expect.
+pub struct WarcWriter {
+    out: Mutex<BufWriter<File>>,
+    gzip: bool,
+}
+
+/// The status line and headers of a response, serialized as they'd appear on the wire.
+pub struct HttpHead {
+    bytes: Vec<u8>,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl HttpHead {
+    /// `Transfer-Encoding` is left out: bodies are stored de-chunked.
+    pub fn from_response(response: &FetchResponse) -> Self {
+        let status = response.status;
+        let mut bytes = format!(
+            "{:?} {} {}\r\n",
+            response.version,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("")
+        )
+        .into_bytes();
+        for (name, value) in &response.headers {
+            if name == reqwest::header::TRANSFER_ENCODING {
+                continue;
+            }
+            bytes.extend_from_slice(name.as_str().as_bytes());
+            bytes.extend_from_slice(b": ");
+            bytes.extend_from_slice(value.as_bytes());
+            bytes.extend_from_slice(b"\r\n");
+        }
+        bytes.extend_from_slice(b"\r\n");
+        Self {
+            bytes,
+            remote_addr: response.remote_addr,
+        }
+    }
+}
+
+impl WarcWriter {
+    /// Create the archive at `path` and write its `warcinfo` record.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create WARC file {}", path.display()))?;
+        let gzip = path.extension().is_some_and(|ext| ext == "gz");
+        let writer = Self {
+            out: Mutex::new(BufWriter::new(file)),
+            gzip,
+        };
+
+        let info = format!(
+            "software: rustcrawler/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        writer.write_record(
+            "warcinfo",
+            &[
+                ("WARC-Filename", filename.as_str()),
+                ("Content-Type", "application/warc-fields"),
+            ],
+            info.as_bytes(),
+        )?;
+        Ok(writer)
+    }
+
+    /// Append a `response` record for `target_uri` with the response's head and body.
+    /// `truncated` marks a body cut at the size limit (`WARC-Truncated: length`).
+    pub fn write_response(
+        &self,
+        target_uri: &str,
+        head: &HttpHead,
+        body: &[u8],
+        truncated: bool,
+    ) -> Result<()> {
+        let mut block = Vec::with_capacity(head.bytes.len() + body.len());
+        block.extend_from_slice(&head.bytes);
+        block.extend_from_slice(body);
+
+        let ip = head.remote_addr.map(|addr| addr.ip().to_string());
+        let mut fields = vec![
+            ("WARC-Target-URI", target_uri),
+            ("Content-Type", "application/http;msgtype=response"),
+        ];
+        if let Some(ip) = &ip {
+            fields.push(("WARC-IP-Address", ip));
+        }
+        if truncated {
+            fields.push(("WARC-Truncated", "length"));
+        }
+        self.write_record("response", &fields, &block)
+    }
+
+    fn write_record(&self, warc_type: &str, fields: &[(&str, &str)], block: &[u8]) -> Result<()> {
+        let mut record = format!(
+            "WARC/1.1\r\nWARC-Type: {}\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {}\r\n",
+            warc_type,
+            Uuid::new_v4(),
+            warc_date(SystemTime::now())
+        )
+        .into_bytes();
+        for (name, value) in fields {
+            record.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        record.extend_from_slice(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+
+        let mut out = self.out.lock().unwrap();
+        if self.gzip {
+            let mut encoder = GzEncoder::new(&mut *out, Compression::default());
+            encoder.write_all(&record)?;
+            encoder.finish()?;
+        } else {
+            out.write_all(&record)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.out
+            .lock()
+            .unwrap()
+            .flush()
+            .context("Failed to flush WARC file")
+    }
+}
+
+/// `WARC-Date` value: UTC with second precision, e.g. `2024-05-01T12:30:00Z`.
+fn warc_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+    use std::time::Duration;
+
+    #[test]
+    fn test_warc_date() {
+        assert_eq!(warc_date(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(warc_date(leap_day), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn test_gzipped_records() {
+        let path = std::env::temp_dir().join(format!("rustcrawler-{}.warc.gz", Uuid::new_v4()));
+        let writer = WarcWriter::create(&path).unwrap();
+        let head = HttpHead {
+            bytes: b"HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\r\n".to_vec(),
+            remote_addr: Some("127.0.0.1:80".parse().unwrap()),
+        };
+        writer
+            .write_response("https://example.com/", &head, b"<p>hi</p>", false)
+            .unwrap();
+        writer.flush().unwrap();
+
+        let mut warc = String::new();
+        MultiGzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut warc)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let records: Vec<&str> = warc.split("WARC/1.1\r\n").skip(1).collect();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].starts_with("WARC-Type: warcinfo\r\n"));
+        let response = records[1];
+        assert!(response.contains("WARC-Target-URI: https://example.com/\r\n"));
+        assert!(response.contains("WARC-IP-Address: 127.0.0.1\r\n"));
+        // 44 bytes of head plus 9 of body
+        assert!(response.contains("Content-Length: 53\r\n\r\nHTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("<p>hi</p>\r\n\r\n"));
+    }
+}
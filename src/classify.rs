@@ -0,0 +1,282 @@
+use crate::crawler::CrawlResult;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "rule-files")]
+use std::fs;
+#[cfg(feature = "rule-files")]
+use std::path::Path;
+
+/// One rule from a `--classify` rules file. Every condition the rule sets must hold;
+/// the first matching rule in the file decides the page's category.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryRule {
+    /// Category stamped on matching pages, e.g. `product`, `article`, `listing`, `login`.
+    pub category: String,
+    /// Regular expression searched for anywhere in the page URL.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// CSS selector that must match at least one element of the page.
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Status codes (`404`) or classes (`"5xx"`) the response must have.
+    #[serde(default)]
+    pub status: Vec<StatusPattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StatusPattern {
+    Code(u16),
+    /// `"4xx"`, `"5xx"`, or a code written as a string.
+    Class(String),
+}
+
+impl StatusPattern {
+    fn matches(&self, status: u16) -> bool {
+        match self {
+            Self::Code(code) => *code == status,
+            Self::Class(class) => match class.strip_suffix("xx") {
+                Some(digit) => digit
+                    .parse::<u16>()
+                    .is_ok_and(|digit| status / 100 == digit),
+                None => class.parse::<u16>().is_ok_and(|code| code == status),
+            },
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Code(_) => true,
+            Self::Class(class) => class
+                .strip_suffix("xx")
+                .unwrap_or(class)
+                .parse::<u16>()
+                .is_ok(),
+        }
+    }
+}
+
+#[cfg(any(feature = "rule-files", test))]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: Vec<CategoryRule>,
+}
+
+/// Read the rules from a YAML file with a top-level `rules:` list.
+#[cfg(feature = "rule-files")]
+pub fn load_rules(path: &Path) -> Result<Vec<CategoryRule>> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+    let file: RulesFile = serde_yaml::from_str(&data)
+        .with_context(|| format!("Failed to parse rules file {}", path.display()))?;
+    Classifier::new(&file.rules).with_context(|| format!("Invalid rule in {}", path.display()))?;
+    Ok(file.rules)
+}
+
+/// Rules compiled once per crawl.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    category: String,
+    url: Option<Regex>,
+    selector: Option<Selector>,
+    status: Vec<StatusPattern>,
+}
+
+impl Classifier {
+    pub fn new(rules: &[CategoryRule]) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            if rule.url.is_none() && rule.selector.is_none() && rule.status.is_empty() {
+                bail!(
+                    "Rule for category {} needs a url, selector or status condition",
+                    rule.category
+                );
+            }
+            let url = rule
+                .url
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(|| format!("Invalid url pattern for category {}", rule.category))?;
+            let selector = rule
+                .selector
+                .as_deref()
+                .map(|selector| {
+                    Selector::parse(selector).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Invalid selector {:?} for category {}: {}",
+                            selector,
+                            rule.category,
+                            e
+                        )
+                    })
+                })
+                .transpose()?;
+            if let Some(pattern) = rule.status.iter().find(|pattern| !pattern.is_valid()) {
+                bail!(
+                    "Invalid status {:?} for category {}",
+                    pattern,
+                    rule.category
+                );
+            }
+            compiled.push(CompiledRule {
+                category: rule.category.clone(),
+                url,
+                selector,
+                status: rule.status.clone(),
+            });
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Category of the first rule matching the page, if any.
+    pub fn classify(&self, url: &str, status: u16, document: &Html) -> Option<String> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.url.as_ref().map_or(true, |re| re.is_match(url))
+                    && (rule.status.is_empty() || rule.status.iter().any(|p| p.matches(status)))
+                    && rule
+                        .selector
+                        .as_ref()
+                        .map_or(true, |selector| document.select(selector).next().is_some())
+            })
+            .map(|rule| rule.category.clone())
+    }
+}
+
+/// Page counts for one category in the crawl report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategorySummary {
+    /// `None` for pages no rule matched.
+    pub category: Option<String>,
+    pub pages: usize,
+    /// Pages with a 4xx or 5xx status.
+    pub errors: usize,
+    pub avg_crawl_time_ms: u128,
+}
+
+impl CategorySummary {
+    pub fn format_output(&self) -> String {
+        format!(
+            "{}: {} page(s), {} error(s), avg {}ms",
+            self.category.as_deref().unwrap_or("uncategorized"),
+            self.pages,
+            self.errors,
+            self.avg_crawl_time_ms
+        )
+    }
+}
+
+/// Per-category totals, categories by name with uncategorized pages last. Empty when
+/// no page has a category.
+pub fn summarize_categories(results: &[CrawlResult]) -> Vec<CategorySummary> {
+    if results.iter().all(|result| result.category.is_none()) {
+        return Vec::new();
+    }
+
+    let mut totals: BTreeMap<(bool, Option<&str>), (usize, usize, u128)> = BTreeMap::new();
+    for result in results {
+        let key = (result.category.is_none(), result.category.as_deref());
+        let (pages, errors, time) = totals.entry(key).or_default();
+        *pages += 1;
+        if result.status_code >= 400 {
+            *errors += 1;
+        }
+        *time += result.crawl_time.as_millis();
+    }
+
+    totals
+        .into_iter()
+        .map(|((_, category), (pages, errors, time))| CategorySummary {
+            category: category.map(str::to_string),
+            pages,
+            errors,
+            avg_crawl_time_ms: time / pages as u128,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+rules:
+  - category: error
+    status: ["4xx", 500]
+  - category: login
+    url: "/(login|signin)\\b"
+  - category: product
+    url: "/products?/"
+    selector: "[itemtype$='schema.org/Product']"
+  - category: listing
+    url: "/products?/?$"
+"#;
+
+    fn classifier() -> Classifier {
+        let file: RulesFile = serde_yaml::from_str(RULES).unwrap();
+        Classifier::new(&file.rules).unwrap()
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let classifier = classifier();
+        let product = Html::parse_document(
+            r#"<div itemscope itemtype="https://schema.org/Product">Shoe</div>"#,
+        );
+        let plain = Html::parse_document("<p>Hi</p>");
+
+        let classify = |url: &str, status: u16, document: &Html| {
+            classifier.classify(&format!("https://shop.example{}", url), status, document)
+        };
+        assert_eq!(
+            classify("/products/1", 200, &product).as_deref(),
+            Some("product")
+        );
+        // URL matches but the selector doesn't
+        assert_eq!(classify("/products/1", 200, &plain), None);
+        assert_eq!(
+            classify("/products", 200, &plain).as_deref(),
+            Some("listing")
+        );
+        assert_eq!(
+            classify("/signin?next=/", 200, &plain).as_deref(),
+            Some("login")
+        );
+        assert_eq!(classify("/login", 404, &plain).as_deref(), Some("error"));
+        assert_eq!(classify("/x", 500, &plain).as_deref(), Some("error"));
+        assert_eq!(classify("/x", 503, &plain), None);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let rule = |url: Option<&str>, selector: Option<&str>, status: Vec<StatusPattern>| {
+            Classifier::new(&[CategoryRule {
+                category: "x".to_string(),
+                url: url.map(str::to_string),
+                selector: selector.map(str::to_string),
+                status,
+            }])
+        };
+        assert!(rule(None, None, Vec::new()).is_err());
+        assert!(rule(Some("("), None, Vec::new()).is_err());
+        assert!(rule(None, Some("div[["), Vec::new()).is_err());
+        assert!(rule(None, None, vec![StatusPattern::Class("bad".to_string())]).is_err());
+        assert!(rule(None, None, vec![StatusPattern::Class("5xx".to_string())]).is_ok());
+    }
+}
@@ -1,4 +1,17 @@
+use crate::crawler::{
+    CrawlStrategy, HttpVersion, PriorityBoost, ProxyRotation, UserAgentRotation, VisitedStore,
+    DEFAULT_EXPECTED_URLS,
+};
+use crate::extraction::{load_extraction_rules, ExtractionRule, Extractor};
+use crate::graph::GraphFormat;
+use crate::output::OutputFormat;
+use crate::render::RenderWait;
+use crate::urls::{seed_without_scheme, CrawlScope};
+use anyhow::Context;
 use clap::Parser;
+use regex::Regex;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use url::Url;
 
@@ -10,9 +23,27 @@ use url::Url;
     long_about = "RustCrawler is a high-performance web crawler that respects robots.txt, supports concurrent crawling with rate limiting, and includes retry mechanisms with exponential backoff."
 )]
 pub struct CliArgs {
-    #[arg(help = "Starting URL(s) to crawl", required = true, value_name = "URL")]
+    #[arg(
+        help = "Starting URL(s) to crawl",
+        required_unless_present_any = ["resume", "seed_files"],
+        value_name = "URL"
+    )]
     pub urls: Vec<String>,
 
+    #[arg(
+        long = "seed-file",
+        help = "Also crawl the URLs in FILE, one per line, skipping blank lines and # comments; - reads standard input (repeatable)",
+        value_name = "FILE"
+    )]
+    pub seed_files: Vec<PathBuf>,
+
+    #[arg(
+        long = "config",
+        help = "Read options, seeds included, from this TOML or YAML crawl profile; command-line flags override its values",
+        value_name = "FILE"
+    )]
+    pub config: Option<PathBuf>,
+
     #[arg(
         short = 'c',
         long = "concurrency",
@@ -22,14 +53,30 @@ pub struct CliArgs {
     )]
     pub concurrency: usize,
 
+    #[arg(
+        long = "max-concurrency-per-host",
+        help = "Maximum number of concurrent requests to any one host",
+        value_name = "NUM"
+    )]
+    pub max_concurrency_per_host: Option<usize>,
+
     #[arg(
         short = 'r',
         long = "rate",
-        help = "Rate limit in requests per second",
+        help = "Rate limit in requests per second, per host",
         value_name = "NUM"
     )]
     pub rate_limit: Option<f64>,
 
+    #[arg(
+        long = "burst",
+        help = "Requests a host may receive back-to-back before --rate spacing applies",
+        value_name = "NUM",
+        default_value_t = 1,
+        requires = "rate_limit"
+    )]
+    pub burst: u32,
+
     #[arg(
         short = 'p',
         long = "proxy",
@@ -38,6 +85,21 @@ pub struct CliArgs {
     )]
     pub proxy: Option<String>,
 
+    #[arg(
+        long = "proxy-list",
+        help = "File with one proxy URL per line to rotate page requests through; # starts a comment",
+        value_name = "FILE"
+    )]
+    pub proxy_list: Option<PathBuf>,
+
+    #[arg(
+        long = "proxy-rotation",
+        help = "How to spread requests over --proxy-list proxies",
+        value_enum,
+        default_value_t = ProxyRotation::RoundRobin
+    )]
+    pub proxy_rotation: ProxyRotation,
+
     #[arg(
         short = 'm',
         long = "max-pages",
@@ -63,14 +125,86 @@ pub struct CliArgs {
     )]
     pub user_agent: String,
 
+    #[arg(
+        long = "user-agent-file",
+        help = "File with one user agent per line to rotate page requests through; robots.txt is still checked for --user-agent",
+        value_name = "FILE"
+    )]
+    pub user_agent_file: Option<PathBuf>,
+
+    #[arg(
+        long = "user-agent-rotation",
+        help = "When to switch to the next --user-agent-file user agent",
+        value_enum,
+        default_value_t = UserAgentRotation::PerRequest
+    )]
+    pub user_agent_rotation: UserAgentRotation,
+
     #[arg(
         long = "timeout",
-        help = "Request timeout in seconds",
+        help = "Request timeout in seconds, body included",
         default_value = "30",
         value_name = "SECONDS"
     )]
     pub timeout: u64,
 
+    #[arg(
+        long = "connect-timeout",
+        help = "Give up connecting to a host after this many seconds, so dead hosts fail before --timeout",
+        value_name = "SECONDS"
+    )]
+    pub connect_timeout: Option<u64>,
+
+    #[arg(
+        long = "stall-timeout",
+        help = "Give up on a response body when nothing arrives for this many seconds, so --timeout can be long for large pages",
+        value_name = "SECONDS"
+    )]
+    pub stall_timeout: Option<u64>,
+
+    #[arg(
+        long = "compression",
+        help = "Accept gzip, brotli and deflate responses, decoding them before parsing",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub compression: bool,
+
+    #[arg(
+        long = "http-version",
+        help = "HTTP version to speak; auto uses HTTP/2 where the server offers it over TLS",
+        value_enum,
+        default_value_t = HttpVersion::Auto
+    )]
+    pub http_version: HttpVersion,
+
+    #[arg(
+        long = "http2-adaptive-window",
+        help = "Size HTTP/2 flow-control windows to the measured bandwidth, for large pages over fast links",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub http2_adaptive_window: bool,
+
+    #[arg(
+        long = "pool-idle-timeout",
+        help = "Seconds an idle connection is kept for reuse (default 90; 0 closes connections once idle)",
+        value_name = "SECONDS"
+    )]
+    pub pool_idle_timeout: Option<u64>,
+
+    #[arg(
+        long = "pool-max-idle-per-host",
+        help = "Maximum number of idle connections kept per host",
+        value_name = "NUM"
+    )]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    #[arg(
+        long = "tcp-keepalive",
+        help = "Send TCP keepalive probes on idle connections every this many seconds",
+        value_name = "SECONDS"
+    )]
+    pub tcp_keepalive: Option<u64>,
+
     #[arg(
         long = "retries",
         help = "Maximum number of retries per request",
@@ -79,6 +213,14 @@ pub struct CliArgs {
     )]
     pub max_retries: usize,
 
+    #[arg(
+        long = "max-retry-after",
+        help = "Longest wait in seconds honored from a Retry-After header on 429/503 responses",
+        default_value = "60",
+        value_name = "SECONDS"
+    )]
+    pub max_retry_after: u64,
+
     #[arg(
         short = 'v',
         long = "verbose",
@@ -88,138 +230,1356 @@ pub struct CliArgs {
     pub verbose: u8,
 
     #[arg(
-        long = "respect-robots",
-        help = "Respect robots.txt rules",
-        default_value = "true",
+        long = "progress",
+        help = "Show a live progress bar instead of log lines while crawling",
         action = clap::ArgAction::SetTrue
     )]
+    pub progress: bool,
+
+    #[arg(
+        long = "robots",
+        visible_alias = "respect-robots",
+        help = "Respect robots.txt rules; --robots=false or --no-robots ignores them",
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new(),
+        value_name = "BOOL",
+        overrides_with = "no_robots",
+        action = clap::ArgAction::Set
+    )]
     pub respect_robots: bool,
 
     #[arg(
-        long = "follow-redirects",
-        help = "Follow HTTP redirects",
-        default_value = "true",
+        long = "no-robots",
+        help = "Ignore robots.txt rules",
+        overrides_with = "respect_robots",
         action = clap::ArgAction::SetTrue
     )]
+    pub no_robots: bool,
+
+    #[arg(
+        long = "robots-ttl",
+        help = "Seconds a robots.txt is cached before it is fetched again; its cache headers can shorten this",
+        default_value = "86400",
+        value_name = "SECONDS"
+    )]
+    pub robots_ttl: u64,
+
+    #[arg(
+        long = "robots-cache",
+        help = "Load cached robots.txt files from FILE and save them back after the crawl, so repeated crawls skip fresh ones",
+        value_name = "FILE"
+    )]
+    pub robots_cache: Option<PathBuf>,
+
+    #[arg(
+        long = "redirects",
+        visible_alias = "follow-redirects",
+        help = "Follow HTTP redirects; --redirects=false or --no-redirects reports them as pages instead",
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new(),
+        value_name = "BOOL",
+        overrides_with = "no_redirects",
+        action = clap::ArgAction::Set
+    )]
     pub follow_redirects: bool,
-}
 
-impl CliArgs {
-    pub fn validate(&self) -> anyhow::Result<()> {
-        // Validate URLs
-        for url_str in &self.urls {
-            Url::parse(url_str).map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", url_str, e))?;
-        }
+    #[arg(
+        long = "no-redirects",
+        help = "Don't follow HTTP redirects",
+        overrides_with = "follow_redirects",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub no_redirects: bool,
 
-        // Validate proxy URL if provided
-        if let Some(proxy_url) = &self.proxy {
-            Url::parse(proxy_url)
-                .map_err(|e| anyhow::anyhow!("Invalid proxy URL '{}': {}", proxy_url, e))?;
-        }
+    #[arg(
+        long = "max-redirects",
+        help = "Redirects followed from one URL before it counts as failed",
+        default_value = "10",
+        value_name = "NUM"
+    )]
+    pub max_redirects: usize,
 
-        // Validate concurrency
-        if self.concurrency == 0 {
-            return Err(anyhow::anyhow!("Concurrency must be greater than 0"));
-        }
+    #[arg(
+        long = "keep-html",
+        help = "Keep the HTML of each crawled page on its result, decoded to UTF-8 (as rendered with --render-js)",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub keep_html: bool,
 
-        // Validate rate limit
-        if let Some(rate) = self.rate_limit {
-            if rate <= 0.0 {
-                return Err(anyhow::anyhow!("Rate limit must be greater than 0"));
-            }
-        }
+    #[arg(
+        long = "keep-html-max-kb",
+        help = "Maximum HTML kept per page in KB; larger bodies are truncated",
+        default_value = "512",
+        value_name = "KB"
+    )]
+    pub keep_html_max_kb: usize,
 
-        // Validate max pages
-        if let Some(max_pages) = self.max_pages {
-            if max_pages == 0 {
-                return Err(anyhow::anyhow!("Max pages must be greater than 0"));
-            }
-        }
+    #[arg(
+        long = "max-links-per-page",
+        help = "Queue at most N links from a single page, preferring same-host links",
+        value_name = "N"
+    )]
+    pub max_links_per_page: Option<usize>,
 
-        // Validate depth
-        if let Some(depth) = self.depth {
-            if depth == 0 {
-                return Err(anyhow::anyhow!("Depth must be greater than 0"));
-            }
-        }
+    #[arg(
+        long = "max-title-length",
+        help = "Cut page titles longer than this many characters (0 = no limit)",
+        default_value = "200",
+        value_name = "CHARS"
+    )]
+    pub max_title_length: usize,
 
-        Ok(())
-    }
+    #[arg(
+        long = "timeline",
+        help = "Print the crawl event timeline after the crawl",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub timeline: bool,
 
-    pub fn get_timeout(&self) -> Duration {
-        Duration::from_secs(self.timeout)
-    }
+    #[arg(
+        long = "languages",
+        help = "Only keep pages in these languages (comma-separated ISO 639-1 codes, e.g. en,es)",
+        value_delimiter = ',',
+        value_name = "CODES"
+    )]
+    pub languages: Vec<String>,
 
-    pub fn get_rate_limit_interval(&self) -> Option<Duration> {
-        self.rate_limit.map(|rate| {
-            let requests_per_second = rate;
-            let interval_ms = (1000.0 / requests_per_second) as u64;
-            Duration::from_millis(interval_ms)
-        })
-    }
-}
+    #[arg(
+        long = "spa-fragments",
+        help = "Treat #/route and #!route fragments as distinct pages (single-page apps)",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub spa_fragments: bool,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[arg(
+        long = "case-insensitive-host",
+        help = "Treat URL paths on this host as case-insensitive when deduplicating (repeatable)",
+        value_name = "HOST"
+    )]
+    pub case_insensitive_hosts: Vec<String>,
 
-    #[test]
-    fn test_validate_valid_args() {
-        let args = CliArgs {
-            urls: vec!["https://example.com".to_string()],
-            concurrency: 10,
-            rate_limit: Some(5.0),
-            proxy: Some("http://proxy.example.com:8080".to_string()),
-            max_pages: Some(100),
-            depth: Some(3),
-            user_agent: "test-agent".to_string(),
-            timeout: 30,
-            max_retries: 3,
-            verbose: 0,
-            respect_robots: true,
-            follow_redirects: true,
-        };
+    #[arg(
+        long = "detect-case-insensitive",
+        help = "Probe each host once to detect case-insensitive paths automatically",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub detect_case_insensitive: bool,
 
-        assert!(args.validate().is_ok());
-    }
+    #[arg(
+        long = "max-inflight-mb",
+        help = "Maximum MB of response bodies buffered at once; new fetches wait while reached",
+        value_name = "MB"
+    )]
+    pub max_inflight_mb: Option<usize>,
 
-    #[test]
-    fn test_validate_invalid_url() {
-        let args = CliArgs {
-            urls: vec!["not-a-valid-url".to_string()],
-            concurrency: 10,
-            rate_limit: None,
-            proxy: None,
-            max_pages: None,
-            depth: None,
-            user_agent: "test-agent".to_string(),
-            timeout: 30,
-            max_retries: 3,
-            verbose: 0,
-            respect_robots: true,
-            follow_redirects: true,
-        };
+    #[arg(
+        long = "max-body-size",
+        help = "Cut response bodies at this many MB, dropping the rest of the response; 0 for no limit",
+        default_value = "5",
+        value_name = "MB"
+    )]
+    pub max_body_size: usize,
 
-        assert!(args.validate().is_err());
-    }
+    #[arg(
+        long,
+        help = "Crawl only this fraction (0-1] of discovered URLs, e.g. 0.1",
+        value_name = "FRACTION"
+    )]
+    pub sample: Option<f64>,
 
-    #[test]
-    fn test_validate_zero_concurrency() {
-        let args = CliArgs {
-            urls: vec!["https://example.com".to_string()],
-            concurrency: 0,
-            rate_limit: None,
-            proxy: None,
-            max_pages: None,
-            depth: None,
-            user_agent: "test-agent".to_string(),
-            timeout: 30,
-            max_retries: 3,
-            verbose: 0,
-            respect_robots: true,
-            follow_redirects: true,
-        };
+    #[arg(
+        long,
+        help = "Seed for --sample and --deterministic ordering",
+        value_name = "SEED"
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Reproducible runs: fixed frontier ordering and one request at a time per host",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub deterministic: bool,
+
+    #[arg(
+        long = "host-alias",
+        help = "Comma-separated hosts serving the same site, canonical host first (repeatable)",
+        value_name = "HOSTS"
+    )]
+    pub host_aliases: Vec<String>,
+
+    #[arg(
+        long = "alias-www",
+        help = "Treat www.<host> and <host> as the same site",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub alias_www: bool,
 
+    #[arg(
+        long = "detect-host-aliases",
+        help = "Treat hosts that permanently redirect to the same path on another host as aliases",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub detect_host_aliases: bool,
+
+    #[arg(
+        long,
+        help = "Which discovered links to follow relative to the seed URLs",
+        value_enum,
+        default_value_t = CrawlScope::All
+    )]
+    pub scope: CrawlScope,
+
+    #[arg(
+        long = "same-domain",
+        help = "Only follow links on the seeds' registrable domains (same as --scope same-domain)",
+        action = clap::ArgAction::SetTrue,
+        conflicts_with = "scope"
+    )]
+    pub same_domain: bool,
+
+    #[arg(
+        long,
+        help = "Order to crawl queued URLs in; priority favors short paths without query strings",
+        value_enum,
+        default_value_t = CrawlStrategy::Bfs
+    )]
+    pub strategy: CrawlStrategy,
+
+    #[arg(
+        long = "boost",
+        help = "Add WEIGHT to the priority of URLs matching the regex PATTERN, with --strategy priority (repeatable)",
+        value_name = "PATTERN=WEIGHT"
+    )]
+    pub boosts: Vec<String>,
+
+    #[arg(
+        long = "classify",
+        help = "YAML rules file mapping URL patterns, selectors and statuses to page categories",
+        value_name = "FILE"
+    )]
+    pub classify: Option<PathBuf>,
+
+    #[arg(
+        long = "extract",
+        help = "Put the text (or ATTRIBUTE) of every element matching SELECTOR into each page's extracted values under NAME (repeatable)",
+        value_name = "NAME=SELECTOR[@ATTRIBUTE]"
+    )]
+    pub extract: Vec<String>,
+
+    #[arg(
+        long = "extract-rules",
+        help = "YAML file of extraction rules (name, selector and optional attribute), applied along with --extract",
+        value_name = "FILE"
+    )]
+    pub extract_rules: Option<PathBuf>,
+
+    #[arg(
+        long = "tls-info",
+        help = "Record issuer, expiry, SANs and fingerprint of each HTTPS host's certificate"
+    )]
+    pub tls_info: bool,
+
+    #[arg(
+        long = "extract-text",
+        help = "Include each page's readable text, without scripts, styles and navigation, in results"
+    )]
+    pub extract_text: bool,
+
+    #[arg(
+        long = "extract-structured-data",
+        help = "Include JSON-LD, OpenGraph/Twitter card metadata and schema.org microdata of each page in results"
+    )]
+    pub extract_structured_data: bool,
+
+    #[arg(
+        long = "include-assets",
+        help = "Record the images, scripts, stylesheets and media each page loads in results, without crawling them"
+    )]
+    pub include_assets: bool,
+
+    #[arg(
+        long = "verify-assets",
+        help = "With --include-assets, check each asset's status and size with a HEAD request",
+        requires = "include_assets"
+    )]
+    pub verify_assets: bool,
+
+    #[arg(
+        long = "simhash",
+        help = "Include a SimHash of each page's readable text in results, for near-duplicate detection"
+    )]
+    pub simhash: bool,
+
+    #[arg(
+        long = "skip-duplicates",
+        help = "Don't follow links from pages whose body is identical to an earlier page's"
+    )]
+    pub skip_duplicates: bool,
+
+    #[arg(
+        long = "allowed-content-types",
+        help = "Only download bodies of these media types (comma-separated, e.g. text/html,application/pdf or image/*); by default images, video, audio, PDFs and archives are skipped",
+        value_delimiter = ',',
+        value_name = "TYPES"
+    )]
+    pub allowed_content_types: Vec<String>,
+
+    #[arg(
+        long = "http-cache",
+        help = "Keep each page's ETag/Last-Modified and result in DIR and re-crawl with conditional requests; unchanged (304) pages reuse the cached result",
+        value_name = "DIR"
+    )]
+    pub http_cache: Option<PathBuf>,
+
+    #[arg(
+        long = "detect-soft-404",
+        help = "Mark 200 pages that look like not-found pages (by title, size, or matching the host's page for an unknown URL) as soft_404"
+    )]
+    pub detect_soft_404: bool,
+
+    #[arg(
+        long = "soft-404-min-body",
+        help = "With --detect-soft-404, bodies smaller than this many bytes count as soft 404s",
+        default_value = "256",
+        value_name = "BYTES"
+    )]
+    pub soft_404_min_body: usize,
+
+    #[arg(
+        long = "soft-404-titles",
+        help = "With --detect-soft-404, title fragments of not-found pages, case-insensitive (comma-separated; default: not found,404,does not exist,doesn't exist,no longer available)",
+        value_delimiter = ',',
+        value_name = "PATTERNS"
+    )]
+    pub soft_404_titles: Vec<String>,
+
+    #[arg(
+        long = "render-js",
+        help = "Render HTML pages in headless Chromium and parse the DOM their scripts build (needs the render feature)",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub render_js: bool,
+
+    #[arg(
+        long = "render-pattern",
+        help = "Render only pages whose URL matches this regex, like --render-js (repeatable)",
+        value_name = "REGEX"
+    )]
+    pub render_patterns: Vec<String>,
+
+    #[arg(
+        long = "render-wait",
+        help = "What a rendered page waits for after loading: milliseconds, network-idle, selector:<css> or js:<expression>",
+        default_value = "500",
+        value_name = "CONDITION"
+    )]
+    pub render_wait: String,
+
+    #[arg(
+        long = "chrome-path",
+        help = "Chrome or Chromium binary for rendering (default: found on the system)",
+        value_name = "PATH"
+    )]
+    pub chrome_path: Option<PathBuf>,
+
+    #[arg(
+        long = "chrome-no-sandbox",
+        help = "Run Chromium without its sandbox, which it needs to start as root (e.g. in containers)",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub chrome_no_sandbox: bool,
+
+    #[arg(
+        long = "cert-expiry-days",
+        help = "With --tls-info, warn about certificates expiring within this many days",
+        default_value = "30",
+        value_name = "DAYS"
+    )]
+    pub cert_expiry_days: u64,
+
+    #[arg(
+        long = "output-format",
+        help = "How to write results",
+        value_enum,
+        default_value_t = OutputFormat::Text
+    )]
+    pub output_format: OutputFormat,
+
+    #[arg(
+        short = 'o',
+        long,
+        help = "Write results to this file instead of stdout",
+        value_name = "FILE"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[arg(
+        long = "warc",
+        help = "Archive every fetched response as WARC 1.1 records; a .gz path is gzip-compressed",
+        value_name = "PATH"
+    )]
+    pub warc: Option<PathBuf>,
+
+    #[cfg(feature = "sqlite")]
+    #[arg(
+        long = "sqlite",
+        help = "Write pages, links and run metadata to this SQLite database as the crawl progresses",
+        value_name = "DB"
+    )]
+    pub sqlite: Option<PathBuf>,
+
+    #[arg(
+        long = "max-results-memory-mb",
+        help = "Spill collected results to a temporary file once they take more than this many MB",
+        value_name = "MB"
+    )]
+    pub max_results_memory_mb: Option<usize>,
+
+    #[arg(
+        long = "report",
+        help = "Write an HTML report (index.html) to this directory when the crawl ends",
+        value_name = "DIR"
+    )]
+    pub report: Option<PathBuf>,
+
+    #[arg(
+        long = "export-graph",
+        help = "Write the link graph (page -> link edges) to FILE when the crawl ends: Graphviz .dot, .graphml or .jsonl edges",
+        value_name = "FILE"
+    )]
+    pub export_graph: Option<PathBuf>,
+
+    #[arg(
+        long = "diff-against",
+        help = "Report pages added, removed or changed (status, title, content) since a previous crawl: a JSON report, NDJSON results or a --sqlite database",
+        value_name = "FILE"
+    )]
+    pub diff_against: Option<PathBuf>,
+
+    #[arg(
+        long = "state-file",
+        help = "Periodically save crawl state to this file so an interrupted crawl can be resumed",
+        value_name = "FILE"
+    )]
+    pub state_file: Option<PathBuf>,
+
+    #[arg(
+        long = "resume",
+        help = "Resume the crawl saved in --state-file",
+        requires = "state_file",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub resume: bool,
+
+    #[arg(
+        long = "checkpoint-interval",
+        help = "Seconds between state file checkpoints",
+        default_value = "30",
+        value_name = "SECONDS"
+    )]
+    pub checkpoint_interval: u64,
+
+    #[arg(
+        long = "visited-store",
+        help = "How to remember visited URLs: exact keeps every URL, fingerprint a 64-bit hash of each, bloom a fixed-size filter that may skip ~0.1% of new URLs",
+        value_enum,
+        default_value_t = VisitedStore::Exact
+    )]
+    pub visited_store: VisitedStore,
+
+    #[arg(
+        long = "expected-urls",
+        help = "URLs the crawl is expected to visit, which sizes the --visited-store bloom filter",
+        default_value_t = DEFAULT_EXPECTED_URLS,
+        value_name = "NUM"
+    )]
+    pub expected_urls: usize,
+
+    #[arg(
+        long = "probe",
+        help = "Probe the first few pages and suggest scope/depth/budget before crawling",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub probe: bool,
+
+    #[arg(
+        long = "dry-run",
+        help = "Probe the site, print suggested settings and exit without crawling",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long = "sitemap-only",
+        help = "List pages from robots.txt and sitemaps without fetching any HTML",
+        conflicts_with = "resume",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub sitemap_only: bool,
+
+    #[arg(
+        long = "head-check",
+        help = "With --sitemap-only, send a HEAD request to each listed URL for its status",
+        requires = "sitemap_only",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub head_check: bool,
+}
+
+impl CliArgs {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        // Validate URLs; `example.com/path` is accepted and gets a scheme before crawling
+        for url_str in &self.urls {
+            if seed_without_scheme(url_str).is_some() {
+                continue;
+            }
+            Url::parse(url_str).map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", url_str, e))?;
+        }
+
+        // Validate proxy URL if provided
+        if let Some(proxy_url) = &self.proxy {
+            Url::parse(proxy_url)
+                .map_err(|e| anyhow::anyhow!("Invalid proxy URL '{}': {}", proxy_url, e))?;
+        }
+        if self.proxy.is_some() && self.proxy_list.is_some() {
+            return Err(anyhow::anyhow!(
+                "Use either --proxy or --proxy-list, not both"
+            ));
+        }
+        if self.proxy_list.is_none() && self.proxy_rotation != ProxyRotation::RoundRobin {
+            return Err(anyhow::anyhow!("--proxy-rotation requires --proxy-list"));
+        }
+        if let Some(path) = &self.user_agent_file {
+            if self.get_user_agents()?.is_empty() {
+                return Err(anyhow::anyhow!("No user agents in {}", path.display()));
+            }
+        } else if self.user_agent_rotation != UserAgentRotation::PerRequest {
+            return Err(anyhow::anyhow!(
+                "--user-agent-rotation requires --user-agent-file"
+            ));
+        }
+        if let Some(path) = &self.proxy_list {
+            let proxies = self.get_proxy_list()?;
+            if proxies.is_empty() {
+                return Err(anyhow::anyhow!("No proxies in {}", path.display()));
+            }
+            for proxy_url in &proxies {
+                Url::parse(proxy_url)
+                    .map_err(|e| anyhow::anyhow!("Invalid proxy URL '{}': {}", proxy_url, e))?;
+            }
+        }
+
+        // Validate concurrency
+        if self.concurrency == 0 {
+            return Err(anyhow::anyhow!("Concurrency must be greater than 0"));
+        }
+        if self.max_concurrency_per_host == Some(0) {
+            return Err(anyhow::anyhow!(
+                "Per-host concurrency must be greater than 0"
+            ));
+        }
+
+        // Validate rate limit
+        if let Some(rate) = self.rate_limit {
+            if rate <= 0.0 {
+                return Err(anyhow::anyhow!("Rate limit must be greater than 0"));
+            }
+        }
+
+        if self.burst == 0 {
+            return Err(anyhow::anyhow!("Burst must be greater than 0"));
+        }
+
+        // Validate max pages
+        if let Some(max_pages) = self.max_pages {
+            if max_pages == 0 {
+                return Err(anyhow::anyhow!("Max pages must be greater than 0"));
+            }
+        }
+
+        if self.connect_timeout == Some(0) || self.stall_timeout == Some(0) {
+            return Err(anyhow::anyhow!(
+                "--connect-timeout and --stall-timeout must be greater than 0"
+            ));
+        }
+
+        if self.tcp_keepalive == Some(0) {
+            return Err(anyhow::anyhow!("--tcp-keepalive must be greater than 0"));
+        }
+
+        if self.max_links_per_page == Some(0) {
+            return Err(anyhow::anyhow!("Max links per page must be greater than 0"));
+        }
+
+        if self.state_file.is_some() && self.visited_store != VisitedStore::Exact {
+            return Err(anyhow::anyhow!(
+                "--state-file needs --visited-store exact, as checkpoints list visited URLs"
+            ));
+        }
+
+        if self.expected_urls == 0 {
+            return Err(anyhow::anyhow!("--expected-urls must be greater than 0"));
+        }
+
+        if self.checkpoint_interval == 0 {
+            return Err(anyhow::anyhow!(
+                "Checkpoint interval must be greater than 0"
+            ));
+        }
+
+        // Validate depth
+        if let Some(depth) = self.depth {
+            if depth == 0 {
+                return Err(anyhow::anyhow!("Depth must be greater than 0"));
+            }
+        }
+
+        // Validate in-flight byte cap
+        if self.max_inflight_mb == Some(0) {
+            return Err(anyhow::anyhow!("Max in-flight MB must be greater than 0"));
+        }
+
+        // Validate results memory cap
+        if self.max_results_memory_mb == Some(0) {
+            return Err(anyhow::anyhow!(
+                "Max results memory MB must be greater than 0"
+            ));
+        }
+
+        // Validate sample fraction
+        if let Some(sample) = self.sample {
+            if !(sample > 0.0 && sample <= 1.0) {
+                return Err(anyhow::anyhow!(
+                    "Sample must be greater than 0 and at most 1"
+                ));
+            }
+        }
+
+        // The progress bar replaces log output, verbose or not
+        if self.progress && self.verbose > 0 {
+            return Err(anyhow::anyhow!(
+                "--progress and --verbose can't be combined"
+            ));
+        }
+
+        // Validate crawl strategy
+        if self.deterministic && self.strategy != CrawlStrategy::Bfs {
+            return Err(anyhow::anyhow!(
+                "--deterministic always crawls breadth-first; drop --strategy"
+            ));
+        }
+        if !self.boosts.is_empty() && self.strategy != CrawlStrategy::Priority {
+            return Err(anyhow::anyhow!("--boost requires --strategy priority"));
+        }
+        for boost in self.get_priority_boosts()? {
+            Regex::new(&boost.pattern)
+                .map_err(|e| anyhow::anyhow!("Invalid boost pattern '{}': {}", boost.pattern, e))?;
+        }
+        Extractor::new(&self.get_extraction_rules()?)?;
+        self.get_render_wait()?;
+
+        // Validate host alias groups
+        for group in self.get_host_alias_groups() {
+            if group.len() < 2 || group.iter().any(|host| host.is_empty()) {
+                return Err(anyhow::anyhow!(
+                    "Host alias must list at least two hosts, e.g. example.com,www.example.com"
+                ));
+            }
+        }
+
+        // Validate language codes
+        for code in &self.languages {
+            if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(anyhow::anyhow!("Invalid language code '{}'", code));
+            }
+        }
+
+        // Validate media types
+        for content_type in &self.allowed_content_types {
+            let valid = content_type
+                .trim()
+                .split_once('/')
+                .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty());
+            if !valid {
+                return Err(anyhow::anyhow!(
+                    "Invalid content type '{}'; expected TYPE/SUBTYPE, e.g. text/html or image/*",
+                    content_type
+                ));
+            }
+        }
+
+        if let Some(path) = &self.export_graph {
+            GraphFormat::from_path(path)?;
+        }
+
+        // Validate retained HTML cap
+        if self.keep_html && self.keep_html_max_kb == 0 {
+            return Err(anyhow::anyhow!("Keep HTML max KB must be greater than 0"));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+
+    pub fn get_connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout.map(Duration::from_secs)
+    }
+
+    pub fn get_stall_timeout(&self) -> Option<Duration> {
+        self.stall_timeout.map(Duration::from_secs)
+    }
+
+    pub fn get_pool_idle_timeout(&self) -> Option<Duration> {
+        self.pool_idle_timeout.map(Duration::from_secs)
+    }
+
+    pub fn get_tcp_keepalive(&self) -> Option<Duration> {
+        self.tcp_keepalive.map(Duration::from_secs)
+    }
+
+    pub fn get_render_wait(&self) -> anyhow::Result<RenderWait> {
+        self.render_wait.parse()
+    }
+
+    pub fn get_max_retry_after(&self) -> Duration {
+        Duration::from_secs(self.max_retry_after)
+    }
+
+    pub fn get_robots_ttl(&self) -> Duration {
+        Duration::from_secs(self.robots_ttl)
+    }
+
+    pub fn get_checkpoint_interval(&self) -> Duration {
+        Duration::from_secs(self.checkpoint_interval)
+    }
+
+    pub fn get_host_alias_groups(&self) -> Vec<Vec<String>> {
+        self.host_aliases
+            .iter()
+            .map(|group| {
+                group
+                    .split(',')
+                    .map(|host| host.trim().to_lowercase())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Add the URLs of every `--seed-file` to `urls`, reading standard input for `-`.
+    pub fn load_seed_files(&mut self) -> anyhow::Result<()> {
+        for path in &self.seed_files {
+            let seeds = if path.as_os_str() == "-" {
+                let mut contents = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut contents)
+                    .context("Failed to read seeds from standard input")?;
+                parse_list(&contents)
+            } else {
+                read_list(path, "seed file")?
+            };
+            self.urls.extend(seeds);
+        }
+        Ok(())
+    }
+
+    /// Proxies listed in the `--proxy-list` file, skipping blank lines and comments.
+    pub fn get_proxy_list(&self) -> anyhow::Result<Vec<String>> {
+        match &self.proxy_list {
+            Some(path) => read_list(path, "proxy list"),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// User agents listed in the `--user-agent-file` file, skipping blank lines and comments.
+    pub fn get_user_agents(&self) -> anyhow::Result<Vec<String>> {
+        match &self.user_agent_file {
+            Some(path) => read_list(path, "user agent file"),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// `--boost` values split at their last `=`, so patterns may contain `=` themselves.
+    pub fn get_priority_boosts(&self) -> anyhow::Result<Vec<PriorityBoost>> {
+        self.boosts
+            .iter()
+            .map(|boost| {
+                let parsed = boost.rsplit_once('=').and_then(|(pattern, weight)| {
+                    let weight = weight.trim().parse().ok()?;
+                    Some(PriorityBoost {
+                        pattern: pattern.to_string(),
+                        weight,
+                    })
+                });
+                parsed.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Invalid boost '{}'; expected PATTERN=WEIGHT, e.g. /products/=5",
+                        boost
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Rules from `--extract-rules`, then the `--extract` flags.
+    pub fn get_extraction_rules(&self) -> anyhow::Result<Vec<ExtractionRule>> {
+        let mut rules = match &self.extract_rules {
+            Some(path) => load_extraction_rules(path)?,
+            None => Vec::new(),
+        };
+        for rule in &self.extract {
+            rules.push(ExtractionRule::parse(rule)?);
+        }
+        Ok(rules)
+    }
+
+    pub fn get_scope(&self) -> CrawlScope {
+        if self.same_domain {
+            CrawlScope::SameDomain
+        } else {
+            self.scope
+        }
+    }
+
+    /// `--robots`, unless turned off by a later `--no-robots`.
+    pub fn get_respect_robots(&self) -> bool {
+        self.respect_robots && !self.no_robots
+    }
+
+    /// `--redirects`, unless turned off by a later `--no-redirects`.
+    pub fn get_follow_redirects(&self) -> bool {
+        self.follow_redirects && !self.no_redirects
+    }
+
+    pub fn get_max_body_size(&self) -> Option<usize> {
+        (self.max_body_size > 0).then(|| self.max_body_size * 1024 * 1024)
+    }
+
+    pub fn get_max_bytes_in_flight(&self) -> Option<usize> {
+        self.max_inflight_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    pub fn get_max_results_memory(&self) -> Option<usize> {
+        self.max_results_memory_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    pub fn get_rate_limit_interval(&self) -> Option<Duration> {
+        self.rate_limit.map(|rate| {
+            let requests_per_second = rate;
+            let interval_ms = (1000.0 / requests_per_second) as u64;
+            Duration::from_millis(interval_ms)
+        })
+    }
+}
+
+/// Non-blank lines of `path`, trimmed, without `#` comment lines.
+fn read_list(path: &Path, what: &str) -> anyhow::Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {} {}", what, path.display()))?;
+    Ok(parse_list(&contents))
+}
+
+fn parse_list(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_args() -> CliArgs {
+        CliArgs {
+            urls: vec!["https://example.com".to_string()],
+            seed_files: Vec::new(),
+            config: None,
+            concurrency: 10,
+            max_concurrency_per_host: None,
+            rate_limit: None,
+            burst: 1,
+            proxy: None,
+            proxy_list: None,
+            proxy_rotation: ProxyRotation::RoundRobin,
+            max_pages: None,
+            depth: None,
+            user_agent: "test-agent".to_string(),
+            user_agent_file: None,
+            user_agent_rotation: UserAgentRotation::PerRequest,
+            timeout: 30,
+            connect_timeout: None,
+            stall_timeout: None,
+            compression: false,
+            http_version: HttpVersion::Auto,
+            http2_adaptive_window: false,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+            max_retries: 3,
+            max_retry_after: 60,
+            verbose: 0,
+            progress: false,
+            respect_robots: true,
+            no_robots: false,
+            robots_ttl: 86400,
+            robots_cache: None,
+            follow_redirects: true,
+            no_redirects: false,
+            max_redirects: 10,
+            keep_html: false,
+            keep_html_max_kb: 512,
+            max_title_length: 200,
+            max_links_per_page: None,
+            timeline: false,
+            languages: Vec::new(),
+            spa_fragments: false,
+            case_insensitive_hosts: Vec::new(),
+            detect_case_insensitive: false,
+            max_inflight_mb: None,
+            max_body_size: 5,
+            sample: None,
+            seed: None,
+            deterministic: false,
+            host_aliases: Vec::new(),
+            alias_www: false,
+            detect_host_aliases: false,
+            scope: CrawlScope::All,
+            same_domain: false,
+            strategy: CrawlStrategy::Bfs,
+            boosts: Vec::new(),
+            classify: None,
+            extract: Vec::new(),
+            extract_rules: None,
+            tls_info: false,
+            extract_text: false,
+            extract_structured_data: false,
+            include_assets: false,
+            verify_assets: false,
+            simhash: false,
+            skip_duplicates: false,
+            allowed_content_types: Vec::new(),
+            http_cache: None,
+            detect_soft_404: false,
+            soft_404_min_body: 256,
+            soft_404_titles: Vec::new(),
+            render_js: false,
+            render_patterns: Vec::new(),
+            render_wait: "500".to_string(),
+            chrome_path: None,
+            chrome_no_sandbox: false,
+            cert_expiry_days: 30,
+            output_format: OutputFormat::Text,
+            output: None,
+            warc: None,
+            #[cfg(feature = "sqlite")]
+            sqlite: None,
+            max_results_memory_mb: None,
+            report: None,
+            export_graph: None,
+            diff_against: None,
+            state_file: None,
+            resume: false,
+            checkpoint_interval: 30,
+            visited_store: VisitedStore::Exact,
+            expected_urls: DEFAULT_EXPECTED_URLS,
+            probe: false,
+            dry_run: false,
+            sitemap_only: false,
+            head_check: false,
+        }
+    }
+
+    #[test]
+    fn test_validate_valid_args() {
+        let args = CliArgs {
+            rate_limit: Some(5.0),
+            proxy: Some("http://proxy.example.com:8080".to_string()),
+            max_pages: Some(100),
+            depth: Some(3),
+            ..test_args()
+        };
+
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_url() {
+        let args = CliArgs {
+            urls: vec!["not-a-valid-url".to_string()],
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_accepts_url_without_scheme() {
+        let args = CliArgs {
+            urls: vec!["example.com/path".to_string(), "localhost:3000".to_string()],
+            ..test_args()
+        };
+
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_robots_and_redirects_flags() {
+        let parse = |flags: &[&str]| {
+            let args = CliArgs::try_parse_from(
+                ["rustcrawler"]
+                    .iter()
+                    .chain(flags)
+                    .chain(&["https://example.com"]),
+            )
+            .unwrap();
+            (args.get_respect_robots(), args.get_follow_redirects())
+        };
+        assert_eq!(parse(&[]), (true, true));
+        assert_eq!(parse(&["--no-robots", "--no-redirects"]), (false, false));
+        assert_eq!(parse(&["--robots=false", "--redirects=no"]), (false, false));
+        assert_eq!(parse(&["--robots", "--redirects=true"]), (true, true));
+        // The old names still work, and the last of a pair wins
+        assert_eq!(
+            parse(&["--respect-robots=false", "--follow-redirects"]),
+            (false, true)
+        );
+        assert_eq!(parse(&["--no-robots", "--robots"]), (true, true));
+        assert_eq!(parse(&["--robots", "--no-robots"]), (false, true));
+        assert!(
+            CliArgs::try_parse_from(["rustcrawler", "--robots=maybe", "https://example.com"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_render_wait_conditions() {
+        let wait = |value: &str| {
+            CliArgs::try_parse_from(["rustcrawler", "--render-wait", value, "https://example.com"])
+                .unwrap()
+                .get_render_wait()
+        };
+        assert_eq!(
+            test_args().get_render_wait().unwrap(),
+            RenderWait::Delay(Duration::from_millis(500))
+        );
+        assert_eq!(wait("network-idle").unwrap(), RenderWait::NetworkIdle);
+        assert_eq!(
+            wait("selector:.results li").unwrap(),
+            RenderWait::Selector(".results li".to_string())
+        );
+        assert!(wait("whenever").is_err());
+
+        let args = CliArgs {
+            render_wait: "js:".to_string(),
+            ..test_args()
+        };
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_zero_concurrency() {
+        let args = CliArgs {
+            concurrency: 0,
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_progress_excludes_verbose() {
+        let args = CliArgs {
+            progress: true,
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+
+        let args = CliArgs {
+            progress: true,
+            verbose: 1,
+            ..test_args()
+        };
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_proxy_list() {
+        let path = std::env::temp_dir().join(format!("proxies-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "# office\nhttp://a.example:8080\n\n  socks5://b.example:1080  \n",
+        )
+        .unwrap();
+        let args = CliArgs {
+            proxy_list: Some(path.clone()),
+            proxy_rotation: ProxyRotation::Sticky,
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            args.get_proxy_list().unwrap(),
+            ["http://a.example:8080", "socks5://b.example:1080"]
+        );
+
+        let both = CliArgs {
+            proxy_list: Some(path.clone()),
+            proxy: Some("http://c.example:8080".to_string()),
+            ..args.clone()
+        };
+        assert!(both.validate().is_err());
+        let rotation_alone = CliArgs {
+            proxy_rotation: ProxyRotation::Random,
+            ..test_args()
+        };
+        assert!(rotation_alone.validate().is_err());
+
+        std::fs::write(&path, "# nothing yet\n").unwrap();
+        assert!(args.validate().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_seed_files() {
+        let path = std::env::temp_dir().join(format!("seeds-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "# exported from analytics
+https://example.com/a
+
+  example.com/b  
+",
+        )
+        .unwrap();
+        let mut args = CliArgs {
+            seed_files: vec![path.clone()],
+            ..test_args()
+        };
+        args.load_seed_files().unwrap();
+        assert_eq!(
+            args.urls,
+            [
+                "https://example.com",
+                "https://example.com/a",
+                "example.com/b"
+            ]
+        );
+        assert!(args.validate().is_ok());
+        std::fs::remove_file(&path).unwrap();
+        assert!(args.load_seed_files().is_err());
+    }
+
+    #[test]
+    fn test_user_agent_file() {
+        let path = std::env::temp_dir().join(format!("agents-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "Mozilla/5.0 (X11; Linux x86_64)\n# old\n\ncurl/8.0\n",
+        )
+        .unwrap();
+        let args = CliArgs {
+            user_agent_file: Some(path.clone()),
+            user_agent_rotation: UserAgentRotation::PerHost,
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            args.get_user_agents().unwrap(),
+            ["Mozilla/5.0 (X11; Linux x86_64)", "curl/8.0"]
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(args.validate().is_err());
+
+        let rotation_alone = CliArgs {
+            user_agent_rotation: UserAgentRotation::PerHost,
+            ..test_args()
+        };
+        assert!(rotation_alone.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_allowed_content_types() {
+        let args = CliArgs {
+            allowed_content_types: vec!["text/html".to_string(), "image/*".to_string()],
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        let invalid = CliArgs {
+            allowed_content_types: vec!["html".to_string()],
+            ..test_args()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_export_graph() {
+        let args = CliArgs {
+            export_graph: Some(PathBuf::from("site.dot")),
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        let invalid = CliArgs {
+            export_graph: Some(PathBuf::from("site.svg")),
+            ..test_args()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_extraction_rules() {
+        let args = CliArgs {
+            extract: vec![
+                "price=.price".to_string(),
+                "next=a[rel=next]@href".to_string(),
+            ],
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        let rules = args.get_extraction_rules().unwrap();
+        assert_eq!(rules[1].name, "next");
+        assert_eq!(rules[1].attribute.as_deref(), Some("href"));
+
+        let duplicate = CliArgs {
+            extract: vec!["price=.price".to_string(), "price=.cost".to_string()],
+            ..test_args()
+        };
+        assert!(duplicate.validate().is_err());
+        let unnamed = CliArgs {
+            extract: vec![".price".to_string()],
+            ..test_args()
+        };
+        assert!(unnamed.validate().is_err());
+    }
+
+    #[test]
+    fn test_priority_boosts() {
+        let args = CliArgs {
+            strategy: CrawlStrategy::Priority,
+            boosts: vec!["/products/=5".to_string(), "[?&]page=\\d+=-2".to_string()],
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        let boosts = args.get_priority_boosts().unwrap();
+        assert_eq!(boosts[1].pattern, "[?&]page=\\d+");
+        assert_eq!(boosts[1].weight, -2);
+
+        let bfs = CliArgs {
+            strategy: CrawlStrategy::Bfs,
+            ..args.clone()
+        };
+        assert!(bfs.validate().is_err());
+        let unweighted = CliArgs {
+            boosts: vec!["/products/".to_string()],
+            ..args
+        };
+        assert!(unweighted.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_zero_concurrency_per_host() {
+        let args = CliArgs {
+            max_concurrency_per_host: Some(0),
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_zero_burst() {
+        let args = CliArgs {
+            rate_limit: Some(2.0),
+            burst: 0,
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_zero_max_links_per_page() {
+        let args = CliArgs {
+            max_links_per_page: Some(0),
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_zero_keep_html_max_kb() {
+        let args = CliArgs {
+            keep_html: true,
+            keep_html_max_kb: 0,
+            ..test_args()
+        };
+
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_sample_fraction() {
+        for sample in [0.0, -0.5, 1.5, f64::NAN] {
+            let args = CliArgs {
+                sample: Some(sample),
+                ..test_args()
+            };
+            assert!(args.validate().is_err(), "sample {} accepted", sample);
+        }
+
+        let args = CliArgs {
+            sample: Some(1.0),
+            seed: Some(42),
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_host_alias_groups() {
+        let args = CliArgs {
+            host_aliases: vec!["Example.com, www.example.com".to_string()],
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            args.get_host_alias_groups(),
+            vec![vec![
+                "example.com".to_string(),
+                "www.example.com".to_string()
+            ]]
+        );
+
+        let args = CliArgs {
+            host_aliases: vec!["example.com".to_string()],
+            ..test_args()
+        };
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_language_codes() {
+        let args = CliArgs {
+            languages: vec!["en".to_string(), "es".to_string()],
+            ..test_args()
+        };
+        assert!(args.validate().is_ok());
+
+        let args = CliArgs {
+            languages: vec!["en-US".to_string()],
+            ..test_args()
+        };
         assert!(args.validate().is_err());
     }
 }
@@ -0,0 +1,206 @@
+use crate::crawler::CrawlResult;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Crawled pages that are the same logical page: linked by a shared canonical URL
+/// or by `hreflang` alternates (locale variants).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageCluster {
+    /// Representative URL: the `x-default` variant, else the canonical, else the first page crawled.
+    pub url: String,
+    pub title: Option<String>,
+    pub variants: Vec<ClusterVariant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterVariant {
+    pub url: String,
+    /// Language/region code another page in the cluster declared for this URL.
+    pub hreflang: Option<String>,
+    pub status_code: u16,
+}
+
+impl PageCluster {
+    pub fn format_output(&self) -> String {
+        let mut langs: Vec<&str> = self
+            .variants
+            .iter()
+            .filter_map(|variant| variant.hreflang.as_deref())
+            .collect();
+        langs.sort_unstable();
+        langs.dedup();
+
+        let title = self.title.as_deref().unwrap_or("No title");
+        if langs.is_empty() {
+            format!(
+                "{} - {} ({} variants)",
+                self.url,
+                title,
+                self.variants.len()
+            )
+        } else {
+            format!(
+                "{} - {} ({} variants: {})",
+                self.url,
+                title,
+                self.variants.len(),
+                langs.join(", ")
+            )
+        }
+    }
+}
+
+/// Union-find over URLs; every URL mentioned as a page, canonical or alternate gets a slot.
+#[derive(Default)]
+struct UrlSets {
+    index: HashMap<String, usize>,
+    parent: Vec<usize>,
+}
+
+impl UrlSets {
+    fn slot(&mut self, url: &str) -> usize {
+        if let Some(&slot) = self.index.get(url) {
+            return slot;
+        }
+        let slot = self.parent.len();
+        self.parent.push(slot);
+        self.index.insert(url.to_string(), slot);
+        slot
+    }
+
+    fn find(&mut self, mut slot: usize) -> usize {
+        while self.parent[slot] != slot {
+            self.parent[slot] = self.parent[self.parent[slot]];
+            slot = self.parent[slot];
+        }
+        slot
+    }
+
+    fn union(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.slot(a), self.slot(b));
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b] = a;
+        }
+    }
+}
+
+/// Group results into logical pages, in the order each cluster was first crawled.
+/// Pages without canonical or hreflang relations form single-page clusters.
+pub fn cluster_results(results: &[CrawlResult]) -> Vec<PageCluster> {
+    let mut sets = UrlSets::default();
+    let mut hreflang_of: HashMap<&str, &str> = HashMap::new();
+    for result in results {
+        sets.slot(&result.url);
+        if let Some(canonical) = &result.canonical {
+            sets.union(&result.url, canonical);
+        }
+        for variant in &result.hreflang {
+            sets.union(&result.url, &variant.url);
+            hreflang_of
+                .entry(variant.url.as_str())
+                .or_insert(variant.hreflang.as_str());
+        }
+    }
+
+    let mut cluster_of_root: HashMap<usize, usize> = HashMap::new();
+    let mut members: Vec<Vec<&CrawlResult>> = Vec::new();
+    for result in results {
+        let slot = sets.slot(&result.url);
+        let root = sets.find(slot);
+        let cluster = *cluster_of_root.entry(root).or_insert_with(|| {
+            members.push(Vec::new());
+            members.len() - 1
+        });
+        // The same URL can be crawled once per run, but guard against duplicates anyway
+        if !members[cluster].iter().any(|page| page.url == result.url) {
+            members[cluster].push(result);
+        }
+    }
+
+    members
+        .into_iter()
+        .map(|pages| {
+            let representative = pages
+                .iter()
+                .find(|page| hreflang_of.get(page.url.as_str()) == Some(&"x-default"))
+                .or_else(|| {
+                    let canonical = pages[0].canonical.as_deref()?;
+                    pages.iter().find(|page| page.url == canonical)
+                })
+                .unwrap_or(&pages[0]);
+
+            PageCluster {
+                url: representative.url.clone(),
+                title: representative.title.clone(),
+                variants: pages
+                    .iter()
+                    .map(|page| ClusterVariant {
+                        url: page.url.clone(),
+                        hreflang: hreflang_of
+                            .get(page.url.as_str())
+                            .map(|lang| lang.to_string()),
+                        status_code: page.status_code,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::PageVariant;
+
+    fn page(url: &str, canonical: Option<&str>, hreflang: &[(&str, &str)]) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code: 200,
+            title: Some(url.to_string()),
+            canonical: canonical.map(str::to_string),
+            hreflang: hreflang
+                .iter()
+                .map(|(lang, url)| PageVariant {
+                    hreflang: lang.to_string(),
+                    url: url.to_string(),
+                })
+                .collect(),
+            final_url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clusters_by_hreflang_and_canonical() {
+        let alternates = [
+            ("en", "https://example.com/en/"),
+            ("de", "https://example.com/de/"),
+            ("x-default", "https://example.com/"),
+        ];
+        let results = vec![
+            page("https://example.com/en/", None, &alternates),
+            page("https://example.com/about", None, &[]),
+            page("https://example.com/de/", None, &alternates),
+            page("https://example.com/", None, &alternates),
+            page(
+                "https://example.com/en/?utm=x",
+                Some("https://example.com/en/"),
+                &[],
+            ),
+        ];
+
+        let clusters = cluster_results(&results);
+        assert_eq!(clusters.len(), 2);
+
+        assert_eq!(clusters[0].url, "https://example.com/");
+        assert_eq!(clusters[0].variants.len(), 4);
+        assert_eq!(
+            clusters[0].format_output(),
+            "https://example.com/ - https://example.com/ (4 variants: de, en, x-default)"
+        );
+
+        assert_eq!(clusters[1].url, "https://example.com/about");
+        assert_eq!(clusters[1].variants.len(), 1);
+    }
+}
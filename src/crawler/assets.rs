@@ -0,0 +1,257 @@
+use super::fetch::header_value;
+use super::Crawler;
+use futures::future::join_all;
+use log::debug;
+use reqwest::StatusCode;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use url::Url;
+
+/// Asset checks of one page in flight at once, with `verify_assets`.
+const ASSET_CHECK_CONCURRENCY: usize = 8;
+
+/// What an asset is loaded as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    /// `<img src/srcset>` or a `<source>` of a `<picture>`
+    Image,
+    /// `<script src>`
+    Script,
+    /// `<link rel="stylesheet">`
+    Stylesheet,
+    /// A `<source>` of a `<video>` or `<audio>`
+    Media,
+}
+
+/// An image, script, stylesheet or media file a page loads, with `include_assets`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageAsset {
+    pub url: String,
+    pub kind: AssetKind,
+    /// Status of the asset, with `verify_assets`; 0 when it couldn't be requested
+    /// (including robots.txt blocking it).
+    #[serde(default)]
+    pub status_code: Option<u16>,
+    /// `Content-Length` of the asset, with `verify_assets`, when the server sent one.
+    #[serde(default)]
+    pub content_length: Option<u64>,
+    /// Loaded over `http://` by an `https://` page, which browsers block or warn about.
+    #[serde(default)]
+    pub mixed_content: bool,
+}
+
+impl AssetKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AssetKind::Image => "image",
+            AssetKind::Script => "script",
+            AssetKind::Stylesheet => "stylesheet",
+            AssetKind::Media => "media",
+        }
+    }
+}
+
+impl PageAsset {
+    /// What's wrong with the asset, e.g. `404, mixed content`; `None` when nothing is.
+    pub fn problem(&self) -> Option<String> {
+        let mut problems = Vec::new();
+        match self.status_code {
+            Some(0) => problems.push("unreachable".to_string()),
+            Some(status) if status >= 400 => problems.push(status.to_string()),
+            _ => {}
+        }
+        if self.mixed_content {
+            problems.push("mixed content".to_string());
+        }
+        (!problems.is_empty()).then(|| problems.join(", "))
+    }
+}
+
+/// Result of checking one asset URL, shared by every page that loads it.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AssetCheck {
+    status_code: u16,
+    content_length: Option<u64>,
+}
+
+impl Crawler {
+    /// Assets `document` loads, in document order without repeats. Only HTTP(S) URLs
+    /// are kept, so inline `data:` images are skipped.
+    pub(super) fn extract_assets(&self, document: &Html, base_url: &Url) -> Vec<PageAsset> {
+        let Ok(selector) = Selector::parse(
+            "img[src], img[srcset], script[src], link[href], source[src], source[srcset]",
+        ) else {
+            return Vec::new();
+        };
+        let mut seen = HashSet::new();
+        let mut assets = Vec::new();
+        for element in document.select(&selector) {
+            let value = element.value();
+            let kind = match value.name() {
+                "img" => AssetKind::Image,
+                "script" => AssetKind::Script,
+                "link" => {
+                    let stylesheet = value.attr("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|r| r.eq_ignore_ascii_case("stylesheet"))
+                    });
+                    if !stylesheet {
+                        continue;
+                    }
+                    AssetKind::Stylesheet
+                }
+                _ => match element
+                    .parent()
+                    .and_then(ElementRef::wrap)
+                    .map(|parent| parent.value().name())
+                {
+                    Some("picture") => AssetKind::Image,
+                    _ => AssetKind::Media,
+                },
+            };
+            let mut urls: Vec<&str> = ["src", "href"]
+                .iter()
+                .filter_map(|attr| value.attr(attr))
+                .collect();
+            if let Some(srcset) = value.attr("srcset") {
+                urls.extend(srcset_urls(srcset));
+            }
+            for url in urls {
+                let Some(url) = base_url
+                    .join(url.trim())
+                    .ok()
+                    .and_then(|url| self.normalize_link(&url))
+                else {
+                    continue;
+                };
+                if seen.insert(url.clone()) {
+                    assets.push(PageAsset {
+                        mixed_content: base_url.scheme() == "https" && url.starts_with("http:"),
+                        url,
+                        kind,
+                        status_code: None,
+                        content_length: None,
+                    });
+                }
+            }
+        }
+        assets
+    }
+
+    /// Fill in the status and size of each asset with a HEAD request (GET when HEAD isn't
+    /// allowed), checking every asset URL once per crawl. Per-host rate limits and
+    /// robots.txt apply; per-host concurrency limits don't, as the page holds its slot.
+    pub(super) async fn verify_assets(&self, assets: &mut [PageAsset]) {
+        let unchecked: Vec<String> = assets
+            .iter()
+            .filter(|asset| !self.asset_checks.contains_key(&asset.url))
+            .map(|asset| asset.url.clone())
+            .collect();
+        for batch in unchecked.chunks(ASSET_CHECK_CONCURRENCY) {
+            let checks = join_all(batch.iter().map(|url| self.check_asset(url))).await;
+            for (url, check) in batch.iter().zip(checks) {
+                self.asset_checks.insert(url.clone(), check);
+            }
+        }
+        for asset in assets {
+            if let Some(check) = self.asset_checks.get(&asset.url) {
+                asset.status_code = Some(check.status_code);
+                asset.content_length = check.content_length;
+            }
+        }
+    }
+
+    async fn check_asset(&self, url: &str) -> AssetCheck {
+        let failed = AssetCheck {
+            status_code: 0,
+            content_length: None,
+        };
+        let Ok(parsed) = Url::parse(url) else {
+            return failed;
+        };
+        if let Err(e) = self.check_robots(&parsed).await {
+            debug!("Not checking asset: {}", e.report());
+            return failed;
+        }
+        self.wait_for_host_slot(&parsed).await;
+        let response = match self.client.head(parsed.clone()).send().await {
+            Ok(response)
+                if response.status() == StatusCode::METHOD_NOT_ALLOWED
+                    || response.status() == StatusCode::NOT_IMPLEMENTED =>
+            {
+                // Dropping the response before reading the body keeps this to the headers
+                self.client.get(parsed).send().await
+            }
+            response => response,
+        };
+        match response {
+            Ok(response) => AssetCheck {
+                status_code: response.status().as_u16(),
+                content_length: header_value(response.headers(), reqwest::header::CONTENT_LENGTH)
+                    .and_then(|length| length.parse().ok()),
+            },
+            Err(e) => {
+                debug!("Asset check for {} failed: {}", url, e);
+                failed
+            }
+        }
+    }
+}
+
+/// URLs of a `srcset` attribute: comma-separated candidates of a URL and an optional
+/// width or density descriptor, e.g. `a.jpg 1x, b.jpg 2x`.
+fn srcset_urls(srcset: &str) -> impl Iterator<Item = &str> {
+    srcset
+        .split(',')
+        .filter_map(|candidate| candidate.split_ascii_whitespace().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+
+    #[test]
+    fn test_extract_assets() {
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            <link rel="icon" href="/favicon.ico">
+            <script src="http://cdn.example.com/app.js"></script>
+            <script>inline()</script>
+            </head><body>
+            <img src="logo.png" srcset="logo.png 1x, logo@2x.png 2x">
+            <img src="data:image/gif;base64,R0lGOD">
+            <picture><source srcset="/hero.webp"><img src="/hero.jpg"></picture>
+            <video><source src="/clip.mp4" type="video/mp4"></video>
+            </body></html>"#;
+        let base = Url::parse("https://example.com/page/").unwrap();
+        let assets = crawler.extract_assets(&Html::parse_document(html), &base);
+        let found: Vec<(&str, AssetKind, bool)> = assets
+            .iter()
+            .map(|asset| (asset.url.as_str(), asset.kind, asset.mixed_content))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "https://example.com/style.css",
+                    AssetKind::Stylesheet,
+                    false
+                ),
+                ("http://cdn.example.com/app.js", AssetKind::Script, true),
+                ("https://example.com/page/logo.png", AssetKind::Image, false),
+                (
+                    "https://example.com/page/logo@2x.png",
+                    AssetKind::Image,
+                    false
+                ),
+                ("https://example.com/hero.webp", AssetKind::Image, false),
+                ("https://example.com/hero.jpg", AssetKind::Image, false),
+                ("https://example.com/clip.mp4", AssetKind::Media, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,691 @@
+use super::frontier::Frontier;
+use super::{CrawlResult, Crawler};
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Bumped whenever `CrawlState` changes shape; older files are rejected on resume.
+const STATE_VERSION: u32 = 2;
+
+/// The journal is compacted into a fresh snapshot once it holds more entries than the
+/// snapshot itself, but never before it reaches this many.
+const MIN_COMPACTION_ENTRIES: usize = 10_000;
+
+/// Snapshot of an unfinished crawl, written to `--state-file` and read back by `--resume`.
+/// Changes since the snapshot live in an append-only journal next to it (see `journal_path`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlState {
+    pub version: u32,
+    pub run_id: String,
+    /// Unix timestamp in milliseconds when the snapshot was written.
+    pub saved_at_ms: u128,
+    /// Identifies the journal written after this snapshot; journals left over from
+    /// an older snapshot carry a different id and are ignored.
+    pub journal_id: String,
+    pub seeds: Vec<String>,
+    /// URLs already crawled (or failed); in-flight URLs are in `pending` instead.
+    pub visited: Vec<String>,
+    /// Queued and in-flight URLs with their depth.
+    pub pending: Vec<(String, usize)>,
+    /// Results produced so far.
+    pub results: Vec<CrawlResult>,
+}
+
+/// One line of the journal.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    /// First line of every journal, naming the snapshot it continues.
+    Start {
+        journal_id: String,
+    },
+    Queued {
+        url: String,
+        depth: usize,
+    },
+    /// A URL was crawled or failed and must not be retried.
+    Finished {
+        url: String,
+    },
+    Result {
+        result: Box<CrawlResult>,
+    },
+}
+
+/// The journal for the state file at `path`: `crawl.json` journals to `crawl.json.journal`.
+fn journal_path(path: &Path) -> PathBuf {
+    let mut journal = path.as_os_str().to_owned();
+    journal.push(".journal");
+    PathBuf::from(journal)
+}
+
+impl CrawlState {
+    /// Read the snapshot at `path` and replay its journal on top of it.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path)
+            .with_context(|| format!("Failed to read state file {}", path.display()))?;
+        let mut state: Self = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse state file {}", path.display()))?;
+        if state.version != STATE_VERSION {
+            bail!(
+                "State file {} has version {}, expected {}",
+                path.display(),
+                state.version,
+                STATE_VERSION
+            );
+        }
+        state.replay_journal(&journal_path(path))?;
+        Ok(state)
+    }
+
+    /// Write to a temporary file first so a crash mid-write never leaves a torn state file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let data = serde_json::to_vec(self).context("Failed to serialize crawl state")?;
+        fs::write(&tmp, data)
+            .with_context(|| format!("Failed to write state file {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace state file {}", path.display()))
+    }
+
+    fn replay_journal(&mut self, path: &Path) -> Result<()> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read journal {}", path.display()))
+            }
+        };
+        let mut lines = BufReader::new(file).lines();
+
+        let header = lines.next().transpose()?;
+        match header.and_then(|line| serde_json::from_str(&line).ok()) {
+            Some(JournalEntry::Start { journal_id }) if journal_id == self.journal_id => {}
+            _ => {
+                debug!("Ignoring journal {} from an older snapshot", path.display());
+                return Ok(());
+            }
+        }
+
+        let mut visited: HashSet<String> = self.visited.iter().cloned().collect();
+        let mut replayed = 0;
+        for line in lines {
+            let line =
+                line.with_context(|| format!("Failed to read journal {}", path.display()))?;
+            // A crash mid-append can leave the last line torn; everything before it is intact
+            let entry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Stopped replaying journal {}: {}", path.display(), e);
+                    break;
+                }
+            };
+            match entry {
+                JournalEntry::Start { .. } => {}
+                JournalEntry::Queued { url, depth } => self.pending.push((url, depth)),
+                JournalEntry::Finished { url } => {
+                    if visited.insert(url.clone()) {
+                        self.visited.push(url);
+                    }
+                }
+                JournalEntry::Result { result } => self.results.push(*result),
+            }
+            replayed += 1;
+        }
+
+        let mut queued = HashSet::new();
+        self.pending
+            .retain(|(url, _)| !visited.contains(url) && queued.insert(url.clone()));
+        debug!(
+            "Replayed {} journal entries from {}",
+            replayed,
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// The journal being appended to, and when to fold it into a new snapshot.
+struct Journal {
+    writer: BufWriter<File>,
+    /// Entries fully appended after the start line.
+    entries: usize,
+    compact_after: usize,
+}
+
+/// The result of a journal line, skipping the rest of it; other entries have none.
+#[derive(Deserialize)]
+struct JournalResult {
+    #[serde(default)]
+    result: Option<Box<RawValue>>,
+}
+
+/// Periodically checkpoints the crawl: new entries are appended to the journal, and
+/// the snapshot is only rewritten when the journal grows too long. Results aren't kept
+/// in memory; compaction copies them from the old snapshot and journal into the new
+/// snapshot one at a time.
+pub(super) struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Instant,
+    seeds: Vec<String>,
+    /// Whether the file at `path` is this crawl's snapshot, whose results compaction
+    /// keeps: set when resuming, or once this run has written one.
+    has_snapshot: bool,
+    /// Entries not yet appended to the journal.
+    unsaved: Vec<JournalEntry>,
+    /// `None` until this run has written a snapshot and started its journal.
+    journal: Option<Journal>,
+}
+
+impl Checkpointer {
+    pub(super) fn new(
+        path: PathBuf,
+        interval: Duration,
+        seeds: Vec<String>,
+        resumed: bool,
+    ) -> Self {
+        Self {
+            path,
+            interval,
+            last_saved: Instant::now(),
+            seeds,
+            has_snapshot: resumed,
+            unsaved: Vec::new(),
+            journal: None,
+        }
+    }
+
+    pub(super) fn record(&mut self, result: &CrawlResult) {
+        self.unsaved.push(JournalEntry::Result {
+            result: Box::new(result.clone()),
+        });
+    }
+
+    /// Note that `url` is done, successfully or not, so a resume won't retry it.
+    pub(super) fn finished(&mut self, url: &str) {
+        self.unsaved.push(JournalEntry::Finished {
+            url: url.to_string(),
+        });
+    }
+
+    pub(super) fn is_due(&self) -> bool {
+        self.last_saved.elapsed() >= self.interval
+    }
+
+    /// Append what changed since the last checkpoint, compacting if the journal has
+    /// outgrown the snapshot or URLs were dropped from the frontier. Failures are logged
+    /// rather than aborting the crawl.
+    pub(super) fn save<'a>(
+        &mut self,
+        crawler: &Crawler,
+        frontier: &mut Frontier,
+        in_flight: impl Iterator<Item = &'a (String, usize)>,
+        run_id: &str,
+    ) {
+        self.unsaved.extend(
+            frontier
+                .take_journal()
+                .into_iter()
+                .map(|(url, depth)| JournalEntry::Queued { url, depth }),
+        );
+        let outgrown = self.journal.as_ref().map_or(true, |journal| {
+            journal.entries + self.unsaved.len() > journal.compact_after
+        });
+        let saved = if frontier.take_dropped() || outgrown {
+            self.compact(crawler, frontier, in_flight, run_id)
+        } else {
+            self.append()
+        };
+        if let Err(e) = saved {
+            warn!("{:#}", e);
+            // A failed append may leave a torn line, so never append to this journal
+            // again; the unsaved entries wait for the next checkpoint, which compacts
+            if let Some(journal) = self.journal.as_mut() {
+                journal.compact_after = 0;
+            }
+        }
+        self.last_saved = Instant::now();
+    }
+
+    /// Write a full snapshot and start a new journal; done at the end of every crawl.
+    pub(super) fn finish<'a>(
+        &mut self,
+        crawler: &Crawler,
+        frontier: &mut Frontier,
+        in_flight: impl Iterator<Item = &'a (String, usize)>,
+        run_id: &str,
+    ) {
+        frontier.take_journal();
+        frontier.take_dropped();
+        if let Err(e) = self.compact(crawler, frontier, in_flight, run_id) {
+            warn!("{:#}", e);
+        }
+    }
+
+    fn append(&mut self) -> Result<()> {
+        let Some(journal) = self.journal.as_mut() else {
+            return Ok(());
+        };
+        let journal_path = journal_path(&self.path);
+        let mut data = Vec::new();
+        for entry in &self.unsaved {
+            serde_json::to_writer(&mut data, entry).context("Failed to serialize journal entry")?;
+            data.push(b'\n');
+        }
+        journal
+            .writer
+            .write_all(&data)
+            .and_then(|()| journal.writer.flush())
+            .with_context(|| format!("Failed to append to journal {}", journal_path.display()))?;
+        journal.entries += self.unsaved.len();
+        self.unsaved.clear();
+        debug!(
+            "Checkpoint appended to {} ({} entries since last compaction)",
+            journal_path.display(),
+            journal.entries
+        );
+        Ok(())
+    }
+
+    fn compact<'a>(
+        &mut self,
+        crawler: &Crawler,
+        frontier: &Frontier,
+        in_flight: impl Iterator<Item = &'a (String, usize)>,
+        run_id: &str,
+    ) -> Result<()> {
+        let mut pending: Vec<(String, usize)> = in_flight.cloned().collect();
+        pending.extend(frontier.snapshot());
+        // URLs are claimed when queued, so the visited set also holds the pending ones;
+        // the snapshot's visited URLs are only the finished ones.
+        // `Crawler::new` only allows checkpoints with the exact store
+        let pending_urls: HashSet<&str> = pending.iter().map(|(url, _)| url.as_str()).collect();
+        let visited: Vec<String> = crawler
+            .visited_urls
+            .urls()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|url| !pending_urls.contains(url.as_str()))
+            .collect();
+
+        let mut tmp = self.path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let file = File::create(&tmp)
+            .with_context(|| format!("Failed to write state file {}", tmp.display()))?;
+        let mut out = BufWriter::new(file);
+
+        // Written field by field so the results can be streamed in last
+        let journal_id = Uuid::new_v4().to_string();
+        let saved_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        out.write_all(b"{")?;
+        write_field(&mut out, "version", &STATE_VERSION)?;
+        write_field(&mut out, "run_id", &run_id)?;
+        write_field(&mut out, "saved_at_ms", &saved_at_ms)?;
+        write_field(&mut out, "journal_id", &journal_id)?;
+        write_field(&mut out, "seeds", &self.seeds)?;
+        write_field(&mut out, "visited", &visited)?;
+        write_field(&mut out, "pending", &pending)?;
+        out.write_all(b"\"results\":[")?;
+        let mut results = ResultsWriter {
+            out: &mut out,
+            count: 0,
+        };
+        if self.has_snapshot {
+            // The journal only counts if it continues this snapshot, and only its fully
+            // appended entries
+            let old_journal_id = copy_snapshot_results(&self.path, &mut results)?;
+            let limit = self.journal.as_ref().map(|journal| journal.entries);
+            copy_journal_results(
+                &journal_path(&self.path),
+                &old_journal_id,
+                limit,
+                &mut results,
+            )?;
+        }
+        for entry in &self.unsaved {
+            if let JournalEntry::Result { result } = entry {
+                results.push(&serde_json::to_string(result)?)?;
+            }
+        }
+        let result_count = results.count;
+        out.write_all(b"]}")?;
+        out.into_inner()
+            .map_err(io::IntoInnerError::into_error)
+            .and_then(|file| file.sync_all())
+            .with_context(|| format!("Failed to write state file {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to replace state file {}", self.path.display()))?;
+
+        // Everything unsaved and journaled is in the new snapshot now. A crash before the
+        // new journal exists leaves the old one, which the new snapshot's id no longer
+        // matches
+        self.has_snapshot = true;
+        self.unsaved.clear();
+        self.journal = None;
+        let journal_path = journal_path(&self.path);
+        let file = File::create(&journal_path)
+            .with_context(|| format!("Failed to create journal {}", journal_path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &JournalEntry::Start { journal_id })?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        let snapshot_entries = visited.len() + pending.len() + result_count;
+        self.journal = Some(Journal {
+            writer,
+            entries: 0,
+            compact_after: snapshot_entries.max(MIN_COMPACTION_ENTRIES),
+        });
+        debug!(
+            "Checkpoint compacted into {} ({} results, {} pending)",
+            self.path.display(),
+            result_count,
+            pending.len()
+        );
+        Ok(())
+    }
+}
+
+fn write_field(out: &mut impl Write, name: &str, value: &impl Serialize) -> Result<()> {
+    write!(out, "\"{}\":", name)?;
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b",")?;
+    Ok(())
+}
+
+/// Writes the elements of a snapshot's `results` array as they come.
+struct ResultsWriter<W> {
+    out: W,
+    count: usize,
+}
+
+impl<W: Write> ResultsWriter<W> {
+    fn push(&mut self, result: &str) -> io::Result<()> {
+        if self.count > 0 {
+            self.out.write_all(b",")?;
+        }
+        self.out.write_all(result.as_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Copy the results of the snapshot at `path` to `results` without parsing them,
+/// returning the snapshot's journal id.
+fn copy_snapshot_results<W: Write>(path: &Path, results: &mut ResultsWriter<W>) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to read state file {}", path.display()))?;
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+    (&mut deserializer)
+        .deserialize_map(SnapshotVisitor { results })
+        .with_context(|| format!("Failed to copy results from {}", path.display()))
+}
+
+/// Copy the results journaled after the snapshot with `journal_id`, reading at most
+/// `limit` entries, or up to the first torn line.
+fn copy_journal_results<W: Write>(
+    path: &Path,
+    journal_id: &str,
+    limit: Option<usize>,
+    results: &mut ResultsWriter<W>,
+) -> Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read journal {}", path.display()))
+        }
+    };
+    let mut lines = BufReader::new(file).lines();
+    let header = lines.next().transpose()?;
+    match header.and_then(|line| serde_json::from_str(&line).ok()) {
+        Some(JournalEntry::Start { journal_id: id }) if id == journal_id => {}
+        _ => return Ok(()),
+    }
+    // The live crawl state already covers the queued and finished URLs
+    for line in lines.take(limit.unwrap_or(usize::MAX)) {
+        let line = line.with_context(|| format!("Failed to read journal {}", path.display()))?;
+        let Ok(entry) = serde_json::from_str::<JournalResult>(&line) else {
+            break;
+        };
+        if let Some(result) = entry.result {
+            results.push(result.get())?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a snapshot's fields, copying its results out one at a time.
+struct SnapshotVisitor<'r, W> {
+    results: &'r mut ResultsWriter<W>,
+}
+
+impl<'de, W: Write> Visitor<'de> for SnapshotVisitor<'_, W> {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a crawl state")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<String, A::Error> {
+        let mut journal_id = String::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "journal_id" => journal_id = map.next_value()?,
+                "results" => map.next_value_seed(CopyResults(&mut *self.results))?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(journal_id)
+    }
+}
+
+/// Copies a `results` array element by element.
+struct CopyResults<'r, W>(&'r mut ResultsWriter<W>);
+
+impl<'de, W: Write> DeserializeSeed<'de> for CopyResults<'_, W> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, W: Write> Visitor<'de> for CopyResults<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of crawl results")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(result) = seq.next_element::<Box<RawValue>>()? {
+            self.0.push(result.get()).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+
+    fn page(url: &str) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code: 200,
+            title: Some("Home".to_string()),
+            links: vec!["https://example.com/a".to_string()],
+            crawl_time: Duration::from_millis(15),
+            run_id: "run".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_state_round_trips_through_file() {
+        let path =
+            std::env::temp_dir().join(format!("rustcrawler-state-{}.json", std::process::id()));
+        let state = CrawlState {
+            version: STATE_VERSION,
+            run_id: "run".to_string(),
+            seeds: vec!["https://example.com/".to_string()],
+            visited: vec!["https://example.com/".to_string()],
+            pending: vec![("https://example.com/a".to_string(), 1)],
+            results: vec![page("https://example.com/")],
+            ..Default::default()
+        };
+
+        state.save(&path).unwrap();
+        let loaded = CrawlState::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.pending, state.pending);
+        assert_eq!(loaded.results[0].crawl_time, Duration::from_millis(15));
+        assert_eq!(loaded.results[0].links, state.results[0].links);
+
+        let stale = CrawlState {
+            version: 0,
+            ..state
+        };
+        stale.save(&path).unwrap();
+        assert!(CrawlState::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_replays_journal_after_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("rustcrawler-journal-{}.json", std::process::id()));
+        let state = CrawlState {
+            version: STATE_VERSION,
+            journal_id: "current".to_string(),
+            visited: vec!["https://example.com/".to_string()],
+            pending: vec![
+                ("https://example.com/a".to_string(), 1),
+                ("https://example.com/b".to_string(), 1),
+            ],
+            ..Default::default()
+        };
+        state.save(&path).unwrap();
+
+        let journal = [
+            r#"{"op":"start","journal_id":"current"}"#,
+            r#"{"op":"finished","url":"https://example.com/a"}"#,
+            r#"{"op":"queued","url":"https://example.com/c","depth":2}"#,
+            r#"{"op":"queued","url":"https://example.com/b","depth":2}"#,
+            r#"{"op":"finished","url":"https://example.com/a"}"#,
+            r#"{"op":"queued","url":"https://exam"#,
+        ];
+        fs::write(journal_path(&path), journal.join("\n")).unwrap();
+
+        let loaded = CrawlState::load(&path).unwrap();
+        assert_eq!(
+            loaded.visited,
+            vec!["https://example.com/", "https://example.com/a"]
+        );
+        assert_eq!(
+            loaded.pending,
+            vec![
+                ("https://example.com/b".to_string(), 1),
+                ("https://example.com/c".to_string(), 2)
+            ]
+        );
+
+        // A journal left over from an older snapshot is ignored
+        fs::write(
+            journal_path(&path),
+            r#"{"op":"start","journal_id":"older"}
+{"op":"finished","url":"https://example.com/b"}"#,
+        )
+        .unwrap();
+        assert_eq!(CrawlState::load(&path).unwrap().pending.len(), 2);
+
+        fs::remove_file(journal_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_compaction_streams_saved_results_into_the_new_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("rustcrawler-compact-{}.json", std::process::id()));
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let mut frontier = Frontier::new(Vec::new(), None, None);
+        let urls = |path: &Path| -> Vec<String> {
+            let state = CrawlState::load(path).unwrap();
+            state.results.into_iter().map(|result| result.url).collect()
+        };
+
+        // A snapshot with one result, and a journal with another after a torn line
+        CrawlState {
+            version: STATE_VERSION,
+            journal_id: "current".to_string(),
+            results: vec![page("https://example.com/")],
+            ..Default::default()
+        }
+        .save(&path)
+        .unwrap();
+        let journaled = serde_json::to_string(&JournalEntry::Result {
+            result: Box::new(page("https://example.com/a")),
+        })
+        .unwrap();
+        let journal = [
+            r#"{"op":"start","journal_id":"current"}"#,
+            r#"{"op":"finished","url":"https://example.com/a"}"#,
+            &journaled,
+            r#"{"op":"result","result":{"url":"https://exam"#,
+        ];
+        fs::write(journal_path(&path), journal.join("\n")).unwrap();
+
+        // Resuming keeps the saved results, ahead of the new ones
+        let mut checkpointer = Checkpointer::new(path.clone(), Duration::ZERO, Vec::new(), true);
+        checkpointer.record(&page("https://example.com/b"));
+        checkpointer.save(&crawler, &mut frontier, std::iter::empty(), "run");
+        assert_eq!(
+            urls(&path),
+            [
+                "https://example.com/",
+                "https://example.com/a",
+                "https://example.com/b"
+            ]
+        );
+
+        // Later results are appended to the journal, then folded in at the end
+        checkpointer.record(&page("https://example.com/c"));
+        checkpointer.save(&crawler, &mut frontier, std::iter::empty(), "run");
+        assert_eq!(checkpointer.journal.as_ref().unwrap().entries, 1);
+        assert_eq!(urls(&path).len(), 4);
+        checkpointer.finish(&crawler, &mut frontier, std::iter::empty(), "run");
+        assert_eq!(checkpointer.journal.as_ref().unwrap().entries, 0);
+        assert_eq!(urls(&path).len(), 4);
+
+        // A fresh crawl replaces whatever the file held
+        let mut checkpointer = Checkpointer::new(path.clone(), Duration::ZERO, Vec::new(), false);
+        checkpointer.record(&page("https://example.com/d"));
+        checkpointer.finish(&crawler, &mut frontier, std::iter::empty(), "run");
+        assert_eq!(urls(&path), ["https://example.com/d"]);
+
+        fs::remove_file(journal_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
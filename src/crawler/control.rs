@@ -0,0 +1,50 @@
+use super::frontier::Frontier;
+use regex::Regex;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// A URL waiting in the frontier of a running crawl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueuedUrl {
+    pub url: String,
+    pub depth: usize,
+}
+
+/// Requests from `Crawler`'s public queue methods, applied by the crawl loop that
+/// owns the frontier between scheduling decisions.
+pub(super) enum FrontierCommand {
+    Peek {
+        limit: usize,
+        reply: oneshot::Sender<Vec<QueuedUrl>>,
+    },
+    Drop {
+        pattern: Regex,
+        reply: oneshot::Sender<usize>,
+    },
+    Prioritize {
+        pattern: Regex,
+        reply: oneshot::Sender<usize>,
+    },
+}
+
+impl FrontierCommand {
+    pub(super) fn apply(self, frontier: &mut Frontier) {
+        // The caller may have given up waiting; nothing to do then
+        match self {
+            Self::Peek { limit, reply } => {
+                let queued = frontier
+                    .peek(limit)
+                    .into_iter()
+                    .map(|(url, depth)| QueuedUrl { url, depth })
+                    .collect();
+                let _ = reply.send(queued);
+            }
+            Self::Drop { pattern, reply } => {
+                let _ = reply.send(frontier.remove_matching(&pattern));
+            }
+            Self::Prioritize { pattern, reply } => {
+                let _ = reply.send(frontier.prioritize_matching(&pattern));
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::fetcher::FetchError;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Source of errors raised outside the crawler, such as a sink or the state file.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why a `Crawler` method, or the crawl of a single URL, failed. URLs that fail
+/// during a crawl are logged and skipped; `Crawler::get_failures` lists them with
+/// their errors. Pages that answer with an error status are not failures: they're results
+/// with that `status_code`.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CrawlerError {
+    /// The crawler couldn't be built from its configuration.
+    #[error("{message}")]
+    Config {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("Invalid URL {url}")]
+    InvalidUrl {
+        url: String,
+        #[source]
+        source: url::ParseError,
+    },
+
+    #[error("URL blocked by robots.txt: {0}")]
+    RobotsBlocked(String),
+
+    /// robots.txt couldn't be checked, so the URL wasn't fetched.
+    #[error("Failed to check robots.txt for {url}")]
+    Robots {
+        url: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// The last attempt timed out.
+    #[error("Timed out fetching {url} after {attempts} attempt(s)")]
+    Timeout { url: String, attempts: usize },
+
+    /// Every attempt failed without a response, the last one with `source`.
+    #[error("Failed to fetch {url} after {attempts} attempt(s)")]
+    TooManyRetries {
+        url: String,
+        attempts: usize,
+        #[source]
+        source: FetchError,
+    },
+
+    /// The connection failed while the body was being read.
+    #[error("Failed to read response body of {url}")]
+    Body {
+        url: String,
+        #[source]
+        source: FetchError,
+    },
+
+    /// No part of the body arrived for `stall_timeout`.
+    #[error("Response body of {url} stalled: nothing received for {}s", .idle.as_secs())]
+    BodyStalled { url: String, idle: Duration },
+
+    /// Every proxy of the proxy list was evicted, so nothing can be fetched.
+    #[error("No working proxy left to fetch {0}")]
+    NoProxy(String),
+
+    /// A redirect pointed back to a URL already in the chain.
+    #[error("Redirect loop at {url}: {}", .chain.join(" -> "))]
+    RedirectLoop { url: String, chain: Vec<String> },
+
+    /// The chain went on past `max_redirects`.
+    #[error("Too many redirects from {url} (limit {limit})")]
+    TooManyRedirects { url: String, limit: usize },
+
+    #[error("Failed to parse {url}")]
+    ParseError {
+        url: String,
+        #[source]
+        source: BoxError,
+    },
+
+    #[error("No sitemap could be read from {}", .0.join(", "))]
+    NoSitemap(Vec<String>),
+
+    /// A `RequestHook` or `ResponseHook` failed on the URL.
+    #[error("Hook failed for {url}")]
+    Hook {
+        url: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// A `RequestHook` skipped the URL; it isn't recorded as a failure.
+    #[error("Skipped by a request hook: {0}")]
+    Skipped(String),
+
+    /// A `ResultSink` failed; the crawl was stopped at that point.
+    #[error("Failed to write results")]
+    Sink(#[source] BoxError),
+}
+
+impl CrawlerError {
+    pub(super) fn config(message: &str, source: impl Into<BoxError>) -> Self {
+        CrawlerError::Config {
+            message: message.to_string(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// The message followed by its causes, like anyhow's `{:#}`.
+    pub(super) fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            report.push_str(": ");
+            report.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        report
+    }
+
+    /// The URL the error is about, if it's about one.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            CrawlerError::InvalidUrl { url, .. }
+            | CrawlerError::Robots { url, .. }
+            | CrawlerError::Timeout { url, .. }
+            | CrawlerError::TooManyRetries { url, .. }
+            | CrawlerError::Body { url, .. }
+            | CrawlerError::BodyStalled { url, .. }
+            | CrawlerError::RedirectLoop { url, .. }
+            | CrawlerError::TooManyRedirects { url, .. }
+            | CrawlerError::ParseError { url, .. }
+            | CrawlerError::Hook { url, .. } => Some(url),
+            CrawlerError::RobotsBlocked(url)
+            | CrawlerError::NoProxy(url)
+            | CrawlerError::Skipped(url) => Some(url),
+            CrawlerError::Config { .. } | CrawlerError::NoSitemap(_) | CrawlerError::Sink(_) => {
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use super::{CrawlResult, Crawler};
+use crate::urls::{ascii_host, registrable_domain};
+use serde::Serialize;
+use url::Url;
+
+/// A domain outside the crawl's scope that crawled pages link to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalDomain {
+    /// Registrable domain, e.g. `example.org` for links to `cdn.example.org`.
+    pub domain: String,
+    /// Links to the domain across all pages, counting each distinct link once per page.
+    pub links: usize,
+    /// Pages linking to the domain, in crawl order.
+    pub pages: Vec<String>,
+}
+
+impl ExternalDomain {
+    pub fn format_output(&self) -> String {
+        format!(
+            "{}: {} link(s) from {} page(s)",
+            self.domain,
+            self.links,
+            self.pages.len()
+        )
+    }
+}
+
+impl Crawler {
+    /// Count the page's `external_links` towards the domains they point to.
+    pub(super) fn record_external_links(&self, result: &CrawlResult) {
+        for link in &result.external_links {
+            let Some(host) = Url::parse(link)
+                .ok()
+                .and_then(|url| url.host_str().map(ascii_host))
+            else {
+                continue;
+            };
+            let domain = registrable_domain(&host);
+            let mut entry = self
+                .external_domains
+                .entry(domain.clone())
+                .or_insert_with(|| ExternalDomain {
+                    domain,
+                    links: 0,
+                    pages: Vec::new(),
+                });
+            entry.links += 1;
+            // A page's links are recorded together, so it can only be the last one
+            if entry.pages.last() != Some(&result.url) {
+                entry.pages.push(result.url.clone());
+            }
+        }
+    }
+
+    /// External domains linked from the crawl so far, most linked first.
+    pub fn get_external_domains(&self) -> Vec<ExternalDomain> {
+        let mut domains: Vec<_> = self
+            .external_domains
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        domains.sort_by(|a, b| b.links.cmp(&a.links).then_with(|| a.domain.cmp(&b.domain)));
+        domains
+    }
+}
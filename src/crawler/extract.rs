@@ -0,0 +1,129 @@
+use crate::text::collapse_whitespace;
+use scraper::{ElementRef, Html, Selector};
+
+/// Elements whose content is never readable page text.
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "head", "nav", "header",
+    "footer", "aside", "form", "button", "select",
+];
+
+/// Elements that start a new line of text.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Readable text of a page: the `<main>` element (or the only `<article>`, or `<body>`)
+/// without scripts, styles and navigation boilerplate, one line per block element.
+/// `None` when the page has no text.
+pub(super) fn extract_text(document: &Html) -> Option<String> {
+    let root = content_root(document)?;
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    walk(root, &mut lines, &mut line);
+    flush(&mut lines, &mut line);
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let select = |css: &str| {
+        let selector = Selector::parse(css).ok()?;
+        let mut matches = document.select(&selector);
+        let first = matches.next()?;
+        matches.next().is_none().then_some(first)
+    };
+    select("main")
+        .or_else(|| select("article"))
+        .or_else(|| select("body"))
+        .or_else(|| Some(document.root_element()))
+}
+
+fn walk(element: ElementRef, lines: &mut Vec<String>, line: &mut String) {
+    let name = element.value().name();
+    if SKIPPED.contains(&name) || element.value().attr("hidden").is_some() {
+        return;
+    }
+    let block = BLOCKS.contains(&name);
+    if block || name == "br" {
+        flush(lines, line);
+    }
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            line.push_str(text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            walk(child, lines, line);
+        }
+    }
+    if block {
+        flush(lines, line);
+    } else if matches!(name, "td" | "th") {
+        // Cells of a row share its line
+        line.push(' ');
+    }
+}
+
+fn flush(lines: &mut Vec<String>, line: &mut String) {
+    let text = collapse_whitespace(line);
+    if !text.is_empty() {
+        lines.push(text);
+    }
+    line.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text_skips_boilerplate() {
+        let html = r#"
+            <html><head><title>Ignored</title><style>p { color: red }</style></head>
+            <body>
+                <nav><a href="/">Home</a> | <a href="/blog">Blog</a></nav>
+                <main>
+                    <h1>Release   notes</h1>
+                    <p>Version <b>2.0</b> is out.<br>Upgrade today.</p>
+                    <script>track("view");</script>
+                    <ul><li>Faster</li><li>Smaller</li></ul>
+                    <div hidden>Secret</div>
+                </main>
+                <footer>© 2024</footer>
+            </body></html>
+        "#;
+        let text = extract_text(&Html::parse_document(html)).unwrap();
+        assert_eq!(
+            text,
+            "Release notes\nVersion 2.0 is out.\nUpgrade today.\nFaster\nSmaller"
+        );
+
+        let bare = Html::parse_document("<p>Just <i>this</i></p><footer>x</footer>");
+        assert_eq!(extract_text(&bare).as_deref(), Some("Just this"));
+        assert_eq!(
+            extract_text(&Html::parse_document("<script>x</script>")),
+            None
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use crate::text::collapse_whitespace;
+use log::debug;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use url::Url;
+
+/// OpenGraph namespaces kept besides `og:`, e.g. `article:published_time`.
+const OPEN_GRAPH_PREFIXES: &[&str] = &[
+    "og:", "article:", "book:", "profile:", "product:", "music:", "video:",
+];
+
+/// Structured data embedded in a page, only populated with `extract_structured_data`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredData {
+    /// Each `<script type="application/ld+json">` block that parses, as written.
+    #[serde(default)]
+    pub json_ld: Vec<Value>,
+    /// OpenGraph properties by lowercase name, every value of repeated ones (several
+    /// `og:image`s) in document order.
+    #[serde(default)]
+    pub open_graph: BTreeMap<String, Vec<String>>,
+    /// `twitter:*` card metadata by lowercase name, first value wins.
+    #[serde(default)]
+    pub twitter: BTreeMap<String, String>,
+    /// Top-level `itemscope` items as JSON objects: `@type` and `@id` from `itemtype`
+    /// and `itemid`, then each property's value (an array when repeated). Nested items
+    /// are objects of the same shape.
+    #[serde(default)]
+    pub microdata: Vec<Value>,
+}
+
+impl StructuredData {
+    pub fn is_empty(&self) -> bool {
+        self.json_ld.is_empty()
+            && self.open_graph.is_empty()
+            && self.twitter.is_empty()
+            && self.microdata.is_empty()
+    }
+}
+
+/// JSON-LD, OpenGraph/Twitter card metadata and microdata of a page; links in
+/// microdata values are resolved against `base_url`.
+pub(super) fn extract_structured_data(document: &Html, base_url: &Url) -> StructuredData {
+    let mut data = StructuredData::default();
+
+    if let Ok(selector) = Selector::parse("script[type]") {
+        for script in document.select(&selector) {
+            let is_json_ld = script
+                .value()
+                .attr("type")
+                .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"));
+            if !is_json_ld {
+                continue;
+            }
+            let source = script.text().collect::<String>();
+            if source.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&source) {
+                Ok(value) => data.json_ld.push(value),
+                Err(e) => debug!("Skipping invalid JSON-LD block on {}: {}", base_url, e),
+            }
+        }
+    }
+
+    if let Ok(selector) = Selector::parse("meta[content]") {
+        for meta in document.select(&selector) {
+            let content = meta.value().attr("content").unwrap_or_default().trim();
+            // OpenGraph uses `property` and Twitter `name`, but both show up with either
+            let Some(name) = meta
+                .value()
+                .attr("property")
+                .or_else(|| meta.value().attr("name"))
+                .map(str::to_ascii_lowercase)
+            else {
+                continue;
+            };
+            if content.is_empty() {
+                continue;
+            }
+            if OPEN_GRAPH_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                data.open_graph
+                    .entry(name)
+                    .or_default()
+                    .push(content.to_string());
+            } else if name.starts_with("twitter:") {
+                data.twitter.entry(name).or_insert(content.to_string());
+            }
+        }
+    }
+
+    if let Ok(selector) = Selector::parse("[itemscope]:not([itemprop])") {
+        data.microdata = document
+            .select(&selector)
+            .map(|item| microdata_item(item, base_url))
+            .collect();
+    }
+
+    data
+}
+
+fn microdata_item(item: ElementRef, base_url: &Url) -> Value {
+    let mut object = Map::new();
+    if let Some(types) = item.value().attr("itemtype") {
+        let types: Vec<Value> = types
+            .split_ascii_whitespace()
+            .map(|kind| Value::String(kind.to_string()))
+            .collect();
+        if !types.is_empty() {
+            object.insert("@type".to_string(), one_or_many(types));
+        }
+    }
+    if let Some(id) = item.value().attr("itemid") {
+        object.insert("@id".to_string(), Value::String(id.trim().to_string()));
+    }
+
+    let mut properties: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    collect_properties(item, base_url, &mut properties);
+    for (name, values) in properties {
+        object.insert(name, one_or_many(values));
+    }
+    Value::Object(object)
+}
+
+/// Properties of the item `element` belongs to, stopping at nested items, whose
+/// properties are their own.
+fn collect_properties(
+    element: ElementRef,
+    base_url: &Url,
+    properties: &mut BTreeMap<String, Vec<Value>>,
+) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let nested = child.value().attr("itemscope").is_some();
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = if nested {
+                microdata_item(child, base_url)
+            } else {
+                Value::String(property_value(child, base_url))
+            };
+            for name in names.split_ascii_whitespace() {
+                properties
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(value.clone());
+            }
+        }
+        if !nested {
+            collect_properties(child, base_url, properties);
+        }
+    }
+}
+
+/// A property's value per the microdata spec: an attribute for elements that carry
+/// one (`src`, `href`, `datetime`, ...), otherwise the element's text. A `content`
+/// attribute wins on any element, as search engines read it that way.
+fn property_value(element: ElementRef, base_url: &Url) -> String {
+    let value = element.value();
+    let url_attr = match value.name() {
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => Some("src"),
+        "a" | "area" | "link" => Some("href"),
+        "object" => Some("data"),
+        _ => None,
+    };
+    if let Some(attr) = url_attr {
+        let raw = value.attr(attr).unwrap_or_default().trim();
+        return base_url
+            .join(raw)
+            .map(String::from)
+            .unwrap_or_else(|_| raw.to_string());
+    }
+    let attr = match value.name() {
+        "data" | "meter" => "value",
+        "time" => "datetime",
+        _ => "content",
+    };
+    match value.attr("content").or_else(|| value.attr(attr)) {
+        Some(content) => content.trim().to_string(),
+        None => collapse_whitespace(&element.text().collect::<String>()),
+    }
+}
+
+fn one_or_many(mut values: Vec<Value>) -> Value {
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_structured_data() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@type": "Product", "name": "Lamp"}</script>
+            <script type="application/ld+json">{ not json</script>
+            <meta property="og:title" content="Lamp">
+            <meta property="og:image" content="https://x.com/1.jpg">
+            <meta property="og:image" content="https://x.com/2.jpg">
+            <meta property="product:price:amount" content="19.99">
+            <meta name="twitter:card" content="summary">
+            <meta name="description" content="Not structured">
+            </head><body>
+            <div itemscope itemtype="https://schema.org/Product" itemid="urn:sku:42">
+              <h1 itemprop="name">Desk  Lamp</h1>
+              <img itemprop="image" src="/lamp.jpg">
+              <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                <meta itemprop="priceCurrency" content="EUR">
+                <span itemprop="price" content="19.99">19,99 €</span>
+              </div>
+              <span itemprop="color">red</span><span itemprop="color">blue</span>
+            </div>
+            </body></html>"#;
+        let base = Url::parse("https://x.com/shop/lamp").unwrap();
+        let data = extract_structured_data(&Html::parse_document(html), &base);
+
+        assert_eq!(data.json_ld.len(), 1);
+        assert_eq!(data.json_ld[0]["@type"], "Product");
+        assert_eq!(data.open_graph["og:title"], ["Lamp"]);
+        assert_eq!(
+            data.open_graph["og:image"],
+            ["https://x.com/1.jpg", "https://x.com/2.jpg"]
+        );
+        assert_eq!(data.open_graph["product:price:amount"], ["19.99"]);
+        assert_eq!(data.twitter["twitter:card"], "summary");
+        assert_eq!(data.open_graph.len() + data.twitter.len(), 4);
+
+        assert_eq!(
+            data.microdata,
+            [json!({
+                "@type": "https://schema.org/Product",
+                "@id": "urn:sku:42",
+                "name": "Desk Lamp",
+                "image": "https://x.com/lamp.jpg",
+                "offers": {
+                    "@type": "https://schema.org/Offer",
+                    "priceCurrency": "EUR",
+                    // `content` wins over the displayed text
+                    "price": "19.99"
+                },
+                "color": ["red", "blue"]
+            })]
+        );
+
+        let empty = extract_structured_data(&Html::parse_document("<p>hi</p>"), &base);
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,487 @@
+use super::fetcher::{FetchError, FetchRequest, FetchResponse};
+use super::hooks::RequestAction;
+use super::{note_host_alias, Crawler, CrawlerError, RedirectHop};
+use crate::inflight::ByteReservation;
+use crate::telemetry::{RetryDisposition, RetryRecord, TimelineEventKind};
+#[cfg(feature = "tls-info")]
+use crate::tls::CertificateInfo;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use log::{debug, warn};
+use regex::bytes::Regex;
+use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use tokio::time::{sleep, timeout};
+use url::Url;
+
+/// HTTP version the client speaks, with `http_version`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+    /// HTTP/2 when the server offers it during the TLS handshake, HTTP/1.1 otherwise
+    #[default]
+    Auto,
+    /// HTTP/1.1 only
+    Http1,
+    /// HTTP/2 only, without negotiating it first; also works over `http://` (h2c)
+    Http2,
+}
+
+impl Crawler {
+    /// Hold off while too many response bytes are already buffered.
+    pub(super) async fn wait_for_body_capacity(&self, url: &str) {
+        let Some(budget) = &self.byte_budget else {
+            return;
+        };
+
+        if budget.in_flight() >= budget.cap() {
+            debug!(
+                "Waiting for in-flight bytes ({}) to drop below {} before fetching {}",
+                budget.in_flight(),
+                budget.cap(),
+                url
+            );
+        }
+        budget.wait_for_capacity().await;
+    }
+
+    /// Fetch `url`, following up to `max_redirects` redirects when `follow_redirects` is
+    /// set. Returns the last response with the redirects that led to it. `headers` only
+    /// go with the request for `url` itself, as they're conditions on that URL's page.
+    pub(super) async fn fetch_following_redirects(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<(FetchResponse, Vec<RedirectHop>), CrawlerError> {
+        let mut chain: Vec<RedirectHop> = Vec::new();
+        let mut current = url.to_string();
+        let mut headers = Some(headers);
+        loop {
+            let response = self
+                .fetch_with_retries(
+                    &current,
+                    self.config.max_retries,
+                    headers.take().unwrap_or_default(),
+                )
+                .await?;
+            let status = response.status;
+            if !self.config.follow_redirects || !status.is_redirection() {
+                return Ok((response, chain));
+            }
+            let Some(target) = header_value(&response.headers, reqwest::header::LOCATION)
+                .and_then(|location| response.url.join(&location).ok())
+            else {
+                // 304s and redirects without a usable Location are pages of their own
+                return Ok((response, chain));
+            };
+
+            chain.push(RedirectHop {
+                url: current,
+                status_code: status.as_u16(),
+            });
+            if chain.iter().any(|hop| hop.url == target.as_str()) {
+                let mut hops: Vec<String> = chain.into_iter().map(|hop| hop.url).collect();
+                hops.push(target.to_string());
+                return Err(CrawlerError::RedirectLoop {
+                    url: url.to_string(),
+                    chain: hops,
+                });
+            }
+            if chain.len() > self.config.max_redirects {
+                return Err(CrawlerError::TooManyRedirects {
+                    url: url.to_string(),
+                    limit: self.config.max_redirects,
+                });
+            }
+            if self.config.detect_host_aliases {
+                note_host_alias(&self.host_aliases, status, &response.url, &target);
+            }
+            debug!("{} redirects ({}) to {}", response.url, status, target);
+            current = target.to_string();
+        }
+    }
+
+    pub(super) async fn fetch_with_retries(
+        &self,
+        url: &str,
+        max_retries: usize,
+        headers: HeaderMap,
+    ) -> Result<FetchResponse, CrawlerError> {
+        let parsed = Url::parse(url).map_err(|source| CrawlerError::InvalidUrl {
+            url: url.to_string(),
+            source,
+        })?;
+        let mut headers = headers;
+        if let Some(agents) = &self.user_agent_pool {
+            if let Ok(agent) = agents.pick(url).parse() {
+                headers.insert(reqwest::header::USER_AGENT, agent);
+            }
+        }
+        let mut last_error = None;
+        let mut backoff = Duration::ZERO;
+
+        for attempt in 0..=max_retries {
+            let mut request = FetchRequest {
+                url: parsed.clone(),
+                headers: headers.clone(),
+            };
+            if self.run_request_hooks(&mut request).await? == RequestAction::Skip {
+                return Err(CrawlerError::Skipped(url.to_string()));
+            }
+            match self.fetcher.fetch(request).await {
+                Ok(response) => {
+                    #[cfg(feature = "tls-info")]
+                    if self.config.tls_info {
+                        self.record_certificate(&response);
+                    }
+                    let status = response.status;
+                    let retryable =
+                        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+
+                    if status == StatusCode::TOO_MANY_REQUESTS
+                        || status == StatusCode::SERVICE_UNAVAILABLE
+                    {
+                        self.record_throttle(url, status.as_u16());
+                    }
+
+                    // Check if we should retry based on status code
+                    if retryable && attempt < max_retries {
+                        let requested = retry_after(&response);
+                        let delay = match requested {
+                            Some(wait) => wait.min(self.config.max_retry_after),
+                            None => Duration::from_secs(2_u64.pow(attempt as u32)),
+                        };
+                        warn!(
+                            "HTTP {} for {}, retrying in {:?}{} (attempt {}/{})",
+                            status,
+                            url,
+                            delay,
+                            if requested.is_some() {
+                                " per Retry-After"
+                            } else {
+                                ""
+                            },
+                            attempt + 1,
+                            max_retries + 1
+                        );
+                        sleep(delay).await;
+                        backoff += delay;
+                        continue;
+                    }
+
+                    if retryable {
+                        self.record_retries(url, attempt, backoff, RetryDisposition::GaveUp);
+                    } else if attempt > 0 {
+                        self.record_retries(url, attempt, backoff, RetryDisposition::Recovered);
+                    }
+
+                    return Ok(response);
+                }
+                Err(FetchError::NoProxy) => return Err(CrawlerError::NoProxy(url.to_string())),
+                Err(e) => {
+                    last_error = Some(e);
+                    if attempt < max_retries {
+                        let delay = Duration::from_secs(2_u64.pow(attempt as u32));
+                        warn!(
+                            "Request failed for {}, retrying in {:?} (attempt {}/{}): {}",
+                            url,
+                            delay,
+                            attempt + 1,
+                            max_retries + 1,
+                            last_error.as_ref().unwrap()
+                        );
+                        sleep(delay).await;
+                        backoff += delay;
+                    }
+                }
+            }
+        }
+
+        self.record_retries(url, max_retries, backoff, RetryDisposition::Failed);
+
+        let (url, attempts) = (url.to_string(), max_retries + 1);
+        match last_error {
+            Some(FetchError::Timeout) => Err(CrawlerError::Timeout { url, attempts }),
+            Some(source) => Err(CrawlerError::TooManyRetries {
+                url,
+                attempts,
+                source,
+            }),
+            None => unreachable!("every attempt either returns or records an error"),
+        }
+    }
+
+    /// Stream the body into memory, growing the in-flight reservation as chunks arrive.
+    /// Stops at `max_body_size`, dropping the rest of the response; the flag says
+    /// whether the body was cut there. Fails when no chunk arrives for `stall_timeout`.
+    pub(super) async fn read_body(
+        &self,
+        mut response: FetchResponse,
+        mut reservation: Option<&mut ByteReservation>,
+    ) -> Result<(Vec<u8>, bool), CrawlerError> {
+        let limit = self.config.max_body_size;
+        let mut body = Vec::new();
+        loop {
+            let next = match self.config.stall_timeout {
+                Some(idle) => timeout(idle, response.chunk()).await.map_err(|_| {
+                    CrawlerError::BodyStalled {
+                        url: response.url.to_string(),
+                        idle,
+                    }
+                })?,
+                None => response.chunk().await,
+            };
+            let Some(chunk) = next.map_err(|e| {
+                let url = response.url.to_string();
+                match e {
+                    FetchError::Timeout => CrawlerError::Timeout { url, attempts: 1 },
+                    source => CrawlerError::Body { url, source },
+                }
+            })?
+            else {
+                break;
+            };
+            body.extend_from_slice(&chunk);
+            if let Some(limit) = limit.filter(|&limit| body.len() > limit) {
+                debug!(
+                    "Body of {} exceeds {} bytes, truncating",
+                    response.url, limit
+                );
+                body.truncate(limit);
+                return Ok((body, true));
+            }
+            if let Some(reservation) = reservation.as_deref_mut() {
+                reservation.grow_to(body.len());
+            }
+        }
+        Ok((body, false))
+    }
+
+    /// Keep the certificate of the first HTTPS response from each host.
+    #[cfg(feature = "tls-info")]
+    fn record_certificate(&self, response: &FetchResponse) {
+        let Some(host) = response.url.host_str() else {
+            return;
+        };
+        if response.url.scheme() != "https" || self.certificates.contains_key(host) {
+            return;
+        }
+        let Some(der) = &response.peer_certificate else {
+            return;
+        };
+
+        let mut certificate = match CertificateInfo::from_der(host, der) {
+            Ok(certificate) => certificate,
+            Err(e) => {
+                warn!("{:#}", e);
+                return;
+            }
+        };
+        let now = SystemTime::now();
+        certificate.expiring_soon =
+            certificate.expires_within(self.config.cert_expiry_warn_days, now);
+        if certificate.expiring_soon {
+            warn!("Certificate for {}", certificate.format_output(now));
+        }
+        self.certificates.insert(host.to_string(), certificate);
+    }
+
+    fn record_throttle(&self, url: &str, status: u16) {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|host| self.host_aliases.canonical(host)))
+        else {
+            return;
+        };
+
+        if self.throttled_hosts.insert(host.clone()) {
+            self.timeline
+                .record(TimelineEventKind::HostThrottled { host, status });
+        }
+    }
+
+    fn record_retries(
+        &self,
+        url: &str,
+        retries: usize,
+        backoff: Duration,
+        disposition: RetryDisposition,
+    ) {
+        self.retry_records.insert(
+            url.to_string(),
+            RetryRecord {
+                url: url.to_string(),
+                attempts: retries + 1,
+                retries,
+                backoff,
+                disposition,
+            },
+        );
+    }
+}
+
+/// How long a 429 or 503 response asks us to wait before retrying.
+fn retry_after(response: &FetchResponse) -> Option<Duration> {
+    let status = response.status;
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let value = header_value(&response.headers, reqwest::header::RETRY_AFTER)?;
+    parse_retry_after(&value, SystemTime::now())
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date; a date in the past
+/// means retry now.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// All response headers by name, joining repeated headers with `, `. Values that
+/// aren't valid UTF-8 are decoded lossily.
+pub(super) fn response_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut joined: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        joined
+            .entry(name.as_str().to_string())
+            .and_modify(|values| {
+                values.push_str(", ");
+                values.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    joined
+}
+
+pub(super) fn header_value(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Bytes searched for a `<meta>` charset declaration, as in the WHATWG prescan.
+const META_PRESCAN_LEN: usize = 1024;
+
+/// Encoding of an HTML body: its BOM, the `Content-Type` charset, or a `<meta charset>`
+/// or `<meta http-equiv="Content-Type">` declaration near the top, in that order.
+/// Without any, UTF-8 when the body is valid UTF-8 and windows-1252 (what browsers
+/// assume) when it isn't.
+pub(super) fn detect_encoding(body: &[u8], declared: Option<&str>) -> &'static Encoding {
+    static META_CHARSET: OnceLock<Regex> = OnceLock::new();
+
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    if let Some(encoding) = declared.and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+    {
+        return encoding;
+    }
+
+    let meta = META_CHARSET.get_or_init(|| {
+        Regex::new(r#"(?i)<meta\b[^>]*?\bcharset\s*=\s*["']?\s*([a-z0-9_.:+-]+)"#)
+            .expect("the meta charset pattern is valid")
+    });
+    let head = &body[..body.len().min(META_PRESCAN_LEN)];
+    // A page can't really declare UTF-16 in ASCII-compatible markup; that means UTF-8
+    let declared_in_meta = meta
+        .captures(head)
+        .and_then(|captures| Encoding::for_label(&captures[1]))
+        .map(Encoding::output_encoding);
+    if let Some(encoding) = declared_in_meta {
+        return encoding;
+    }
+
+    // A body cut at the size limit may end mid-character
+    if body.len() - Encoding::utf8_valid_up_to(body) < 4 {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
+
+/// Decode a body with `encoding`; a BOM still wins.
+pub(super) fn decode_body(body: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
+
+pub(super) fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_body_uses_charset() {
+        let decode = |body: &[u8], declared| decode_body(body, detect_encoding(body, declared));
+        assert_eq!(decode(b"caf\xe9", Some("iso-8859-1")), "café");
+        assert_eq!(decode("café".as_bytes(), None), "café");
+
+        // <meta charset> and <meta http-equiv> when the header has none
+        let shift_jis = b"<meta charset=\"Shift_JIS\"><title>\x93\xfa\x96\x7b</title>";
+        assert_eq!(detect_encoding(shift_jis, None).name(), "Shift_JIS");
+        assert!(decode(shift_jis, None).contains("<title>日本</title>"));
+        let gbk = b"<META http-equiv=\"Content-Type\" content=\"text/html; charset=gbk\">";
+        assert_eq!(detect_encoding(gbk, None).name(), "GBK");
+        assert_eq!(detect_encoding(gbk, Some("utf-8")), UTF_8);
+        assert_eq!(detect_encoding(b"<meta charset=utf-16>", None), UTF_8);
+
+        // The BOM beats the header; undeclared non-UTF-8 falls back to windows-1252
+        assert_eq!(
+            decode(b"\xef\xbb\xbfcaf\xc3\xa9", Some("iso-8859-1")),
+            "café"
+        );
+        assert_eq!(
+            decode(b"<p>caf\xe9 cr\xe8me</p>", None),
+            "<p>café crème</p>"
+        );
+        // ...but a body truncated mid-character is still UTF-8
+        assert_eq!(detect_encoding(b"caf\xc3", None), UTF_8);
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds_and_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2026 07:28:00 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_charset_from_content_type() {
+        assert_eq!(
+            charset_from_content_type("text/html; charset=\"Shift_JIS\""),
+            Some("Shift_JIS".to_string())
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+    }
+}
@@ -0,0 +1,237 @@
+use super::error::BoxError;
+use super::proxy::ProxyPool;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::header::HeaderMap;
+use reqwest::tls::TlsInfo;
+use reqwest::{Client, StatusCode, Version};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use thiserror::Error;
+use url::Url;
+
+/// A page request, made once per attempt. Redirects come back as responses; the
+/// crawler follows them itself to record the chain.
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub url: Url,
+    /// Conditional and `User-Agent` headers the crawler wants sent.
+    pub headers: HeaderMap,
+}
+
+/// A response to a `FetchRequest`, its body still to be read.
+pub struct FetchResponse {
+    /// URL the response is for; the request's, as redirects aren't followed.
+    pub url: Url,
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    /// Size of the body as it will be read, when known up front.
+    pub content_length: Option<u64>,
+    pub remote_addr: Option<SocketAddr>,
+    /// The server's certificate in DER form, for `tls_info`.
+    pub peer_certificate: Option<Vec<u8>>,
+    /// The body, decompressed, in chunks as they arrive.
+    pub body: BoxStream<'static, Result<Bytes, FetchError>>,
+}
+
+impl FetchResponse {
+    /// A response with its whole body at hand, e.g. from a cache or a test double.
+    pub fn new(url: Url, status: StatusCode, headers: HeaderMap, body: impl Into<Bytes>) -> Self {
+        let body = body.into();
+        Self {
+            url,
+            status,
+            version: Version::HTTP_11,
+            headers,
+            content_length: Some(body.len() as u64),
+            remote_addr: None,
+            peer_certificate: None,
+            body: stream::once(async move { Ok(body) }).boxed(),
+        }
+    }
+
+    /// The next chunk of the body, `None` at its end.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, FetchError> {
+        self.body.next().await.transpose()
+    }
+}
+
+/// Why a page couldn't be fetched. Timeouts are told apart, as the crawler reports them
+/// on their own.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FetchError {
+    #[error("request timed out")]
+    Timeout,
+
+    /// Every proxy of `proxy_list` was evicted.
+    #[error("no working proxy left")]
+    NoProxy,
+
+    #[error(transparent)]
+    Other(BoxError),
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            FetchError::Timeout
+        } else {
+            FetchError::Other(e.into())
+        }
+    }
+}
+
+/// Transport for page fetches, registered on a crawler with `Crawler::with_fetcher`.
+/// The crawler handles retries, redirects, rate limits and body limits around it, so
+/// an implementation only makes single requests: through a headless browser, out of a
+/// cache, or from canned pages in tests. robots.txt, sitemaps and the HEAD checks of
+/// assets and inventories still go through the built-in HTTP client.
+// `async_trait` marks the boxed futures `#[must_use]` again, which newer clippy flags
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse, FetchError>;
+}
+
+/// The default fetcher: reqwest, through the proxies of `proxy_list` when there are any.
+pub(super) struct HttpFetcher {
+    /// Doesn't follow redirects.
+    client: Client,
+    proxy_pool: Option<Arc<ProxyPool>>,
+}
+
+impl HttpFetcher {
+    pub(super) fn new(client: Client, proxy_pool: Option<Arc<ProxyPool>>) -> Self {
+        Self { client, proxy_pool }
+    }
+}
+
+#[async_trait]
+impl Fetcher for HttpFetcher {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse, FetchError> {
+        // With a proxy list, each attempt can go through a different proxy
+        let proxy = match &self.proxy_pool {
+            Some(pool) => {
+                let index = pool.pick(request.url.as_str()).ok_or(FetchError::NoProxy)?;
+                Some((pool, index))
+            }
+            None => None,
+        };
+        let client = proxy.map_or(&self.client, |(pool, index)| pool.client(index));
+        let sent = client
+            .get(request.url)
+            .headers(request.headers)
+            .send()
+            .await;
+        if let Some((pool, index)) = proxy {
+            // Connection failures and 407s are the proxy's fault; timeouts may be the site's
+            match &sent {
+                Ok(response) if response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+                    pool.failed(index)
+                }
+                Ok(_) => pool.succeeded(index),
+                Err(e) if e.is_connect() => pool.failed(index),
+                Err(_) => {}
+            }
+        }
+        let response = sent?;
+        Ok(FetchResponse {
+            url: response.url().clone(),
+            status: response.status(),
+            version: response.version(),
+            headers: response.headers().clone(),
+            content_length: response.content_length(),
+            remote_addr: response.remote_addr(),
+            peer_certificate: response
+                .extensions()
+                .get::<TlsInfo>()
+                .and_then(|tls| tls.peer_certificate())
+                .map(<[u8]>::to_vec),
+            body: response
+                .bytes_stream()
+                .map(|chunk| chunk.map_err(FetchError::from))
+                .boxed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::{Crawler, CrawlerConfig, CrawlerError};
+    use std::collections::HashMap;
+
+    /// Serves canned pages by path and fails every other request.
+    struct CannedPages(HashMap<&'static str, (StatusCode, &'static str)>);
+
+    #[async_trait]
+    impl Fetcher for CannedPages {
+        async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse, FetchError> {
+            let Some(&(status, body)) = self.0.get(request.url.path()) else {
+                return Err(FetchError::Other("connection refused".into()));
+            };
+            let mut headers = HeaderMap::new();
+            if status.is_redirection() {
+                headers.insert(reqwest::header::LOCATION, body.parse().unwrap());
+            } else {
+                headers.insert(reqwest::header::CONTENT_TYPE, "text/html".parse().unwrap());
+            }
+            Ok(FetchResponse::new(request.url, status, headers, body))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crawl_through_custom_fetcher() {
+        let pages = CannedPages(HashMap::from([
+            (
+                "/",
+                (
+                    StatusCode::OK,
+                    r#"<title>Home</title><a href="/old">Old</a><a href="/gone">Gone</a>"#,
+                ),
+            ),
+            ("/old", (StatusCode::MOVED_PERMANENTLY, "/new")),
+            ("/new", (StatusCode::OK, "<title>New</title>")),
+        ]));
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_retries: 0,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_fetcher(pages);
+
+        let mut results = crawler
+            .crawl(vec!["https://canned.test/".to_string()])
+            .await
+            .unwrap();
+        results.sort_by(|a, b| a.url.cmp(&b.url));
+        let titles: Vec<_> = results
+            .iter()
+            .map(|result| (result.url.as_str(), result.title.as_deref()))
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                ("https://canned.test/", Some("Home")),
+                ("https://canned.test/old", Some("New")),
+            ]
+        );
+        assert_eq!(results[1].final_url, "https://canned.test/new");
+        assert_eq!(results[1].redirect_chain.len(), 1);
+
+        let failures = crawler.get_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].url, "https://canned.test/gone");
+        assert!(matches!(
+            failures[0].error.as_deref(),
+            Some(CrawlerError::TooManyRetries {
+                source: FetchError::Other(_),
+                ..
+            })
+        ));
+    }
+}
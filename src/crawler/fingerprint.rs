@@ -0,0 +1,72 @@
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Words per shingle; pairs of neighbors keep some word order in the fingerprint.
+const SHINGLE_WORDS: usize = 2;
+
+/// SHA-256 of `bytes` as lowercase hex.
+pub(super) fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(64);
+    for byte in Sha256::digest(bytes) {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+/// Charikar's SimHash over lowercased word shingles of `text`: similar texts get
+/// fingerprints that differ in few bits, unlike a cryptographic hash.
+pub(super) fn simhash(text: &str) -> u64 {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len()).max(1)) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &weight)| weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// FNV-1a, stable across runs and platforms unlike `DefaultHasher`.
+fn fnv1a(words: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in words.join(" ").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprints() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let article = "Rust 1.80 adds lazy cells to the standard library, lets ranges be \
+                       used in patterns with exclusive bounds and stabilizes many APIs.";
+        let print_view = format!("{} Print this page", article);
+        let other = "Our shop is closed on public holidays; orders ship the next day.";
+        let distance = |a: &str, b: &str| (simhash(a) ^ simhash(b)).count_ones();
+        assert_eq!(distance(article, article), 0);
+        assert!(distance(article, &print_view) < distance(article, other));
+        assert_eq!(simhash(""), 0);
+    }
+}
@@ -0,0 +1,863 @@
+use super::visited::VisitedSet;
+use super::CrawlResult;
+use crate::urls::{classify_scope, ScopeFilter, UrlScope};
+use log::debug;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use url::Url;
+
+/// Order in which the continuous scheduler hands out queued URLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlStrategy {
+    /// Breadth-first: every URL at one depth before any deeper one
+    #[default]
+    Bfs,
+    /// Depth-first: the deepest queued URLs first
+    Dfs,
+    /// Highest-scoring URLs first: short paths without query strings, plus boosts
+    Priority,
+}
+
+/// Adds `weight` to the priority score of URLs that `pattern` matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PriorityBoost {
+    pub pattern: String,
+    pub weight: i64,
+}
+
+/// Score lost per path segment in priority mode.
+const SEGMENT_PENALTY: i64 = 1;
+/// Score lost by URLs with a query string in priority mode.
+const QUERY_PENALTY: i64 = 3;
+
+/// Keeps a fixed fraction of discovered URLs. The decision is a hash of the URL
+/// and seed, so it doesn't depend on discovery order or concurrency.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Sampler {
+    rate: f64,
+    seed: u64,
+}
+
+impl Sampler {
+    pub(super) fn new(rate: f64, seed: u64) -> Self {
+        Self { rate, seed }
+    }
+
+    fn keeps(&self, url: &str) -> bool {
+        let unit = (seeded_hash(url, self.seed) >> 11) as f64 / (1_u64 << 53) as f64;
+        unit < self.rate
+    }
+}
+
+/// Stable across runs and platforms, unlike `std`'s `DefaultHasher`.
+pub(super) fn seeded_hash(url: &str, seed: u64) -> u64 {
+    // FNV-1a, then the splitmix64 finalizer to spread the bits
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in url.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    hash
+}
+
+/// URLs queued at one depth, one queue per host served round-robin, so a crawl
+/// seeded with many sites interleaves them instead of draining one host first.
+#[derive(Debug, Default)]
+struct DepthQueue {
+    by_host: HashMap<String, VecDeque<String>>,
+    /// Hosts with queued URLs, next to be served first.
+    rotation: VecDeque<String>,
+}
+
+impl DepthQueue {
+    fn is_empty(&self) -> bool {
+        self.rotation.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.by_host.values().map(VecDeque::len).sum()
+    }
+
+    fn push(&mut self, url: String) {
+        let host = Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let urls = self.by_host.entry(host).or_insert_with_key(|host| {
+            self.rotation.push_back(host.clone());
+            VecDeque::new()
+        });
+        urls.push_back(url);
+    }
+
+    /// Take a URL from the host at the front of the rotation, which then goes to the back.
+    fn pop(&mut self) -> Option<String> {
+        let host = self.rotation.pop_front()?;
+        let urls = self.by_host.get_mut(&host)?;
+        let url = urls.pop_front();
+        if urls.is_empty() {
+            self.by_host.remove(&host);
+        } else {
+            self.rotation.push_back(host);
+        }
+        url
+    }
+
+    /// Queued URLs in the order `pop` hands them out.
+    fn iter(&self) -> impl Iterator<Item = &String> {
+        let rounds = self.by_host.values().map(VecDeque::len).max().unwrap_or(0);
+        (0..rounds).flat_map(move |round| {
+            self.rotation
+                .iter()
+                .filter_map(move |host| self.by_host[host].get(round))
+        })
+    }
+
+    fn into_vec(mut self) -> Vec<String> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) -> usize {
+        let mut removed = 0;
+        self.by_host.retain(|_, urls| {
+            let before = urls.len();
+            urls.retain(|url| keep(url));
+            removed += before - urls.len();
+            !urls.is_empty()
+        });
+        let by_host = &self.by_host;
+        self.rotation.retain(|host| by_host.contains_key(host));
+        removed
+    }
+
+    /// Move matching URLs to the front of their host's queue and those hosts to the
+    /// front of the rotation, keeping relative order.
+    fn prioritize(&mut self, matches: impl Fn(&str) -> bool) -> usize {
+        let mut moved = 0;
+        for urls in self.by_host.values_mut() {
+            let (mut matching, rest): (VecDeque<String>, VecDeque<String>) = std::mem::take(urls)
+                .into_iter()
+                .partition(|url| matches(url));
+            moved += matching.len();
+            matching.extend(rest);
+            *urls = matching;
+        }
+        let by_host = &self.by_host;
+        let (mut first, rest): (VecDeque<String>, VecDeque<String>) =
+            std::mem::take(&mut self.rotation)
+                .into_iter()
+                .partition(|host| by_host[host].front().is_some_and(|url| matches(url)));
+        first.extend(rest);
+        self.rotation = first;
+        moved
+    }
+}
+
+impl Extend<String> for DepthQueue {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, urls: I) {
+        for url in urls {
+            self.push(url);
+        }
+    }
+}
+
+impl FromIterator<String> for DepthQueue {
+    fn from_iter<I: IntoIterator<Item = String>>(urls: I) -> Self {
+        let mut queue = Self::default();
+        queue.extend(urls);
+        queue
+    }
+}
+
+/// URLs waiting to be crawled, queued by rank and depth, lowest first, and within a
+/// queue served fairly across hosts. The rank comes from the strategy: the depth
+/// itself for BFS, so shallower URLs always go first.
+///
+/// A URL is claimed in the visited set when it's queued, so it's queued at most once
+/// however many pages link to it. Seeds and restored URLs are claimed by the caller.
+#[derive(Debug)]
+pub(super) struct Frontier {
+    pending: BTreeMap<(i64, usize), DepthQueue>,
+    strategy: CrawlStrategy,
+    boosts: Vec<(Regex, i64)>,
+    max_depth: Option<usize>,
+    sampler: Option<Sampler>,
+    /// When set, batches are ordered by a hash seeded with this value.
+    order_seed: Option<u64>,
+    scope: Option<ScopeFilter>,
+    max_links_per_page: Option<usize>,
+    /// Links queued since the last `take_journal`, kept only when checkpointing.
+    journal: Option<Vec<(String, usize)>>,
+    /// Set when URLs were dropped, which an append-only journal can't express.
+    dropped: bool,
+}
+
+impl Frontier {
+    pub(super) fn new(
+        seeds: Vec<String>,
+        max_depth: Option<usize>,
+        sampler: Option<Sampler>,
+    ) -> Self {
+        Self {
+            pending: if seeds.is_empty() {
+                BTreeMap::new()
+            } else {
+                BTreeMap::from([((0, 0), seeds.into_iter().collect())])
+            },
+            strategy: CrawlStrategy::Bfs,
+            boosts: Vec::new(),
+            max_depth,
+            sampler,
+            order_seed: None,
+            scope: None,
+            max_links_per_page: None,
+            journal: None,
+            dropped: false,
+        }
+    }
+
+    /// Hand out URLs in `strategy` order; `boosts` only apply to `Priority`.
+    pub(super) fn with_strategy(
+        mut self,
+        strategy: CrawlStrategy,
+        boosts: Vec<(Regex, i64)>,
+    ) -> Self {
+        self.strategy = strategy;
+        self.boosts = boosts;
+        for ((_, depth), urls) in std::mem::take(&mut self.pending) {
+            for url in urls.into_vec() {
+                self.queue(url, depth);
+            }
+        }
+        self
+    }
+
+    /// Queue key for `url` found at `depth`; lower keys are crawled first.
+    fn key(&self, url: &str, depth: usize) -> (i64, usize) {
+        let rank = match self.strategy {
+            CrawlStrategy::Bfs => depth as i64,
+            CrawlStrategy::Dfs => -(depth as i64),
+            CrawlStrategy::Priority => -self.score(url),
+        };
+        (rank, depth)
+    }
+
+    /// Priority score: minus one per path segment and three for a query string, plus the
+    /// weight of every boost matching the URL.
+    fn score(&self, url: &str) -> i64 {
+        let boost: i64 = self
+            .boosts
+            .iter()
+            .filter(|(pattern, _)| pattern.is_match(url))
+            .map(|(_, weight)| weight)
+            .sum();
+        let Ok(parsed) = Url::parse(url) else {
+            return boost;
+        };
+        let segments = parsed
+            .path_segments()
+            .map_or(0, |segments| segments.filter(|s| !s.is_empty()).count());
+        let query = if parsed.query().is_some() {
+            QUERY_PENALTY
+        } else {
+            0
+        };
+        boost - segments as i64 * SEGMENT_PENALTY - query
+    }
+
+    /// Only queue discovered links that `scope` allows.
+    pub(super) fn with_scope(mut self, scope: ScopeFilter) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub(super) fn scope(&self) -> Option<&ScopeFilter> {
+        self.scope.as_ref()
+    }
+
+    /// Queue at most `max` links from any one page.
+    pub(super) fn with_link_limit(mut self, max: usize) -> Self {
+        self.max_links_per_page = Some(max);
+        self
+    }
+
+    /// Remember queued links so checkpoints can append them instead of rewriting the frontier.
+    pub(super) fn with_journal(mut self) -> Self {
+        self.journal = Some(Vec::new());
+        self
+    }
+
+    /// Give every batch a fixed order so identical sites yield identical runs.
+    pub(super) fn with_deterministic_order(mut self, seed: u64) -> Self {
+        self.order_seed = Some(seed);
+        self
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Queued URLs.
+    pub(super) fn len(&self) -> usize {
+        self.pending.values().map(DepthQueue::len).sum()
+    }
+
+    /// Queue `url` at `depth`, e.g. when restoring a checkpoint; `url` must already be
+    /// claimed.
+    pub(super) fn queue(&mut self, url: String, depth: usize) {
+        let key = self.key(&url, depth);
+        self.pending.entry(key).or_default().push(url);
+    }
+
+    /// Every queued URL with its depth, in queue order.
+    pub(super) fn snapshot(&self) -> Vec<(String, usize)> {
+        self.pending
+            .iter()
+            .flat_map(|((_, depth), urls)| urls.iter().map(|url| (url.clone(), *depth)))
+            .collect()
+    }
+
+    /// Links queued by `enqueue_links` since the last call.
+    pub(super) fn take_journal(&mut self) -> Vec<(String, usize)> {
+        self.journal
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Whether URLs were dropped since the last call, so the next checkpoint must
+    /// rewrite the frontier instead of appending to the journal.
+    pub(super) fn take_dropped(&mut self) -> bool {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Up to `limit` queued URLs in the order `next_url` hands them out.
+    pub(super) fn peek(&self, limit: usize) -> Vec<(String, usize)> {
+        self.pending
+            .iter()
+            .filter(|((_, depth), _)| self.within_depth(*depth))
+            .flat_map(|((_, depth), urls)| urls.iter().map(move |url| (url, *depth)))
+            .take(limit)
+            .map(|(url, depth)| (url.clone(), depth))
+            .collect()
+    }
+
+    /// Remove every queued URL `pattern` matches; returns how many were removed. They
+    /// stay claimed, so pages found later don't queue them again.
+    pub(super) fn remove_matching(&mut self, pattern: &Regex) -> usize {
+        let mut removed = 0;
+        self.pending.retain(|_, urls| {
+            removed += urls.retain(|url| !pattern.is_match(url));
+            !urls.is_empty()
+        });
+        if removed > 0 {
+            self.dropped = true;
+        }
+        removed
+    }
+
+    /// Move queued URLs `pattern` matches ahead of the rest of their queue, keeping their
+    /// relative order. The strategy's order between queues still comes first, and
+    /// deterministic ordering re-sorts each batch, so this only affects the continuous
+    /// scheduler.
+    pub(super) fn prioritize_matching(&mut self, pattern: &Regex) -> usize {
+        self.pending
+            .values_mut()
+            .map(|urls| urls.prioritize(|url| pattern.is_match(url)))
+            .sum()
+    }
+
+    /// Depth of the shallowest queued URL.
+    pub(super) fn lowest_depth(&self) -> Option<usize> {
+        match self.strategy {
+            CrawlStrategy::Bfs => self.pending.keys().next().map(|(_, depth)| *depth),
+            _ => self.pending.keys().map(|(_, depth)| *depth).min(),
+        }
+    }
+
+    fn within_depth(&self, depth: usize) -> bool {
+        self.max_depth.map_or(true, |max_depth| depth <= max_depth)
+    }
+
+    /// Take the first queue, a whole depth level with BFS, skipping anything past
+    /// `max_depth`. Past `limit` URLs, the rest of the level stays queued.
+    pub(super) fn next_batch(&mut self, limit: Option<usize>) -> Vec<(String, usize)> {
+        let Some(((_, depth), level)) = self.pending.pop_first() else {
+            return Vec::new();
+        };
+        if !self.within_depth(depth) {
+            return Vec::new();
+        }
+
+        let mut level = level.into_vec();
+        if let Some(seed) = self.order_seed {
+            // Sorted first so URLs sharing a hash still come out in a fixed order
+            level.sort();
+            level.sort_by_cached_key(|url| seeded_hash(url, seed));
+        }
+
+        let limit = limit.unwrap_or(usize::MAX);
+        let rest = level.split_off(limit.min(level.len()));
+        for url in rest {
+            self.queue(url, depth);
+        }
+        level.into_iter().map(|url| (url, depth)).collect()
+    }
+
+    /// Take the next URL to crawl in strategy order.
+    pub(super) fn next_url(&mut self) -> Option<(String, usize)> {
+        let max_depth = self.max_depth;
+        while let Some(mut entry) = self.pending.first_entry() {
+            let (_, depth) = *entry.key();
+            if max_depth.is_some_and(|max_depth| depth > max_depth) {
+                entry.remove();
+                continue;
+            }
+            let Some(url) = entry.get_mut().pop() else {
+                entry.remove();
+                continue;
+            };
+            return Some((url, depth));
+        }
+        None
+    }
+
+    /// Queue a page's links for the next depth level, returning the ones queued. Each
+    /// link is claimed in `visited` as it's queued, so links already queued or crawled
+    /// are skipped. Seeds are always crawled; only discovered links are subject to
+    /// scope, sampling and the per-page cap.
+    pub(super) fn enqueue_links<'r>(
+        &mut self,
+        result: &'r CrawlResult,
+        visited: &VisitedSet,
+    ) -> Vec<&'r String> {
+        if let Some(max_depth) = self.max_depth {
+            if result.depth >= max_depth {
+                return Vec::new();
+            }
+        }
+        let mut links: Vec<&String> = result
+            .links
+            .iter()
+            .filter(|link| !visited.contains(link.as_str()))
+            .filter(|link| self.scope.as_ref().map_or(true, |scope| scope.allows(link)))
+            .filter(|link| self.sampler.map_or(true, |sampler| sampler.keeps(link)))
+            .collect();
+
+        if let Some(max) = self.max_links_per_page.filter(|max| links.len() > *max) {
+            // Keep links on the page's own host, then its subdomains, then everything else
+            if let Ok(page) = Url::parse(&result.url) {
+                links.sort_by_cached_key(|link| {
+                    Url::parse(link).map_or(UrlScope::External, |url| classify_scope(&url, &page))
+                });
+            }
+            debug!(
+                "Queueing {} of {} links from {}",
+                max,
+                links.len(),
+                result.url
+            );
+            links.truncate(max);
+        }
+        // Claimed only once they survive the cap, so a link another page keeps still gets
+        // queued; this also drops a link repeated on the page
+        links.retain(|link| visited.insert(link));
+
+        if !links.is_empty() {
+            let depth = result.depth + 1;
+            if let Some(journal) = self.journal.as_mut() {
+                journal.extend(links.iter().map(|link| (link.to_string(), depth)));
+            }
+            if self.strategy == CrawlStrategy::Bfs {
+                self.pending
+                    .entry((depth as i64, depth))
+                    .or_default()
+                    .extend(links.iter().map(|link| link.to_string()));
+            } else {
+                for link in &links {
+                    self.queue(link.to_string(), depth);
+                }
+            }
+        }
+        links
+    }
+}
+
+/// Tracks in-flight URLs per depth so the continuous scheduler can tell when a depth
+/// level is finished: nothing at or above it is queued or still being crawled.
+#[derive(Debug, Default)]
+pub(super) struct DepthProgress {
+    in_flight: BTreeMap<usize, usize>,
+    /// Depths that produced at least one page.
+    with_pages: BTreeSet<usize>,
+    next_to_report: usize,
+}
+
+impl DepthProgress {
+    pub(super) fn started(&mut self, depth: usize) {
+        *self.in_flight.entry(depth).or_default() += 1;
+    }
+
+    pub(super) fn finished(&mut self, depth: usize, produced_page: bool) {
+        if let Some(count) = self.in_flight.get_mut(&depth) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_flight.remove(&depth);
+            }
+        }
+        if produced_page {
+            self.with_pages.insert(depth);
+        }
+    }
+
+    /// Depths with pages that just became complete, given the shallowest queued depth.
+    pub(super) fn completed(&mut self, lowest_queued: Option<usize>) -> Vec<usize> {
+        let lowest_in_flight = self.in_flight.keys().next().copied();
+        let active = match (lowest_queued, lowest_in_flight) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b).unwrap_or(usize::MAX),
+        };
+        let Some(&deepest) = self.with_pages.last() else {
+            return Vec::new();
+        };
+
+        let mut completed = Vec::new();
+        while self.next_to_report < active && self.next_to_report <= deepest {
+            if self.with_pages.contains(&self.next_to_report) {
+                completed.push(self.next_to_report);
+            }
+            self.next_to_report += 1;
+        }
+        completed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(depth: usize, links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: "https://example.com/".to_string(),
+            status_code: 200,
+            links: links.iter().map(|l| l.to_string()).collect(),
+            depth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_frontier_respects_depth_and_visited() {
+        let visited = VisitedSet::default();
+        visited.insert("https://example.com/");
+        let mut frontier = Frontier::new(vec!["https://example.com/".to_string()], Some(1), None);
+
+        let batch = frontier.next_batch(None);
+        assert_eq!(batch, vec![("https://example.com/".to_string(), 0)]);
+        assert!(frontier.is_empty());
+
+        frontier.enqueue_links(
+            &result(0, &["https://example.com/", "https://example.com/a"]),
+            &visited,
+        );
+        let batch = frontier.next_batch(None);
+        assert_eq!(batch, vec![("https://example.com/a".to_string(), 1)]);
+
+        // Links found at max depth are not queued
+        frontier.enqueue_links(&result(1, &["https://example.com/b"]), &visited);
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    fn test_urls_linked_from_several_pages_are_queued_once() {
+        let visited = VisitedSet::default();
+        let mut frontier = Frontier::new(Vec::new(), None, None);
+        frontier.enqueue_links(&result(0, &["https://example.com/a"]), &visited);
+        let page = result(
+            0,
+            &[
+                "https://example.com/b",
+                "https://example.com/a",
+                "https://example.com/b",
+            ],
+        );
+        let queued = frontier.enqueue_links(&page, &visited);
+        assert_eq!(queued, ["https://example.com/b"]);
+        assert_eq!(frontier.len(), 2);
+        assert_eq!(frontier.peek(10).len(), 2);
+
+        let mut batch = frontier.next_batch(None);
+        batch.sort();
+        assert_eq!(
+            batch,
+            [
+                ("https://example.com/a".to_string(), 1),
+                ("https://example.com/b".to_string(), 1),
+            ]
+        );
+        assert_eq!(visited.len(), 2);
+    }
+
+    #[test]
+    fn test_next_batch_limit_leaves_the_rest_queued() {
+        let visited = VisitedSet::default();
+        let mut frontier = Frontier::new(Vec::new(), None, None);
+        let links = ["https://a.com/1", "https://a.com/2", "https://b.com/1"];
+        frontier.enqueue_links(&result(0, &links), &visited);
+
+        let batch = frontier.next_batch(Some(2));
+        assert_eq!(batch.len(), 2);
+        assert_eq!(frontier.len(), 1);
+        assert_eq!(frontier.next_batch(None).len(), 1);
+        assert_eq!(visited.len(), 3);
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic_per_seed() {
+        let links: Vec<String> = (0..1000)
+            .map(|i| format!("https://example.com/page/{}", i))
+            .collect();
+        let links: Vec<&str> = links.iter().map(String::as_str).collect();
+
+        let sampled = |seed: u64| {
+            let mut frontier = Frontier::new(Vec::new(), None, Some(Sampler::new(0.1, seed)));
+            frontier.enqueue_links(&result(0, &links), &VisitedSet::default());
+            frontier.next_batch(None)
+        };
+
+        let first = sampled(42);
+        assert_eq!(first, sampled(42));
+        assert_ne!(first, sampled(7));
+        assert!((50..150).contains(&first.len()), "kept {}", first.len());
+    }
+
+    #[test]
+    fn test_deterministic_order_ignores_discovery_order() {
+        let links = [
+            "https://example.com/c",
+            "https://example.com/a",
+            "https://example.com/b",
+            "https://example.com/a",
+        ];
+        let mut reversed = links;
+        reversed.reverse();
+
+        let ordered = |links: &[&str]| {
+            let mut frontier = Frontier::new(Vec::new(), None, None).with_deterministic_order(0);
+            frontier.enqueue_links(&result(0, links), &VisitedSet::default());
+            frontier.next_batch(None)
+        };
+
+        let batch = ordered(&links);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch, ordered(&reversed));
+    }
+
+    #[test]
+    fn test_next_url_goes_shallowest_first() {
+        let visited = VisitedSet::default();
+        let mut frontier = Frontier::new(vec!["https://example.com/".to_string()], Some(2), None);
+
+        assert_eq!(
+            frontier.next_url(),
+            Some(("https://example.com/".to_string(), 0))
+        );
+        frontier.enqueue_links(&result(1, &["https://example.com/deep"]), &visited);
+        frontier.enqueue_links(
+            &result(0, &["https://example.com/a", "https://example.com/b"]),
+            &visited,
+        );
+
+        let order: Vec<_> = std::iter::from_fn(|| frontier.next_url()).collect();
+        assert_eq!(
+            order,
+            vec![
+                ("https://example.com/a".to_string(), 1),
+                ("https://example.com/b".to_string(), 1),
+                ("https://example.com/deep".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dfs_and_priority_strategies() {
+        let seed = vec!["https://example.com/".to_string()];
+        let links = [
+            "https://example.com/blog/2020/01/02/old-post",
+            "https://example.com/search?q=x",
+            "https://example.com/products/",
+            "https://example.com/about",
+        ];
+        let crawl = |mut frontier: Frontier| {
+            let visited = VisitedSet::default();
+            let mut order = Vec::new();
+            while let Some((url, depth)) = frontier.next_url() {
+                if url == "https://example.com/" {
+                    frontier.enqueue_links(&result(depth, &links), &visited);
+                } else if url.ends_with("/about") {
+                    frontier.enqueue_links(&result(depth, &["https://example.com/team"]), &visited);
+                }
+                order.push(url.trim_start_matches("https://example.com").to_string());
+            }
+            order
+        };
+
+        let dfs =
+            Frontier::new(seed.clone(), None, None).with_strategy(CrawlStrategy::Dfs, Vec::new());
+        assert_eq!(
+            crawl(dfs),
+            [
+                "/",
+                "/blog/2020/01/02/old-post",
+                "/search?q=x",
+                "/products/",
+                "/about",
+                "/team"
+            ]
+        );
+
+        let boosts = vec![(Regex::new("/products/").unwrap(), 2)];
+        let priority =
+            Frontier::new(seed, Some(1), None).with_strategy(CrawlStrategy::Priority, boosts);
+        // /team is past max_depth
+        assert_eq!(
+            crawl(priority),
+            [
+                "/",
+                "/products/",
+                "/about",
+                "/search?q=x",
+                "/blog/2020/01/02/old-post"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_peek_drop_and_prioritize_queued_urls() {
+        let visited = VisitedSet::default();
+        let mut frontier = Frontier::new(Vec::new(), None, None);
+        frontier.enqueue_links(
+            &result(
+                0,
+                &[
+                    "https://example.com/a",
+                    "https://example.com/tag/x",
+                    "https://example.com/b",
+                    "https://example.com/tag/y",
+                    "https://example.com/a",
+                ],
+            ),
+            &visited,
+        );
+
+        let urls = |queued: Vec<(String, usize)>| -> Vec<String> {
+            queued.into_iter().map(|(url, _)| url).collect()
+        };
+        assert_eq!(
+            urls(frontier.peek(3)),
+            vec![
+                "https://example.com/a",
+                "https://example.com/tag/x",
+                "https://example.com/b"
+            ]
+        );
+
+        assert_eq!(frontier.prioritize_matching(&Regex::new("/b$").unwrap()), 1);
+        assert_eq!(frontier.remove_matching(&Regex::new("/tag/").unwrap()), 2);
+        assert!(frontier.take_dropped());
+        assert!(!frontier.take_dropped());
+        assert_eq!(
+            urls(frontier.peek(10)),
+            vec!["https://example.com/b", "https://example.com/a"]
+        );
+        assert_eq!(
+            frontier.next_url(),
+            Some(("https://example.com/b".to_string(), 1))
+        );
+    }
+
+    #[test]
+    fn test_hosts_are_served_round_robin() {
+        let seeds = [
+            "https://a.com/1",
+            "https://a.com/2",
+            "https://a.com/3",
+            "https://b.com/1",
+            "https://c.com/1",
+            "https://c.com/2",
+        ];
+        let mut frontier = Frontier::new(seeds.map(str::to_string).to_vec(), None, None);
+        let peeked = frontier.peek(10);
+
+        let order: Vec<_> = std::iter::from_fn(|| frontier.next_url()).collect();
+        assert_eq!(peeked, order);
+        let urls: Vec<&str> = order.iter().map(|(url, _)| url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.com/1",
+                "https://b.com/1",
+                "https://c.com/1",
+                "https://a.com/2",
+                "https://c.com/2",
+                "https://a.com/3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_depth_progress_reports_each_finished_depth_once() {
+        let mut progress = DepthProgress::default();
+        progress.started(0);
+        progress.finished(0, true);
+        // Depth 1 is still queued, so only depth 0 is done
+        assert_eq!(progress.completed(Some(1)), vec![0]);
+
+        progress.started(1);
+        progress.started(1);
+        progress.finished(1, true);
+        assert!(progress.completed(Some(2)).is_empty());
+        progress.finished(1, false);
+        assert_eq!(progress.completed(None), vec![1]);
+        assert!(progress.completed(None).is_empty());
+    }
+
+    #[test]
+    fn test_link_limit_prefers_unvisited_same_host_links() {
+        let visited = VisitedSet::default();
+        visited.insert("https://example.com/seen");
+        let mut frontier = Frontier::new(Vec::new(), None, None).with_link_limit(2);
+
+        frontier.enqueue_links(
+            &result(
+                0,
+                &[
+                    "https://other.com/x",
+                    "https://example.com/seen",
+                    "https://blog.example.com/y",
+                    "https://example.com/a",
+                ],
+            ),
+            &visited,
+        );
+        let batch: Vec<String> = frontier
+            .next_batch(None)
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(
+            batch,
+            vec!["https://example.com/a", "https://blog.example.com/y"]
+        );
+        // Links over the cap aren't claimed, so another page can still queue them
+        assert!(!visited.contains("https://other.com/x"));
+    }
+}
@@ -0,0 +1,179 @@
+use super::fetcher::FetchRequest;
+use super::{CrawlResult, Crawler, CrawlerError};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// What a `RequestHook` wants done with a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestAction {
+    /// Send it, with whatever the hook changed.
+    Send,
+    /// Don't fetch the URL at all; it's left out of the results without counting as a
+    /// failure.
+    Skip,
+}
+
+/// Runs before each page request is sent, retries and redirect hops included, and may
+/// change its URL or headers (e.g. to add fresh credentials) or skip the URL. Hooks
+/// run in the order they were registered with `Crawler::with_request_hook`; a failing
+/// hook fails the URL.
+// `async_trait` marks the boxed futures `#[must_use]` again, which newer clippy flags
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait RequestHook: Send + Sync {
+    async fn on_request(&self, request: &mut FetchRequest) -> Result<RequestAction>;
+}
+
+/// Runs on each page's result before its links are queued and it's yielded, and may
+/// change it: add extracted fields, drop links, clean up the title. Hooks run in the
+/// order they were registered with `Crawler::with_response_hook`; a failing hook fails
+/// the URL.
+// `async_trait` marks the boxed futures `#[must_use]` again, which newer clippy flags
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait ResponseHook: Send + Sync {
+    async fn on_response(&self, result: &mut CrawlResult) -> Result<()>;
+}
+
+impl Crawler {
+    /// Pass every page request through `hook` before it's sent.
+    pub fn with_request_hook(mut self, hook: impl RequestHook + 'static) -> Self {
+        self.request_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Pass every page result through `hook`.
+    pub fn with_response_hook(mut self, hook: impl ResponseHook + 'static) -> Self {
+        self.response_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Run the request hooks on `request`, stopping at the first that skips it.
+    pub(super) async fn run_request_hooks(
+        &self,
+        request: &mut FetchRequest,
+    ) -> Result<RequestAction, CrawlerError> {
+        for hook in &self.request_hooks {
+            let action = hook
+                .on_request(request)
+                .await
+                .map_err(|e| CrawlerError::Hook {
+                    url: request.url.to_string(),
+                    source: e.into(),
+                })?;
+            if action == RequestAction::Skip {
+                return Ok(RequestAction::Skip);
+            }
+        }
+        Ok(RequestAction::Send)
+    }
+
+    /// `crawl_single_url`, then the response hooks on its result.
+    pub(super) async fn crawl_url(
+        &self,
+        url: String,
+        depth: usize,
+        run_id: &str,
+    ) -> Result<CrawlResult, CrawlerError> {
+        let mut result = self.crawl_single_url(url, depth, run_id).await?;
+        for hook in &self.response_hooks {
+            hook.on_response(&mut result)
+                .await
+                .map_err(|e| CrawlerError::Hook {
+                    url: result.url.clone(),
+                    source: e.into(),
+                })?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::{CrawlerConfig, FetchError, FetchResponse, Fetcher};
+    use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE};
+    use reqwest::StatusCode;
+
+    /// Links to /private and /public, and titles pages with the credentials sent.
+    struct EchoAuth;
+
+    #[async_trait]
+    impl Fetcher for EchoAuth {
+        async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse, FetchError> {
+            let auth = request
+                .headers
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("anonymous");
+            let body = format!(
+                r#"<title>{}</title><a href="/private">P</a><a href="/public">P</a>"#,
+                auth
+            );
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, "text/html".parse().unwrap());
+            Ok(FetchResponse::new(
+                request.url,
+                StatusCode::OK,
+                headers,
+                body,
+            ))
+        }
+    }
+
+    struct SignAndSkipPrivate;
+
+    #[async_trait]
+    impl RequestHook for SignAndSkipPrivate {
+        async fn on_request(&self, request: &mut FetchRequest) -> Result<RequestAction> {
+            if request.url.path() == "/private" {
+                return Ok(RequestAction::Skip);
+            }
+            request
+                .headers
+                .insert(AUTHORIZATION, "Bearer token".parse()?);
+            Ok(RequestAction::Send)
+        }
+    }
+
+    struct UppercaseTitles;
+
+    #[async_trait]
+    impl ResponseHook for UppercaseTitles {
+        async fn on_response(&self, result: &mut CrawlResult) -> Result<()> {
+            result.title = result.title.as_deref().map(str::to_uppercase);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_and_response_hooks() {
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_fetcher(EchoAuth)
+        .with_request_hook(SignAndSkipPrivate)
+        .with_response_hook(UppercaseTitles);
+
+        let mut results = crawler
+            .crawl(vec!["https://hooks.test/".to_string()])
+            .await
+            .unwrap();
+        results.sort_by(|a, b| a.url.cmp(&b.url));
+        let pages: Vec<_> = results
+            .iter()
+            .map(|result| (result.url.as_str(), result.title.as_deref()))
+            .collect();
+        assert_eq!(
+            pages,
+            [
+                ("https://hooks.test/", Some("BEARER TOKEN")),
+                ("https://hooks.test/public", Some("BEARER TOKEN")),
+            ]
+        );
+        // Skipped URLs aren't failures
+        assert!(crawler.get_failures().is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use super::fingerprint::sha256_hex;
+use super::CrawlResult;
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A page's validators and the result crawled with them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct CachedPage {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub result: CrawlResult,
+}
+
+impl CachedPage {
+    /// `If-None-Match`/`If-Modified-Since` for a re-crawl of the page.
+    pub(super) fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let validators = [
+            (IF_NONE_MATCH, &self.etag),
+            (IF_MODIFIED_SINCE, &self.last_modified),
+        ];
+        for (name, value) in validators {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
+}
+
+/// HTTP cache for `http_cache_dir`: one JSON file per URL with the page's `ETag` and
+/// `Last-Modified` and its last result, so a later crawl can ask whether the page
+/// changed and reuse the result on a 304.
+pub(super) struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    pub(super) fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create HTTP cache {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// The cached page for `url`; unreadable entries count as missing.
+    pub(super) fn get(&self, url: &str) -> Option<CachedPage> {
+        let data = match fs::read(self.path(url)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                debug!("Ignoring HTTP cache entry for {}: {}", url, e);
+                return None;
+            }
+        };
+        match serde_json::from_slice::<CachedPage>(&data) {
+            // Keys are hashes, so check the entry really is this URL's
+            Ok(page) if page.result.url == url => Some(page),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Ignoring HTTP cache entry for {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    /// Save `result` with the validators in its response headers; a page without
+    /// `ETag` or `Last-Modified` can't be revalidated, so its entry is dropped instead.
+    pub(super) fn store(&self, result: &CrawlResult) {
+        let header = |name: &str| result.response_headers.get(name).cloned();
+        let page = CachedPage {
+            etag: header("etag"),
+            last_modified: header("last-modified"),
+            result: result.clone(),
+        };
+        if page.etag.is_none() && page.last_modified.is_none() {
+            self.remove(&result.url);
+            return;
+        }
+        if let Err(e) = self.write(&page) {
+            warn!("Failed to cache {}: {:#}", result.url, e);
+        }
+    }
+
+    pub(super) fn remove(&self, url: &str) {
+        match fs::remove_file(self.path(url)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                warn!("Failed to drop HTTP cache entry for {}: {}", url, e);
+            }
+            _ => {}
+        }
+    }
+
+    /// Write to a temporary file first so a crash mid-write never leaves a torn entry.
+    fn write(&self, page: &CachedPage) -> Result<()> {
+        let path = self.path(&page.result.url);
+        let tmp = path.with_extension("json.tmp");
+        let data = serde_json::to_vec(page).context("Failed to serialize cache entry")?;
+        fs::write(&tmp, data).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", sha256_hex(url.as_bytes())))
+    }
+}
@@ -0,0 +1,207 @@
+use super::fetch::{header_value, response_headers};
+use super::{CrawlResult, Crawler, CrawlerError};
+use crate::robots::RobotsDirectives;
+use crate::run::RunMetadata;
+use crate::sitemap;
+use crate::urls::{display_url, ScopeFilter};
+use futures::stream::{self, StreamExt};
+use log::{debug, error, info};
+use std::time::Instant;
+use url::Url;
+
+/// Sitemaps read at most for an inventory; enough for ~50M URLs at 50k per sitemap.
+const MAX_INVENTORY_SITEMAPS: usize = 1_000;
+
+impl Crawler {
+    /// List the seeds' pages from their sitemaps instead of crawling them: one result per
+    /// sitemap URL that's in scope and allowed by robots.txt, up to `max_pages`, at depth 0.
+    /// No page is fetched unless `head_check` is set, in which case each URL gets a single
+    /// HEAD request for its status and headers. Unchecked or unreachable URLs have
+    /// status 0.
+    pub async fn sitemap_inventory(
+        &self,
+        seeds: Vec<String>,
+        head_check: bool,
+    ) -> Result<Vec<CrawlResult>, CrawlerError> {
+        let run = RunMetadata::new(&self.config);
+        let run_id = run.run_id.clone();
+        info!("Sitemap inventory run {}", run_id);
+        *self.current_run.lock().unwrap() = Some(run);
+
+        let roots = sitemap::find_sitemaps(&self.client, &seeds).await;
+        let Some(urls) = sitemap::collect_urls(&self.client, &roots, MAX_INVENTORY_SITEMAPS).await
+        else {
+            return Err(CrawlerError::NoSitemap(roots));
+        };
+        info!("Sitemaps list {} URL(s)", urls.len());
+
+        let scope = ScopeFilter::new(self.config.scope, &seeds, self.host_aliases.clone());
+        let mut listed = Vec::new();
+        for url in urls {
+            if self.config.max_pages.is_some_and(|max| listed.len() >= max) {
+                break;
+            }
+            let Ok(parsed) = Url::parse(&url) else {
+                debug!("Skipping invalid sitemap URL {}", url);
+                continue;
+            };
+            if !scope.allows(&url) {
+                debug!("Skipping out-of-scope sitemap URL {}", url);
+                continue;
+            }
+            if let Err(e) = self.check_robots(&parsed).await {
+                debug!("Skipping sitemap URL: {}", e.report());
+                continue;
+            }
+            listed.push((url, parsed));
+        }
+
+        let results: Vec<CrawlResult> = stream::iter(listed)
+            .map(|(url, parsed)| self.inventory_entry(url, parsed, head_check, &run_id))
+            .buffered(self.config.max_concurrency.max(1))
+            .collect()
+            .await;
+        for result in &results {
+            self.emit(result).await;
+        }
+        if let Err(e) = self.robots_manager.save_cache() {
+            error!("{:#}", e);
+        }
+        self.flush_sinks().await;
+        Ok(results)
+    }
+
+    async fn inventory_entry(
+        &self,
+        url: String,
+        parsed: Url,
+        head_check: bool,
+        run_id: &str,
+    ) -> CrawlResult {
+        let mut result = CrawlResult {
+            final_url: url.clone(),
+            display_url: display_url(&url),
+            url,
+            run_id: run_id.to_string(),
+            ..Default::default()
+        };
+        if !head_check {
+            return result;
+        }
+
+        let _host_permit = self.acquire_host_permit(&parsed).await;
+        self.wait_for_host_slot(&parsed).await;
+        let start_time = Instant::now();
+        match self.client.head(parsed).send().await {
+            Ok(response) => {
+                result.status_code = response.status().as_u16();
+                result.final_url = response.url().to_string();
+                result.content_type =
+                    header_value(response.headers(), reqwest::header::CONTENT_TYPE);
+                result.content_length =
+                    header_value(response.headers(), reqwest::header::CONTENT_LENGTH)
+                        .and_then(|length| length.parse().ok());
+                result.response_headers = response_headers(response.headers());
+                result.noindex = result
+                    .response_headers
+                    .get("x-robots-tag")
+                    .is_some_and(|value| {
+                        RobotsDirectives::from_header(value, &self.config.user_agent).noindex
+                    });
+            }
+            Err(e) => debug!("HEAD {} failed: {}", result.url, e),
+        }
+        result.crawl_time = start_time.elapsed();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+    use crate::urls::CrawlScope;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_sitemap_inventory_reads_nested_sitemaps_without_fetching_pages() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let base = format!("http://{}", addr);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let server_base = base.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 1024];
+                let n = socket.read(&mut request).await.unwrap_or(0);
+                let line = String::from_utf8_lossy(&request[..n])
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                seen.lock().unwrap().push(line.clone());
+                let body = match line.split(' ').nth(1).unwrap_or_default() {
+                    "/robots.txt" => format!(
+                        "User-agent: *\nDisallow: /private\nSitemap: {}/index.xml\n",
+                        server_base
+                    ),
+                    // Lists itself and the same child twice
+                    "/index.xml" => format!(
+                        "<sitemapindex><sitemap><loc>{0}/pages.xml</loc></sitemap>\
+                         <sitemap><loc>{0}/index.xml</loc></sitemap>\
+                         <sitemap><loc>{0}/pages.xml</loc></sitemap></sitemapindex>",
+                        server_base
+                    ),
+                    "/pages.xml" => format!(
+                        "<urlset><url><loc>{0}/a</loc></url><url><loc>{0}/private/b</loc></url>\
+                         <url><loc>{0}/c</loc></url><url><loc>{0}/a</loc></url>\
+                         <url><loc>https://elsewhere.example/d</loc></url></urlset>",
+                        server_base
+                    ),
+                    "/a" => String::new(),
+                    _ => {
+                        let _ = socket
+                            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                            .await;
+                        continue;
+                    }
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let crawler = Crawler::new(CrawlerConfig {
+            scope: CrawlScope::SameHost,
+            ..Default::default()
+        })
+        .unwrap();
+        let seeds = vec![format!("{}/", base)];
+        let listed = crawler
+            .sitemap_inventory(seeds.clone(), false)
+            .await
+            .unwrap();
+        let urls: Vec<&str> = listed.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, [format!("{}/a", base), format!("{}/c", base)]);
+        assert!(listed.iter().all(|r| r.status_code == 0));
+        assert!(!requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|line| line.contains(" /a ") || line.contains(" /c ")));
+
+        let checked = crawler.sitemap_inventory(seeds, true).await.unwrap();
+        let statuses: Vec<u16> = checked.iter().map(|r| r.status_code).collect();
+        assert_eq!(statuses, [200, 404]);
+        assert!(requests
+            .lock()
+            .unwrap()
+            .contains(&"HEAD /a HTTP/1.1".to_string()));
+    }
+}
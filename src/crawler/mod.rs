@@ -0,0 +1,2294 @@
+mod assets;
+mod checkpoint;
+mod control;
+mod error;
+mod external;
+mod extract;
+mod extractors;
+mod fetch;
+mod fetcher;
+mod fingerprint;
+mod frontier;
+mod hooks;
+mod http_cache;
+mod inventory;
+mod observer;
+mod parse;
+mod politeness;
+mod proxy;
+mod rate_limit;
+mod results;
+mod sinks;
+mod sniff;
+mod soft404;
+mod user_agents;
+mod visited;
+
+pub use assets::{AssetKind, PageAsset};
+pub use checkpoint::CrawlState;
+pub use control::QueuedUrl;
+pub use error::CrawlerError;
+pub use external::ExternalDomain;
+pub use extractors::StructuredData;
+pub use fetch::HttpVersion;
+pub use fetcher::{FetchError, FetchRequest, FetchResponse, Fetcher};
+pub use frontier::{CrawlStrategy, PriorityBoost};
+pub use hooks::{RequestAction, RequestHook, ResponseHook};
+pub use observer::{CrawlObserver, CrawlSummary};
+pub use proxy::ProxyRotation;
+pub use results::{CrawlFailure, CrawlResult, PageVariant, RedirectHop};
+pub use soft404::{Soft404Reason, DEFAULT_SOFT_404_TITLES};
+pub use user_agents::UserAgentRotation;
+pub use visited::VisitedStore;
+
+#[cfg(feature = "warc")]
+use crate::archive::{HttpHead, WarcWriter};
+use crate::classify::{CategoryRule, Classifier};
+use crate::extraction::{ExtractionRule, Extractor};
+use crate::inflight::ByteBudget;
+use crate::language::detect_language;
+use crate::output::ResultSink;
+use crate::render::RenderWait;
+#[cfg(feature = "render")]
+use crate::render::{RenderOptions, Renderer};
+use crate::robots::{RobotsDirectives, RobotsManager, DEFAULT_ROBOTS_TTL};
+use crate::run::RunMetadata;
+use crate::telemetry::{RetryRecord, RetrySummary, Timeline, TimelineEvent, TimelineEventKind};
+use crate::tls::CertificateInfo;
+use crate::urls::{
+    ascii_host, display_url, CrawlScope, HostAliases, NormalizeOptions, ScopeFilter,
+};
+use anyhow::Result;
+use assets::AssetCheck;
+use async_stream::stream;
+use checkpoint::Checkpointer;
+use control::FrontierCommand;
+use dashmap::mapref::entry::Entry;
+use dashmap::{DashMap, DashSet};
+use fetch::{
+    charset_from_content_type, decode_body, detect_encoding, header_value, response_headers,
+};
+use fetcher::HttpFetcher;
+use fingerprint::sha256_hex;
+use frontier::{DepthProgress, Frontier, Sampler};
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use http_cache::HttpCache;
+#[cfg(feature = "render")]
+use log::warn;
+use log::{debug, error, info};
+use parse::ParsedPage;
+use proxy::ProxyPool;
+use rate_limit::HostRateLimiter;
+use regex::Regex;
+use reqwest::{Client, Proxy, StatusCode};
+use serde::Serialize;
+use sniff::{route_body, wants_body};
+use soft404::NotFoundTemplate;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, Notify, Semaphore};
+use url::Url;
+use user_agents::UserAgentPool;
+use uuid::Uuid;
+use visited::VisitedSet;
+
+/// Largest response body read by default, 5 MB; see `CrawlerConfig::max_body_size`.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 5 * 1024 * 1024;
+/// Default `expected_urls`: a Bloom filter of about 18 MB.
+pub const DEFAULT_EXPECTED_URLS: usize = 10_000_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawlerConfig {
+    pub max_concurrency: usize,
+    /// Requests in flight to any one host at most; `None` leaves only `max_concurrency`.
+    pub max_concurrency_per_host: Option<usize>,
+    /// Minimum interval between requests to the same host, on average.
+    pub rate_limit: Option<Duration>,
+    /// Requests a host may receive back-to-back before `rate_limit` spacing applies.
+    pub burst: u32,
+    pub max_retries: usize,
+    /// Longest wait honored from a `Retry-After` header on 429/503 responses.
+    pub max_retry_after: Duration,
+    /// Limit on a whole request, body included.
+    pub timeout: Duration,
+    /// Limit on connecting (TCP and TLS), so dead hosts fail fast; `None` leaves only
+    /// `timeout`.
+    pub connect_timeout: Option<Duration>,
+    /// Give up on a body when no data arrives for this long, so slow but steady
+    /// downloads can get a long `timeout` while stalled ones still fail.
+    pub stall_timeout: Option<Duration>,
+    /// Ask for gzip, brotli and deflate bodies and decode them transparently.
+    pub compression: bool,
+    pub http_version: HttpVersion,
+    /// Grow HTTP/2 flow-control windows with the connection's measured bandwidth
+    /// instead of using fixed ones.
+    pub http2_adaptive_window: bool,
+    /// How long an idle pooled connection is kept for reuse; `None` keeps reqwest's
+    /// default of 90 seconds.
+    pub pool_idle_timeout: Option<Duration>,
+    /// Idle connections kept per host; `None` leaves it unlimited.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Send TCP keepalive probes on idle connections at this interval.
+    pub tcp_keepalive: Option<Duration>,
+    pub user_agent: String,
+    /// User agents page requests rotate through; robots.txt is still checked against
+    /// `user_agent`.
+    pub user_agents: Vec<String>,
+    pub user_agent_rotation: UserAgentRotation,
+    pub max_pages: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub respect_robots: bool,
+    /// Longest time a robots.txt is cached before it's fetched again; its cache headers
+    /// can shorten this.
+    pub robots_ttl: Duration,
+    /// Load the robots.txt cache from this file and save it back after the crawl.
+    pub robots_cache_file: Option<PathBuf>,
+    pub follow_redirects: bool,
+    /// Redirects followed from one URL before giving up on it.
+    pub max_redirects: usize,
+    pub proxy: Option<String>,
+    /// Proxies page fetches rotate through, instead of `proxy`; proxies that fail
+    /// repeatedly are dropped from the rotation.
+    pub proxy_list: Vec<String>,
+    pub proxy_rotation: ProxyRotation,
+    pub keep_html: bool,
+    pub keep_html_max_kb: usize,
+    /// Only keep pages in these languages (ISO 639-1 codes); empty disables filtering.
+    pub languages: Vec<String>,
+    /// Treat `#/route` and `#!route` fragments as distinct pages (single-page apps).
+    pub spa_fragments: bool,
+    /// Hosts whose paths are case-insensitive (IIS/Windows); their paths are lowercased for dedup.
+    pub case_insensitive_hosts: Vec<String>,
+    /// Probe each host once with a case-swapped path to detect case-insensitivity automatically.
+    pub detect_case_insensitive: bool,
+    /// Cap on response body bytes buffered concurrently; new fetches wait while it's reached.
+    pub max_bytes_in_flight: Option<usize>,
+    /// Response bodies are cut at this many bytes, dropping the rest of the response.
+    pub max_body_size: Option<usize>,
+    /// Fraction (0, 1] of discovered URLs to crawl; seeds are always crawled.
+    pub sample_rate: Option<f64>,
+    /// Seed for sampling and deterministic ordering; a random one is picked (and recorded)
+    /// for sampling when unset.
+    pub seed: Option<u64>,
+    /// Reproducible runs: fixed frontier ordering and one request at a time per host.
+    pub deterministic: bool,
+    /// Groups of hosts serving the same site; the first host in each group is canonical.
+    pub host_aliases: Vec<Vec<String>>,
+    /// Treat `www.<host>` and `<host>` as the same site.
+    pub alias_www: bool,
+    /// Record an alias when a host permanently redirects to the same path on another host.
+    pub detect_host_aliases: bool,
+    /// Which discovered links to follow relative to the seed URLs.
+    pub scope: CrawlScope,
+    /// Order queued URLs are crawled in; ignored with `deterministic`, which always
+    /// crawls level by level.
+    pub strategy: CrawlStrategy,
+    /// Score adjustments for `CrawlStrategy::Priority`.
+    pub priority_boosts: Vec<PriorityBoost>,
+    /// Titles longer than this many characters are cut with a marker; 0 keeps them whole.
+    pub max_title_length: usize,
+    /// Queue at most this many links from a single page, preferring same-host links.
+    pub max_links_per_page: Option<usize>,
+    /// Periodically save crawl state here so an interrupted crawl can be resumed.
+    pub state_file: Option<PathBuf>,
+    /// Continue the crawl saved in `state_file` instead of starting from the seeds.
+    pub resume: bool,
+    /// How often to write `state_file` while crawling.
+    pub checkpoint_interval: Duration,
+    /// How visited URLs are remembered; only `VisitedStore::Exact` works with
+    /// `state_file`, as checkpoints list the URLs.
+    pub visited_store: VisitedStore,
+    /// URLs the crawl is expected to visit, which sizes the `VisitedStore::Bloom` filter.
+    pub expected_urls: usize,
+    /// Rules stamping a category on each page; the first matching rule wins.
+    pub category_rules: Vec<CategoryRule>,
+    /// Selectors whose matches fill `CrawlResult::extracted`.
+    pub extraction_rules: Vec<ExtractionRule>,
+    /// Record the certificate each HTTPS host presents. Needs the `tls-info` feature.
+    pub tls_info: bool,
+    /// Warn about recorded certificates that expire within this many days.
+    pub cert_expiry_warn_days: u64,
+    /// Extract each page's readable text into `CrawlResult::text`.
+    pub extract_text: bool,
+    /// Pull JSON-LD, OpenGraph/Twitter card metadata and microdata into
+    /// `CrawlResult::metadata`.
+    pub extract_structured_data: bool,
+    /// Record the images, scripts, stylesheets and media each page loads in
+    /// `CrawlResult::assets`, without crawling them.
+    pub include_assets: bool,
+    /// With `include_assets`, check each asset's status and size with a HEAD request.
+    pub verify_assets: bool,
+    /// Archive every fetched response to this WARC file, gzipped when it ends in `.gz`.
+    /// Needs the `warc` feature.
+    pub warc_path: Option<PathBuf>,
+    /// Fingerprint each page's readable text into `CrawlResult::simhash`.
+    pub simhash: bool,
+    /// Don't follow links from pages whose body matches an earlier page's (mirrors,
+    /// print views).
+    pub skip_duplicates: bool,
+    /// Media types (`text/html`, `image/*`, `*/*`) whose bodies are downloaded; empty
+    /// downloads anything but media, documents and archives.
+    pub allowed_content_types: Vec<String>,
+    /// Keep each page's `ETag`/`Last-Modified` and result in this directory, and re-crawl
+    /// it with a conditional request; a 304 reuses the stored result.
+    pub http_cache_dir: Option<PathBuf>,
+    /// Check successful HTML pages for signs of a not-found page and mark them in
+    /// `CrawlResult::soft_404`.
+    pub detect_soft_404: bool,
+    /// With `detect_soft_404`, bodies smaller than this many bytes count as soft 404s.
+    pub soft_404_min_body: usize,
+    /// With `detect_soft_404`, case-insensitive title fragments of not-found pages;
+    /// empty uses `DEFAULT_SOFT_404_TITLES`.
+    pub soft_404_titles: Vec<String>,
+    /// Render every successful HTML page in headless Chromium and parse the DOM its
+    /// scripts build instead of the HTML as served. Needs the `render` feature.
+    pub render_js: bool,
+    /// Regexes of URLs to render like `render_js` does, leaving other pages as served.
+    pub render_patterns: Vec<String>,
+    /// What a rendered page waits for after loading before its DOM is read: a fixed
+    /// delay, network idle, a selector or a JavaScript predicate.
+    pub render_wait: RenderWait,
+    /// Chrome or Chromium binary to render with; `None` looks for one.
+    pub chrome_path: Option<PathBuf>,
+    /// Run Chromium without its sandbox, which it needs to start as root.
+    pub chrome_no_sandbox: bool,
+}
+
+impl Default for CrawlerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 50,
+            max_concurrency_per_host: None,
+            rate_limit: None,
+            burst: 1,
+            max_retries: 3,
+            max_retry_after: Duration::from_secs(60),
+            timeout: Duration::from_secs(30),
+            connect_timeout: None,
+            stall_timeout: None,
+            compression: false,
+            http_version: HttpVersion::Auto,
+            http2_adaptive_window: false,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+            user_agent: "rustcrawler/0.1.0".to_string(),
+            user_agents: Vec::new(),
+            user_agent_rotation: UserAgentRotation::PerRequest,
+            max_pages: None,
+            max_depth: None,
+            respect_robots: true,
+            robots_ttl: DEFAULT_ROBOTS_TTL,
+            robots_cache_file: None,
+            follow_redirects: true,
+            max_redirects: 10,
+            proxy: None,
+            proxy_list: Vec::new(),
+            proxy_rotation: ProxyRotation::RoundRobin,
+            keep_html: false,
+            keep_html_max_kb: 512,
+            languages: Vec::new(),
+            spa_fragments: false,
+            case_insensitive_hosts: Vec::new(),
+            detect_case_insensitive: false,
+            max_bytes_in_flight: None,
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+            sample_rate: None,
+            seed: None,
+            deterministic: false,
+            host_aliases: Vec::new(),
+            alias_www: false,
+            detect_host_aliases: false,
+            scope: CrawlScope::All,
+            strategy: CrawlStrategy::Bfs,
+            priority_boosts: Vec::new(),
+            max_title_length: 200,
+            max_links_per_page: None,
+            state_file: None,
+            resume: false,
+            checkpoint_interval: Duration::from_secs(30),
+            visited_store: VisitedStore::Exact,
+            expected_urls: DEFAULT_EXPECTED_URLS,
+            category_rules: Vec::new(),
+            extraction_rules: Vec::new(),
+            tls_info: false,
+            cert_expiry_warn_days: 30,
+            extract_text: false,
+            extract_structured_data: false,
+            include_assets: false,
+            verify_assets: false,
+            warc_path: None,
+            simhash: false,
+            skip_duplicates: false,
+            allowed_content_types: Vec::new(),
+            http_cache_dir: None,
+            detect_soft_404: false,
+            soft_404_min_body: 256,
+            soft_404_titles: Vec::new(),
+            render_js: false,
+            render_patterns: Vec::new(),
+            render_wait: RenderWait::default(),
+            chrome_path: None,
+            chrome_no_sandbox: false,
+        }
+    }
+}
+
+pub struct Crawler {
+    client: Client,
+    /// Page fetches; they never follow redirects, as the crawler follows them by hand
+    /// to record the chain.
+    fetcher: Arc<dyn Fetcher>,
+    /// Clients for `proxy_list`, used by the default fetcher.
+    proxy_pool: Option<Arc<ProxyPool>>,
+    /// `user_agents`, sent with page fetches instead of `user_agent`.
+    user_agent_pool: Option<UserAgentPool>,
+    config: CrawlerConfig,
+    visited_urls: Arc<VisitedSet>,
+    pages_crawled: Arc<AtomicUsize>,
+    /// Size of the frontier of the crawl in progress, updated as it changes.
+    queued_urls: AtomicUsize,
+    retained_html_bytes: Arc<AtomicUsize>,
+    retry_records: Arc<DashMap<String, RetryRecord>>,
+    throttled_hosts: Arc<DashSet<String>>,
+    timeline: Arc<Timeline>,
+    semaphore: Arc<Semaphore>,
+    /// Per-host request slots, with `max_concurrency_per_host`.
+    host_semaphores: DashMap<String, Arc<Semaphore>>,
+    robots_manager: RobotsManager,
+    normalize_options: NormalizeOptions,
+    case_insensitive_hosts: Arc<DashSet<String>>,
+    probed_hosts: Arc<DashSet<String>>,
+    byte_budget: Option<Arc<ByteBudget>>,
+    current_run: Mutex<Option<RunMetadata>>,
+    host_aliases: Arc<HostAliases>,
+    rate_limiter: HostRateLimiter,
+    /// State loaded for `resume`, consumed by the next crawl.
+    resumed_state: Mutex<Option<CrawlState>>,
+    stop_requested: AtomicBool,
+    stop_notify: Notify,
+    classifier: Classifier,
+    extractor: Extractor,
+    /// `priority_boosts`, compiled.
+    priority_boosts: Vec<(Regex, i64)>,
+    /// Reaches the frontier of the crawl in progress, if any.
+    frontier_control: Mutex<Option<mpsc::UnboundedSender<FrontierCommand>>>,
+    /// Certificates seen per HTTPS host, with `tls_info`.
+    certificates: DashMap<String, CertificateInfo>,
+    /// Status and size of each asset URL checked, with `verify_assets`.
+    asset_checks: DashMap<String, AssetCheck>,
+    /// Each origin's not-found page, with `detect_soft_404`.
+    soft_404_templates: DashMap<String, NotFoundTemplate>,
+    /// `render_patterns`, compiled.
+    render_patterns: Vec<Regex>,
+    /// Browser for rendered pages, started on the first one; `None` if it failed to start.
+    #[cfg(feature = "render")]
+    renderer: tokio::sync::OnceCell<Option<Renderer>>,
+    /// Links outside the crawl's scope, by registrable domain.
+    external_domains: DashMap<String, ExternalDomain>,
+    #[cfg(feature = "warc")]
+    warc: Option<WarcWriter>,
+    http_cache: Option<HttpCache>,
+    /// First URL seen with each body hash, among 2xx pages.
+    content_hashes: DashMap<String, String>,
+    /// Added with `with_request_hook`, run before each page request.
+    request_hooks: Vec<Box<dyn RequestHook>>,
+    /// Added with `with_response_hook`, run on each page result.
+    response_hooks: Vec<Box<dyn ResponseHook>>,
+    /// Extra destinations for results, added with `with_sink`.
+    sinks: tokio::sync::Mutex<Vec<Box<dyn ResultSink>>>,
+    /// First sink write failure; it stops the crawl.
+    sink_error: Mutex<Option<anyhow::Error>>,
+    /// URLs the current crawl failed to fetch.
+    failures: Mutex<Vec<CrawlFailure>>,
+    /// Told about the events of the crawl in progress, by `crawl_with_observer`.
+    observer: Mutex<Option<Arc<dyn CrawlObserver>>>,
+}
+
+impl Crawler {
+    pub fn new(mut config: CrawlerConfig) -> Result<Self, CrawlerError> {
+        // Pin the seed so the run metadata is enough to reproduce the run
+        if config.seed.is_none() {
+            if config.deterministic {
+                config.seed = Some(0);
+            } else if config.sample_rate.is_some() {
+                config.seed = Some(Uuid::new_v4().as_u64_pair().0);
+            }
+        }
+
+        let new_proxy = |url: &str| {
+            Proxy::all(url).map_err(|e| CrawlerError::config("Failed to create proxy", e))
+        };
+        let build_client = |policy: reqwest::redirect::Policy, proxy: Option<Proxy>| {
+            let mut client_builder = Client::builder()
+                .timeout(config.timeout)
+                .user_agent(&config.user_agent)
+                .tls_info(config.tls_info)
+                .redirect(policy)
+                .gzip(config.compression)
+                .brotli(config.compression)
+                .deflate(config.compression)
+                .http2_adaptive_window(config.http2_adaptive_window)
+                .tcp_keepalive(config.tcp_keepalive);
+            client_builder = match config.http_version {
+                HttpVersion::Auto => client_builder,
+                HttpVersion::Http1 => client_builder.http1_only(),
+                HttpVersion::Http2 => client_builder.http2_prior_knowledge(),
+            };
+            if let Some(connect_timeout) = config.connect_timeout {
+                client_builder = client_builder.connect_timeout(connect_timeout);
+            }
+            if let Some(idle_timeout) = config.pool_idle_timeout {
+                client_builder = client_builder.pool_idle_timeout(idle_timeout);
+            }
+            if let Some(max_idle) = config.pool_max_idle_per_host {
+                client_builder = client_builder.pool_max_idle_per_host(max_idle);
+            }
+            if let Some(proxy) = proxy {
+                client_builder = client_builder.proxy(proxy);
+            }
+            client_builder
+                .build()
+                .map_err(|e| CrawlerError::config("Failed to build HTTP client", e))
+        };
+        // robots.txt, sitemaps and HEAD checks don't rotate; they use the first proxy
+        let proxy = config
+            .proxy
+            .as_deref()
+            .or(config.proxy_list.first().map(String::as_str))
+            .map(new_proxy)
+            .transpose()?;
+
+        let host_aliases = Arc::new(HostAliases::new(&config.host_aliases, config.alias_www));
+
+        let policy = if config.follow_redirects && config.detect_host_aliases {
+            alias_detecting_policy(host_aliases.clone(), config.max_redirects)
+        } else if config.follow_redirects {
+            reqwest::redirect::Policy::limited(config.max_redirects)
+        } else {
+            reqwest::redirect::Policy::none()
+        };
+        let client = build_client(policy, proxy.clone())?;
+        let page_client = build_client(reqwest::redirect::Policy::none(), proxy)?;
+        let proxy_pool = if config.proxy_list.is_empty() {
+            None
+        } else {
+            let proxies = config
+                .proxy_list
+                .iter()
+                .map(|url| {
+                    let client =
+                        build_client(reqwest::redirect::Policy::none(), Some(new_proxy(url)?))?;
+                    Ok((url.clone(), client))
+                })
+                .collect::<Result<_, CrawlerError>>()?;
+            Some(Arc::new(ProxyPool::new(proxies, config.proxy_rotation)))
+        };
+
+        #[cfg(feature = "warc")]
+        let warc = config
+            .warc_path
+            .as_deref()
+            .map(WarcWriter::create)
+            .transpose()
+            .map_err(|e| CrawlerError::config("Failed to open WARC output", e))?;
+        let http_cache = config
+            .http_cache_dir
+            .as_deref()
+            .map(HttpCache::open)
+            .transpose()
+            .map_err(|e| CrawlerError::config("Failed to open HTTP cache", e))?;
+
+        let mut robots_manager = RobotsManager::new(client.clone(), config.user_agent.clone())
+            .with_ttl(config.robots_ttl);
+        if let Some(path) = &config.robots_cache_file {
+            robots_manager = robots_manager
+                .with_cache_file(path)
+                .map_err(|e| CrawlerError::config("Failed to load robots.txt cache", e))?;
+        }
+        let classifier = Classifier::new(&config.category_rules)
+            .map_err(|e| CrawlerError::config("Invalid category rules", e))?;
+        let extractor = Extractor::new(&config.extraction_rules)
+            .map_err(|e| CrawlerError::config("Invalid extraction rules", e))?;
+
+        let priority_boosts = config
+            .priority_boosts
+            .iter()
+            .map(|boost| {
+                Regex::new(&boost.pattern)
+                    .map(|pattern| (pattern, boost.weight))
+                    .map_err(|e| CrawlerError::config("Invalid priority boost pattern", e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let render_patterns = config
+            .render_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| CrawlerError::config("Invalid render pattern", e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if cfg!(not(feature = "render")) && (config.render_js || !render_patterns.is_empty()) {
+            return Err(CrawlerError::Config {
+                message: "This build can't render JavaScript; rebuild with the `render` feature"
+                    .to_string(),
+                source: None,
+            });
+        }
+        if cfg!(not(feature = "warc")) && config.warc_path.is_some() {
+            return Err(CrawlerError::Config {
+                message: "This build can't write WARC archives; rebuild with the `warc` feature"
+                    .to_string(),
+                source: None,
+            });
+        }
+        if cfg!(not(feature = "tls-info")) && config.tls_info {
+            return Err(CrawlerError::Config {
+                message:
+                    "This build can't read TLS certificates; rebuild with the `tls-info` feature"
+                        .to_string(),
+                source: None,
+            });
+        }
+
+        if config.state_file.is_some() && config.visited_store != VisitedStore::Exact {
+            return Err(CrawlerError::Config {
+                message: "Checkpoints to a state file need the exact visited store".to_string(),
+                source: None,
+            });
+        }
+
+        let resumed_state = if config.resume {
+            let path = config
+                .state_file
+                .as_deref()
+                .ok_or_else(|| CrawlerError::Config {
+                    message: "Resuming requires a state file".to_string(),
+                    source: None,
+                })?;
+            let state = CrawlState::load(path)
+                .map_err(|e| CrawlerError::config("Failed to load crawl state", e))?;
+            Some(state)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            client,
+            fetcher: Arc::new(HttpFetcher::new(page_client, proxy_pool.clone())),
+            proxy_pool,
+            user_agent_pool: UserAgentPool::new(
+                config.user_agents.clone(),
+                config.user_agent_rotation,
+            ),
+            visited_urls: Arc::new(VisitedSet::new(config.visited_store, config.expected_urls)),
+            pages_crawled: Arc::new(AtomicUsize::new(0)),
+            queued_urls: AtomicUsize::new(0),
+            retained_html_bytes: Arc::new(AtomicUsize::new(0)),
+            retry_records: Arc::new(DashMap::new()),
+            throttled_hosts: Arc::new(DashSet::new()),
+            timeline: Arc::new(Timeline::new()),
+            semaphore: Arc::new(Semaphore::new(config.max_concurrency)),
+            host_semaphores: DashMap::new(),
+            robots_manager,
+            normalize_options: NormalizeOptions {
+                spa_fragments: config.spa_fragments,
+            },
+            case_insensitive_hosts: Arc::new(
+                config
+                    .case_insensitive_hosts
+                    .iter()
+                    .map(|host| ascii_host(host))
+                    .collect(),
+            ),
+            probed_hosts: Arc::new(DashSet::new()),
+            byte_budget: config
+                .max_bytes_in_flight
+                .map(|cap| Arc::new(ByteBudget::new(cap))),
+            current_run: Mutex::new(None),
+            host_aliases,
+            rate_limiter: HostRateLimiter::new(config.rate_limit, config.burst),
+            resumed_state: Mutex::new(resumed_state),
+            stop_requested: AtomicBool::new(false),
+            stop_notify: Notify::new(),
+            classifier,
+            extractor,
+            priority_boosts,
+            frontier_control: Mutex::new(None),
+            certificates: DashMap::new(),
+            asset_checks: DashMap::new(),
+            soft_404_templates: DashMap::new(),
+            render_patterns,
+            #[cfg(feature = "render")]
+            renderer: tokio::sync::OnceCell::new(),
+            external_domains: DashMap::new(),
+            #[cfg(feature = "warc")]
+            warc,
+            http_cache,
+            content_hashes: DashMap::new(),
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
+            sinks: tokio::sync::Mutex::new(Vec::new()),
+            sink_error: Mutex::new(None),
+            failures: Mutex::new(Vec::new()),
+            observer: Mutex::new(None),
+            config,
+        })
+    }
+
+    pub async fn crawl(&self, start_urls: Vec<String>) -> Result<Vec<CrawlResult>, CrawlerError> {
+        Ok(self.crawl_stream(start_urls).collect().await)
+    }
+
+    /// Like `crawl`, but yields each result as it completes instead of buffering
+    /// the whole crawl. Results arrive in completion order, or in the same fixed
+    /// order every run with `deterministic`. When resuming, results saved in the
+    /// state file come first.
+    pub fn crawl_stream(&self, start_urls: Vec<String>) -> impl Stream<Item = CrawlResult> + '_ {
+        stream! {
+            // A stop requested during an earlier crawl doesn't end this one
+            self.stop_requested.store(false, Ordering::Relaxed);
+            let resumed = self.resumed_state.lock().unwrap().take();
+            let mut run = RunMetadata::new(&self.config);
+            if let Some(state) = &resumed {
+                run.run_id = state.run_id.clone();
+            }
+            let run_id = run.run_id.clone();
+            info!("Crawl run {}", run_id);
+            *self.current_run.lock().unwrap() = Some(run);
+            self.failures.lock().unwrap().clear();
+
+            // Seeds given on the command line win; otherwise use the ones saved with the state
+            let start_urls = match &resumed {
+                Some(state) if start_urls.is_empty() => state.seeds.clone(),
+                _ => start_urls,
+            };
+            self.timeline.record(TimelineEventKind::CrawlStarted {
+                seeds: start_urls.len(),
+            });
+            let sampler = self.config.sample_rate.map(|rate| {
+                let seed = self.config.seed.unwrap_or_default();
+                info!(
+                    "Sampling {}% of discovered URLs (seed {})",
+                    rate * 100.0,
+                    seed
+                );
+                Sampler::new(rate, seed)
+            });
+            let scope = ScopeFilter::new(self.config.scope, &start_urls, self.host_aliases.clone());
+            let checkpointer = self.config.state_file.clone().map(|path| {
+                Checkpointer::new(
+                    path,
+                    self.config.checkpoint_interval,
+                    start_urls.clone(),
+                    resumed.is_some(),
+                )
+            });
+            // Queued URLs are claimed in `visited_urls`, seeds included
+            let seeds = if resumed.is_some() {
+                Vec::new()
+            } else {
+                start_urls
+                    .into_iter()
+                    .filter(|url| self.visited_urls.insert(url))
+                    .collect()
+            };
+            let mut frontier =
+                Frontier::new(seeds, self.config.max_depth, sampler).with_scope(scope);
+            if let Some(max) = self.config.max_links_per_page {
+                frontier = frontier.with_link_limit(max);
+            }
+            if checkpointer.is_some() {
+                frontier = frontier.with_journal();
+            }
+
+            let (control, commands) = mpsc::unbounded_channel();
+            *self.frontier_control.lock().unwrap() = Some(control);
+
+            let mut pages = 0;
+            if let Some(state) = resumed {
+                info!(
+                    "Resuming with {} result(s), {} visited and {} pending URL(s)",
+                    state.results.len(),
+                    state.visited.len(),
+                    state.pending.len()
+                );
+                for url in state.visited {
+                    self.visited_urls.insert(&url);
+                }
+                for (url, depth) in state.pending {
+                    if self.visited_urls.insert(&url) {
+                        frontier.queue(url, depth);
+                    }
+                }
+                self.pages_crawled.store(state.results.len(), Ordering::Relaxed);
+                for result in state.results {
+                    if let (Some(hash), None) = (&result.content_hash, &result.duplicate_of) {
+                        self.content_hashes.insert(hash.clone(), result.url.clone());
+                    }
+                    self.record_external_links(&result);
+                    pages += 1;
+                    self.emit(&result).await;
+                    yield result;
+                }
+            }
+
+            let results = if self.config.deterministic {
+                frontier = frontier.with_deterministic_order(self.config.seed.unwrap_or_default());
+                self.crawl_levels(frontier, checkpointer, commands, &run_id).right_stream()
+            } else {
+                frontier = frontier
+                    .with_strategy(self.config.strategy, self.priority_boosts.clone());
+                self.crawl_continuous(frontier, checkpointer, commands, &run_id).left_stream()
+            };
+            for await result in results {
+                pages += 1;
+                self.emit(&result).await;
+                yield result;
+            }
+            self.frontier_control.lock().unwrap().take();
+            #[cfg(feature = "warc")]
+            if let Some(Err(e)) = self.warc.as_ref().map(WarcWriter::flush) {
+                error!("{:#}", e);
+            }
+            if let Err(e) = self.robots_manager.save_cache() {
+                error!("{:#}", e);
+            }
+            self.flush_sinks().await;
+
+            self.timeline.record(TimelineEventKind::CrawlFinished { pages });
+        }
+    }
+
+    /// Schedule URLs as soon as a concurrency slot frees up, so one slow page never
+    /// holds back the rest of its depth level.
+    fn crawl_continuous<'a>(
+        &'a self,
+        mut frontier: Frontier,
+        mut checkpointer: Option<Checkpointer>,
+        mut commands: mpsc::UnboundedReceiver<FrontierCommand>,
+        run_id: &'a str,
+    ) -> impl Stream<Item = CrawlResult> + 'a {
+        stream! {
+            let mut in_flight = FuturesUnordered::new();
+            let mut active = HashSet::new();
+            let mut progress = DepthProgress::default();
+            let mut pages = 0;
+
+            while !self.stop_requested.load(Ordering::Relaxed) {
+                while in_flight.len() < self.config.max_concurrency
+                    && !self.page_budget_reached(in_flight.len())
+                {
+                    let Some((url, depth)) = frontier.next_url() else {
+                        break;
+                    };
+                    progress.started(depth);
+                    active.insert((url.clone(), depth));
+                    in_flight.push(async move {
+                        let result = self.crawl_url(url.clone(), depth, run_id).await;
+                        (url, depth, result)
+                    });
+                }
+                self.queued_urls.store(frontier.len(), Ordering::Relaxed);
+
+                let next = tokio::select! {
+                    next = in_flight.next() => next,
+                    _ = self.stop_notify.notified() => continue,
+                    Some(command) = commands.recv() => {
+                        command.apply(&mut frontier);
+                        continue;
+                    }
+                };
+                let Some((url, depth, result)) = next else {
+                    break;
+                };
+                if let Some(checkpointer) = checkpointer.as_mut() {
+                    checkpointer.finished(&url);
+                }
+                let accepted = self.accept_result(&mut frontier, &url, depth, result);
+                active.remove(&(url, depth));
+                progress.finished(depth, accepted.is_some());
+                if let Some(crawl_result) = accepted {
+                    if let Some(checkpointer) = checkpointer.as_mut() {
+                        checkpointer.record(&crawl_result);
+                    }
+                    pages += 1;
+                    yield crawl_result;
+                }
+
+                for depth in progress.completed(frontier.lowest_depth()) {
+                    self.timeline
+                        .record(TimelineEventKind::DepthCompleted { depth, pages });
+                }
+                if let Some(checkpointer) = checkpointer.as_mut().filter(|c| c.is_due()) {
+                    checkpointer.save(self, &mut frontier, active.iter(), run_id);
+                }
+            }
+
+            self.queued_urls.store(0, Ordering::Relaxed);
+            if let Some(max_pages) = self.config.max_pages.filter(|_| self.page_budget_reached(0)) {
+                info!("Reached maximum pages limit: {}", max_pages);
+            }
+            // URLs still in flight after a stop are saved as pending, so a resume retries them
+            if let Some(checkpointer) = checkpointer.as_mut() {
+                checkpointer.finish(self, &mut frontier, active.iter(), run_id);
+            }
+        }
+    }
+
+    /// Crawl one whole depth level at a time, one request at a time per host, so
+    /// the same site always yields the same results in the same order.
+    fn crawl_levels<'a>(
+        &'a self,
+        mut frontier: Frontier,
+        mut checkpointer: Option<Checkpointer>,
+        mut commands: mpsc::UnboundedReceiver<FrontierCommand>,
+        run_id: &'a str,
+    ) -> impl Stream<Item = CrawlResult> + 'a {
+        stream! {
+            let mut pages = 0;
+
+            loop {
+                // Levels are crawled as a whole, so queue changes apply between batches
+                while let Ok(command) = commands.try_recv() {
+                    command.apply(&mut frontier);
+                }
+                if frontier.is_empty() || self.stop_requested.load(Ordering::Relaxed) {
+                    break;
+                }
+                if self.page_budget_reached(0) {
+                    info!(
+                        "Reached maximum pages limit: {}",
+                        self.config.max_pages.unwrap_or_default()
+                    );
+                    break;
+                }
+
+                // Apply the depth limit, taking no more URLs than the page budget has left
+                let remaining = self.config.max_pages.map(|max| {
+                    max.saturating_sub(self.pages_crawled.load(Ordering::Relaxed))
+                });
+                let urls_to_crawl = frontier.next_batch(remaining);
+                self.queued_urls.store(frontier.len(), Ordering::Relaxed);
+                if urls_to_crawl.is_empty() {
+                    continue;
+                }
+
+                // The whole batch is done before the next checkpoint
+                if let Some(checkpointer) = checkpointer.as_mut() {
+                    for (url, _) in &urls_to_crawl {
+                        checkpointer.finished(url);
+                    }
+                }
+                let mut batch_depth = None;
+                let results = self.crawl_batch_per_host(urls_to_crawl.clone(), run_id).await;
+                for ((url, depth), result) in urls_to_crawl.into_iter().zip(results) {
+                    if let Some(crawl_result) = self.accept_result(&mut frontier, &url, depth, result) {
+                        if let Some(checkpointer) = checkpointer.as_mut() {
+                            checkpointer.record(&crawl_result);
+                        }
+                        batch_depth = Some(crawl_result.depth);
+                        pages += 1;
+                        yield crawl_result;
+                    }
+                }
+
+                if let Some(depth) = batch_depth {
+                    self.timeline
+                        .record(TimelineEventKind::DepthCompleted { depth, pages });
+                }
+                if let Some(checkpointer) = checkpointer.as_mut().filter(|c| c.is_due()) {
+                    checkpointer.save(self, &mut frontier, std::iter::empty(), run_id);
+                }
+            }
+
+            self.queued_urls.store(0, Ordering::Relaxed);
+            if let Some(checkpointer) = checkpointer.as_mut() {
+                checkpointer.finish(self, &mut frontier, std::iter::empty(), run_id);
+            }
+        }
+    }
+
+    /// Stop scheduling new URLs and end the crawl stream early. With a state file,
+    /// the final checkpoint keeps unfinished URLs so the crawl can be resumed. Only the
+    /// crawl in progress stops; the crawler can run another one afterwards.
+    pub fn request_stop(&self) {
+        self.stop_requested.store(true, Ordering::Relaxed);
+        self.stop_notify.notify_one();
+    }
+
+    /// The next `limit` URLs the running crawl will fetch, or `None` when no crawl is running.
+    pub async fn queued_urls(&self, limit: usize) -> Option<Vec<QueuedUrl>> {
+        self.control_frontier(|reply| FrontierCommand::Peek { limit, reply })
+            .await
+    }
+
+    /// Remove queued URLs matching `pattern` from the running crawl, returning how many
+    /// were removed. URLs discovered later are still queued.
+    pub async fn drop_queued(&self, pattern: Regex) -> Option<usize> {
+        self.control_frontier(|reply| FrontierCommand::Drop { pattern, reply })
+            .await
+    }
+
+    /// Crawl queued URLs matching `pattern` before others at the same depth, returning
+    /// how many were moved.
+    pub async fn prioritize_queued(&self, pattern: Regex) -> Option<usize> {
+        self.control_frontier(|reply| FrontierCommand::Prioritize { pattern, reply })
+            .await
+    }
+
+    async fn control_frontier<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> FrontierCommand,
+    ) -> Option<T> {
+        let (reply, response) = oneshot::channel();
+        let control = self.frontier_control.lock().unwrap().clone()?;
+        control.send(command(reply)).ok()?;
+        // Dropped unanswered if the crawl ends first
+        response.await.ok()
+    }
+
+    /// True once crawled plus in-flight pages reach `max_pages`.
+    fn page_budget_reached(&self, in_flight: usize) -> bool {
+        self.config
+            .max_pages
+            .is_some_and(|max| self.pages_crawled.load(Ordering::Relaxed) + in_flight >= max)
+    }
+
+    /// Record failures, apply the language filter, sort out external links and queue
+    /// the links of kept pages.
+    fn accept_result(
+        &self,
+        frontier: &mut Frontier,
+        url: &str,
+        depth: usize,
+        result: Result<CrawlResult, CrawlerError>,
+    ) -> Option<CrawlResult> {
+        let mut crawl_result = match result {
+            Ok(crawl_result) => crawl_result,
+            Err(CrawlerError::Skipped(_)) => {
+                debug!("Skipping {} (request hook)", url);
+                return None;
+            }
+            Err(e) => {
+                self.record_failure(url, depth, e);
+                return None;
+            }
+        };
+        if !self.passes_language_filter(&crawl_result) {
+            debug!(
+                "Skipping {} (language {:?} not in {:?})",
+                crawl_result.url, crawl_result.language, self.config.languages
+            );
+            return None;
+        }
+        // Only bodies of kept pages count as retained
+        if let Some(html) = &crawl_result.html {
+            self.retained_html_bytes
+                .fetch_add(html.len(), Ordering::Relaxed);
+        }
+
+        if let Some(scope) = frontier.scope() {
+            crawl_result.external_links = crawl_result
+                .links
+                .iter()
+                .filter(|link| !scope.is_internal(link))
+                .cloned()
+                .collect();
+            self.record_external_links(&crawl_result);
+        }
+
+        // Collect links for the next depth level
+        let queued = frontier.enqueue_links(&crawl_result, &self.visited_urls);
+        if let Some(observer) = self.observer() {
+            for url in queued {
+                observer.on_enqueue(url, crawl_result.depth + 1);
+            }
+        }
+        debug!("Crawled: {}", crawl_result.url);
+        Some(crawl_result)
+    }
+
+    fn record_failure(&self, url: &str, depth: usize, error: CrawlerError) {
+        let reason = error.report();
+        error!("Crawl error: {}", reason);
+        let attempts = match &error {
+            CrawlerError::Timeout { attempts, .. }
+            | CrawlerError::TooManyRetries { attempts, .. } => *attempts,
+            CrawlerError::InvalidUrl { .. }
+            | CrawlerError::RobotsBlocked(_)
+            | CrawlerError::Robots { .. } => 0,
+            // Failed after a response, possibly following retries
+            _ => self
+                .retry_records
+                .get(url)
+                .map_or(1, |record| record.attempts),
+        };
+        let failure = CrawlFailure {
+            url: url.to_string(),
+            depth,
+            reason,
+            attempts,
+            error: Some(Arc::new(error)),
+        };
+        if let Some(observer) = self.observer() {
+            observer.on_error(&failure);
+        }
+        self.failures.lock().unwrap().push(failure);
+    }
+
+    /// Whether `url` is rendered in the browser, with `render_js` or a matching
+    /// `render_patterns` rule.
+    fn wants_render(&self, url: &str) -> bool {
+        self.config.render_js
+            || self
+                .render_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(url))
+    }
+
+    /// The DOM of `url` once its scripts ran, or `html` as served when the browser
+    /// can't render it. The render counts as another request against the host's rate.
+    #[cfg(feature = "render")]
+    async fn render_page(&self, url: &str, html: String) -> String {
+        let renderer = self
+            .renderer
+            .get_or_init(|| async {
+                let options = RenderOptions {
+                    chrome_path: self.config.chrome_path.as_deref(),
+                    no_sandbox: self.config.chrome_no_sandbox,
+                    user_agent: &self.config.user_agent,
+                    timeout: self.config.timeout,
+                    wait: self.config.render_wait.clone(),
+                };
+                match Renderer::launch(options).await {
+                    Ok(renderer) => Some(renderer),
+                    Err(e) => {
+                        error!("{:#}; parsing pages as served", e);
+                        None
+                    }
+                }
+            })
+            .await;
+        let (Some(renderer), Ok(parsed_url)) = (renderer, Url::parse(url)) else {
+            return html;
+        };
+        self.wait_for_host_slot(&parsed_url).await;
+        match renderer.render(url).await {
+            Ok(rendered) => {
+                debug!("Rendered {} ({} bytes)", url, rendered.len());
+                rendered
+            }
+            Err(e) => {
+                warn!("{:#}; parsing the page as served", e);
+                html
+            }
+        }
+    }
+
+    #[cfg(not(feature = "render"))]
+    async fn render_page(&self, _url: &str, html: String) -> String {
+        // `Crawler::new` refuses rendering settings without the feature
+        html
+    }
+
+    /// Fetch and parse one URL, which was claimed in `visited_urls` when it was queued.
+    async fn crawl_single_url(
+        &self,
+        url: String,
+        depth: usize,
+        run_id: &str,
+    ) -> Result<CrawlResult, CrawlerError> {
+        let parsed_url = match Url::parse(&url) {
+            Ok(parsed_url) => parsed_url,
+            Err(source) => return Err(CrawlerError::InvalidUrl { url, source }),
+        };
+
+        // Wait for a slot on the host before taking a global one, so requests queued
+        // for a busy host don't hold up other hosts
+        let _host_permit = self.acquire_host_permit(&parsed_url).await;
+
+        // Acquire semaphore permit for concurrency control
+        let mut permit = self
+            .semaphore
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+
+        // Check robots.txt compliance
+        self.check_robots(&parsed_url).await?;
+
+        // Per-host rate limiting; give the permit back while waiting so other hosts keep
+        // going. The slot is reserved, so workers on the same host queue up one interval
+        // apart rather than racing for the next token when they wake
+        let wait = self.reserve_host_slot(&parsed_url);
+        if !wait.is_zero() {
+            drop(permit);
+            tokio::time::sleep(wait).await;
+            permit = self
+                .semaphore
+                .acquire()
+                .await
+                .expect("the semaphore is never closed");
+        }
+        let _permit = permit;
+        let start_time = Instant::now();
+
+        // Hold off while too many response bytes are already buffered
+        self.wait_for_body_capacity(&url).await;
+
+        // Ask whether the page changed since the cached copy, if there is one
+        let cached = self.http_cache.as_ref().and_then(|cache| cache.get(&url));
+        let conditional_headers = cached
+            .as_ref()
+            .map(|page| page.conditional_headers())
+            .unwrap_or_default();
+
+        // Perform HTTP request with retries, following redirects
+        let (response, redirect_chain) = self
+            .fetch_following_redirects(&url, conditional_headers)
+            .await?;
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status, cached) {
+            debug!("{} not modified since it was cached", url);
+            drop(response);
+            self.count_page();
+            return Ok(CrawlResult {
+                depth,
+                crawl_time: start_time.elapsed(),
+                run_id: run_id.to_string(),
+                not_modified: true,
+                ..cached.result
+            });
+        }
+        let status_code = response.status.as_u16();
+        let final_url = response.url.to_string();
+        let response_headers = response_headers(&response.headers);
+        let content_language = header_value(&response.headers, reqwest::header::CONTENT_LANGUAGE);
+        let content_type = header_value(&response.headers, reqwest::header::CONTENT_TYPE);
+        let charset = content_type.as_deref().and_then(charset_from_content_type);
+        let body_skipped = !wants_body(content_type.as_deref(), &self.config.allowed_content_types);
+        let declared_length = response.content_length.map(|len| len as usize);
+        // Skipped bodies aren't archived either
+        #[cfg(feature = "warc")]
+        let warc_head = self
+            .warc
+            .as_ref()
+            .filter(|_| !body_skipped)
+            .map(|_| HttpHead::from_response(&response));
+
+        // Account for the body against the in-flight budget until we're done with it
+        let mut reservation = self
+            .byte_budget
+            .as_ref()
+            .filter(|_| !body_skipped)
+            .map(|budget| budget.reserve());
+        if let (Some(reservation), Some(len)) = (reservation.as_mut(), declared_length) {
+            reservation.grow_to(len.min(self.config.max_body_size.unwrap_or(usize::MAX)));
+        }
+
+        let (body, body_truncated) = if body_skipped {
+            debug!(
+                "Not downloading the {} body of {}",
+                content_type.as_deref().unwrap_or_default(),
+                url
+            );
+            // Dropping the response closes the connection without reading the body
+            drop(response);
+            (Vec::new(), false)
+        } else {
+            self.read_body(response, reservation.as_mut()).await?
+        };
+        #[cfg(feature = "warc")]
+        if let (Some(warc), Some(head)) = (&self.warc, &warc_head) {
+            if let Err(e) = warc.write_response(&final_url, head, &body, body_truncated) {
+                error!("Failed to archive {}: {:#}", final_url, e);
+            }
+        }
+        let content_length = if body_skipped {
+            declared_length
+        } else {
+            Some(body.len())
+        };
+        let content_hash = (!body.is_empty()).then(|| sha256_hex(&body));
+        // A truncated body only says the prefix is the same
+        let duplicate_of = match &content_hash {
+            Some(hash) if (200..300).contains(&status_code) && !body_truncated => {
+                match self.content_hashes.entry(hash.clone()) {
+                    Entry::Occupied(first) => Some(first.get().clone()),
+                    Entry::Vacant(slot) => {
+                        slot.insert(url.clone());
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        // Trust the body over the header: binary data labeled as HTML is not parsed
+        let (is_html, mime_mismatch) = if body_skipped {
+            (false, false)
+        } else {
+            route_body(content_type.as_deref(), &body)
+        };
+        if mime_mismatch {
+            debug!(
+                "Body of {} doesn't match its Content-Type {:?}",
+                url, content_type
+            );
+        }
+        let encoding = is_html.then(|| detect_encoding(&body, charset.as_deref()));
+        let html_content = encoding
+            .map(|encoding| decode_body(&body, encoding))
+            .unwrap_or_default();
+        drop(body);
+        let html_content =
+            if is_html && (200..300).contains(&status_code) && self.wants_render(&final_url) {
+                self.render_page(&final_url, html_content).await
+            } else {
+                html_content
+            };
+
+        if is_html && self.config.detect_case_insensitive && (200..300).contains(&status_code) {
+            self.probe_case_insensitivity(&parsed_url, html_content.len())
+                .await;
+        }
+
+        let ParsedPage {
+            title,
+            mut links,
+            canonical,
+            hreflang,
+            category,
+            meta_description,
+            h1,
+            h2,
+            open_graph,
+            text,
+            simhash,
+            metadata,
+            extracted,
+            mut assets,
+            mixed_content,
+            insecure_links,
+            robots,
+        } = if is_html {
+            self.parse_html(&html_content, &parsed_url, status_code)
+                .map_err(|e| CrawlerError::ParseError {
+                    url: url.clone(),
+                    source: e.into(),
+                })?
+        } else {
+            ParsedPage::default()
+        };
+
+        let soft_404 = match simhash {
+            Some(text_hash) if self.config.detect_soft_404 && status_code == 200 => {
+                self.detect_soft_404(&parsed_url, title.as_deref(), html_content.len(), text_hash)
+                    .await
+            }
+            _ => None,
+        };
+        if let Some(reason) = soft_404 {
+            debug!("{} looks like a soft 404 ({})", url, reason.label());
+        }
+
+        if self.config.verify_assets && !assets.is_empty() {
+            self.verify_assets(&mut assets).await;
+        }
+
+        let robots = match response_headers.get("x-robots-tag") {
+            Some(value) => robots.merge(RobotsDirectives::from_header(
+                value,
+                &self.config.user_agent,
+            )),
+            None => robots,
+        };
+        if robots.nofollow && self.config.respect_robots {
+            debug!("Not following links from {} (nofollow)", url);
+            links.clear();
+        }
+        if let (Some(first), true) = (&duplicate_of, self.config.skip_duplicates) {
+            debug!("Not following links from {} (duplicate of {})", url, first);
+            links.clear();
+        }
+
+        let language = if !is_html || self.config.languages.is_empty() {
+            None
+        } else {
+            detect_language(
+                content_language.as_deref(),
+                encoding.map(|encoding| encoding.name()),
+                &html_content,
+            )
+            .map(|detection| {
+                debug!(
+                    "Language {} for {} via {:?}",
+                    detection.code, url, detection.source
+                );
+                detection.code
+            })
+        };
+
+        // Keep the HTML around for downstream processing if requested
+        let (html, html_truncated) = if is_html && self.config.keep_html {
+            self.retain_html(html_content)
+        } else {
+            (None, false)
+        };
+
+        self.count_page();
+        let crawl_time = start_time.elapsed();
+
+        let display_url = display_url(&url);
+        let result = CrawlResult {
+            url,
+            status_code,
+            title,
+            links,
+            depth,
+            crawl_time,
+            html,
+            html_truncated,
+            language,
+            run_id: run_id.to_string(),
+            canonical,
+            hreflang,
+            final_url,
+            content_type,
+            content_length,
+            response_headers,
+            category,
+            mime_mismatch,
+            display_url,
+            meta_description,
+            h1,
+            h2,
+            open_graph,
+            text,
+            noindex: robots.noindex,
+            content_hash,
+            simhash: simhash
+                .filter(|_| self.config.simhash)
+                .map(|hash| format!("{:016x}", hash)),
+            duplicate_of,
+            redirect_chain,
+            metadata,
+            extracted,
+            body_skipped,
+            body_truncated,
+            not_modified: false,
+            external_links: Vec::new(),
+            assets,
+            mixed_content,
+            insecure_links,
+            soft_404,
+        };
+        // Only whole pages answered directly can be revalidated later
+        if let Some(cache) = &self.http_cache {
+            if result.status_code == 200
+                && result.redirect_chain.is_empty()
+                && !result.body_truncated
+            {
+                cache.store(&result);
+            } else {
+                cache.remove(&result.url);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Count a crawled page towards `max_pages`.
+    fn count_page(&self) {
+        let pages = self.pages_crawled.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(max_pages) = self.config.max_pages {
+            if pages == (max_pages + 1) / 2 {
+                self.timeline
+                    .record(TimelineEventKind::BudgetHalfReached { pages, max_pages });
+            }
+        }
+    }
+
+    /// URLs the most recent (or in-progress) call to `crawl` couldn't fetch, in the
+    /// order they failed.
+    pub fn get_failures(&self) -> Vec<CrawlFailure> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// Response body bytes currently buffered, when `max_bytes_in_flight` is set.
+    pub fn get_bytes_in_flight(&self) -> usize {
+        self.byte_budget
+            .as_ref()
+            .map(|budget| budget.in_flight())
+            .unwrap_or(0)
+    }
+
+    /// Metadata for the most recent (or in-progress) call to `crawl`.
+    pub fn get_run_metadata(&self) -> Option<RunMetadata> {
+        self.current_run.lock().unwrap().clone()
+    }
+
+    pub fn robots_manager(&self) -> &RobotsManager {
+        &self.robots_manager
+    }
+
+    /// HTTP client with the crawl's user agent, timeout and proxy.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn get_crawled_count(&self) -> usize {
+        self.pages_crawled.load(Ordering::Relaxed)
+    }
+
+    /// URLs the crawl has claimed: crawled, being crawled or still queued.
+    pub fn get_visited_count(&self) -> usize {
+        self.visited_urls.len()
+    }
+
+    /// URLs waiting to be crawled in the crawl in progress.
+    pub fn get_queued_count(&self) -> usize {
+        self.queued_urls.load(Ordering::Relaxed)
+    }
+
+    /// Number of URLs the current crawl failed to fetch, without copying them.
+    pub fn get_failure_count(&self) -> usize {
+        self.failures.lock().unwrap().len()
+    }
+
+    /// Per-URL retry records for every URL that needed a retry or ultimately failed.
+    pub fn get_retry_records(&self) -> Vec<RetryRecord> {
+        self.retry_records
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Certificates recorded with `tls_info`, sorted by host.
+    pub fn get_certificates(&self) -> Vec<CertificateInfo> {
+        let mut certificates: Vec<_> = self
+            .certificates
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        certificates.sort_by(|a, b| a.host.cmp(&b.host));
+        certificates
+    }
+
+    pub fn get_retry_summary(&self) -> RetrySummary {
+        RetrySummary::from_records_by_host(&self.get_retry_records(), |host| {
+            self.host_aliases.canonical(host)
+        })
+    }
+
+    /// Configured and detected host aliases as `(alias, canonical)` pairs.
+    pub fn get_host_aliases(&self) -> Vec<(String, String)> {
+        self.host_aliases.entries()
+    }
+
+    pub fn get_timeline(&self) -> Vec<TimelineEvent> {
+        self.timeline.events()
+    }
+
+    /// Total bytes of HTML kept on the results this crawler has yielded.
+    pub fn get_retained_html_bytes(&self) -> usize {
+        self.retained_html_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Fetch pages with `fetcher` instead of the built-in HTTP client. `proxy_list`
+    /// only applies to the built-in one.
+    pub fn with_fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+        self.fetcher = Arc::new(fetcher);
+        self
+    }
+
+    /// Proxies of `proxy_list` that haven't been evicted for failing.
+    pub fn get_live_proxies(&self) -> Vec<String> {
+        self.proxy_pool
+            .as_ref()
+            .map(|pool| pool.live_urls())
+            .unwrap_or_default()
+    }
+}
+
+/// Follows up to `limit` redirects like `Policy::limited`, recording host aliases
+/// along the way.
+fn alias_detecting_policy(aliases: Arc<HostAliases>, limit: usize) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > limit {
+            return attempt.error("too many redirects");
+        }
+        if let Some(previous) = attempt.previous().last() {
+            note_host_alias(&aliases, attempt.status(), previous, attempt.url());
+        }
+        attempt.follow()
+    })
+}
+
+/// Record an alias when a permanent redirect keeps the path but moves to another host.
+fn note_host_alias(aliases: &HostAliases, status: StatusCode, from: &Url, to: &Url) {
+    let permanent = matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+    );
+    if let (true, Some(from_host), Some(to_host)) = (permanent, from.host_str(), to.host_str()) {
+        if from_host != to_host && from.path() == to.path() && aliases.add(from_host, to_host) {
+            info!("Detected host alias {} -> {}", from_host, to_host);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_server, Response};
+
+    #[tokio::test]
+    async fn test_crawler_creation() {
+        let config = CrawlerConfig {
+            max_concurrency: 10,
+            rate_limit: None,
+            max_retries: 3,
+            timeout: Duration::from_secs(30),
+            user_agent: "test-agent".to_string(),
+            max_pages: Some(100),
+            max_depth: Some(3),
+            respect_robots: true,
+            follow_redirects: true,
+            proxy: None,
+            ..Default::default()
+        };
+
+        let crawler = Crawler::new(config);
+        assert!(crawler.is_ok());
+    }
+
+    #[test]
+    fn test_render_settings() {
+        let render = |pattern: &str| {
+            Crawler::new(CrawlerConfig {
+                render_patterns: vec![pattern.to_string()],
+                ..Default::default()
+            })
+        };
+        assert!(render("(").is_err());
+        if cfg!(feature = "render") {
+            let crawler = render("/app/").unwrap();
+            assert!(crawler.wants_render("https://example.com/app/orders"));
+            assert!(!crawler.wants_render("https://example.com/about"));
+        } else {
+            assert!(render("/app/").is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_controls_need_a_running_crawl() {
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        assert_eq!(crawler.queued_urls(10).await, None);
+        assert_eq!(crawler.drop_queued(Regex::new(".").unwrap()).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_crawl_stream_skips_failed_pages() {
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 0,
+            respect_robots: false,
+            timeout: Duration::from_secs(2),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let results: Vec<_> = crawler
+            .crawl_stream(vec!["http://127.0.0.1:9/".to_string()])
+            .collect()
+            .await;
+        assert!(results.is_empty());
+        assert_eq!(crawler.get_visited_count(), 1);
+
+        let events = crawler.get_timeline();
+        assert!(matches!(
+            events.last().map(|e| &e.kind),
+            Some(TimelineEventKind::CrawlFinished { pages: 0 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_skip_duplicates_stops_mirrored_pages() {
+        let addr = test_server(|request| async move {
+            // The print view mirrors /a, so its relative link leads somewhere new
+            Response::html(match request.path.as_str() {
+                "/" => r#"<a href="/a">A</a> <a href="/print/a">Print</a>"#,
+                "/a" | "/print/a" => r#"<title>A</title><a href="more">More</a>"#,
+                _ => "<title>More</title>",
+            })
+        })
+        .await;
+
+        let crawl = |skip_duplicates| async move {
+            let crawler = Crawler::new(CrawlerConfig {
+                respect_robots: false,
+                skip_duplicates,
+                // One at a time in link order, so /a is always seen before its print view
+                max_concurrency: 1,
+                ..Default::default()
+            })
+            .unwrap();
+            crawler
+                .crawl(vec![format!("http://{}/", addr)])
+                .await
+                .unwrap()
+        };
+
+        let all = crawl(false).await;
+        assert_eq!(all.len(), 5);
+        let a = all.iter().find(|r| r.url.ends_with("/a")).unwrap();
+        let print = all.iter().find(|r| r.url.ends_with("/print/a")).unwrap();
+        assert_eq!(a.content_hash, print.content_hash);
+        assert!(a.duplicate_of.is_none());
+        assert_eq!(print.duplicate_of.as_deref(), Some(a.url.as_str()));
+
+        let skipped = crawl(true).await;
+        assert_eq!(skipped.len(), 4);
+        assert!(!skipped.iter().any(|r| r.url.ends_with("/print/more")));
+    }
+
+    #[tokio::test]
+    async fn test_max_body_size_truncates_endless_body() {
+        // No Content-Length and a body that never ends
+        let addr =
+            test_server(|_| async { Response::html("<title>Endless</title>").endless() }).await;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_body_size: Some(4096),
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].body_truncated);
+        assert_eq!(results[0].content_length, Some(4096));
+        assert_eq!(results[0].title.as_deref(), Some("Endless"));
+    }
+
+    #[tokio::test]
+    async fn test_external_links_are_classified_and_counted() {
+        let addr = test_server(|_| async {
+            Response::html(
+                r#"<a href="/about">About</a>
+                    <a href="https://www.partner.example/deal">Deal</a>
+                    <a href="https://shop.partner.example/">Shop</a>
+                    <a href="https://other.example/">Other</a>"#,
+            )
+        })
+        .await;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_depth: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        let seed = format!("http://{}/", addr);
+        let results = crawler.crawl(vec![seed.clone()]).await.unwrap();
+        assert_eq!(results[0].links.len(), 4);
+        assert_eq!(
+            results[0].external_links,
+            [
+                "https://other.example/",
+                "https://shop.partner.example/",
+                "https://www.partner.example/deal",
+            ]
+        );
+
+        let domains = crawler.get_external_domains();
+        assert_eq!(domains.len(), 2);
+        assert_eq!(domains[0].domain, "partner.example");
+        assert_eq!(domains[0].links, 2);
+        assert_eq!(domains[0].pages, [seed]);
+        assert_eq!(
+            domains[1].format_output(),
+            "other.example: 1 link(s) from 1 page(s)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_cache_reuses_unmodified_pages() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let full_responses = Arc::new(AtomicUsize::new(0));
+        let served = full_responses.clone();
+        let addr = test_server(move |request| {
+            let response = if request.header("if-none-match") == Some("\"v1\"") {
+                Response::new("304 Not Modified").header("ETag", "\"v1\"")
+            } else {
+                served.fetch_add(1, Ordering::SeqCst);
+                Response::html(r#"<title>Cached</title><a href="/other">x</a>"#)
+                    .header("ETag", "\"v1\"")
+            };
+            async { response }
+        })
+        .await;
+
+        let dir = std::env::temp_dir().join(format!("rustcrawler-http-cache-{}", Uuid::new_v4()));
+        let config = CrawlerConfig {
+            respect_robots: false,
+            max_depth: Some(0),
+            http_cache_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let seed = format!("http://{}/", addr);
+        let first = Crawler::new(config.clone())
+            .unwrap()
+            .crawl(vec![seed.clone()])
+            .await
+            .unwrap();
+        assert!(!first[0].not_modified);
+
+        let second = Crawler::new(config)
+            .unwrap()
+            .crawl(vec![seed])
+            .await
+            .unwrap();
+        assert!(second[0].not_modified);
+        assert_eq!(second[0].status_code, 200);
+        assert_eq!(second[0].title.as_deref(), Some("Cached"));
+        assert_eq!(second[0].links, first[0].links);
+        assert_ne!(second[0].run_id, first[0].run_id);
+        assert_eq!(full_responses.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_crawl_delay_spaces_concurrent_requests() {
+        let page_times = Arc::new(Mutex::new(Vec::new()));
+        let times = page_times.clone();
+        let addr = test_server(move |request| {
+            let body = if request.path == "/robots.txt" {
+                "User-agent: *\nCrawl-delay: 1\n"
+            } else {
+                times.lock().unwrap().push(Instant::now());
+                r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#
+            };
+            async move { Response::html(body) }
+        })
+        .await;
+
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        let times = page_times.lock().unwrap();
+        assert_eq!(times.len(), 4);
+        for pair in times.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(950));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_pages_limits_fetches_within_a_level() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = requests.clone();
+        let addr = test_server(move |_| {
+            served.fetch_add(1, Ordering::SeqCst);
+            let body: String = (0..20)
+                .map(|i| format!("<a href=\"/{}\">x</a>", i))
+                .collect();
+            async { Response::html(body) }
+        })
+        .await;
+
+        for deterministic in [false, true] {
+            requests.store(0, Ordering::SeqCst);
+            let crawler = Crawler::new(CrawlerConfig {
+                respect_robots: false,
+                max_pages: Some(5),
+                deterministic,
+                ..Default::default()
+            })
+            .unwrap();
+            let results = crawler
+                .crawl(vec![format!("http://{}/", addr)])
+                .await
+                .unwrap();
+            assert_eq!(results.len(), 5);
+            assert_eq!(requests.load(Ordering::SeqCst), 5);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_assets_checks_each_asset_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let logo_checks = Arc::new(AtomicUsize::new(0));
+        let checks = logo_checks.clone();
+        let addr = test_server(move |request| {
+            let response = match (request.method.as_str(), request.path.as_str()) {
+                ("HEAD", "/logo.png") => {
+                    checks.fetch_add(1, Ordering::SeqCst);
+                    Response::new("200 OK")
+                        .header("Content-Type", "image/png")
+                        .header("Content-Length", "1234")
+                }
+                ("HEAD", _) => Response::new("404 Not Found"),
+                _ => Response::html(
+                    r#"<img src="/logo.png"><script src="/gone.js"></script><a href="/b">b</a>"#,
+                ),
+            };
+            async { response }
+        })
+        .await;
+
+        let config = CrawlerConfig {
+            respect_robots: false,
+            max_depth: Some(1),
+            include_assets: true,
+            verify_assets: true,
+            ..Default::default()
+        };
+        let results = Crawler::new(config)
+            .unwrap()
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            let found: Vec<(AssetKind, Option<u16>, Option<u64>)> = result
+                .assets
+                .iter()
+                .map(|asset| (asset.kind, asset.status_code, asset.content_length))
+                .collect();
+            assert_eq!(
+                found,
+                [
+                    (AssetKind::Image, Some(200), Some(1234)),
+                    (AssetKind::Script, Some(404), Some(0)),
+                ]
+            );
+        }
+        assert_eq!(logo_checks.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_soft_404_matches_not_found_template() {
+        let addr = test_server(|request| async move {
+            // Every unknown path gets the same page, with a 200
+            Response::html(if request.path == "/" {
+                r#"<title>Shop</title><p>Fresh bread and pastries baked every morning.</p><a href="/gone">Old offer</a>"#
+            } else {
+                "<title>Shop</title><p>Sorry, we couldn't find what you were looking for.</p>"
+            })
+        })
+        .await;
+
+        let config = CrawlerConfig {
+            respect_robots: false,
+            detect_soft_404: true,
+            soft_404_min_body: 0,
+            ..Default::default()
+        };
+        let results = Crawler::new(config)
+            .unwrap()
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        let soft_404 = |path: &str| {
+            let url = format!("http://{}{}", addr, path);
+            results.iter().find(|r| r.url == url).unwrap().soft_404
+        };
+        assert_eq!(soft_404("/"), None);
+        assert_eq!(soft_404("/gone"), Some(Soft404Reason::NotFoundTemplate));
+        assert!(results.iter().all(|r| r.simhash.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_body_fails_before_timeout() {
+        let addr = test_server(|_| async {
+            Response::html("<title>Half")
+                .header("Content-Length", "1000")
+                .hold(Duration::from_secs(30))
+        })
+        .await;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_retries: 0,
+            stall_timeout: Some(Duration::from_millis(300)),
+            ..Default::default()
+        })
+        .unwrap();
+        let start = Instant::now();
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        let failures = crawler.get_failures();
+        assert!(
+            failures[0].reason.contains("stalled"),
+            "{}",
+            failures[0].reason
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compression_decodes_gzip_bodies() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let addr = test_server(|request| async move {
+            let gzip = request
+                .header("accept-encoding")
+                .is_some_and(|encodings| encodings.contains("gzip"));
+            if gzip {
+                let mut encoder = GzEncoder::new(Vec::new(), Default::default());
+                encoder.write_all(b"<title>Compressed</title>").unwrap();
+                Response::html(encoder.finish().unwrap()).header("Content-Encoding", "gzip")
+            } else {
+                Response::html("<title>Plain</title>")
+            }
+        })
+        .await;
+
+        for (compression, title) in [(false, "Plain"), (true, "Compressed")] {
+            let crawler = Crawler::new(CrawlerConfig {
+                respect_robots: false,
+                compression,
+                http_version: HttpVersion::Http1,
+                pool_max_idle_per_host: Some(0),
+                ..Default::default()
+            })
+            .unwrap();
+            let results = crawler
+                .crawl(vec![format!("http://{}/", addr)])
+                .await
+                .unwrap();
+            assert_eq!(results[0].title.as_deref(), Some(title));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrency_per_host_limits_requests_in_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let server_peak = peak.clone();
+        let addr = test_server(move |request| {
+            let (active, peak) = (active.clone(), server_peak.clone());
+            async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let body = if request.path == "/" {
+                    (0..8)
+                        .map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i))
+                        .collect()
+                } else {
+                    String::new()
+                };
+                active.fetch_sub(1, Ordering::SeqCst);
+                Response::html(body)
+            }
+        })
+        .await;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_concurrency: 10,
+            max_concurrency_per_host: Some(2),
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 9);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_urls_keep_typed_errors() {
+        let addr = test_server(|request| async move {
+            Response::html(if request.path == "/robots.txt" {
+                "User-agent: *\nDisallow: /private\n"
+            } else {
+                r#"<a href="/private">Private</a>"#
+            })
+        })
+        .await;
+        // Nothing listens here once the listener is dropped
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 0,
+            deterministic: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![
+                format!("http://{}/", addr),
+                format!("http://{}/", closed),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let failures = crawler.get_failures();
+        assert_eq!(failures.len(), 2);
+        let refused = failures
+            .iter()
+            .find(|f| f.url == format!("http://{}/", closed))
+            .unwrap();
+        // Its robots.txt can't be fetched either, which blocks the whole host
+        assert_eq!((refused.depth, refused.attempts), (0, 0));
+        assert!(matches!(
+            refused.error.as_deref(),
+            Some(CrawlerError::RobotsBlocked(_))
+        ));
+        let blocked = failures
+            .iter()
+            .find(|f| f.url.ends_with("/private"))
+            .unwrap();
+        assert_eq!((blocked.depth, blocked.attempts), (1, 0));
+        assert!(matches!(
+            blocked.error.as_deref(),
+            Some(CrawlerError::RobotsBlocked(_))
+        ));
+        assert!(blocked.reason.starts_with("URL blocked by robots.txt"));
+
+        // Each crawl reports its own failures
+        crawler.crawl(Vec::new()).await.unwrap();
+        assert!(crawler.get_failures().is_empty());
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 0,
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap();
+        crawler
+            .crawl(vec![format!("http://{}/", closed)])
+            .await
+            .unwrap();
+        let failures = crawler.get_failures();
+        assert_eq!((failures[0].depth, failures[0].attempts), (0, 1));
+        assert!(matches!(
+            failures[0].error.as_deref(),
+            Some(CrawlerError::TooManyRetries { attempts: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retained_html_counts_only_kept_pages() {
+        let addr = test_server(|request| async move {
+            Response::html(if request.path == "/" {
+                r#"<html lang="en"><a href="/fr">Français</a></html>"#
+            } else {
+                r#"<html lang="fr"><p>Bonjour</p></html>"#
+            })
+        })
+        .await;
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            keep_html: true,
+            languages: vec!["en".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let html = results[0].html.as_deref().unwrap();
+        assert_eq!(crawler.get_retained_html_bytes(), html.len());
+    }
+
+    #[tokio::test]
+    async fn test_crawler_runs_again_after_a_stop() {
+        let addr = test_server(|_| async { Response::html("<title>Home</title>") }).await;
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap();
+
+        crawler.request_stop();
+        let results = crawler
+            .crawl(vec![format!("http://{}/", addr)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_chains_and_loops() {
+        let addr = test_server(|request| async move {
+            let redirect = match request.path.as_str() {
+                "/start" => Some(("301 Moved Permanently", "/middle")),
+                "/middle" => Some(("302 Found", "end")),
+                "/loop-a" => Some(("302 Found", "/loop-b")),
+                "/loop-b" => Some(("301 Moved Permanently", "/loop-a")),
+                _ => None,
+            };
+            match redirect {
+                Some((status, location)) => Response::new(status).header("Location", location),
+                None => Response::html(r#"<a href="/loop-a">Loop</a>"#),
+            }
+        })
+        .await;
+        let base = format!("http://{}", addr);
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 0,
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![format!("{}/start", base)])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status_code, 200);
+        assert_eq!(results[0].final_url, format!("{}/end", base));
+        let chain: Vec<(String, u16)> = results[0]
+            .redirect_chain
+            .iter()
+            .map(|hop| (hop.url.clone(), hop.status_code))
+            .collect();
+        assert_eq!(
+            chain,
+            [
+                (format!("{}/start", base), 301),
+                (format!("{}/middle", base), 302)
+            ]
+        );
+
+        let failures = crawler.get_failures();
+        assert_eq!(failures.len(), 1);
+        match failures[0].error.as_deref() {
+            Some(CrawlerError::RedirectLoop { url, chain }) => {
+                assert_eq!(url, &format!("{}/loop-a", base));
+                assert_eq!(chain.len(), 3);
+                assert_eq!(chain.first(), chain.last());
+            }
+            other => panic!("expected a redirect loop, got {:?}", other),
+        }
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 0,
+            respect_robots: false,
+            max_redirects: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![format!("{}/start", base)])
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(matches!(
+            crawler.get_failures()[0].error.as_deref(),
+            Some(CrawlerError::TooManyRedirects { limit: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_list_rotates_past_dead_proxies() {
+        // Answers every proxied request itself
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let live = test_server(move |request| {
+            seen.lock()
+                .unwrap()
+                .push(format!("{} {}", request.method, request.path));
+            async { Response::html("<title>Proxied</title>") }
+        })
+        .await;
+        let live = format!("http://{}", live);
+        let dead = format!(
+            "http://{}",
+            tokio::net::TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap()
+                .local_addr()
+                .unwrap()
+        );
+
+        let crawler = Crawler::new(CrawlerConfig {
+            max_retries: 1,
+            respect_robots: false,
+            proxy_list: vec![dead.clone(), live.clone()],
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec!["http://site.invalid/".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title.as_deref(), Some("Proxied"));
+        assert_eq!(
+            requests.lock().unwrap().as_slice(),
+            ["GET http://site.invalid/"]
+        );
+        assert_eq!(crawler.get_live_proxies(), [dead, live]);
+    }
+
+    #[tokio::test]
+    async fn test_crawl_records_response_metadata() {
+        let addr = test_server(|request| async move {
+            if request.path == "/start" {
+                Response::new("302 Found").header("Location", "/page")
+            } else {
+                Response::new("200 OK")
+                    .header("Content-Type", "text/html; charset=utf-8")
+                    .header("X-Test", "a")
+                    .header("X-Test", "b")
+                    .body("<title>Page</title>hello!")
+            }
+        })
+        .await;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_depth: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+        let results = crawler
+            .crawl(vec![format!("http://{}/start", addr)])
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.url, format!("http://{}/start", addr));
+        assert_eq!(result.final_url, format!("http://{}/page", addr));
+        assert_eq!(
+            result.content_type.as_deref(),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(result.content_length, Some(25));
+        assert_eq!(result.response_headers["x-test"], "a, b");
+    }
+}
@@ -0,0 +1,193 @@
+use super::{CrawlFailure, CrawlResult, Crawler, CrawlerError};
+use futures::StreamExt;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How a crawl run by `Crawler::crawl_with_observer` went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrawlSummary {
+    pub run_id: String,
+    /// Results yielded, including ones restored from a state file.
+    pub pages: usize,
+    pub failures: usize,
+    pub elapsed: Duration,
+}
+
+/// Receives the events of a crawl as they happen, for embedders that show or store
+/// progress instead of waiting for the final results. Calls come from the crawl's own
+/// task, so slow work belongs on a channel. Every method does nothing by default.
+pub trait CrawlObserver: Send + Sync {
+    /// A page was crawled; called in the order `crawl_stream` yields results.
+    fn on_page(&self, _result: &CrawlResult) {}
+
+    /// A URL couldn't be crawled.
+    fn on_error(&self, _failure: &CrawlFailure) {}
+
+    /// A discovered link was queued to be crawled at `depth`.
+    fn on_enqueue(&self, _url: &str, _depth: usize) {}
+
+    /// The crawl ended, whether it ran out of URLs, hit a limit or was stopped.
+    fn on_finish(&self, _summary: &CrawlSummary) {}
+}
+
+/// Lets the caller keep a handle to the observer it hands over.
+impl<T: CrawlObserver + ?Sized> CrawlObserver for Arc<T> {
+    fn on_page(&self, result: &CrawlResult) {
+        (**self).on_page(result)
+    }
+
+    fn on_error(&self, failure: &CrawlFailure) {
+        (**self).on_error(failure)
+    }
+
+    fn on_enqueue(&self, url: &str, depth: usize) {
+        (**self).on_enqueue(url, depth)
+    }
+
+    fn on_finish(&self, summary: &CrawlSummary) {
+        (**self).on_finish(summary)
+    }
+}
+
+/// Detaches the observer when the crawl ends or its future is dropped.
+struct ObserverGuard<'a>(&'a Crawler);
+
+impl Drop for ObserverGuard<'_> {
+    fn drop(&mut self) {
+        self.0.observer.lock().unwrap().take();
+    }
+}
+
+impl Crawler {
+    /// Like `crawl`, but reports each page, failure and queued link to `observer` as it
+    /// happens instead of collecting the results.
+    pub async fn crawl_with_observer(
+        &self,
+        start_urls: Vec<String>,
+        observer: impl CrawlObserver + 'static,
+    ) -> Result<CrawlSummary, CrawlerError> {
+        let started = Instant::now();
+        let observer: Arc<dyn CrawlObserver> = Arc::new(observer);
+        *self.observer.lock().unwrap() = Some(observer.clone());
+        let _guard = ObserverGuard(self);
+
+        let mut pages = 0;
+        let mut results = pin!(self.crawl_stream(start_urls));
+        while let Some(result) = results.next().await {
+            pages += 1;
+            observer.on_page(&result);
+        }
+        let summary = CrawlSummary {
+            run_id: self
+                .get_run_metadata()
+                .map(|run| run.run_id)
+                .unwrap_or_default(),
+            pages,
+            failures: self.failures.lock().unwrap().len(),
+            elapsed: started.elapsed(),
+        };
+        observer.on_finish(&summary);
+        Ok(summary)
+    }
+
+    /// The observer of the crawl in progress, if it has one.
+    pub(super) fn observer(&self) -> Option<Arc<dyn CrawlObserver>> {
+        self.observer.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::{CrawlerConfig, FetchError, FetchRequest, FetchResponse, Fetcher};
+    use async_trait::async_trait;
+    use reqwest::header::{HeaderMap, CONTENT_TYPE};
+    use reqwest::StatusCode;
+    use std::sync::Mutex;
+
+    /// The home page links to /a and /broken, which fails.
+    struct TwoLinks;
+
+    #[async_trait]
+    impl Fetcher for TwoLinks {
+        async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse, FetchError> {
+            let body = match request.url.path() {
+                "/" => r#"<a href="/a">A</a><a href="/broken">B</a>"#,
+                "/a" => "<title>A</title>",
+                _ => return Err(FetchError::Other("connection reset".into())),
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, "text/html".parse().unwrap());
+            Ok(FetchResponse::new(
+                request.url,
+                StatusCode::OK,
+                headers,
+                body,
+            ))
+        }
+    }
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+
+    impl CrawlObserver for Events {
+        fn on_page(&self, result: &CrawlResult) {
+            self.0.lock().unwrap().push(format!("page {}", result.url));
+        }
+
+        fn on_error(&self, failure: &CrawlFailure) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("error {}", failure.url));
+        }
+
+        fn on_enqueue(&self, url: &str, depth: usize) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("enqueue {} {}", url, depth));
+        }
+
+        fn on_finish(&self, summary: &CrawlSummary) {
+            let line = format!("finish {} {}", summary.pages, summary.failures);
+            self.0.lock().unwrap().push(line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_every_event() {
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            max_retries: 0,
+            deterministic: true,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_fetcher(TwoLinks);
+        let events = Arc::new(Events::default());
+
+        let summary = crawler
+            .crawl_with_observer(vec!["https://observed.test/".to_string()], events.clone())
+            .await
+            .unwrap();
+        assert_eq!((summary.pages, summary.failures), (2, 1));
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events[0], "enqueue https://observed.test/a 1");
+        assert_eq!(events[1], "enqueue https://observed.test/broken 1");
+        assert_eq!(events[2], "page https://observed.test/");
+        let mut rest = events[3..5].to_vec();
+        rest.sort();
+        assert_eq!(
+            rest,
+            [
+                "error https://observed.test/broken",
+                "page https://observed.test/a"
+            ]
+        );
+        assert_eq!(events[5], "finish 2 1");
+        assert!(crawler.observer().is_none());
+    }
+}
@@ -0,0 +1,529 @@
+use super::assets::PageAsset;
+use super::extract::extract_text;
+use super::extractors::{extract_structured_data, StructuredData};
+use super::fingerprint::simhash;
+use super::{Crawler, PageVariant};
+use crate::robots::RobotsDirectives;
+use crate::text::clean_title;
+use crate::urls::{lowercase_path, normalize_url_with};
+use anyhow::Result;
+use log::{debug, info};
+use scraper::{Html, Selector};
+use std::collections::BTreeMap;
+use url::Url;
+
+/// What `parse_html` pulls out of a page.
+#[derive(Debug, Default)]
+pub(super) struct ParsedPage {
+    pub title: Option<String>,
+    pub links: Vec<String>,
+    /// Target of `<link rel="canonical">`.
+    pub canonical: Option<String>,
+    /// `<link rel="alternate" hreflang>` locale variants.
+    pub hreflang: Vec<PageVariant>,
+    /// Category from the first matching `--classify` rule.
+    pub category: Option<String>,
+    /// Content of `<meta name="description">`.
+    pub meta_description: Option<String>,
+    pub h1: Vec<String>,
+    pub h2: Vec<String>,
+    /// `og:*` meta properties by lowercase name, first value wins.
+    pub open_graph: BTreeMap<String, String>,
+    /// Readable page text, with `extract_text`.
+    pub text: Option<String>,
+    /// SimHash of the readable text, with `simhash` or `detect_soft_404`.
+    pub simhash: Option<u64>,
+    /// Structured data, with `extract_structured_data`.
+    pub metadata: Option<StructuredData>,
+    /// Values of each `--extract` rule that matched.
+    pub extracted: BTreeMap<String, Vec<String>>,
+    /// Images, scripts, stylesheets and media, with `include_assets`.
+    pub assets: Vec<PageAsset>,
+    /// `http://` resources of an `https://` page.
+    pub mixed_content: Vec<String>,
+    /// `http://` links of an `https://` page.
+    pub insecure_links: Vec<String>,
+    /// Directives from `<meta name="robots">`.
+    pub robots: RobotsDirectives,
+}
+
+impl Crawler {
+    pub(super) fn parse_html(
+        &self,
+        html: &str,
+        base_url: &Url,
+        status_code: u16,
+    ) -> Result<ParsedPage> {
+        let document = Html::parse_document(html);
+
+        // Extract title
+        let title_selector = Selector::parse("title")
+            .map_err(|e| anyhow::anyhow!("Failed to parse title selector: {}", e))?;
+
+        let title = document.select(&title_selector).next().and_then(|el| {
+            clean_title(&el.text().collect::<String>(), self.config.max_title_length)
+        });
+
+        // Extract links
+        let link_selector = Selector::parse("a[href]")
+            .map_err(|e| anyhow::anyhow!("Failed to parse link selector: {}", e))?;
+
+        let mut links = Vec::new();
+        for element in document.select(&link_selector) {
+            let nofollow = element.value().attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("nofollow"))
+            });
+            if nofollow && self.config.respect_robots {
+                continue;
+            }
+            if let Some(href) = element.value().attr("href") {
+                // Resolve relative URLs to absolute URLs
+                match base_url.join(href) {
+                    Ok(absolute_url) => {
+                        if let Some(link) = self.normalize_link(&absolute_url) {
+                            links.push(link);
+                        }
+                    }
+                    Err(e) => {
+                        debug!("Failed to resolve URL {}: {}", href, e);
+                    }
+                }
+            }
+        }
+
+        // Remove duplicates while preserving order
+        links.sort();
+        links.dedup();
+
+        // Extract canonical and hreflang relations for variant clustering
+        let head_link_selector = Selector::parse("link[rel][href]")
+            .map_err(|e| anyhow::anyhow!("Failed to parse link selector: {}", e))?;
+
+        let mut canonical = None;
+        let mut hreflang = Vec::new();
+        for element in document.select(&head_link_selector) {
+            let rel = element.value().attr("rel").unwrap_or_default();
+            let Some(url) = element
+                .value()
+                .attr("href")
+                .and_then(|href| base_url.join(href.trim()).ok())
+                .and_then(|url| self.normalize_link(&url))
+            else {
+                continue;
+            };
+
+            let has_rel = |name: &str| {
+                rel.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case(name))
+            };
+            if has_rel("canonical") && canonical.is_none() {
+                canonical = Some(url);
+            } else if has_rel("alternate") {
+                if let Some(lang) = element.value().attr("hreflang") {
+                    hreflang.push(PageVariant {
+                        hreflang: lang.trim().to_lowercase(),
+                        url,
+                    });
+                }
+            }
+        }
+
+        // Extract SEO metadata: description, OpenGraph properties and headings
+        let meta_selector = Selector::parse("meta[content]")
+            .map_err(|e| anyhow::anyhow!("Failed to parse meta selector: {}", e))?;
+
+        let mut meta_description = None;
+        let mut open_graph = BTreeMap::new();
+        let mut robots = RobotsDirectives::default();
+        for element in document.select(&meta_selector) {
+            let content = element.value().attr("content").unwrap_or_default();
+            let name = element.value().attr("name").map(str::to_ascii_lowercase);
+            // OpenGraph uses `property`, but `name="og:..."` is common too
+            let property = element
+                .value()
+                .attr("property")
+                .map(str::to_ascii_lowercase)
+                .or_else(|| name.clone());
+
+            if name.as_deref() == Some("robots") {
+                robots = robots.merge(RobotsDirectives::parse(content));
+            }
+            if name.as_deref() == Some("description") && meta_description.is_none() {
+                meta_description = clean_title(content, 0);
+            }
+            if let Some(property) = property.filter(|p| p.starts_with("og:")) {
+                if let Some(value) = clean_title(content, 0) {
+                    open_graph.entry(property).or_insert(value);
+                }
+            }
+        }
+
+        let headings = |tag: &str| -> Result<Vec<String>> {
+            let selector = Selector::parse(tag)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {} selector: {}", tag, e))?;
+            Ok(document
+                .select(&selector)
+                .filter_map(|el| {
+                    clean_title(&el.text().collect::<String>(), self.config.max_title_length)
+                })
+                .collect())
+        };
+        let h1 = headings("h1")?;
+        let h2 = headings("h2")?;
+
+        let fingerprint = self.config.simhash || self.config.detect_soft_404;
+        let readable = if self.config.extract_text || fingerprint {
+            extract_text(&document)
+        } else {
+            None
+        };
+        let simhash = if fingerprint {
+            Some(simhash(readable.as_deref().unwrap_or_default()))
+        } else {
+            None
+        };
+        let text = readable.filter(|_| self.config.extract_text);
+
+        let metadata = if self.config.extract_structured_data {
+            Some(extract_structured_data(&document, base_url)).filter(|data| !data.is_empty())
+        } else {
+            None
+        };
+
+        let extracted = self.extractor.extract(&document, base_url);
+
+        // Assets are found on every HTTPS page for the mixed content audit
+        let secure = base_url.scheme() == "https";
+        let mut assets = if self.config.include_assets || secure {
+            self.extract_assets(&document, base_url)
+        } else {
+            Vec::new()
+        };
+        let mixed_content: Vec<String> = assets
+            .iter()
+            .filter(|asset| asset.mixed_content)
+            .map(|asset| asset.url.clone())
+            .collect();
+        if !self.config.include_assets {
+            assets.clear();
+        }
+        let insecure_links: Vec<String> = if secure {
+            links
+                .iter()
+                .filter(|link| link.starts_with("http:"))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let category = self
+            .classifier
+            .classify(base_url.as_str(), status_code, &document);
+
+        Ok(ParsedPage {
+            title,
+            links,
+            canonical,
+            hreflang,
+            category,
+            meta_description,
+            h1,
+            h2,
+            open_graph,
+            text,
+            simhash,
+            metadata,
+            extracted,
+            assets,
+            mixed_content,
+            insecure_links,
+            robots,
+        })
+    }
+
+    /// Canonical string for a discovered link, or `None` for non-HTTP(S) schemes.
+    pub(super) fn normalize_link(&self, url: &Url) -> Option<String> {
+        // Only include HTTP/HTTPS URLs
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return None;
+        }
+
+        let mut normalized = normalize_url_with(url, &self.normalize_options);
+        if normalized
+            .host_str()
+            .is_some_and(|host| self.case_insensitive_hosts.contains(host))
+        {
+            lowercase_path(&mut normalized);
+        }
+        Some(normalized.to_string())
+    }
+
+    /// Request `url` with its path case swapped once per host; if the server answers
+    /// with a success and a body of about the same size, treat the host as case-insensitive.
+    pub(super) async fn probe_case_insensitivity(&self, url: &Url, body_len: usize) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        if self.case_insensitive_hosts.contains(host) || !self.probed_hosts.insert(host.to_string())
+        {
+            return;
+        }
+
+        let swapped: String = url
+            .path()
+            .chars()
+            .map(|c| {
+                if c.is_lowercase() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+        if swapped == url.path() {
+            // Nothing to swap in e.g. "/" - try again on the next page from this host
+            self.probed_hosts.remove(host);
+            return;
+        }
+
+        let mut probe_url = url.clone();
+        probe_url.set_path(&swapped);
+
+        let Ok(response) = self.client.get(probe_url.as_str()).send().await else {
+            return;
+        };
+        if !response.status().is_success() {
+            return;
+        }
+        let Ok(probe_body) = response.text().await else {
+            return;
+        };
+
+        let tolerance = body_len / 20;
+        if probe_body.len().abs_diff(body_len) <= tolerance {
+            info!("Detected case-insensitive paths on {}", host);
+            self.case_insensitive_hosts.insert(host.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_html_basic() {
+        let config = CrawlerConfig {
+            max_concurrency: 10,
+            rate_limit: None,
+            max_retries: 3,
+            timeout: Duration::from_secs(30),
+            user_agent: "test-agent".to_string(),
+            max_pages: Some(100),
+            max_depth: Some(3),
+            respect_robots: true,
+            follow_redirects: true,
+            proxy: None,
+            ..Default::default()
+        };
+
+        let crawler = Crawler::new(config).unwrap();
+        let html = r#"
+            <html>
+                <head><title>Test Page</title></head>
+                <body>
+                    <a href="/page1">Link 1</a>
+                    <a href="https://example.com/page2">Link 2</a>
+                    <a href="/page1#section">Link 1 again</a>
+                </body>
+            </html>
+        "#;
+
+        let base_url = Url::parse("https://example.com").unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+
+        assert_eq!(page.title, Some("Test Page".to_string()));
+        assert_eq!(page.links.len(), 2);
+        assert!(page
+            .links
+            .contains(&"https://example.com/page1".to_string()));
+        assert!(page
+            .links
+            .contains(&"https://example.com/page2".to_string()));
+    }
+
+    #[test]
+    fn test_parse_html_canonical_and_hreflang() {
+        let html = r#"
+            <html><head>
+                <link rel="canonical" href="/en/pricing">
+                <link rel="alternate" hreflang="en" href="/en/pricing">
+                <link rel="alternate" hreflang="DE" href="https://example.com/de/preise">
+                <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+                <link rel="stylesheet" href="/site.css">
+            </head></html>
+        "#;
+        let base_url = Url::parse("https://example.com/en/pricing?ref=nav").unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert_eq!(
+            page.canonical.as_deref(),
+            Some("https://example.com/en/pricing")
+        );
+        assert_eq!(
+            page.hreflang,
+            vec![
+                PageVariant {
+                    hreflang: "en".to_string(),
+                    url: "https://example.com/en/pricing".to_string(),
+                },
+                PageVariant {
+                    hreflang: "de".to_string(),
+                    url: "https://example.com/de/preise".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_seo_metadata() {
+        let html = r#"
+            <html><head>
+                <meta name="Description" content="  Plans   &amp; pricing ">
+                <meta name="description" content="Ignored second description">
+                <meta property="og:title" content="Pricing">
+                <meta property="og:image" content="https://example.com/a.png">
+                <meta property="og:image" content="https://example.com/b.png">
+                <meta name="og:type" content="website">
+                <meta property="twitter:card" content="summary">
+            </head><body>
+                <h1>Pricing <em>plans</em></h1>
+                <h2>Free</h2><h2> </h2><h2>Pro</h2>
+            </body></html>
+        "#;
+        let base_url = Url::parse("https://example.com/pricing").unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert_eq!(page.meta_description.as_deref(), Some("Plans & pricing"));
+        assert_eq!(page.h1, vec!["Pricing plans"]);
+        assert_eq!(page.h2, vec!["Free", "Pro"]);
+        assert_eq!(
+            page.open_graph.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    "og:image".to_string(),
+                    "https://example.com/a.png".to_string()
+                ),
+                ("og:title".to_string(), "Pricing".to_string()),
+                ("og:type".to_string(), "website".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_robots_meta_and_nofollow_links() {
+        let html = r#"
+            <html><head><meta name="ROBOTS" content="noindex"></head><body>
+                <a href="/a">A</a>
+                <a href="/ad" rel="sponsored NoFollow">Ad</a>
+            </body></html>
+        "#;
+        let base_url = Url::parse("https://example.com/").unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert!(page.robots.noindex && !page.robots.nofollow);
+        assert_eq!(page.links, vec!["https://example.com/a"]);
+
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert_eq!(page.links.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_html_spa_fragments() {
+        let html = r##"
+            <html><body>
+                <a href="#/products">Products</a>
+                <a href="#!/about">About</a>
+                <a href="#top">Top</a>
+            </body></html>
+        "##;
+        let base_url = Url::parse("https://example.com/").unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+        let links = crawler.parse_html(html, &base_url, 200).unwrap().links;
+        assert_eq!(links, vec!["https://example.com/".to_string()]);
+
+        let crawler = Crawler::new(CrawlerConfig {
+            spa_fragments: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let links = crawler.parse_html(html, &base_url, 200).unwrap().links;
+        assert_eq!(links.len(), 3);
+        assert!(links.contains(&"https://example.com/#/products".to_string()));
+        assert!(links.contains(&"https://example.com/#!/about".to_string()));
+        assert!(links.contains(&"https://example.com/".to_string()));
+    }
+
+    #[test]
+    fn test_parse_html_insecure_content() {
+        let html = r#"
+            <html><head><script src="http://cdn.example.com/app.js"></script></head><body>
+                <img src="/logo.png">
+                <a href="http://example.com/old">Old</a>
+                <a href="/new">New</a>
+            </body></html>
+        "#;
+        let crawler = Crawler::new(CrawlerConfig::default()).unwrap();
+
+        let base_url = Url::parse("https://example.com/").unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert_eq!(page.mixed_content, vec!["http://cdn.example.com/app.js"]);
+        assert_eq!(page.insecure_links, vec!["http://example.com/old"]);
+        // Without include_assets the assets themselves aren't kept
+        assert!(page.assets.is_empty());
+
+        let base_url = Url::parse("http://example.com/").unwrap();
+        let page = crawler.parse_html(html, &base_url, 200).unwrap();
+        assert!(page.mixed_content.is_empty());
+        assert!(page.insecure_links.is_empty());
+    }
+
+    #[test]
+    fn test_parse_html_case_insensitive_host() {
+        let html = r#"
+            <html><body>
+                <a href="/About">About</a>
+                <a href="/about">about</a>
+                <a href="https://other.com/About">Other</a>
+            </body></html>
+        "#;
+        let base_url = Url::parse("https://example.com/").unwrap();
+
+        let crawler = Crawler::new(CrawlerConfig {
+            case_insensitive_hosts: vec!["Example.com".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let links = crawler.parse_html(html, &base_url, 200).unwrap().links;
+        assert_eq!(
+            links,
+            vec![
+                "https://example.com/about".to_string(),
+                "https://other.com/About".to_string()
+            ]
+        );
+    }
+}
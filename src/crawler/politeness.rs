@@ -0,0 +1,111 @@
+use super::{CrawlResult, Crawler, CrawlerError};
+use futures::future::join_all;
+use log::debug;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+impl Crawler {
+    /// Reserve the next request slot on `url`'s host, returning how long to wait before
+    /// sending. The rate from `--rate` and robots.txt crawl-delay both apply per host.
+    pub(super) fn reserve_host_slot(&self, url: &Url) -> Duration {
+        let host = url
+            .host_str()
+            .map(|host| self.host_aliases.canonical(host))
+            .unwrap_or_default();
+        self.rate_limiter.reserve(&host)
+    }
+
+    /// Wait for a request slot on `url`'s host, for requests outside page fetches.
+    pub(super) async fn wait_for_host_slot(&self, url: &Url) {
+        let wait = self.reserve_host_slot(url);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Wait for one of `max_concurrency_per_host` request slots on `url`'s host; the
+    /// slot is held until the permit is dropped. `None` when there's no per-host limit.
+    pub(super) async fn acquire_host_permit(&self, url: &Url) -> Option<OwnedSemaphorePermit> {
+        let limit = self.config.max_concurrency_per_host?;
+        let host = url
+            .host_str()
+            .map(|host| self.host_aliases.canonical(host))
+            .unwrap_or_default();
+        let semaphore = self
+            .host_semaphores
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone();
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("host semaphores are never closed");
+        Some(permit)
+    }
+
+    /// Enforce robots.txt rules for `url`, handing its crawl-delay to the host rate
+    /// limiter; errors if the URL is disallowed.
+    pub(super) async fn check_robots(&self, url: &Url) -> Result<(), CrawlerError> {
+        if !self.config.respect_robots {
+            return Ok(());
+        }
+
+        let allowed = self
+            .robots_manager
+            .check_robots_compliance(url)
+            .await
+            .map_err(|e| CrawlerError::Robots {
+                url: url.to_string(),
+                source: e.into(),
+            })?;
+        if !allowed {
+            return Err(CrawlerError::RobotsBlocked(url.to_string()));
+        }
+
+        if let (Some(delay), Some(host)) = (self.robots_manager.crawl_delay(url), url.host_str()) {
+            debug!("Applying crawl delay of {:?} for {}", delay, host);
+            self.rate_limiter
+                .apply_crawl_delay(&self.host_aliases.canonical(host), delay);
+        }
+
+        Ok(())
+    }
+
+    /// Crawl a batch with a single worker per host, returning results in batch order.
+    /// Hosts still run in parallel, but each host sees its URLs one at a time.
+    pub(super) async fn crawl_batch_per_host(
+        &self,
+        batch: Vec<(String, usize)>,
+        run_id: &str,
+    ) -> Vec<Result<CrawlResult, CrawlerError>> {
+        // Group by host, keeping each URL's position in the batch
+        let mut host_index: HashMap<String, usize> = HashMap::new();
+        let mut hosts: Vec<Vec<(usize, String, usize)>> = Vec::new();
+        for (index, (url, depth)) in batch.into_iter().enumerate() {
+            let host = Url::parse(&url)
+                .ok()
+                .and_then(|u| u.host_str().map(|host| self.host_aliases.canonical(host)))
+                .unwrap_or_default();
+            let slot = *host_index.entry(host).or_insert_with(|| {
+                hosts.push(Vec::new());
+                hosts.len() - 1
+            });
+            hosts[slot].push((index, url, depth));
+        }
+
+        let workers = hosts.into_iter().map(|urls| async move {
+            let mut results = Vec::with_capacity(urls.len());
+            for (index, url, depth) in urls {
+                results.push((index, self.crawl_url(url, depth, run_id).await));
+            }
+            results
+        });
+
+        let mut results: Vec<_> = join_all(workers).await.into_iter().flatten().collect();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
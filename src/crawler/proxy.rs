@@ -0,0 +1,189 @@
+use dashmap::DashMap;
+use log::warn;
+use reqwest::Client;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use url::Url;
+use uuid::Uuid;
+
+/// Consecutive failures after which a proxy is taken out of the rotation.
+const MAX_PROXY_FAILURES: usize = 3;
+
+/// How requests are spread over the proxies of a proxy list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyRotation {
+    /// Each request goes through the next proxy in the list
+    #[default]
+    RoundRobin,
+    /// Each request goes through a proxy picked at random
+    Random,
+    /// Every request to a host goes through the same proxy while it works
+    Sticky,
+}
+
+struct PooledProxy {
+    url: String,
+    /// Doesn't follow redirects, like the crawler's page client.
+    client: Client,
+    /// Failures since the last successful request.
+    failures: AtomicUsize,
+    evicted: AtomicBool,
+}
+
+/// The proxies of a proxy list, each with its own client, and which ones still work.
+pub(super) struct ProxyPool {
+    proxies: Vec<PooledProxy>,
+    rotation: ProxyRotation,
+    next: AtomicUsize,
+    /// Proxy assigned to each host, with `ProxyRotation::Sticky`.
+    by_host: DashMap<String, usize>,
+}
+
+impl ProxyPool {
+    pub(super) fn new(proxies: Vec<(String, Client)>, rotation: ProxyRotation) -> Self {
+        Self {
+            proxies: proxies
+                .into_iter()
+                .map(|(url, client)| PooledProxy {
+                    url,
+                    client,
+                    failures: AtomicUsize::new(0),
+                    evicted: AtomicBool::new(false),
+                })
+                .collect(),
+            rotation,
+            next: AtomicUsize::new(0),
+            by_host: DashMap::new(),
+        }
+    }
+
+    /// Index of the proxy to send a request for `url` through; `None` once every proxy
+    /// has been evicted.
+    pub(super) fn pick(&self, url: &str) -> Option<usize> {
+        match self.rotation {
+            ProxyRotation::RoundRobin => self.next_live(),
+            ProxyRotation::Random => {
+                let live: Vec<usize> = (0..self.proxies.len())
+                    .filter(|&index| self.is_live(index))
+                    .collect();
+                if live.is_empty() {
+                    return None;
+                }
+                let pick = Uuid::new_v4().as_u64_pair().0 as usize % live.len();
+                Some(live[pick])
+            }
+            ProxyRotation::Sticky => {
+                let host = Url::parse(url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .unwrap_or_default();
+                if let Some(index) = self.by_host.get(&host).map(|index| *index) {
+                    if self.is_live(index) {
+                        return Some(index);
+                    }
+                }
+                let index = self.next_live()?;
+                self.by_host.insert(host, index);
+                Some(index)
+            }
+        }
+    }
+
+    pub(super) fn client(&self, index: usize) -> &Client {
+        &self.proxies[index].client
+    }
+
+    pub(super) fn succeeded(&self, index: usize) {
+        self.proxies[index].failures.store(0, Ordering::Relaxed);
+    }
+
+    /// Count a failure against the proxy, evicting it after `MAX_PROXY_FAILURES` in a row.
+    pub(super) fn failed(&self, index: usize) {
+        let proxy = &self.proxies[index];
+        let failures = proxy.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= MAX_PROXY_FAILURES && !proxy.evicted.swap(true, Ordering::Relaxed) {
+            warn!(
+                "Evicting proxy {} after {} failures in a row ({} left)",
+                proxy.url,
+                failures,
+                self.live_urls().len()
+            );
+        }
+    }
+
+    /// Proxies still in the rotation.
+    pub(super) fn live_urls(&self) -> Vec<String> {
+        self.proxies
+            .iter()
+            .filter(|proxy| !proxy.evicted.load(Ordering::Relaxed))
+            .map(|proxy| proxy.url.clone())
+            .collect()
+    }
+
+    fn is_live(&self, index: usize) -> bool {
+        !self.proxies[index].evicted.load(Ordering::Relaxed)
+    }
+
+    fn next_live(&self) -> Option<usize> {
+        (0..self.proxies.len())
+            .map(|_| self.next.fetch_add(1, Ordering::Relaxed) % self.proxies.len())
+            .find(|&index| self.is_live(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(rotation: ProxyRotation) -> ProxyPool {
+        let proxies = ["http://a:8080", "http://b:8080", "http://c:8080"]
+            .iter()
+            .map(|url| (url.to_string(), Client::new()))
+            .collect();
+        ProxyPool::new(proxies, rotation)
+    }
+
+    #[test]
+    fn test_rotation_and_eviction() {
+        let pool = pool(ProxyRotation::RoundRobin);
+        let picks: Vec<_> = (0..4)
+            .map(|_| pool.pick("https://x.com/").unwrap())
+            .collect();
+        assert_eq!(picks, [0, 1, 2, 0]);
+
+        // A success in between resets the count
+        pool.failed(1);
+        pool.failed(1);
+        pool.succeeded(1);
+        pool.failed(1);
+        assert_eq!(pool.live_urls().len(), 3);
+        pool.failed(1);
+        pool.failed(1);
+        assert_eq!(pool.live_urls(), ["http://a:8080", "http://c:8080"]);
+        let picks: Vec<_> = (0..4)
+            .map(|_| pool.pick("https://x.com/").unwrap())
+            .collect();
+        assert!(!picks.contains(&1));
+
+        let sticky = self::pool(ProxyRotation::Sticky);
+        let first = sticky.pick("https://x.com/a").unwrap();
+        assert_eq!(sticky.pick("https://x.com/b"), Some(first));
+        assert_ne!(sticky.pick("https://y.com/"), Some(first));
+        for _ in 0..MAX_PROXY_FAILURES {
+            sticky.failed(first);
+        }
+        let moved = sticky.pick("https://x.com/a").unwrap();
+        assert_ne!(moved, first);
+        assert_eq!(sticky.pick("https://x.com/c"), Some(moved));
+
+        let random = self::pool(ProxyRotation::Random);
+        for index in 0..3 {
+            for _ in 0..MAX_PROXY_FAILURES {
+                random.failed(index);
+            }
+        }
+        assert_eq!(random.pick("https://x.com/"), None);
+    }
+}
@@ -0,0 +1,207 @@
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+/// Token bucket per host: up to `burst` requests may go out back-to-back, then one
+/// per interval, so the long-run rate holds. Hosts are limited independently.
+///
+/// Callers reserve a send time rather than polling for a free token, so concurrent
+/// workers waiting on one host are released one interval apart in the order they
+/// asked, instead of all waking at once and racing for the next token. Rate limiting
+/// crates such as governor only say when the next token frees up and can't book one
+/// ahead, hence the small scheduler below.
+pub(super) struct HostRateLimiter {
+    /// Interval from `--rate`; applies to every host.
+    default_interval: Option<Duration>,
+    burst: u32,
+    limiters: DashMap<String, HostLimiter>,
+}
+
+/// Schedule of one host, as a generic cell rate algorithm: `next` is when the next
+/// request would go out if requests were evenly spaced, and a request may go up to
+/// `burst - 1` intervals ahead of it.
+struct HostLimiter {
+    interval: Duration,
+    burst: u32,
+    next: Instant,
+}
+
+impl HostLimiter {
+    fn new(interval: Duration, burst: u32) -> Option<Self> {
+        (!interval.is_zero()).then(|| Self {
+            interval,
+            burst,
+            next: Instant::now(),
+        })
+    }
+
+    /// Book the earliest allowed send time at or after `now`, returning the wait until it.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let tolerance = self.interval * (self.burst - 1);
+        let earliest = self.next.checked_sub(tolerance).unwrap_or(now);
+        let slot = earliest.max(now);
+        self.next = self.next.max(now) + self.interval;
+        slot - now
+    }
+}
+
+impl HostRateLimiter {
+    pub(super) fn new(default_interval: Option<Duration>, burst: u32) -> Self {
+        Self {
+            default_interval,
+            burst: burst.max(1),
+            limiters: DashMap::new(),
+        }
+    }
+
+    /// Reserve the next request slot for `host`, returning how long to wait before
+    /// sending; zero when it may go now. The slot is taken either way.
+    pub(super) fn reserve(&self, host: &str) -> Duration {
+        let now = Instant::now();
+        if let Some(mut limiter) = self.limiters.get_mut(host) {
+            return limiter.reserve(now);
+        }
+        let Some(limiter) = self
+            .default_interval
+            .and_then(|interval| HostLimiter::new(interval, self.burst))
+        else {
+            return Duration::ZERO;
+        };
+        self.limiters
+            .entry(host.to_string())
+            .or_insert(limiter)
+            .reserve(now)
+    }
+
+    /// Slow `host` down to robots.txt's crawl-delay when it's longer than the configured
+    /// rate. Crawl-delay asks for evenly spaced requests, so the host loses its burst.
+    /// Slots already reserved are kept, and later ones are spaced from them.
+    pub(super) fn apply_crawl_delay(&self, host: &str, delay: Duration) {
+        let interval = self.default_interval.map_or(delay, |d| d.max(delay));
+        let mut limiter = match self.limiters.get_mut(host) {
+            Some(limiter) => limiter,
+            None => match HostLimiter::new(interval, 1) {
+                Some(limiter) => self.limiters.entry(host.to_string()).or_insert(limiter),
+                None => return,
+            },
+        };
+        if limiter.interval < interval {
+            limiter.interval = interval;
+            limiter.burst = 1;
+        }
+    }
+
+    /// Current interval between requests to `host`, if it is limited.
+    #[cfg(test)]
+    pub(super) fn interval(&self, host: &str) -> Option<Duration> {
+        self.limiters
+            .get(host)
+            .map(|limiter| limiter.interval)
+            .or(self.default_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_steady_rate_per_host() {
+        let limiter = HostRateLimiter::new(Some(Duration::from_secs(1)), 3);
+
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve("a.com"), Duration::ZERO);
+        }
+        let wait = limiter.reserve("a.com");
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+
+        // Other hosts have their own bucket
+        assert_eq!(limiter.reserve("b.com"), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_reservations_are_spaced_one_interval_apart() {
+        let limiter = HostRateLimiter::new(None, 1);
+        limiter.apply_crawl_delay("a.com", Duration::from_secs(2));
+        let waits: Vec<Duration> = (0..4).map(|_| limiter.reserve("a.com")).collect();
+        assert_eq!(waits[0], Duration::ZERO);
+        for pair in waits.windows(2) {
+            let gap = pair[1] - pair[0];
+            assert!(
+                gap > Duration::from_millis(1990) && gap <= Duration::from_secs(2),
+                "{:?}",
+                waits
+            );
+        }
+    }
+
+    #[test]
+    fn test_burst_refills_one_slot_per_interval() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut limiter = HostLimiter {
+            interval: Duration::from_secs(1),
+            burst: 3,
+            next: start,
+        };
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(at(0)), Duration::ZERO);
+        }
+
+        // One interval later one slot is back, and only one
+        assert_eq!(limiter.reserve(at(1)), Duration::ZERO);
+        assert_eq!(limiter.reserve(at(1)), Duration::from_secs(1));
+
+        // After a long pause the whole burst is back, but never more
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(at(60)), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(at(60)), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_concurrent_reservations_get_distinct_slots() {
+        let interval = Duration::from_millis(100);
+        let limiter = HostRateLimiter::new(Some(interval), 3);
+        let start = std::sync::Barrier::new(8);
+        let mut waits: Vec<Duration> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        start.wait();
+                        limiter.reserve("a.com")
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        waits.sort();
+
+        // The burst goes at once and the rest one interval apart, none sharing a slot
+        let slack = Duration::from_millis(20);
+        assert!(waits[..3].iter().all(|wait| *wait < slack), "{:?}", waits);
+        for (i, wait) in waits[3..].iter().enumerate() {
+            let slot = interval * (i as u32 + 1);
+            assert!(*wait <= slot && *wait + slack > slot, "{:?}", waits);
+        }
+    }
+
+    #[test]
+    fn test_crawl_delay_only_slows_hosts_down() {
+        let limiter = HostRateLimiter::new(Some(Duration::from_secs(2)), 5);
+        limiter.apply_crawl_delay("slow.com", Duration::from_secs(5));
+        limiter.apply_crawl_delay("fast.com", Duration::from_secs(1));
+        assert_eq!(limiter.interval("slow.com"), Some(Duration::from_secs(5)));
+        assert_eq!(limiter.interval("fast.com"), Some(Duration::from_secs(2)));
+
+        // No burst under crawl-delay
+        assert_eq!(limiter.reserve("slow.com"), Duration::ZERO);
+        assert!(limiter.reserve("slow.com") > Duration::from_secs(4));
+
+        let unlimited = HostRateLimiter::new(None, 1);
+        assert_eq!(unlimited.reserve("a.com"), Duration::ZERO);
+        assert_eq!(unlimited.reserve("a.com"), Duration::ZERO);
+        unlimited.apply_crawl_delay("a.com", Duration::from_secs(3));
+        assert_eq!(unlimited.reserve("a.com"), Duration::ZERO);
+        assert!(unlimited.reserve("a.com") > Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,243 @@
+use super::{Crawler, CrawlerError, PageAsset, Soft404Reason, StructuredData};
+use crate::language::language_allowed;
+use crate::urls::display_url;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlResult {
+    pub url: String,
+    pub status_code: u16,
+    pub title: Option<String>,
+    pub links: Vec<String>,
+    pub depth: usize,
+    #[serde(
+        rename = "crawl_time_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub crawl_time: Duration,
+    /// The page's HTML, decoded to UTF-8 and, for rendered pages, the DOM after its
+    /// scripts ran; only populated when `keep_html` is enabled.
+    pub html: Option<String>,
+    /// True when `html` was cut at `keep_html_max_kb`.
+    pub html_truncated: bool,
+    /// Detected page language, only populated when language filtering is enabled.
+    pub language: Option<String>,
+    /// ID of the crawl run that produced this result.
+    pub run_id: String,
+    /// Target of the page's `<link rel="canonical">`, if any.
+    pub canonical: Option<String>,
+    /// Locale variants declared with `<link rel="alternate" hreflang>`.
+    pub hreflang: Vec<PageVariant>,
+    /// URL the response came from after following redirects.
+    #[serde(default)]
+    pub final_url: String,
+    /// `Content-Type` header, e.g. `text/html; charset=utf-8`.
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Size of the response body in bytes as received, before decoding.
+    #[serde(default)]
+    pub content_length: Option<usize>,
+    /// Response headers by lowercase name; repeated headers are joined with `, `.
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+    /// Category from the first matching `--classify` rule.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// The body contradicted `content_type`, e.g. binary data labeled `text/html`.
+    /// Processing followed the body.
+    #[serde(default)]
+    pub mime_mismatch: bool,
+    /// `url` with an internationalized host shown in Unicode, e.g. `https://bücher.de/`;
+    /// `None` when it reads the same as `url`. Requests always use `url`.
+    #[serde(default)]
+    pub display_url: Option<String>,
+    /// Content of the page's `<meta name="description">`, if any.
+    #[serde(default)]
+    pub meta_description: Option<String>,
+    /// Text of each `<h1>`, in document order.
+    #[serde(default)]
+    pub h1: Vec<String>,
+    /// Text of each `<h2>`, in document order.
+    #[serde(default)]
+    pub h2: Vec<String>,
+    /// OpenGraph `og:*` meta properties, e.g. `og:title` and `og:image`.
+    #[serde(default)]
+    pub open_graph: BTreeMap<String, String>,
+    /// Readable page text, one line per block, only populated with `extract_text`.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// The page asked not to be indexed, via `<meta name="robots">` or `X-Robots-Tag`.
+    #[serde(default)]
+    pub noindex: bool,
+    /// SHA-256 of the response body as received, as lowercase hex; `None` when empty.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// 64-bit SimHash of the page's readable text as hex, only populated with `simhash`.
+    /// Near-duplicate pages differ in few bits.
+    #[serde(default)]
+    pub simhash: Option<String>,
+    /// The earlier page of this crawl with the same `content_hash`, for 2xx pages.
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// Redirects followed to reach `final_url`, in order; empty when the URL answered
+    /// directly.
+    #[serde(default)]
+    pub redirect_chain: Vec<RedirectHop>,
+    /// JSON-LD, OpenGraph/Twitter card metadata and microdata found on the page, only
+    /// populated with `extract_structured_data`.
+    #[serde(default)]
+    pub metadata: Option<StructuredData>,
+    /// Values of each extraction rule that matched the page, by rule name.
+    #[serde(default)]
+    pub extracted: BTreeMap<String, Vec<String>>,
+    /// The body wasn't downloaded because of its `Content-Type` (images, PDFs, archives
+    /// or anything outside `allowed_content_types`); `content_length` is the declared one.
+    #[serde(default)]
+    pub body_skipped: bool,
+    /// The body was cut at `max_body_size`; only that much was read and parsed.
+    #[serde(default)]
+    pub body_truncated: bool,
+    /// The server answered a conditional re-crawl with 304 Not Modified, so the rest of
+    /// the result is the one cached by `http_cache_dir`.
+    #[serde(default)]
+    pub not_modified: bool,
+    /// The `links` pointing outside the seeds' site (their hosts, or registrable
+    /// domains with `CrawlScope::SameDomain`), whether or not the crawl follows them.
+    #[serde(default)]
+    pub external_links: Vec<String>,
+    /// Images, scripts, stylesheets and media the page loads, only populated with
+    /// `include_assets`; they aren't crawled as pages.
+    #[serde(default)]
+    pub assets: Vec<PageAsset>,
+    /// Images, scripts, stylesheets and media an `https://` page loads over `http://`,
+    /// which browsers block or warn about.
+    #[serde(default)]
+    pub mixed_content: Vec<String>,
+    /// Links of an `https://` page to `http://` URLs.
+    #[serde(default)]
+    pub insecure_links: Vec<String>,
+    /// Why the page, though answered with 200, looks like a not-found page, with
+    /// `detect_soft_404`.
+    #[serde(default)]
+    pub soft_404: Option<Soft404Reason>,
+}
+
+/// One redirect response on the way to a page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectHop {
+    /// URL that answered with the redirect.
+    pub url: String,
+    pub status_code: u16,
+}
+
+/// One `hreflang` alternate of a page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageVariant {
+    /// Language/region code as declared, lowercased (e.g. `en-gb`, `x-default`).
+    pub hreflang: String,
+    pub url: String,
+}
+
+/// A URL that couldn't be crawled, kept alongside the results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlFailure {
+    pub url: String,
+    pub depth: usize,
+    /// The error and its causes.
+    pub reason: String,
+    /// Requests sent for the URL; 0 when it was never requested (robots.txt, bad URL).
+    pub attempts: usize,
+    /// The error itself, for failures from this process; `None` once deserialized.
+    #[serde(skip)]
+    pub error: Option<Arc<CrawlerError>>,
+}
+
+impl CrawlFailure {
+    pub fn format_output(&self) -> String {
+        let url = display_url(&self.url).unwrap_or_else(|| self.url.clone());
+        format!("{} - FAILED - {}", url, self.reason)
+    }
+}
+
+impl CrawlResult {
+    pub fn format_output(&self) -> String {
+        let title = self.title.as_deref().unwrap_or("No title");
+        format!(
+            "{} - {} - {} ({}ms)",
+            self.display_url.as_deref().unwrap_or(&self.url),
+            self.status_code,
+            title,
+            self.crawl_time.as_millis()
+        )
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+fn deserialize_millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+impl Crawler {
+    /// Truncate `html` to `keep_html_max_kb`.
+    pub(super) fn retain_html(&self, mut html: String) -> (Option<String>, bool) {
+        let max_bytes = self.config.keep_html_max_kb * 1024;
+        let truncated = html.len() > max_bytes;
+
+        if truncated {
+            // Cut on a char boundary so the retained body stays valid UTF-8
+            let mut cut = max_bytes;
+            while !html.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            html.truncate(cut);
+        }
+
+        (Some(html), truncated)
+    }
+
+    /// Pages whose language couldn't be determined are kept.
+    pub(super) fn passes_language_filter(&self, result: &CrawlResult) -> bool {
+        if self.config.languages.is_empty() {
+            return true;
+        }
+        match &result.language {
+            Some(code) => language_allowed(code, &self.config.languages),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+
+    #[test]
+    fn test_retain_html_truncates_on_char_boundary() {
+        let config = CrawlerConfig {
+            keep_html: true,
+            keep_html_max_kb: 1,
+            ..Default::default()
+        };
+
+        let crawler = Crawler::new(config).unwrap();
+
+        let (html, truncated) = crawler.retain_html("<p>short</p>".to_string());
+        assert_eq!(html.as_deref(), Some("<p>short</p>"));
+        assert!(!truncated);
+
+        // 'é' is two bytes, so 1024 bytes would split the last character
+        let long = format!("a{}", "é".repeat(600));
+        let (html, truncated) = crawler.retain_html(long);
+        let html = html.unwrap();
+        assert!(truncated);
+        assert_eq!(html.len(), 1023);
+    }
+}
@@ -0,0 +1,122 @@
+use super::{CrawlResult, Crawler, CrawlerError};
+use crate::output::{CrawlReport, ResultSink};
+use log::error;
+
+impl Crawler {
+    /// Send every result this crawler yields to `sink` as well, in yield order.
+    pub fn with_sink(mut self, sink: impl ResultSink + 'static) -> Self {
+        self.sinks.get_mut().push(Box::new(sink));
+        self
+    }
+
+    /// Whether any registered sink needs full results for `close_sinks`.
+    pub async fn sinks_need_full_results(&self) -> bool {
+        self.sinks
+            .lock()
+            .await
+            .iter()
+            .any(|sink| sink.needs_full_results())
+    }
+
+    /// Hand `result` to every sink. A failing sink stops the crawl, as if interrupted,
+    /// and its error is returned from `close_sinks`; nothing more is written after it.
+    pub(super) async fn emit(&self, result: &CrawlResult) {
+        if self.sink_error.lock().unwrap().is_some() {
+            return;
+        }
+        for sink in self.sinks.lock().await.iter_mut() {
+            if let Err(e) = sink.write(result).await {
+                error!("Failed to write {}: {:#}", result.url, e);
+                *self.sink_error.lock().unwrap() = Some(e);
+                self.request_stop();
+                return;
+            }
+        }
+    }
+
+    pub(super) async fn flush_sinks(&self) {
+        for sink in self.sinks.lock().await.iter_mut() {
+            if let Err(e) = sink.flush().await {
+                error!("{:#}", e);
+            }
+        }
+    }
+
+    /// Close every sink with the final report, then drop them. Returns the first error,
+    /// including one that stopped the crawl.
+    pub async fn close_sinks(&self, report: &CrawlReport<'_>) -> Result<(), CrawlerError> {
+        let mut first_error = self.sink_error.lock().unwrap().take();
+        for mut sink in self.sinks.lock().await.drain(..) {
+            if let Err(e) = sink.close(report).await {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), |e| Err(CrawlerError::Sink(e.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// Records calls so the test can check what the crawler sent.
+    #[derive(Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl ResultSink for RecordingSink {
+        async fn write(&mut self, result: &CrawlResult) -> Result<()> {
+            self.0.lock().unwrap().push(format!("write {}", result.url));
+            Ok(())
+        }
+
+        async fn flush(&mut self) -> Result<()> {
+            self.0.lock().unwrap().push("flush".to_string());
+            Ok(())
+        }
+
+        async fn close(&mut self, report: &CrawlReport<'_>) -> Result<()> {
+            let line = format!("close {}", report.results.len());
+            self.0.lock().unwrap().push(line);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sinks_see_every_result_then_close() {
+        use crate::test_server::{test_server, Response};
+
+        let addr = test_server(|_| async { Response::html("<title>Page</title>") }).await;
+
+        let sink = RecordingSink::default();
+        let crawler = Crawler::new(CrawlerConfig {
+            respect_robots: false,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_sink(sink.clone());
+        assert!(!crawler.sinks_need_full_results().await);
+
+        let url = format!("http://{}/", addr);
+        let results = crawler.crawl(vec![url.clone()]).await.unwrap();
+        crawler
+            .close_sinks(&CrawlReport {
+                results: &results,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            [
+                format!("write {}", url),
+                "flush".to_string(),
+                "close 1".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+/// What a response body looks like from its first bytes, regardless of `Content-Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BodyKind {
+    Html,
+    /// Text that isn't recognizably HTML: plain text, JSON, XML, HTML fragments.
+    Text,
+    Binary,
+}
+
+/// Bytes examined when sniffing, as in the WHATWG MIME sniffing standard.
+const SNIFF_LEN: usize = 512;
+
+/// Tags that mark a document as HTML when it starts with one (WHATWG MIME sniffing).
+const HTML_PREFIXES: &[&[u8]] = &[
+    b"<!doctype html",
+    b"<html",
+    b"<head",
+    b"<script",
+    b"<iframe",
+    b"<h1",
+    b"<div",
+    b"<font",
+    b"<table",
+    b"<a",
+    b"<style",
+    b"<title",
+    b"<b",
+    b"<body",
+    b"<br",
+    b"<p",
+    b"<!--",
+];
+
+/// Signatures of common binary formats crawlers run into.
+const BINARY_MAGIC: &[&[u8]] = &[
+    b"\x89PNG",
+    b"GIF8",
+    b"\xff\xd8\xff",
+    b"%PDF-",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"RIFF",
+];
+
+pub(super) fn sniff(body: &[u8]) -> BodyKind {
+    let head = &body[..body.len().min(SNIFF_LEN)];
+    // A UTF-16 BOM means text even though every other byte is NUL
+    if head.starts_with(b"\xfe\xff") || head.starts_with(b"\xff\xfe") {
+        return BodyKind::Text;
+    }
+    if BINARY_MAGIC.iter().any(|magic| head.starts_with(magic))
+        || head.iter().any(|&byte| is_binary_byte(byte))
+    {
+        return BodyKind::Binary;
+    }
+
+    let start = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let start = match start.iter().position(|byte| !byte.is_ascii_whitespace()) {
+        Some(offset) => &start[offset..],
+        None => return BodyKind::Text,
+    };
+    let is_html = HTML_PREFIXES.iter().any(|prefix| {
+        start.len() > prefix.len()
+            && start[..prefix.len()].eq_ignore_ascii_case(prefix)
+            // The tag name has to end here: `<b>` but not `<book>`
+            && matches!(start[prefix.len()], b' ' | b'>' | b'\t' | b'\n' | b'\r' | b'\x0c')
+    });
+    if is_html {
+        BodyKind::Html
+    } else {
+        BodyKind::Text
+    }
+}
+
+/// Control bytes that never appear in text (WHATWG "binary data byte").
+fn is_binary_byte(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0b | 0x0e..=0x1a | 0x1c..=0x1f)
+}
+
+/// Media types whose bodies aren't downloaded unless allowed explicitly: media,
+/// documents and archives, which hold no links the crawler could follow.
+const SKIPPED_TYPES: &[&str] = &[
+    "image/*",
+    "audio/*",
+    "video/*",
+    "font/*",
+    "application/pdf",
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-tar",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/vnd.rar",
+    "application/msword",
+    "application/vnd.ms-*",
+    "application/vnd.openxmlformats-officedocument.*",
+    "application/vnd.oasis.opendocument.*",
+    "application/x-msdownload",
+    "application/x-iso9660-image",
+    "application/x-apple-diskimage",
+    "application/wasm",
+];
+
+/// Media type of a `Content-Type` header, lowercased and without parameters.
+fn mime_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Whether a `Content-Type` header says the body is HTML.
+fn declares_html(content_type: &str) -> bool {
+    let mime = mime_type(content_type);
+    mime == "text/html" || mime == "application/xhtml+xml"
+}
+
+/// Match a media type against `type/subtype`, a trailing `*` wildcard (`image/*`,
+/// `application/vnd.ms-*`) or `*/*`.
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    if pattern == "*/*" || pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime.starts_with(prefix),
+        None => mime == pattern,
+    }
+}
+
+/// Whether to download a body with this `Content-Type`: one matching `allowed`, or
+/// when that's empty, anything but the media, documents and archives in
+/// `SKIPPED_TYPES`. Unlabeled bodies are always downloaded so they can be sniffed.
+pub(super) fn wants_body(content_type: Option<&str>, allowed: &[String]) -> bool {
+    let Some(content_type) = content_type else {
+        return true;
+    };
+    let mime = mime_type(content_type);
+    if allowed.is_empty() {
+        !SKIPPED_TYPES
+            .iter()
+            .any(|pattern| mime_matches(pattern, &mime))
+    } else {
+        allowed.iter().any(|pattern| mime_matches(pattern, &mime))
+    }
+}
+
+/// Decide whether to parse a body as HTML. Returns `(parse, mismatch)`, where `mismatch`
+/// is set when the body contradicts its `Content-Type`: binary data labeled as HTML, or
+/// an HTML document labeled as something else.
+pub(super) fn route_body(content_type: Option<&str>, body: &[u8]) -> (bool, bool) {
+    let declared_html = content_type.is_some_and(declares_html);
+    match sniff(body) {
+        BodyKind::Binary => (false, declared_html),
+        BodyKind::Html => (true, content_type.is_some() && !declared_html),
+        // Fragments and near-empty pages are still parsed when the server says HTML
+        BodyKind::Text => (declared_html || content_type.is_none(), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_body_kinds() {
+        assert_eq!(
+            sniff(b"\xef\xbb\xbf\n  <!DOCTYPE html><html>"),
+            BodyKind::Html
+        );
+        assert_eq!(sniff(b"<P>hello</P>"), BodyKind::Html);
+        assert_eq!(sniff(b"<book>not html</book>"), BodyKind::Text);
+        assert_eq!(sniff(b"{\"a\": 1}"), BodyKind::Text);
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n...."), BodyKind::Binary);
+        assert_eq!(sniff(b"abc\x00def"), BodyKind::Binary);
+        assert_eq!(sniff(b""), BodyKind::Text);
+    }
+
+    #[test]
+    fn test_route_body_flags_mismatches() {
+        let html = b"<!doctype html><title>x</title>".as_slice();
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00".as_slice();
+
+        assert_eq!(
+            route_body(Some("text/html; charset=utf-8"), html),
+            (true, false)
+        );
+        assert_eq!(route_body(Some("text/html"), png), (false, true));
+        assert_eq!(
+            route_body(Some("application/octet-stream"), html),
+            (true, true)
+        );
+        assert_eq!(route_body(Some("image/png"), png), (false, false));
+        assert_eq!(route_body(None, html), (true, false));
+        assert_eq!(route_body(Some("application/json"), b"{}"), (false, false));
+        assert_eq!(route_body(Some("text/html"), b"just text"), (true, false));
+    }
+
+    #[test]
+    fn test_wants_body() {
+        assert!(wants_body(Some("text/html; charset=utf-8"), &[]));
+        assert!(wants_body(Some("application/octet-stream"), &[]));
+        assert!(wants_body(None, &[]));
+        assert!(!wants_body(Some("image/jpeg"), &[]));
+        assert!(!wants_body(Some("Application/PDF"), &[]));
+        assert!(!wants_body(Some("application/vnd.ms-excel"), &[]));
+
+        let allowed = vec!["text/html".to_string(), "application/*".to_string()];
+        assert!(wants_body(Some("text/html"), &allowed));
+        assert!(wants_body(Some("application/pdf"), &allowed));
+        assert!(!wants_body(Some("text/plain"), &allowed));
+        assert!(wants_body(None, &allowed));
+        assert!(wants_body(Some("video/mp4"), &["*/*".to_string()]));
+    }
+}
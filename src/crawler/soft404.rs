@@ -0,0 +1,165 @@
+use super::extract::extract_text;
+use super::fingerprint::simhash;
+use super::Crawler;
+use log::{debug, info};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use url::Url;
+use uuid::Uuid;
+
+/// Title fragments of not-found pages, when `soft_404_titles` is empty.
+pub const DEFAULT_SOFT_404_TITLES: &[&str] = &[
+    "not found",
+    "404",
+    "does not exist",
+    "doesn't exist",
+    "no longer available",
+];
+/// Differing SimHash bits up to which a page counts as the host's not-found page.
+const TEMPLATE_DISTANCE: u32 = 3;
+
+/// Why a 200 page looks like a "soft 404": a not-found page served as a success.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Soft404Reason {
+    /// The title matches one of `soft_404_titles`.
+    NotFoundTitle,
+    /// The text matches what the host serves for a URL that can't exist.
+    NotFoundTemplate,
+    /// The body is smaller than `soft_404_min_body`.
+    TinyBody,
+}
+
+impl Soft404Reason {
+    pub fn label(self) -> &'static str {
+        match self {
+            Soft404Reason::NotFoundTitle => "not-found title",
+            Soft404Reason::NotFoundTemplate => "not-found template",
+            Soft404Reason::TinyBody => "tiny body",
+        }
+    }
+}
+
+/// SimHash of what a host answers with 200 for a made-up URL, found once per host;
+/// `None` when the host answers such URLs properly.
+pub(super) type NotFoundTemplate = Arc<OnceCell<Option<u64>>>;
+
+impl Crawler {
+    /// Whether a successful HTML page is really a not-found page, with `detect_soft_404`.
+    /// `text_hash` is the SimHash of its readable text.
+    pub(super) async fn detect_soft_404(
+        &self,
+        url: &Url,
+        title: Option<&str>,
+        body_len: usize,
+        text_hash: u64,
+    ) -> Option<Soft404Reason> {
+        if let Some(title) = title {
+            let title = title.to_lowercase();
+            let matches = |pattern: &str| title.contains(&pattern.to_lowercase());
+            let found = if self.config.soft_404_titles.is_empty() {
+                DEFAULT_SOFT_404_TITLES
+                    .iter()
+                    .any(|pattern| matches(pattern))
+            } else {
+                self.config
+                    .soft_404_titles
+                    .iter()
+                    .any(|pattern| matches(pattern))
+            };
+            if found {
+                return Some(Soft404Reason::NotFoundTitle);
+            }
+        }
+        if let Some(template) = self.not_found_template(url).await {
+            if (template ^ text_hash).count_ones() <= TEMPLATE_DISTANCE {
+                return Some(Soft404Reason::NotFoundTemplate);
+            }
+        }
+        (body_len < self.config.soft_404_min_body).then_some(Soft404Reason::TinyBody)
+    }
+
+    async fn not_found_template(&self, url: &Url) -> Option<u64> {
+        let origin = url.origin().ascii_serialization();
+        let cell = self
+            .soft_404_templates
+            .entry(origin.clone())
+            .or_default()
+            .clone();
+        *cell
+            .get_or_init(|| async {
+                let template = self.probe_not_found(url).await;
+                if template.is_some() {
+                    info!(
+                        "{} answers unknown URLs with 200; comparing pages to that",
+                        origin
+                    );
+                }
+                template
+            })
+            .await
+    }
+
+    /// Request a random path on `url`'s host; a success without a redirect is the
+    /// host's not-found page. Hosts sending unknown URLs to e.g. the home page don't
+    /// get a template, or the home page would match it.
+    async fn probe_not_found(&self, url: &Url) -> Option<u64> {
+        let mut probe_url = url.clone();
+        probe_url.set_path(&format!("/{}", Uuid::new_v4().simple()));
+        probe_url.set_query(None);
+        if let Err(e) = self.check_robots(&probe_url).await {
+            debug!("Not probing for a not-found page: {}", e.report());
+            return None;
+        }
+        self.wait_for_host_slot(&probe_url).await;
+        let response = self.client.get(probe_url.as_str()).send().await.ok()?;
+        if !response.status().is_success() || response.url() != &probe_url {
+            return None;
+        }
+        let body = response.text().await.ok()?;
+        let text = extract_text(&Html::parse_document(&body)).unwrap_or_default();
+        Some(simhash(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+
+    #[tokio::test]
+    async fn test_detect_soft_404_title_and_size() {
+        let crawler = Crawler::new(CrawlerConfig {
+            detect_soft_404: true,
+            soft_404_titles: vec!["Oops".to_string()],
+            soft_404_min_body: 100,
+            ..Default::default()
+        })
+        .unwrap();
+        // Known template, so nothing is requested
+        let url = Url::parse("http://example.invalid/page").unwrap();
+        crawler.soft_404_templates.insert(
+            url.origin().ascii_serialization(),
+            Arc::new(OnceCell::new_with(Some(Some(0)))),
+        );
+
+        let detect =
+            |title, body_len, text_hash| crawler.detect_soft_404(&url, title, body_len, text_hash);
+        assert_eq!(
+            detect(Some("OOPS, wrong turn"), 5000, u64::MAX).await,
+            Some(Soft404Reason::NotFoundTitle)
+        );
+        // Custom patterns replace the defaults
+        assert_eq!(detect(Some("Page not found"), 5000, u64::MAX).await, None);
+        assert_eq!(
+            detect(Some("Home"), 5000, 0b101).await,
+            Some(Soft404Reason::NotFoundTemplate)
+        );
+        assert_eq!(
+            detect(None, 50, u64::MAX).await,
+            Some(Soft404Reason::TinyBody)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use url::Url;
+
+/// How often page requests switch to the next user agent of a user agent list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum UserAgentRotation {
+    /// Every request uses the next user agent in the list
+    #[default]
+    PerRequest,
+    /// Every request to a host uses the same user agent
+    PerHost,
+}
+
+/// User agents sent with page requests, in turn. robots.txt rules are still matched
+/// against the crawler's own `user_agent`.
+pub(super) struct UserAgentPool {
+    agents: Vec<String>,
+    rotation: UserAgentRotation,
+    next: AtomicUsize,
+    /// User agent assigned to each host, with `UserAgentRotation::PerHost`.
+    by_host: DashMap<String, usize>,
+}
+
+impl UserAgentPool {
+    /// `None` without any user agents.
+    pub(super) fn new(agents: Vec<String>, rotation: UserAgentRotation) -> Option<Self> {
+        (!agents.is_empty()).then(|| Self {
+            agents,
+            rotation,
+            next: AtomicUsize::new(0),
+            by_host: DashMap::new(),
+        })
+    }
+
+    /// User agent for a request to `url`.
+    pub(super) fn pick(&self, url: &str) -> &str {
+        let index = match self.rotation {
+            UserAgentRotation::PerRequest => self.next_index(),
+            UserAgentRotation::PerHost => {
+                let host = Url::parse(url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .unwrap_or_default();
+                *self
+                    .by_host
+                    .entry(host)
+                    .or_insert_with(|| self.next_index())
+            }
+        };
+        &self.agents[index]
+    }
+
+    fn next_index(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % self.agents.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let agents = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let per_request =
+            UserAgentPool::new(agents.clone(), UserAgentRotation::PerRequest).unwrap();
+        let picks: Vec<&str> = (0..4).map(|_| per_request.pick("https://x.com/")).collect();
+        assert_eq!(picks, ["a", "b", "c", "a"]);
+
+        let per_host = UserAgentPool::new(agents, UserAgentRotation::PerHost).unwrap();
+        assert_eq!(per_host.pick("https://x.com/1"), "a");
+        assert_eq!(per_host.pick("https://y.com/"), "b");
+        assert_eq!(per_host.pick("https://x.com/2"), "a");
+
+        assert!(UserAgentPool::new(Vec::new(), UserAgentRotation::PerHost).is_none());
+    }
+}
@@ -0,0 +1,175 @@
+use super::frontier::seeded_hash;
+use dashmap::DashSet;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Share of new URLs a Bloom filter sized for its expected URLs mistakes for visited.
+const BLOOM_FALSE_POSITIVE_RATE: f64 = 0.001;
+/// Seed of the second hash a Bloom filter derives its bit positions from.
+const BLOOM_SECOND_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// How the crawler remembers which URLs it has visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum VisitedStore {
+    /// Every URL in full; exact, and needed for `state_file` checkpoints
+    #[default]
+    Exact,
+    /// A 64-bit hash per URL, a fraction of a URL's size; two URLs sharing a hash is
+    /// vanishingly rare even across tens of millions of URLs
+    Fingerprint,
+    /// A Bloom filter of fixed size for `expected_urls`, about 2 bytes per URL;
+    /// skips about 0.1% of new URLs as already visited, more once it's over capacity
+    Bloom,
+}
+
+/// The visited URLs of a crawl, kept as `VisitedStore` says.
+#[derive(Debug)]
+pub(super) enum VisitedSet {
+    Exact(DashSet<String>),
+    Fingerprint(DashSet<u64>),
+    Bloom(BloomFilter),
+}
+
+impl Default for VisitedSet {
+    fn default() -> Self {
+        VisitedSet::Exact(DashSet::new())
+    }
+}
+
+impl VisitedSet {
+    /// An empty set; `expected_urls` sizes the Bloom filter.
+    pub(super) fn new(store: VisitedStore, expected_urls: usize) -> Self {
+        match store {
+            VisitedStore::Exact => VisitedSet::default(),
+            VisitedStore::Fingerprint => VisitedSet::Fingerprint(DashSet::new()),
+            VisitedStore::Bloom => VisitedSet::Bloom(BloomFilter::new(expected_urls)),
+        }
+    }
+
+    pub(super) fn contains(&self, url: &str) -> bool {
+        match self {
+            VisitedSet::Exact(urls) => urls.contains(url),
+            VisitedSet::Fingerprint(hashes) => hashes.contains(&seeded_hash(url, 0)),
+            VisitedSet::Bloom(filter) => filter.contains(url),
+        }
+    }
+
+    /// Add `url`, returning whether it wasn't already in the set.
+    pub(super) fn insert(&self, url: &str) -> bool {
+        match self {
+            VisitedSet::Exact(urls) => urls.insert(url.to_string()),
+            VisitedSet::Fingerprint(hashes) => hashes.insert(seeded_hash(url, 0)),
+            VisitedSet::Bloom(filter) => filter.insert(url),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        match self {
+            VisitedSet::Exact(urls) => urls.len(),
+            VisitedSet::Fingerprint(hashes) => hashes.len(),
+            VisitedSet::Bloom(filter) => filter.len(),
+        }
+    }
+
+    /// The visited URLs themselves, which only the exact store keeps.
+    pub(super) fn urls(&self) -> Option<Vec<String>> {
+        match self {
+            VisitedSet::Exact(urls) => Some(urls.iter().map(|url| url.key().clone()).collect()),
+            VisitedSet::Fingerprint(_) | VisitedSet::Bloom(_) => None,
+        }
+    }
+}
+
+/// A Bloom filter that threads can add to at once without locking.
+#[derive(Debug)]
+pub(super) struct BloomFilter {
+    words: Vec<AtomicU64>,
+    bits: u64,
+    hashes: u32,
+    /// URLs added, less the ones it took for already added.
+    len: AtomicUsize,
+}
+
+impl BloomFilter {
+    fn new(expected_urls: usize) -> Self {
+        let expected_urls = expected_urls.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-expected_urls * BLOOM_FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil() as u64;
+        let words = bits / 64 + 1;
+        let hashes = ((bits as f64 / expected_urls) * ln2).round().max(1.0) as u32;
+        Self {
+            words: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            bits: words * 64,
+            hashes,
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Bit positions of `url`, by double hashing.
+    fn positions(&self, url: &str) -> impl Iterator<Item = u64> + '_ {
+        let first = seeded_hash(url, 0);
+        let second = seeded_hash(url, BLOOM_SECOND_SEED) | 1;
+        (0..u64::from(self.hashes))
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % self.bits)
+    }
+
+    fn contains(&self, url: &str) -> bool {
+        self.positions(url).all(|bit| {
+            self.words[(bit / 64) as usize].load(Ordering::Relaxed) & (1 << (bit % 64)) != 0
+        })
+    }
+
+    fn insert(&self, url: &str) -> bool {
+        let mut added = false;
+        for bit in self.positions(url) {
+            let mask = 1 << (bit % 64);
+            let previous = self.words[(bit / 64) as usize].fetch_or(mask, Ordering::Relaxed);
+            added |= previous & mask == 0;
+        }
+        if added {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+        added
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visited_stores() {
+        for store in [
+            VisitedStore::Exact,
+            VisitedStore::Fingerprint,
+            VisitedStore::Bloom,
+        ] {
+            let visited = VisitedSet::new(store, 1000);
+            assert!(visited.insert("https://example.com/"));
+            assert!(!visited.insert("https://example.com/"));
+            assert!(visited.contains("https://example.com/"));
+            assert!(!visited.contains("https://example.com/other"));
+            assert_eq!(visited.len(), 1);
+            assert_eq!(visited.urls().is_some(), store == VisitedStore::Exact);
+        }
+    }
+
+    #[test]
+    fn test_bloom_false_positives_stay_near_target() {
+        let filter = BloomFilter::new(10_000);
+        for i in 0..10_000 {
+            filter.insert(&format!("https://example.com/page/{}", i));
+        }
+        assert!((0..10_000).all(|i| filter.contains(&format!("https://example.com/page/{}", i))));
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(&format!("https://example.com/other/{}", i)))
+            .count();
+        assert!(false_positives < 50, "{}", false_positives);
+    }
+}
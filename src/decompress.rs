@@ -0,0 +1,112 @@
+use anyhow::{bail, Context, Result};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::Response;
+use std::io::Read;
+
+/// Largest decompressed body accepted; the sitemap protocol caps files at 50 MB.
+pub const MAX_DECOMPRESSED_BYTES: usize = 50 * 1024 * 1024;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read a response body as text, inflating gzip or deflate payloads. The HTTP client
+/// doesn't decompress on its own, and robots.txt and sitemaps are often served
+/// gzipped with a missing or wrong `Content-Encoding`, so the body is sniffed as well.
+pub async fn response_text(response: Response) -> Result<String> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_ascii_lowercase)
+    };
+    let encoding = header(CONTENT_ENCODING);
+    let body = response.bytes().await?;
+    let body = decompress(&body, encoding.as_deref())?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Inflate `body` if its leading bytes or `Content-Encoding` say it is compressed.
+/// gzip is recognized by its magic bytes alone, so a body labeled gzip that isn't
+/// is used as is. A body that only looks like zlib but fails to inflate is returned
+/// unchanged.
+pub fn decompress(body: &[u8], content_encoding: Option<&str>) -> Result<Vec<u8>> {
+    let declared_deflate = content_encoding.is_some_and(|e| e.contains("deflate"));
+
+    if body.starts_with(&GZIP_MAGIC) {
+        return inflate(GzDecoder::new(body)).context("Invalid gzip body");
+    }
+    if looks_like_zlib(body) {
+        match inflate(ZlibDecoder::new(body)) {
+            Ok(inflated) => return Ok(inflated),
+            Err(e) if declared_deflate => return Err(e.context("Invalid deflate body")),
+            // Plain text that happens to start like a zlib header
+            Err(_) => {}
+        }
+    }
+    if declared_deflate {
+        // Some servers send raw deflate without the zlib wrapper
+        if let Ok(inflated) = inflate(DeflateDecoder::new(body)) {
+            return Ok(inflated);
+        }
+    }
+    Ok(body.to_vec())
+}
+
+/// zlib header: deflate method, a window of at most 32K and a valid check value.
+fn looks_like_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => {
+            cmf & 0x0f == 8 && cmf >> 4 <= 7 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+fn inflate(decoder: impl Read) -> Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSED_BYTES as u64 + 1)
+        .read_to_end(&mut inflated)?;
+    if inflated.len() > MAX_DECOMPRESSED_BYTES {
+        bail!(
+            "Decompressed body is larger than {} bytes",
+            MAX_DECOMPRESSED_BYTES
+        );
+    }
+    Ok(inflated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    const ROBOTS: &str = "User-agent: *\nDisallow: /private\n";
+
+    #[test]
+    fn test_decompress_by_magic_bytes_and_header() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(ROBOTS.as_bytes()).unwrap();
+        let gz = gz.finish().unwrap();
+        // No Content-Encoding, as when a .gz file is served as is
+        assert_eq!(decompress(&gz, None).unwrap(), ROBOTS.as_bytes());
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(ROBOTS.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        assert_eq!(
+            decompress(&zlib, Some("deflate")).unwrap(),
+            ROBOTS.as_bytes()
+        );
+
+        // Uncompressed bodies pass through, even when labeled gzip
+        assert_eq!(
+            decompress(ROBOTS.as_bytes(), Some("gzip")).unwrap(),
+            ROBOTS.as_bytes()
+        );
+        assert_eq!(decompress(b"x^ plain", None).unwrap(), b"x^ plain");
+    }
+}
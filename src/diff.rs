@@ -0,0 +1,312 @@
+use crate::crawler::CrawlResult;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// First bytes of every SQLite database file.
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Crawled now but not in the previous crawl.
+    Added,
+    /// In the previous crawl but not crawled now.
+    Removed,
+    /// Crawled both times with a different status, title or body.
+    Changed,
+}
+
+impl ChangeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// One field of a page that differs between the two crawls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    /// `status_code`, `title` or `content_hash`.
+    pub field: &'static str,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// A page that was added, removed or changed since the previous crawl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageChange {
+    pub kind: ChangeKind,
+    pub url: String,
+    /// What differs, for changed pages.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+impl FieldChange {
+    pub fn format_output(&self) -> String {
+        match self.field {
+            // Hashes mean nothing to a reader; that the body changed is enough
+            "content_hash" => "content".to_string(),
+            field => format!(
+                "{} {} -> {}",
+                field,
+                self.before.as_deref().unwrap_or("none"),
+                self.after.as_deref().unwrap_or("none")
+            ),
+        }
+    }
+}
+
+impl PageChange {
+    /// What differs, e.g. `status_code 200 -> 404, content`; empty unless changed.
+    pub fn detail(&self) -> String {
+        let fields: Vec<String> = self.fields.iter().map(FieldChange::format_output).collect();
+        fields.join(", ")
+    }
+
+    pub fn format_output(&self) -> String {
+        let mut line = format!("[{}] {}", self.kind.label(), self.url);
+        if !self.fields.is_empty() {
+            line.push_str(": ");
+            line.push_str(&self.detail());
+        }
+        line
+    }
+}
+
+/// How a crawl differs from a previous crawl of the same site, for `--diff-against`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CrawlDiff {
+    /// Run the crawl was compared with, if the previous results name one.
+    pub previous_run_id: Option<String>,
+    /// Pages crawled both times without a difference.
+    pub unchanged: usize,
+    /// Added, then removed, then changed pages, each by URL.
+    pub changes: Vec<PageChange>,
+}
+
+impl CrawlDiff {
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .count()
+    }
+}
+
+/// Compare two crawls page by page, matching pages by URL. A page changed when its
+/// status code, title or `content_hash` differs; hashes are only compared when both
+/// crawls have one, so pages whose body wasn't downloaded don't count as changed.
+/// Pages missing from a crawl that stopped early count as removed.
+pub fn diff_crawls(previous: &[CrawlResult], current: &[CrawlResult]) -> CrawlDiff {
+    // Resumed crawls can list a page twice; the later copy is the one that counts
+    let by_url = |results: &[CrawlResult]| -> BTreeMap<String, CrawlResult> {
+        results
+            .iter()
+            .map(|result| (result.url.clone(), result.clone()))
+            .collect()
+    };
+    let previous_pages = by_url(previous);
+    let current_pages = by_url(current);
+
+    let mut diff = CrawlDiff {
+        previous_run_id: previous
+            .first()
+            .map(|result| result.run_id.clone())
+            .filter(|run_id| !run_id.is_empty()),
+        ..Default::default()
+    };
+    for (url, page) in &current_pages {
+        let Some(before) = previous_pages.get(url) else {
+            diff.changes.push(PageChange {
+                kind: ChangeKind::Added,
+                url: url.clone(),
+                fields: Vec::new(),
+            });
+            continue;
+        };
+        let fields = changed_fields(before, page);
+        if fields.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changes.push(PageChange {
+                kind: ChangeKind::Changed,
+                url: url.clone(),
+                fields,
+            });
+        }
+    }
+    diff.changes.extend(
+        previous_pages
+            .keys()
+            .filter(|url| !current_pages.contains_key(*url))
+            .map(|url| PageChange {
+                kind: ChangeKind::Removed,
+                url: url.clone(),
+                fields: Vec::new(),
+            }),
+    );
+    diff.changes
+        .sort_by(|a, b| (a.kind, &a.url).cmp(&(b.kind, &b.url)));
+    diff
+}
+
+fn changed_fields(before: &CrawlResult, after: &CrawlResult) -> Vec<FieldChange> {
+    let mut fields = Vec::new();
+    if before.status_code != after.status_code {
+        fields.push(FieldChange {
+            field: "status_code",
+            before: Some(before.status_code.to_string()),
+            after: Some(after.status_code.to_string()),
+        });
+    }
+    if before.title != after.title {
+        fields.push(FieldChange {
+            field: "title",
+            before: before.title.clone(),
+            after: after.title.clone(),
+        });
+    }
+    if let (Some(old), Some(new)) = (&before.content_hash, &after.content_hash) {
+        if old != new {
+            fields.push(FieldChange {
+                field: "content_hash",
+                before: Some(old.clone()),
+                after: Some(new.clone()),
+            });
+        }
+    }
+    fields
+}
+
+/// Results of an earlier crawl to diff against: a `--output-format json` report, an
+/// NDJSON file of results, or a `--sqlite` database (its most recent run).
+pub fn load_crawl_results(path: &Path) -> Result<Vec<CrawlResult>> {
+    let data = fs::read(path)
+        .with_context(|| format!("Failed to read previous crawl {}", path.display()))?;
+    if data.starts_with(SQLITE_HEADER) {
+        #[cfg(feature = "sqlite")]
+        return crate::storage::load_latest_run(path);
+        #[cfg(not(feature = "sqlite"))]
+        bail!(
+            "{} is a SQLite database, but this build has no SQLite support",
+            path.display()
+        );
+    }
+    let text = std::str::from_utf8(&data)
+        .with_context(|| format!("Previous crawl {} is not UTF-8", path.display()))?;
+    parse_crawl_results(text)
+        .with_context(|| format!("Failed to parse previous crawl {}", path.display()))
+}
+
+fn parse_crawl_results(text: &str) -> Result<Vec<CrawlResult>> {
+    // A JSON report is one document; an NDJSON file with one line parses as one too
+    if let Ok(document) = serde_json::from_str::<Value>(text) {
+        match document {
+            Value::Object(mut report) if report.contains_key("results") => {
+                let results = report.remove("results").unwrap_or_default();
+                return serde_json::from_value(results).context("Invalid results in report");
+            }
+            Value::Array(results) => {
+                return serde_json::from_value(Value::Array(results))
+                    .context("Invalid results array");
+            }
+            _ => {}
+        }
+    }
+    let mut results = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = serde_json::from_str(line)
+            .with_context(|| format!("Invalid result on line {}", number + 1))?;
+        results.push(result);
+    }
+    if results.is_empty() {
+        bail!("No results found");
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn page(url: &str, status_code: u16, title: &str, hash: Option<&str>) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code,
+            title: Some(title.to_string()),
+            crawl_time: Duration::from_millis(10),
+            run_id: "before".to_string(),
+            final_url: url.to_string(),
+            content_hash: hash.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_crawls() {
+        let previous = vec![
+            page("https://x.com/", 200, "Home", Some("a")),
+            page("https://x.com/about", 200, "About", Some("b")),
+            page("https://x.com/old", 200, "Old", Some("c")),
+            page("https://x.com/pdf", 200, "", None),
+        ];
+        let current = vec![
+            page("https://x.com/", 200, "Home", Some("a")),
+            page("https://x.com/about", 404, "Not found", Some("d")),
+            page("https://x.com/new", 200, "New", Some("e")),
+            page("https://x.com/pdf", 200, "", Some("f")),
+        ];
+        let diff = diff_crawls(&previous, &current);
+
+        assert_eq!(diff.previous_run_id.as_deref(), Some("before"));
+        assert_eq!(diff.unchanged, 2);
+        let summary: Vec<(ChangeKind, &str)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.kind, change.url.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ChangeKind::Added, "https://x.com/new"),
+                (ChangeKind::Removed, "https://x.com/old"),
+                (ChangeKind::Changed, "https://x.com/about"),
+            ]
+        );
+        assert_eq!(
+            diff.changes[2].format_output(),
+            "[changed] https://x.com/about: status_code 200 -> 404, title About -> Not found, content"
+        );
+        assert_eq!(diff.count(ChangeKind::Removed), 1);
+    }
+
+    #[test]
+    fn test_parse_report_and_ndjson() {
+        let results = vec![page("https://x.com/", 200, "Home", None)];
+        let ndjson = format!(
+            "{}\n\n{}\n",
+            serde_json::to_string(&results[0]).unwrap(),
+            serde_json::to_string(&page("https://x.com/a", 200, "A", None)).unwrap()
+        );
+        assert_eq!(parse_crawl_results(&ndjson).unwrap().len(), 2);
+
+        let report = serde_json::json!({ "run": null, "results": results });
+        let parsed = parse_crawl_results(&report.to_string()).unwrap();
+        assert_eq!(parsed[0].title.as_deref(), Some("Home"));
+
+        assert!(parse_crawl_results("").is_err());
+        assert!(parse_crawl_results("not json\n").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::text::collapse_whitespace;
+#[cfg(feature = "rule-files")]
+use anyhow::Context;
+use anyhow::{bail, Result};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "rule-files")]
+use std::fs;
+#[cfg(feature = "rule-files")]
+use std::path::Path;
+use url::Url;
+
+/// Attributes holding a link, whose values are resolved against the page URL.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "action", "poster", "data"];
+
+/// One `--extract` rule: what to pull out of every page into its `extracted` map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractionRule {
+    /// Key of the values in the page's `extracted` map, e.g. `price`.
+    pub name: String,
+    /// CSS selector evaluated against the page; every match contributes a value.
+    pub selector: String,
+    /// Attribute to take from matched elements instead of their text, e.g. `href`.
+    #[serde(default)]
+    pub attribute: Option<String>,
+}
+
+impl ExtractionRule {
+    /// Parse a `NAME=SELECTOR` flag value, with an optional `@ATTRIBUTE` suffix, e.g.
+    /// `price=.product .price` or `links=a.next@href`.
+    pub fn parse(value: &str) -> Result<Self> {
+        let Some((name, selector)) = value.split_once('=') else {
+            bail!(
+                "Invalid extraction rule '{}'; expected NAME=SELECTOR, e.g. price=.price",
+                value
+            );
+        };
+        // `@` only starts an attribute when a plain attribute name follows, so
+        // selectors like `a[href^="mailto:a@b"]` stay whole
+        let (selector, attribute) = match selector.rsplit_once('@') {
+            Some((selector, attribute)) if is_attribute_name(attribute.trim()) => {
+                (selector, Some(attribute.trim().to_string()))
+            }
+            _ => (selector, None),
+        };
+        Ok(Self {
+            name: name.trim().to_string(),
+            selector: selector.trim().to_string(),
+            attribute,
+        })
+    }
+}
+
+fn is_attribute_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || "-_:.".contains(c))
+}
+
+#[cfg(any(feature = "rule-files", test))]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: Vec<ExtractionRule>,
+}
+
+/// Read extraction rules from a YAML file with a top-level `rules:` list.
+#[cfg(feature = "rule-files")]
+pub fn load_extraction_rules(path: &Path) -> Result<Vec<ExtractionRule>> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read extraction rules {}", path.display()))?;
+    let file: RulesFile = serde_yaml::from_str(&data)
+        .with_context(|| format!("Failed to parse extraction rules {}", path.display()))?;
+    Extractor::new(&file.rules).with_context(|| format!("Invalid rule in {}", path.display()))?;
+    Ok(file.rules)
+}
+
+/// Extraction rules compiled once per crawl.
+#[derive(Debug, Default)]
+pub struct Extractor {
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    name: String,
+    selector: Selector,
+    attribute: Option<String>,
+}
+
+impl Extractor {
+    pub fn new(rules: &[ExtractionRule]) -> Result<Self> {
+        let mut names = HashSet::new();
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            if rule.name.is_empty() {
+                bail!("Extraction rule for {:?} needs a name", rule.selector);
+            }
+            if !names.insert(rule.name.as_str()) {
+                bail!("Duplicate extraction rule name {}", rule.name);
+            }
+            let selector = Selector::parse(&rule.selector).map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid selector {:?} for extraction rule {}: {}",
+                    rule.selector,
+                    rule.name,
+                    e
+                )
+            })?;
+            compiled.push(CompiledRule {
+                name: rule.name.clone(),
+                selector,
+                attribute: rule.attribute.clone(),
+            });
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Values of every rule with at least one match, in document order: the element's
+    /// text with whitespace collapsed, or the attribute's value, skipping empty ones.
+    /// Link attributes such as `href` and `src` are made absolute against `base_url`.
+    pub fn extract(&self, document: &Html, base_url: &Url) -> BTreeMap<String, Vec<String>> {
+        let mut extracted = BTreeMap::new();
+        for rule in &self.rules {
+            let values: Vec<String> = document
+                .select(&rule.selector)
+                .filter_map(|element| {
+                    let value = match &rule.attribute {
+                        Some(attribute) => {
+                            let value = element.value().attr(attribute)?.trim();
+                            if URL_ATTRIBUTES.contains(&attribute.as_str()) && !value.is_empty() {
+                                base_url
+                                    .join(value)
+                                    .map_or_else(|_| value.to_string(), String::from)
+                            } else {
+                                value.to_string()
+                            }
+                        }
+                        None => collapse_whitespace(&element.text().collect::<String>()),
+                    };
+                    (!value.is_empty()).then_some(value)
+                })
+                .collect();
+            if !values.is_empty() {
+                extracted.insert(rule.name.clone(), values);
+            }
+        }
+        extracted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flag() {
+        let rule = ExtractionRule::parse("price=.product .price").unwrap();
+        assert_eq!(rule.name, "price");
+        assert_eq!(rule.selector, ".product .price");
+        assert_eq!(rule.attribute, None);
+
+        let rule = ExtractionRule::parse("next=a[rel=next]@href").unwrap();
+        assert_eq!(rule.selector, "a[rel=next]");
+        assert_eq!(rule.attribute.as_deref(), Some("href"));
+
+        let rule = ExtractionRule::parse(r#"mail=a[href^="mailto:a@b.com"]"#).unwrap();
+        assert_eq!(rule.selector, r#"a[href^="mailto:a@b.com"]"#);
+        assert_eq!(rule.attribute, None);
+
+        assert!(ExtractionRule::parse(".price").is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let rules: RulesFile = serde_yaml::from_str(
+            r#"
+rules:
+  - name: price
+    selector: ".price"
+  - name: images
+    selector: "img.product"
+    attribute: src
+  - name: sku
+    selector: "[data-sku]"
+    attribute: data-sku
+  - name: missing
+    selector: ".nothing"
+"#,
+        )
+        .unwrap();
+        let extractor = Extractor::new(&rules.rules).unwrap();
+        let document = Html::parse_document(
+            r#"<span class="price">
+                 19.99 EUR </span>
+               <img class="product" src="/a.jpg"><img class="product" src="b.jpg">
+               <div data-sku="SKU-1"></div><span class="price"></span>"#,
+        );
+        let base = Url::parse("https://shop.example/items/lamp").unwrap();
+        let extracted = extractor.extract(&document, &base);
+        assert_eq!(extracted["price"], ["19.99 EUR"]);
+        assert_eq!(
+            extracted["images"],
+            [
+                "https://shop.example/a.jpg",
+                "https://shop.example/items/b.jpg"
+            ]
+        );
+        assert_eq!(extracted["sku"], ["SKU-1"]);
+        assert!(!extracted.contains_key("missing"));
+
+        let duplicate = vec![rules.rules[0].clone(), rules.rules[0].clone()];
+        assert!(Extractor::new(&duplicate).is_err());
+        let invalid = ExtractionRule::parse("bad=div[").unwrap();
+        assert!(Extractor::new(&[invalid]).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::crawler::CrawlResult;
+use crate::output::{CrawlReport, ResultSink};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// File format of `--export-graph`, picked by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz (`.dot`, `.gv`)
+    Dot,
+    /// GraphML for Gephi, yEd or networkx (`.graphml`)
+    GraphMl,
+    /// One `{"source", "target"}` edge per line (`.jsonl`, `.ndjson`)
+    Jsonl,
+}
+
+impl GraphFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "dot" | "gv" => Ok(Self::Dot),
+            "graphml" => Ok(Self::GraphMl),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            _ => bail!(
+                "Can't tell the graph format of {}; use a .dot, .graphml or .jsonl file",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// A page of the link graph: a crawled page, or a link target that wasn't crawled
+/// (out of scope, past the depth limit or beyond `max_pages`).
+#[derive(Debug, Default)]
+struct Node {
+    url: String,
+    status_code: Option<u16>,
+    title: Option<String>,
+    depth: Option<usize>,
+}
+
+/// Link graph of the crawl, collected from each result's links as it arrives, so
+/// results don't have to be kept for it.
+#[derive(Debug, Default)]
+pub struct LinkGraph {
+    nodes: Vec<Node>,
+    ids: HashMap<String, usize>,
+    edges: Vec<(usize, usize)>,
+    seen_edges: HashSet<(usize, usize)>,
+}
+
+impl LinkGraph {
+    /// Add a crawled page and an edge to each of its links. Adding a page again (as
+    /// resumed crawls do) doesn't duplicate its edges.
+    pub fn add(&mut self, result: &CrawlResult) {
+        let source = self.node(&result.url);
+        let node = &mut self.nodes[source];
+        node.status_code = Some(result.status_code);
+        node.title = result.title.clone();
+        node.depth = Some(result.depth);
+        for link in &result.links {
+            let target = self.node(link);
+            if self.seen_edges.insert((source, target)) {
+                self.edges.push((source, target));
+            }
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn node(&mut self, url: &str) -> usize {
+        if let Some(&id) = self.ids.get(url) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            url: url.to_string(),
+            ..Default::default()
+        });
+        self.ids.insert(url.to_string(), id);
+        id
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.render_dot(),
+            GraphFormat::GraphMl => self.render_graphml(),
+            GraphFormat::Jsonl => self.render_jsonl(),
+        }
+    }
+
+    /// Nodes are labeled with their URL; uncrawled ones are drawn dashed.
+    fn render_dot(&self) -> String {
+        let mut out = String::from("digraph crawl {\n  node [shape=box];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let _ = write!(out, "  n{} [label={}", id, dot_string(&node.url));
+            match node.status_code {
+                Some(status) => {
+                    let _ = write!(out, ", status={}", status);
+                }
+                None => out.push_str(", style=dashed"),
+            }
+            if let Some(title) = &node.title {
+                let _ = write!(out, ", tooltip={}", dot_string(title));
+            }
+            out.push_str("];\n");
+        }
+        for (source, target) in &self.edges {
+            let _ = writeln!(out, "  n{} -> n{};", source, target);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_graphml(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+             \x20 <key id=\"url\" for=\"node\" attr.name=\"url\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"status\" for=\"node\" attr.name=\"status_code\" attr.type=\"int\"/>\n\
+             \x20 <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n\
+             \x20 <graph id=\"crawl\" edgedefault=\"directed\">\n",
+        );
+        for (id, node) in self.nodes.iter().enumerate() {
+            let _ = write!(
+                out,
+                "    <node id=\"n{}\"><data key=\"url\">{}</data>",
+                id,
+                xml_escape(&node.url)
+            );
+            if let Some(title) = &node.title {
+                let _ = write!(out, "<data key=\"title\">{}</data>", xml_escape(title));
+            }
+            if let Some(status) = node.status_code {
+                let _ = write!(out, "<data key=\"status\">{}</data>", status);
+            }
+            if let Some(depth) = node.depth {
+                let _ = write!(out, "<data key=\"depth\">{}</data>", depth);
+            }
+            out.push_str("</node>\n");
+        }
+        for (source, target) in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge source=\"n{}\" target=\"n{}\"/>",
+                source, target
+            );
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn render_jsonl(&self) -> String {
+        let mut out = String::new();
+        for (source, target) in &self.edges {
+            let edge = json!({
+                "source": self.nodes[*source].url,
+                "target": self.nodes[*target].url,
+            });
+            let _ = writeln!(out, "{}", edge);
+        }
+        out
+    }
+}
+
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the crawl's link graph to a file for `--export-graph` when the crawl ends.
+pub struct GraphSink {
+    format: GraphFormat,
+    out: BufWriter<File>,
+    graph: LinkGraph,
+}
+
+impl GraphSink {
+    /// Create the graph file up front, so a bad path fails before the crawl.
+    pub fn create(path: &Path) -> Result<Self> {
+        let format = GraphFormat::from_path(path)?;
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create graph file {}", path.display()))?;
+        Ok(Self {
+            format,
+            out: BufWriter::new(file),
+            graph: LinkGraph::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl ResultSink for GraphSink {
+    async fn write(&mut self, result: &CrawlResult) -> Result<()> {
+        self.graph.add(result);
+        Ok(())
+    }
+
+    async fn close(&mut self, _report: &CrawlReport<'_>) -> Result<()> {
+        self.out
+            .write_all(self.graph.render(self.format).as_bytes())
+            .and_then(|_| self.out.flush())
+            .context("Failed to write graph file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn page(url: &str, title: &str, links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code: 200,
+            title: Some(title.to_string()),
+            links: links.iter().map(|link| link.to_string()).collect(),
+            crawl_time: Duration::from_millis(10),
+            final_url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_link_graph_formats() {
+        let mut graph = LinkGraph::default();
+        let home = page(
+            "https://x.com/",
+            "Home \"x\"",
+            &["https://x.com/a", "https://y.com/"],
+        );
+        graph.add(&home);
+        graph.add(&page("https://x.com/a", "A & B", &["https://x.com/"]));
+        graph.add(&home);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 3);
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.contains(
+            "  n0 [label=\"https://x.com/\", status=200, tooltip=\"Home \\\"x\\\"\"];\n"
+        ));
+        assert!(dot.contains("  n2 [label=\"https://y.com/\", style=dashed];\n"));
+        assert!(dot.contains("  n1 -> n0;\n"));
+
+        let graphml = graph.render(GraphFormat::GraphMl);
+        assert!(graphml.contains("<data key=\"title\">A &amp; B</data>"));
+        assert!(graphml.contains("<edge source=\"n0\" target=\"n2\"/>"));
+
+        let jsonl = graph.render(GraphFormat::Jsonl);
+        assert_eq!(
+            jsonl.lines().next().unwrap(),
+            r#"{"source":"https://x.com/","target":"https://x.com/a"}"#
+        );
+
+        assert_eq!(
+            GraphFormat::from_path(Path::new("site.GraphML")).unwrap(),
+            GraphFormat::GraphMl
+        );
+        assert!(GraphFormat::from_path(Path::new("site.png")).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use crate::crawler::CrawlResult;
+use crate::markdown::broken_links;
+use crate::output::{CrawlReport, ResultSink};
+use crate::urls::display_url;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Pages listed under "Slowest pages".
+const SLOWEST_PAGES: usize = 20;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #222; }
+h1 { margin-bottom: 0.25rem; }
+h2 { margin-top: 2.5rem; border-bottom: 1px solid #ddd; padding-bottom: 0.25rem; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9rem; }
+th, td { text-align: left; padding: 0.3rem 0.5rem; border-bottom: 1px solid #eee; vertical-align: top; }
+th { background: #f6f6f6; }
+td.num { text-align: right; white-space: nowrap; }
+td.url { word-break: break-all; }
+.stats { display: flex; flex-wrap: wrap; gap: 1rem; padding: 0; list-style: none; }
+.stats li { background: #f6f6f6; border-radius: 6px; padding: 0.75rem 1rem; min-width: 9rem; }
+.stats b { display: block; font-size: 1.5rem; }
+.s2 { color: #1a7f37; } .s3 { color: #9a6700; } .s4, .s5, .s0 { color: #cf222e; }
+#search { width: 100%; padding: 0.5rem; font-size: 1rem; margin-bottom: 0.5rem; box-sizing: border-box; }
+.muted { color: #666; }
+";
+
+/// Filters the page table as the user types; matches URL, status and title.
+const SCRIPT: &str = "
+const search = document.getElementById('search');
+const rows = Array.from(document.querySelectorAll('#pages tbody tr'));
+const shown = document.getElementById('shown');
+search.addEventListener('input', () => {
+  const terms = search.value.toLowerCase().split(/\\s+/).filter(Boolean);
+  let count = 0;
+  for (const row of rows) {
+    const text = row.textContent.toLowerCase();
+    const match = terms.every(term => text.includes(term));
+    row.hidden = !match;
+    if (match) count++;
+  }
+  shown.textContent = count;
+});
+";
+
+/// Render a crawl report as a self-contained HTML page: summary stats, a status code
+/// breakdown, the slowest pages, broken links, duplicate titles, failed URLs and a
+/// searchable table of every page. Needs no network access to view.
+pub fn render_report(report: &CrawlReport) -> String {
+    let results = report.results;
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Crawl report</title>\n<style>",
+    );
+    out.push_str(STYLE);
+    out.push_str("</style>\n</head>\n<body>\n<h1>Crawl report</h1>\n");
+    if let Some(run) = &report.run {
+        let _ = writeln!(
+            out,
+            "<p class=\"muted\">Run <code>{}</code></p>",
+            escape(&run.run_id)
+        );
+    }
+
+    let errors = results.iter().filter(|r| r.status_code >= 400).count();
+    let broken = broken_links(results);
+    let _ = write!(
+        out,
+        "<ul class=\"stats\">\n<li><b>{}</b>pages crawled</li>\n<li><b>{}</b>error pages</li>\n\
+         <li><b>{}</b>broken links</li>\n",
+        results.len(),
+        errors,
+        broken.len()
+    );
+    if !report.failures.is_empty() {
+        let _ = writeln!(out, "<li><b>{}</b>failed URLs</li>", report.failures.len());
+    }
+    if !results.is_empty() {
+        let total_ms: u128 = results.iter().map(|r| r.crawl_time.as_millis()).sum();
+        let _ = writeln!(
+            out,
+            "<li><b>{}ms</b>average response time</li>",
+            total_ms / results.len() as u128
+        );
+    }
+    out.push_str("</ul>\n");
+
+    let mut by_status: BTreeMap<u16, usize> = BTreeMap::new();
+    for result in results {
+        *by_status.entry(result.status_code).or_default() += 1;
+    }
+    if !by_status.is_empty() {
+        out.push_str(
+            "<h2>Status codes</h2>\n<table>\n<tr><th>Status</th><th>Pages</th><th>Share</th></tr>\n",
+        );
+        for (status, count) in &by_status {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td></tr>",
+                status_cell(*status),
+                count,
+                *count as f64 * 100.0 / results.len() as f64
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    let mut slowest: Vec<&CrawlResult> = results.iter().filter(|r| r.status_code != 0).collect();
+    slowest.sort_by_key(|r| Reverse(r.crawl_time));
+    if !slowest.is_empty() {
+        out.push_str(
+            "<h2>Slowest pages</h2>\n<table>\n<tr><th>Time</th><th>Status</th><th>URL</th></tr>\n",
+        );
+        for result in slowest.iter().take(SLOWEST_PAGES) {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"num\">{}ms</td><td>{}</td><td class=\"url\">{}</td></tr>",
+                result.crawl_time.as_millis(),
+                status_cell(result.status_code),
+                url_link(&result.url)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Broken links</h2>\n");
+    if broken.is_empty() {
+        out.push_str("<p>No links to error pages.</p>\n");
+    } else {
+        out.push_str("<table>\n<tr><th>Link</th><th>Status</th><th>Linked from</th></tr>\n");
+        for (link, status, sources) in &broken {
+            let sources: Vec<String> = sources.iter().map(|source| url_link(source)).collect();
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"url\">{}</td><td>{}</td><td class=\"url\">{}</td></tr>",
+                url_link(link),
+                status_cell(*status),
+                sources.join("<br>")
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    let duplicates = duplicate_titles(results);
+    out.push_str("<h2>Duplicate titles</h2>\n");
+    if duplicates.is_empty() {
+        out.push_str("<p>Every successful page has its own title.</p>\n");
+    } else {
+        out.push_str("<table>\n<tr><th>Title</th><th>Pages</th><th>URLs</th></tr>\n");
+        for (title, urls) in &duplicates {
+            let urls: Vec<String> = urls.iter().map(|url| url_link(url)).collect();
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"url\">{}</td></tr>",
+                escape(title),
+                urls.len(),
+                urls.join("<br>")
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if !report.failures.is_empty() {
+        out.push_str(
+            "<h2>Failed URLs</h2>\n<table>\n<tr><th>URL</th><th>Depth</th><th>Attempts</th>\
+             <th>Reason</th></tr>\n",
+        );
+        for failure in &report.failures {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"url\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td>{}</td></tr>",
+                url_link(&failure.url),
+                failure.depth,
+                failure.attempts,
+                escape(&failure.reason)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    let _ = write!(
+        out,
+        "<h2>Pages</h2>\n<input id=\"search\" type=\"search\" placeholder=\"Filter by URL, status or title\">\n\
+         <p class=\"muted\">Showing <span id=\"shown\">{}</span> of {} pages.</p>\n\
+         <table id=\"pages\">\n<thead><tr><th>URL</th><th>Status</th><th>Title</th><th>Depth</th>\
+         <th>Time</th></tr></thead>\n<tbody>\n",
+        results.len(),
+        results.len()
+    );
+    for result in results {
+        let _ = writeln!(
+            out,
+            "<tr><td class=\"url\">{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}ms</td></tr>",
+            url_link(&result.url),
+            status_cell(result.status_code),
+            escape(result.title.as_deref().unwrap_or("")),
+            result.depth,
+            result.crawl_time.as_millis()
+        );
+    }
+    out.push_str("</tbody>\n</table>\n<script>");
+    out.push_str(SCRIPT);
+    out.push_str("</script>\n</body>\n</html>\n");
+    out
+}
+
+/// Titles shared by more than one 2xx page, with those pages, most shared first.
+fn duplicate_titles(results: &[CrawlResult]) -> Vec<(&str, Vec<&str>)> {
+    let mut by_title: HashMap<&str, Vec<&str>> = HashMap::new();
+    for result in results {
+        let Some(title) = result.title.as_deref().filter(|title| !title.is_empty()) else {
+            continue;
+        };
+        if (200..300).contains(&result.status_code) {
+            by_title.entry(title).or_default().push(&result.url);
+        }
+    }
+    let mut duplicates: Vec<(&str, Vec<&str>)> = by_title
+        .into_iter()
+        .filter(|(_, urls)| urls.len() > 1)
+        .collect();
+    duplicates.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+    duplicates
+}
+
+fn status_cell(status: u16) -> String {
+    if status == 0 {
+        return "<span class=\"s0\">not fetched</span>".to_string();
+    }
+    format!("<span class=\"s{}\">{}</span>", status / 100, status)
+}
+
+/// Link to `url`, labeled with internationalized hosts in Unicode.
+fn url_link(url: &str) -> String {
+    let label = display_url(url).unwrap_or_else(|| url.to_string());
+    format!("<a href=\"{}\">{}</a>", escape(url), escape(&label))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes the HTML report to `index.html` in a directory when the crawl ends (`--report`).
+pub struct HtmlReportSink {
+    dir: PathBuf,
+}
+
+impl HtmlReportSink {
+    /// Create `dir` if it doesn't exist yet, so a bad path fails before the crawl.
+    pub fn create(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create report directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+#[async_trait]
+impl ResultSink for HtmlReportSink {
+    async fn write(&mut self, _result: &CrawlResult) -> Result<()> {
+        Ok(())
+    }
+
+    async fn close(&mut self, report: &CrawlReport<'_>) -> Result<()> {
+        let path = self.dir.join("index.html");
+        fs::write(&path, render_report(report))
+            .with_context(|| format!("Failed to write report {}", path.display()))
+    }
+
+    /// Broken links need every page's links.
+    fn needs_full_results(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlFailure;
+    use std::time::Duration;
+
+    fn page(url: &str, status_code: u16, title: &str, links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code,
+            title: Some(title.to_string()),
+            links: links.iter().map(|l| l.to_string()).collect(),
+            crawl_time: Duration::from_millis(10),
+            final_url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_is_written_with_escaped_content() {
+        let results = vec![
+            page(
+                "https://example.com/",
+                200,
+                "Shop <Home>",
+                &["https://example.com/gone"],
+            ),
+            page("https://example.com/a", 200, "Shop <Home>", &[]),
+            page("https://example.com/gone", 404, "Not found", &[]),
+        ];
+        let report = CrawlReport {
+            results: &results,
+            failures: vec![CrawlFailure {
+                url: "https://example.com/down".to_string(),
+                depth: 1,
+                reason: "Timed out".to_string(),
+                attempts: 3,
+                error: None,
+            }],
+            ..Default::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!("rustcrawler-report-{}", uuid::Uuid::new_v4()));
+        let mut sink = HtmlReportSink::create(&dir).unwrap();
+        sink.close(&report).await.unwrap();
+        let html = fs::read_to_string(dir.join("index.html")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(html.contains("<li><b>3</b>pages crawled</li>"));
+        assert!(html.contains("<li><b>1</b>broken links</li>"));
+        assert!(html.contains("<td><span class=\"s4\">404</span></td><td class=\"num\">1</td>"));
+        assert!(!html.contains("<Home>"));
+        let duplicates = html.split("<h2>Duplicate titles</h2>").nth(1).unwrap();
+        assert!(duplicates.starts_with("\n<table>\n<tr><th>Title</th><th>Pages</th><th>URLs</th></tr>\n<tr><td>Shop &lt;Home&gt;</td><td class=\"num\">2</td>"));
+        assert!(html.contains("<h2>Failed URLs</h2>"));
+        assert!(html.contains("<table id=\"pages\">"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Caps the total size of response bodies buffered at the same time.
+///
+/// New fetches wait in [`ByteBudget::wait_for_capacity`] while the cap is reached.
+/// Bodies already being read are never blocked, so one response larger than the
+/// cap can't deadlock the crawl; it just keeps new fetches waiting until it's done.
+#[derive(Debug)]
+pub struct ByteBudget {
+    cap: usize,
+    in_flight: AtomicUsize,
+    notify: Notify,
+}
+
+impl ByteBudget {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            in_flight: AtomicUsize::new(0),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn cap(&self) -> usize {
+        self.cap
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    pub async fn wait_for_capacity(&self) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.in_flight() < self.cap {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Start accounting for a body; bytes are released when the reservation drops.
+    pub fn reserve(self: &Arc<Self>) -> ByteReservation {
+        ByteReservation {
+            budget: Arc::clone(self),
+            bytes: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ByteReservation {
+    budget: Arc<ByteBudget>,
+    bytes: usize,
+}
+
+impl ByteReservation {
+    /// Grow the reservation to at least `bytes` (e.g. Content-Length, then streamed size).
+    pub fn grow_to(&mut self, bytes: usize) {
+        if bytes > self.bytes {
+            self.budget
+                .in_flight
+                .fetch_add(bytes - self.bytes, Ordering::Relaxed);
+            self.bytes = bytes;
+        }
+    }
+}
+
+impl Drop for ByteReservation {
+    fn drop(&mut self) {
+        self.budget
+            .in_flight
+            .fetch_sub(self.bytes, Ordering::Relaxed);
+        self.budget.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_reservation_blocks_until_released() {
+        let budget = Arc::new(ByteBudget::new(100));
+        let mut reservation = budget.reserve();
+        reservation.grow_to(60);
+        reservation.grow_to(40); // never shrinks
+        assert_eq!(budget.in_flight(), 60);
+
+        // Still under the cap
+        budget.wait_for_capacity().await;
+
+        reservation.grow_to(150);
+        let waiter = {
+            let budget = Arc::clone(&budget);
+            tokio::spawn(async move { budget.wait_for_capacity().await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(reservation);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should be released")
+            .unwrap();
+        assert_eq!(budget.in_flight(), 0);
+    }
+}
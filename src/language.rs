@@ -0,0 +1,188 @@
+use scraper::{Html, Selector};
+
+/// Which signal decided a page's language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageSource {
+    ContentLanguageHeader,
+    HtmlLangAttribute,
+    Charset,
+    TextDetection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageDetection {
+    /// Lowercase ISO 639-1 code where one exists (e.g. "en"), otherwise ISO 639-3.
+    pub code: String,
+    pub source: LanguageSource,
+}
+
+/// Detect a page's language, trying cheap signals before text detection:
+/// the `Content-Language` header, then `<html lang>`, then charsets that
+/// only make sense for one language, and finally statistical detection.
+pub fn detect_language(
+    content_language: Option<&str>,
+    charset: Option<&str>,
+    html: &str,
+) -> Option<LanguageDetection> {
+    // Multi-language headers ("en, fr") don't tell us which one this page is
+    if let Some(code) = content_language
+        .filter(|value| !value.contains(','))
+        .and_then(primary_subtag)
+    {
+        return Some(LanguageDetection {
+            code,
+            source: LanguageSource::ContentLanguageHeader,
+        });
+    }
+
+    let document = Html::parse_document(html);
+
+    let html_selector = Selector::parse("html[lang]").ok()?;
+    if let Some(code) = document
+        .select(&html_selector)
+        .next()
+        .and_then(|el| el.value().attr("lang"))
+        .and_then(primary_subtag)
+    {
+        return Some(LanguageDetection {
+            code,
+            source: LanguageSource::HtmlLangAttribute,
+        });
+    }
+
+    if let Some(code) = charset.and_then(charset_language) {
+        return Some(LanguageDetection {
+            code: code.to_string(),
+            source: LanguageSource::Charset,
+        });
+    }
+
+    detect_from_text(&document)
+}
+
+/// Statistical detection over the body text; needs the `language-detect` feature.
+#[cfg(feature = "language-detect")]
+fn detect_from_text(document: &Html) -> Option<LanguageDetection> {
+    let body_selector = Selector::parse("body").ok()?;
+    let text: String = document
+        .select(&body_selector)
+        .flat_map(|el| el.text())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let info = whatlang::detect(&text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+
+    Some(LanguageDetection {
+        code: iso_639_1(info.lang().code()).to_string(),
+        source: LanguageSource::TextDetection,
+    })
+}
+
+#[cfg(not(feature = "language-detect"))]
+fn detect_from_text(_document: &Html) -> Option<LanguageDetection> {
+    None
+}
+
+/// Whether `code` matches one of the `allowed` language codes.
+pub fn language_allowed(code: &str, allowed: &[String]) -> bool {
+    allowed.iter().any(|lang| lang.eq_ignore_ascii_case(code))
+}
+
+/// "en-US" -> "en"
+fn primary_subtag(tag: &str) -> Option<String> {
+    let primary = tag.trim().split(['-', '_']).next()?.trim();
+    if primary.is_empty() || primary == "*" {
+        None
+    } else {
+        Some(primary.to_lowercase())
+    }
+}
+
+/// Charsets that are effectively tied to a single language.
+fn charset_language(charset: &str) -> Option<&'static str> {
+    match charset.trim().to_lowercase().as_str() {
+        "shift_jis" | "shift-jis" | "sjis" | "euc-jp" | "iso-2022-jp" => Some("ja"),
+        "gb2312" | "gbk" | "gb18030" | "big5" => Some("zh"),
+        "euc-kr" => Some("ko"),
+        "koi8-r" => Some("ru"),
+        "koi8-u" => Some("uk"),
+        "windows-1253" | "iso-8859-7" => Some("el"),
+        "windows-1255" | "iso-8859-8" => Some("he"),
+        "windows-874" | "tis-620" => Some("th"),
+        _ => None,
+    }
+}
+
+/// Map whatlang's ISO 639-3 codes to ISO 639-1 for common languages.
+#[cfg(feature = "language-detect")]
+fn iso_639_1(code: &str) -> &str {
+    match code {
+        "eng" => "en",
+        "spa" => "es",
+        "fra" => "fr",
+        "deu" => "de",
+        "ita" => "it",
+        "por" => "pt",
+        "nld" => "nl",
+        "rus" => "ru",
+        "ukr" => "uk",
+        "pol" => "pl",
+        "ces" => "cs",
+        "swe" => "sv",
+        "dan" => "da",
+        "nob" => "nb",
+        "fin" => "fi",
+        "tur" => "tr",
+        "ell" => "el",
+        "heb" => "he",
+        "ara" => "ar",
+        "hin" => "hi",
+        "jpn" => "ja",
+        "cmn" => "zh",
+        "kor" => "ko",
+        "tha" => "th",
+        "vie" => "vi",
+        "ind" => "id",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_takes_precedence() {
+        let html = r#"<html lang="fr"><body>Bonjour</body></html>"#;
+        let detection = detect_language(Some("en-US"), None, html).unwrap();
+        assert_eq!(detection.code, "en");
+        assert_eq!(detection.source, LanguageSource::ContentLanguageHeader);
+    }
+
+    #[test]
+    fn test_html_lang_and_charset_signals() {
+        let html = r#"<html lang="de-AT"><body>Hallo</body></html>"#;
+        let detection = detect_language(Some("en, fr"), None, html).unwrap();
+        assert_eq!(detection.code, "de");
+        assert_eq!(detection.source, LanguageSource::HtmlLangAttribute);
+
+        let html = "<html><body>x</body></html>";
+        let detection = detect_language(None, Some("Shift_JIS"), html).unwrap();
+        assert_eq!(detection.code, "ja");
+        assert_eq!(detection.source, LanguageSource::Charset);
+    }
+
+    #[cfg(feature = "language-detect")]
+    #[test]
+    fn test_text_detection_fallback() {
+        let html = "<html><body><p>The quick brown fox jumps over the lazy dog while the \
+                    farmer watches from the old wooden porch of his house.</p></body></html>";
+        let detection = detect_language(None, Some("utf-8"), html).unwrap();
+        assert_eq!(detection.code, "en");
+        assert_eq!(detection.source, LanguageSource::TextDetection);
+        assert!(language_allowed("EN", &["en".to_string()]));
+    }
+}
@@ -1,8 +1,85 @@
+//! A concurrent web crawler, as the library behind the `rustcrawler` and
+//! `rustcrawler-mcp` binaries.
+//!
+//! Without default features this is fetching, parsing, robots.txt handling and the
+//! crawl itself. Reports, crawl diffs and the link graph (`reports`), WARC archiving
+//! (`warc`) and certificate parsing (`tls-info`) are features of their own, which the
+//! binaries turn on.
+
+pub mod anomalies;
+#[cfg(feature = "warc")]
+pub mod archive;
+pub mod classify;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod clusters;
 pub mod crawler;
+pub mod decompress;
+#[cfg(feature = "reports")]
+pub mod diff;
+pub mod extraction;
+#[cfg(feature = "reports")]
+pub mod graph;
+#[cfg(feature = "reports")]
+pub mod html_report;
+pub mod inflight;
+pub mod language;
+#[cfg(feature = "reports")]
+pub mod markdown;
+#[cfg(feature = "mcp")]
 pub mod mcp;
+pub mod output;
+pub mod probe;
+#[cfg(feature = "cli")]
+pub mod profile;
+#[cfg(feature = "cli")]
+pub mod progress;
+pub mod render;
 pub mod robots;
+pub mod run;
+pub mod sitemap;
+pub mod spill;
+#[cfg(feature = "sqlite")]
+pub mod storage;
+pub mod telemetry;
+#[cfg(test)]
+mod test_server;
+pub mod text;
+pub mod tls;
+pub mod urls;
 
+pub use anomalies::{detect_anomalies, Anomaly, AnomalyKind};
+pub use classify::{CategoryRule, CategorySummary, Classifier};
+#[cfg(feature = "cli")]
 pub use cli::CliArgs;
-pub use crawler::{CrawlResult, Crawler, CrawlerConfig};
-pub use robots::{RobotsInfo, RobotsManager};
+pub use clusters::{cluster_results, ClusterVariant, PageCluster};
+pub use crawler::{
+    AssetKind, CrawlFailure, CrawlObserver, CrawlResult, CrawlStrategy, CrawlSummary, Crawler,
+    CrawlerConfig, CrawlerError, ExternalDomain, FetchError, FetchRequest, FetchResponse, Fetcher,
+    HttpVersion, PageAsset, PageVariant, PriorityBoost, ProxyRotation, RedirectHop, RequestAction,
+    RequestHook, ResponseHook, Soft404Reason, StructuredData, UserAgentRotation, VisitedStore,
+    DEFAULT_EXPECTED_URLS, DEFAULT_SOFT_404_TITLES,
+};
+#[cfg(feature = "reports")]
+pub use diff::{diff_crawls, load_crawl_results, ChangeKind, CrawlDiff, FieldChange, PageChange};
+pub use extraction::{ExtractionRule, Extractor};
+#[cfg(feature = "reports")]
+pub use graph::{GraphFormat, GraphSink, LinkGraph};
+#[cfg(feature = "reports")]
+pub use html_report::HtmlReportSink;
+pub use output::{CrawlReport, OutputFormat, ResultSink, ResultWriter};
+pub use probe::{probe_site, ScopeSuggestion, SiteProfile};
+pub use render::RenderWait;
+pub use robots::{RobotsFetchStatus, RobotsInfo, RobotsManager, RobotsRules};
+pub use run::RunMetadata;
+pub use spill::ResultStore;
+#[cfg(feature = "sqlite")]
+pub use storage::SqliteSink;
+pub use telemetry::{
+    RetryDisposition, RetryRecord, RetrySummary, Timeline, TimelineEvent, TimelineEventKind,
+};
+pub use tls::CertificateInfo;
+pub use urls::{
+    classify_scope, classify_scope_with, normalize_url, normalize_url_with, registrable_domain,
+    CrawlScope, HostAliases, NormalizeOptions, ScopeFilter, UrlScope,
+};
@@ -1,13 +1,27 @@
-mod cli;
-mod crawler;
-mod robots;
-
-use crate::cli::CliArgs;
-use crate::crawler::{Crawler, CrawlerConfig};
 use anyhow::Result;
-use clap::Parser;
-use log::{error, info};
+use futures::StreamExt;
+use log::{error, info, warn};
+use rustcrawler::classify::{load_rules, summarize_categories};
+use rustcrawler::probe::{resolve_seed_schemes, DEFAULT_PROBE_PAGES};
+use rustcrawler::profile::parse_args;
+use rustcrawler::progress::CrawlProgress;
+use rustcrawler::{
+    cluster_results, detect_anomalies, diff_crawls, load_crawl_results, probe_site, ChangeKind,
+    CliArgs, CrawlDiff, CrawlReport, CrawlResult, CrawlScope, CrawlStrategy, Crawler,
+    CrawlerConfig, HttpVersion, ResultStore, ResultWriter, VisitedStore,
+};
+use std::pin::pin;
 use std::process;
+use std::time::{Duration, SystemTime};
+
+/// How often `--progress` refreshes the queue size and error count.
+const PROGRESS_REFRESH: Duration = Duration::from_millis(250);
+/// Seeds listed in the configuration log; long `--seed-file` lists are summarized.
+const MAX_LOGGED_SEEDS: usize = 10;
+/// External domains listed in the end-of-crawl statistics; reports list more.
+const TOP_EXTERNAL_DOMAINS: usize = 10;
+/// HTTPS pages with insecure content listed in the log; the Markdown report has more.
+const TOP_INSECURE_PAGES: usize = 10;
 
 #[tokio::main]
 async fn main() {
@@ -16,8 +30,19 @@ async fn main() {
         .filter_level(log::LevelFilter::Info)
         .init();
 
-    // Parse command line arguments
-    let args = CliArgs::parse();
+    // Parse command line arguments, with defaults from --config
+    let mut args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = args.load_seed_files() {
+        error!("{:#}", e);
+        process::exit(1);
+    }
 
     // Validate arguments
     if let Err(e) = args.validate() {
@@ -34,11 +59,81 @@ async fn main() {
 
     info!("Starting RustCrawler v0.1.0");
     info!("Configuration:");
-    info!("  URLs: {:?}", args.urls);
+    if args.urls.len() > MAX_LOGGED_SEEDS {
+        info!(
+            "  URLs: {} ({:?}, ...)",
+            args.urls.len(),
+            &args.urls[..MAX_LOGGED_SEEDS]
+        );
+    } else {
+        info!("  URLs: {:?}", args.urls);
+    }
+    if !args.seed_files.is_empty() {
+        let files: Vec<String> = args
+            .seed_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        info!("  Seed Files: {}", files.join(", "));
+    }
+    if let Some(ref config) = args.config {
+        info!("  Config: {}", config.display());
+    }
     info!("  Concurrency: {}", args.concurrency);
+    if let Some(per_host) = args.max_concurrency_per_host {
+        info!("  Concurrency per host: {}", per_host);
+    }
     info!("  User Agent: {}", args.user_agent);
+    if let Some(ref user_agent_file) = args.user_agent_file {
+        info!(
+            "  User Agent File: {} ({:?} rotation)",
+            user_agent_file.display(),
+            args.user_agent_rotation
+        );
+    }
+    info!("  Timeout: {}s", args.timeout);
+    if let Some(connect_timeout) = args.connect_timeout {
+        info!("  Connect Timeout: {}s", connect_timeout);
+    }
+    if let Some(stall_timeout) = args.stall_timeout {
+        info!("  Stall Timeout: {}s", stall_timeout);
+    }
+    if args.compression {
+        info!("  Compression: gzip, brotli, deflate");
+    }
+    if args.http_version != HttpVersion::Auto {
+        info!("  HTTP Version: {:?}", args.http_version);
+    }
+    if args.http2_adaptive_window {
+        info!("  HTTP/2 Adaptive Window: on");
+    }
+    if let Some(pool_idle_timeout) = args.pool_idle_timeout {
+        info!("  Pool Idle Timeout: {}s", pool_idle_timeout);
+    }
+    if let Some(pool_max_idle_per_host) = args.pool_max_idle_per_host {
+        info!("  Pool Max Idle Per Host: {}", pool_max_idle_per_host);
+    }
+    if let Some(tcp_keepalive) = args.tcp_keepalive {
+        info!("  TCP Keepalive: {}s", tcp_keepalive);
+    }
+    info!("  Max Retry-After: {}s", args.max_retry_after);
+    if args.get_follow_redirects() {
+        info!("  Max Redirects: {}", args.max_redirects);
+    } else {
+        info!("  Follow Redirects: off");
+    }
+    if !args.get_respect_robots() {
+        info!("  Robots.txt: ignored");
+    }
+    info!("  robots.txt TTL: {}s", args.robots_ttl);
+    if let Some(ref robots_cache) = args.robots_cache {
+        info!("  robots.txt Cache: {}", robots_cache.display());
+    }
     if let Some(rate) = args.rate_limit {
-        info!("  Rate Limit: {} req/sec", rate);
+        info!("  Rate Limit: {} req/sec per host", rate);
+        if args.burst > 1 {
+            info!("  Burst: {} requests", args.burst);
+        }
     }
     if let Some(max_pages) = args.max_pages {
         info!("  Max Pages: {}", max_pages);
@@ -46,26 +141,334 @@ async fn main() {
     if let Some(depth) = args.depth {
         info!("  Max Depth: {}", depth);
     }
+    if let Some(max_links) = args.max_links_per_page {
+        info!("  Max Links Per Page: {}", max_links);
+    }
+    if let Some(ref warc) = args.warc {
+        info!("  WARC Archive: {}", warc.display());
+    }
+    if args.extract_text {
+        info!("  Extract Text: on");
+    }
+    if args.extract_structured_data {
+        info!("  Extract Structured Data: on");
+    }
+    if args.include_assets {
+        info!(
+            "  Include Assets: on{}",
+            if args.verify_assets {
+                " (verified)"
+            } else {
+                ""
+            }
+        );
+    }
+    if args.simhash {
+        info!("  SimHash: on");
+    }
+    if !args.allowed_content_types.is_empty() {
+        info!(
+            "  Allowed Content Types: {}",
+            args.allowed_content_types.join(", ")
+        );
+    }
+    if args.skip_duplicates {
+        info!("  Skip Duplicates: on");
+    }
+    if let Some(ref http_cache) = args.http_cache {
+        info!("  HTTP Cache: {}", http_cache.display());
+    }
+    if args.detect_soft_404 {
+        info!("  Soft 404 Detection: on");
+    }
+    if args.render_js {
+        info!("  Render JavaScript: all pages");
+    } else if !args.render_patterns.is_empty() {
+        info!(
+            "  Render JavaScript: URLs matching {}",
+            args.render_patterns.join(", ")
+        );
+    }
+    if args.progress {
+        info!("  Progress: on");
+    }
+    if let Some(ref report) = args.report {
+        info!("  HTML Report: {}", report.join("index.html").display());
+    }
+    if let Some(ref graph) = args.export_graph {
+        info!("  Link Graph: {}", graph.display());
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(ref sqlite) = args.sqlite {
+        info!("  SQLite Database: {}", sqlite.display());
+    }
+    if args.sitemap_only {
+        info!(
+            "  Sitemap Only: on{}",
+            if args.head_check {
+                " (HEAD-checking URLs)"
+            } else {
+                ""
+            }
+        );
+    }
+    if args.tls_info {
+        info!(
+            "  TLS Info: on (warning {} days before expiry)",
+            args.cert_expiry_days
+        );
+    }
+    if let Some(ref state_file) = args.state_file {
+        info!(
+            "  State File: {}{}",
+            state_file.display(),
+            if args.resume { " (resuming)" } else { "" }
+        );
+    }
+    match args.visited_store {
+        VisitedStore::Exact => {}
+        VisitedStore::Fingerprint => info!("  Visited Store: fingerprint"),
+        VisitedStore::Bloom => info!(
+            "  Visited Store: bloom (sized for {} URLs)",
+            args.expected_urls
+        ),
+    }
     if let Some(ref proxy) = args.proxy {
         info!("  Proxy: {}", proxy);
     }
+    if let Some(ref proxy_list) = args.proxy_list {
+        info!(
+            "  Proxy List: {} ({:?} rotation)",
+            proxy_list.display(),
+            args.proxy_rotation
+        );
+    }
+    if !args.languages.is_empty() {
+        info!("  Languages: {}", args.languages.join(", "));
+    }
+    if args.max_body_size > 0 {
+        info!("  Max Body Size: {} MB", args.max_body_size);
+    }
+    if let Some(max_inflight_mb) = args.max_inflight_mb {
+        info!("  Max In-Flight Bodies: {} MB", max_inflight_mb);
+    }
+    if let Some(max_results_memory_mb) = args.max_results_memory_mb {
+        info!("  Max Results Memory: {} MB", max_results_memory_mb);
+    }
+    if let Some(sample) = args.sample {
+        info!("  Sample: {}", sample);
+    }
+    if args.deterministic {
+        info!("  Deterministic: one request at a time per host");
+    }
+    if let Some(seed) = args.seed {
+        info!("  Seed: {}", seed);
+    }
+    if args.strategy != CrawlStrategy::Bfs {
+        info!("  Strategy: {:?}", args.strategy);
+        for boost in &args.boosts {
+            info!("  Boost: {}", boost);
+        }
+    }
+    if args.get_scope() != CrawlScope::All {
+        info!("  Scope: {:?}", args.get_scope());
+    }
+    for group in args.get_host_alias_groups() {
+        info!("  Host Alias: {}", group.join(" = "));
+    }
+    if args.keep_html {
+        info!("  Keep HTML: up to {} KB per page", args.keep_html_max_kb);
+    }
+    if let Some(ref classify) = args.classify {
+        info!("  Classify: {}", classify.display());
+    }
+
+    let category_rules = match args.classify.as_deref().map(load_rules).transpose() {
+        Ok(rules) => rules.unwrap_or_default(),
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+
+    let extraction_rules = match args.get_extraction_rules() {
+        Ok(rules) => rules,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+    if !extraction_rules.is_empty() {
+        info!("  Extraction Rules: {}", extraction_rules.len());
+    }
+
+    let previous = match args
+        .diff_against
+        .as_deref()
+        .map(load_crawl_results)
+        .transpose()
+    {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+    if let (Some(path), Some(previous)) = (&args.diff_against, &previous) {
+        info!(
+            "  Diff Against: {} ({} pages)",
+            path.display(),
+            previous.len()
+        );
+    }
+
+    let proxy_list = match args.get_proxy_list() {
+        Ok(proxies) => proxies,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+    let user_agents = match args.get_user_agents() {
+        Ok(agents) => agents,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
 
     // Create crawler configuration
     let config = CrawlerConfig {
         max_concurrency: args.concurrency,
+        max_concurrency_per_host: args.max_concurrency_per_host,
         rate_limit: args.get_rate_limit_interval(),
+        burst: args.burst,
         max_retries: args.max_retries,
+        max_retry_after: args.get_max_retry_after(),
         timeout: args.get_timeout(),
+        connect_timeout: args.get_connect_timeout(),
+        stall_timeout: args.get_stall_timeout(),
+        compression: args.compression,
+        http_version: args.http_version,
+        http2_adaptive_window: args.http2_adaptive_window,
+        pool_idle_timeout: args.get_pool_idle_timeout(),
+        pool_max_idle_per_host: args.pool_max_idle_per_host,
+        tcp_keepalive: args.get_tcp_keepalive(),
         user_agent: args.user_agent.clone(),
+        user_agents,
+        user_agent_rotation: args.user_agent_rotation,
         max_pages: args.max_pages,
         max_depth: args.depth,
-        respect_robots: args.respect_robots,
-        follow_redirects: args.follow_redirects,
+        respect_robots: args.get_respect_robots(),
+        robots_ttl: args.get_robots_ttl(),
+        robots_cache_file: args.robots_cache.clone(),
+        follow_redirects: args.get_follow_redirects(),
+        max_redirects: args.max_redirects,
         proxy: args.proxy.clone(),
+        proxy_list,
+        proxy_rotation: args.proxy_rotation,
+        keep_html: args.keep_html,
+        keep_html_max_kb: args.keep_html_max_kb,
+        languages: args.languages.iter().map(|l| l.to_lowercase()).collect(),
+        spa_fragments: args.spa_fragments,
+        case_insensitive_hosts: args.case_insensitive_hosts.clone(),
+        detect_case_insensitive: args.detect_case_insensitive,
+        max_bytes_in_flight: args.get_max_bytes_in_flight(),
+        max_body_size: args.get_max_body_size(),
+        sample_rate: args.sample,
+        seed: args.seed,
+        deterministic: args.deterministic,
+        host_aliases: args.get_host_alias_groups(),
+        alias_www: args.alias_www,
+        detect_host_aliases: args.detect_host_aliases,
+        scope: args.get_scope(),
+        strategy: args.strategy,
+        // Checked by validate
+        priority_boosts: args.get_priority_boosts().unwrap_or_default(),
+        max_title_length: args.max_title_length,
+        max_links_per_page: args.max_links_per_page,
+        state_file: args.state_file.clone(),
+        resume: args.resume,
+        checkpoint_interval: args.get_checkpoint_interval(),
+        visited_store: args.visited_store,
+        expected_urls: args.expected_urls,
+        category_rules,
+        extraction_rules,
+        tls_info: args.tls_info,
+        cert_expiry_warn_days: args.cert_expiry_days,
+        extract_text: args.extract_text,
+        extract_structured_data: args.extract_structured_data,
+        include_assets: args.include_assets,
+        verify_assets: args.verify_assets,
+        simhash: args.simhash,
+        skip_duplicates: args.skip_duplicates,
+        allowed_content_types: args.allowed_content_types.clone(),
+        http_cache_dir: args.http_cache.clone(),
+        detect_soft_404: args.detect_soft_404,
+        soft_404_min_body: args.soft_404_min_body,
+        soft_404_titles: args.soft_404_titles.clone(),
+        render_js: args.render_js,
+        render_patterns: args.render_patterns.clone(),
+        render_wait: args.get_render_wait().unwrap_or_default(),
+        chrome_path: args.chrome_path.clone(),
+        chrome_no_sandbox: args.chrome_no_sandbox,
+        warc_path: args.warc.clone(),
     };
 
+    let seeds = match resolve_seed_schemes(&config, args.urls.clone()).await {
+        Ok(seeds) => seeds,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+
+    if args.probe || args.dry_run {
+        match probe_site(&config, &seeds, DEFAULT_PROBE_PAGES).await {
+            Ok(profile) => {
+                info!("Site probe:");
+                for line in profile.format_output() {
+                    info!("  {}", line);
+                }
+                let suggestion = profile.suggest();
+                info!("Suggested settings: {}", suggestion.to_args());
+                for reason in &suggestion.reasons {
+                    info!("  - {}", reason);
+                }
+            }
+            Err(e) => error!("Site probe failed: {:#}", e),
+        }
+        if args.dry_run {
+            return;
+        }
+    }
+
     // Create and run crawler
-    match run_crawler(config, args.urls).await {
+    let checkpointing = config.state_file.is_some();
+    let crawler = match build_crawler(config, &args) {
+        Ok(crawler) => crawler,
+        Err(e) => {
+            error!("{:#}", e);
+            process::exit(1);
+        }
+    };
+    let outcome = if args.sitemap_only {
+        run_sitemap_inventory(crawler, seeds, args.head_check, previous).await
+    } else {
+        let store = ResultStore::new(args.get_max_results_memory());
+        let progress = args.progress.then(|| CrawlProgress::new(args.max_pages));
+        run_crawler(
+            crawler,
+            seeds,
+            args.timeline,
+            checkpointing,
+            progress,
+            store,
+            previous,
+        )
+        .await
+    };
+    match outcome {
         Ok(results) => {
             info!("Crawling completed successfully!");
             info!("Total pages crawled: {}", results.len());
@@ -98,24 +501,305 @@ async fn main() {
     }
 }
 
-async fn run_crawler(
-    config: CrawlerConfig,
+/// The crawler with every output the arguments ask for registered as a sink.
+fn build_crawler(config: CrawlerConfig, args: &CliArgs) -> Result<Crawler> {
+    let writer = ResultWriter::new(args.output_format, args.output.as_deref())?;
+    let mut crawler = Crawler::new(config)?.with_sink(writer);
+    if let Some(dir) = &args.report {
+        crawler = crawler.with_sink(rustcrawler::HtmlReportSink::create(dir)?);
+    }
+    if let Some(path) = &args.export_graph {
+        crawler = crawler.with_sink(rustcrawler::GraphSink::create(path)?);
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.sqlite {
+        crawler = crawler.with_sink(rustcrawler::SqliteSink::open(path)?);
+    }
+    Ok(crawler)
+}
+
+async fn run_sitemap_inventory(
+    crawler: Crawler,
     start_urls: Vec<String>,
-) -> Result<Vec<crawler::CrawlResult>> {
-    // Create crawler
-    let crawler = Crawler::new(config)?;
+    head_check: bool,
+    previous: Option<Vec<CrawlResult>>,
+) -> Result<Vec<CrawlResult>> {
+    info!(
+        "Listing pages from the sitemaps of {} URL(s)",
+        start_urls.len()
+    );
+    let results = crawler.sitemap_inventory(start_urls, head_check).await?;
+    let diff = previous.map(|previous| diff_crawls(&previous, &results));
+    crawler
+        .close_sinks(&CrawlReport {
+            run: crawler.get_run_metadata(),
+            results: &results,
+            failures: crawler.get_failures(),
+            clusters: cluster_results(&results),
+            categories: summarize_categories(&results),
+            retry_summary: crawler.get_retry_summary(),
+            timeline: crawler.get_timeline(),
+            certificates: crawler.get_certificates(),
+            external_domains: crawler.get_external_domains(),
+            anomalies: detect_anomalies(&results),
+            diff: diff.clone(),
+        })
+        .await?;
 
+    info!("Inventory statistics:");
+    if let Some(run) = crawler.get_run_metadata() {
+        info!("  Run ID: {}", run.run_id);
+    }
+    info!("  URLs listed: {}", results.len());
+    if let Some(diff) = &diff {
+        log_diff(diff);
+    }
+    Ok(results)
+}
+
+async fn run_crawler(
+    crawler: Crawler,
+    start_urls: Vec<String>,
+    print_timeline: bool,
+    checkpointing: bool,
+    mut progress: Option<CrawlProgress>,
+    mut store: ResultStore,
+    previous: Option<Vec<CrawlResult>>,
+) -> Result<Vec<CrawlResult>> {
+    // Results reach the crawler's sinks as the crawl yields them
     // Start crawling
     info!("Starting crawl from {} URL(s)", start_urls.len());
-    let results = crawler.crawl(start_urls).await?;
+    let mut stream = pin!(crawler.crawl_stream(start_urls));
+    // With a state file, the first Ctrl-C stops gracefully and checkpoints; a second one exits
+    let mut stopping = false;
+    // Log lines would scroll the progress bar away; it reports errors itself
+    if progress.is_some() {
+        log::set_max_level(log::LevelFilter::Off);
+    }
+    let mut refresh = tokio::time::interval(PROGRESS_REFRESH);
+    loop {
+        tokio::select! {
+            result = stream.next() => {
+                let Some(result) = result else {
+                    break;
+                };
+                if let Some(progress) = progress.as_mut() {
+                    progress.record(&result);
+                }
+                store.push(result)?;
+            }
+            _ = refresh.tick(), if progress.is_some() => {
+                if let Some(progress) = &progress {
+                    progress.update(&crawler);
+                }
+            }
+            _ = tokio::signal::ctrl_c(), if checkpointing => {
+                if stopping {
+                    process::exit(130);
+                }
+                info!("Interrupted; saving crawl state (press Ctrl-C again to exit now)");
+                crawler.request_stop();
+                stopping = true;
+            }
+        }
+    }
+    if let Some(progress) = &progress {
+        progress.finish(&crawler);
+        log::set_max_level(log::LevelFilter::Info);
+    }
+    if stopping {
+        info!("Crawl state saved; continue with --resume");
+    }
+
+    if store.spilled() > 0 {
+        info!(
+            "Spilled {} of {} results to disk",
+            store.spilled(),
+            store.len()
+        );
+    }
+    // Clustering and categories only need what summaries keep
+    let clusters = cluster_results(store.summaries());
+    let categories = summarize_categories(store.summaries());
+    let anomalies = detect_anomalies(store.summaries());
+    let diff = previous.map(|previous| diff_crawls(&previous, store.summaries()));
+    // Text and NDJSON went out as results arrived; only reports need spilled results back
+    let results = if crawler.sinks_need_full_results().await {
+        store.into_results()?
+    } else {
+        store.into_summaries()
+    };
+    crawler
+        .close_sinks(&CrawlReport {
+            run: crawler.get_run_metadata(),
+            results: &results,
+            failures: crawler.get_failures(),
+            clusters: clusters.clone(),
+            categories: categories.clone(),
+            retry_summary: crawler.get_retry_summary(),
+            timeline: crawler.get_timeline(),
+            certificates: crawler.get_certificates(),
+            external_domains: crawler.get_external_domains(),
+            anomalies: anomalies.clone(),
+            diff: diff.clone(),
+        })
+        .await?;
 
     info!("Crawl statistics:");
+    if let Some(run) = crawler.get_run_metadata() {
+        info!("  Run ID: {}", run.run_id);
+    }
     info!("  Pages crawled: {}", crawler.get_crawled_count());
-    info!("  URLs visited: {}", crawler.get_visited_count());
+    info!("  URLs discovered: {}", crawler.get_visited_count());
+    let failures = crawler.get_failures();
+    if !failures.is_empty() {
+        info!("  Failed URLs: {}", failures.len());
+    }
+    if crawler.get_retained_html_bytes() > 0 {
+        let truncated = results.iter().filter(|r| r.html_truncated).count();
+        info!(
+            "  Retained HTML: {} bytes ({} page(s) truncated)",
+            crawler.get_retained_html_bytes(),
+            truncated
+        );
+    }
+
+    if clusters.len() < results.len() {
+        info!(
+            "  Logical pages: {} ({} locale/canonical variants grouped)",
+            clusters.len(),
+            results.len() - clusters.len()
+        );
+        for cluster in clusters.iter().filter(|c| c.variants.len() > 1) {
+            info!("    {}", cluster.format_output());
+        }
+    }
+
+    if !categories.is_empty() {
+        info!("  Categories:");
+        for summary in &categories {
+            info!("    {}", summary.format_output());
+        }
+    }
+
+    if !anomalies.is_empty() {
+        info!("  Anomalies (most significant first):");
+        for anomaly in &anomalies {
+            warn!("    {}", anomaly.format_output());
+        }
+    }
+
+    if let Some(diff) = &diff {
+        log_diff(diff);
+    }
+
+    let soft_404s: Vec<&CrawlResult> = results.iter().filter(|r| r.soft_404.is_some()).collect();
+    if !soft_404s.is_empty() {
+        warn!("  Soft 404s: {}", soft_404s.len());
+        for result in &soft_404s {
+            if let Some(reason) = result.soft_404 {
+                warn!("    {} ({})", result.url, reason.label());
+            }
+        }
+    }
+
+    let insecure: Vec<&CrawlResult> = results
+        .iter()
+        .filter(|r| !r.mixed_content.is_empty() || !r.insecure_links.is_empty())
+        .collect();
+    if !insecure.is_empty() {
+        warn!("  Insecure content on {} HTTPS page(s):", insecure.len());
+        for result in insecure.iter().take(TOP_INSECURE_PAGES) {
+            warn!(
+                "    {}: {} mixed content resource(s), {} http:// link(s)",
+                result.url,
+                result.mixed_content.len(),
+                result.insecure_links.len()
+            );
+        }
+    }
+
+    let assets: usize = results.iter().map(|r| r.assets.len()).sum();
+    if assets > 0 {
+        let problems = results
+            .iter()
+            .flat_map(|r| &r.assets)
+            .filter(|asset| asset.problem().is_some())
+            .count();
+        info!(
+            "  Assets: {} ({} broken or mixed content)",
+            assets, problems
+        );
+    }
+
+    let external_domains = crawler.get_external_domains();
+    if !external_domains.is_empty() {
+        info!("  External domains: {}", external_domains.len());
+        for domain in external_domains.iter().take(TOP_EXTERNAL_DOMAINS) {
+            info!("    {}", domain.format_output());
+        }
+    }
+
+    let certificates = crawler.get_certificates();
+    if !certificates.is_empty() {
+        let now = SystemTime::now();
+        info!("  Certificates:");
+        for certificate in &certificates {
+            if certificate.expiring_soon {
+                warn!("    {}", certificate.format_output(now));
+            } else {
+                info!("    {}", certificate.format_output(now));
+            }
+        }
+    }
+
+    for (alias, canonical) in crawler.get_host_aliases() {
+        info!("  Host alias: {} -> {}", alias, canonical);
+    }
+
+    let retry_summary = crawler.get_retry_summary();
+    if retry_summary.total_retries > 0 || retry_summary.failed > 0 {
+        info!("Retry telemetry:");
+        info!(
+            "  {} retries across {} URL(s), {:?} spent in backoff",
+            retry_summary.total_retries, retry_summary.urls_retried, retry_summary.total_backoff
+        );
+        info!(
+            "  Recovered: {}, gave up: {}, failed: {}",
+            retry_summary.recovered, retry_summary.gave_up, retry_summary.failed
+        );
+        for (host, retries) in &retry_summary.top_hosts {
+            info!("  {}: {} retries", host, retries);
+        }
+    }
+
+    if print_timeline {
+        info!("Timeline:");
+        for event in crawler.get_timeline() {
+            info!("  {}", event.format_output());
+        }
+    }
 
     Ok(results)
 }
 
+/// Log the counts of a `--diff-against` comparison and each page that differs.
+fn log_diff(diff: &CrawlDiff) {
+    info!(
+        "  Changes since {}: {} added, {} removed, {} changed, {} unchanged",
+        diff.previous_run_id
+            .as_deref()
+            .unwrap_or("the previous crawl"),
+        diff.count(ChangeKind::Added),
+        diff.count(ChangeKind::Removed),
+        diff.count(ChangeKind::Changed),
+        diff.unchanged
+    );
+    for change in &diff.changes {
+        info!("    {}", change.format_output());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,6 +809,7 @@ mod tests {
         let config = CrawlerConfig {
             max_concurrency: 10,
             rate_limit: None,
+            burst: 1,
             max_retries: 3,
             timeout: std::time::Duration::from_secs(30),
             user_agent: "test-agent".to_string(),
@@ -133,6 +818,7 @@ mod tests {
             respect_robots: true,
             follow_redirects: true,
             proxy: None,
+            ..Default::default()
         };
 
         let _urls = ["https://httpbin.org/html".to_string()];
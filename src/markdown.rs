@@ -0,0 +1,454 @@
+use crate::crawler::{CrawlResult, PageAsset};
+use crate::diff::ChangeKind;
+use crate::output::CrawlReport;
+use crate::urls::display_url;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Rows shown in each table before the rest are summarized in one line.
+const MAX_TABLE_ROWS: usize = 20;
+/// Pages listed under "Slowest pages".
+const SLOWEST_PAGES: usize = 10;
+
+/// Render a crawl report as Markdown: a summary followed by tables of error pages,
+/// broken links and the slowest pages. Meant for pasting into issues or handing to an LLM.
+pub fn render_report(report: &CrawlReport) -> String {
+    let results = report.results;
+    let mut out = String::from("# Crawl report\n\n");
+
+    out.push_str("## Summary\n\n");
+    if let Some(run) = &report.run {
+        let _ = writeln!(out, "- **Run ID:** `{}`", run.run_id);
+    }
+    let _ = writeln!(out, "- **Pages crawled:** {}", results.len());
+    if !report.failures.is_empty() {
+        let _ = writeln!(out, "- **Failed URLs:** {}", report.failures.len());
+    }
+    if report.clusters.len() < results.len() {
+        let _ = writeln!(out, "- **Logical pages:** {}", report.clusters.len());
+    }
+    let mut by_class: BTreeMap<u16, usize> = BTreeMap::new();
+    for result in results {
+        *by_class.entry(result.status_code / 100).or_default() += 1;
+    }
+    if !by_class.is_empty() {
+        let classes: Vec<String> = by_class
+            .iter()
+            .map(|(class, count)| format!("{}xx: {}", class, count))
+            .collect();
+        let _ = writeln!(out, "- **Status codes:** {}", classes.join(", "));
+    }
+    if !results.is_empty() {
+        let total_ms: u128 = results.iter().map(|r| r.crawl_time.as_millis()).sum();
+        let _ = writeln!(
+            out,
+            "- **Average response time:** {}ms",
+            total_ms / results.len() as u128
+        );
+    }
+    let retries = &report.retry_summary;
+    if retries.urls_retried > 0 {
+        let _ = writeln!(
+            out,
+            "- **Retries:** {} URL(s) retried, {} recovered, {} gave up",
+            retries.urls_retried, retries.recovered, retries.gave_up
+        );
+    }
+
+    if !report.anomalies.is_empty() {
+        out.push_str("\n## Anomalies\n\nMost significant first.\n\n| Kind | Where | Detail |\n|---|---|---|\n");
+        for anomaly in report.anomalies.iter().take(MAX_TABLE_ROWS) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                anomaly.kind.label(),
+                escape_cell(&anomaly.subject),
+                escape_cell(&anomaly.detail)
+            );
+        }
+        push_overflow(&mut out, report.anomalies.len());
+    }
+
+    if let Some(diff) = &report.diff {
+        out.push_str("\n## Changes\n\n");
+        let _ = writeln!(
+            out,
+            "Since {}: {} added, {} removed, {} changed, {} unchanged.",
+            diff.previous_run_id.as_deref().map_or(
+                "the previous crawl".to_string(),
+                |run| format!("run `{}`", run)
+            ),
+            diff.count(ChangeKind::Added),
+            diff.count(ChangeKind::Removed),
+            diff.count(ChangeKind::Changed),
+            diff.unchanged
+        );
+        if !diff.changes.is_empty() {
+            out.push_str("\n| Change | URL | Detail |\n|---|---|---|\n");
+            for change in diff.changes.iter().take(MAX_TABLE_ROWS) {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    change.kind.label(),
+                    escape_cell(&display_form(&change.url)),
+                    escape_cell(&change.detail())
+                );
+            }
+            push_overflow(&mut out, diff.changes.len());
+        }
+    }
+
+    if !report.categories.is_empty() {
+        out.push_str(
+            "\n## Categories\n\n| Category | Pages | Errors | Avg time |\n|---|---|---|---|\n",
+        );
+        for summary in &report.categories {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {}ms |",
+                escape_cell(summary.category.as_deref().unwrap_or("uncategorized")),
+                summary.pages,
+                summary.errors,
+                summary.avg_crawl_time_ms
+            );
+        }
+    }
+
+    // With categories, error pages are listed category by category
+    let mut errors: Vec<&CrawlResult> = results.iter().filter(|r| r.status_code >= 400).collect();
+    let categorized = !report.categories.is_empty();
+    if categorized {
+        errors.sort_by_key(|r| (r.category.is_none(), r.category.as_deref()));
+    }
+    out.push_str("\n## Errors\n\n");
+    if errors.is_empty() {
+        out.push_str("No pages returned an error status.\n");
+    } else {
+        if categorized {
+            out.push_str("| Category | Status | URL | Title |\n|---|---|---|---|\n");
+        } else {
+            out.push_str("| Status | URL | Title |\n|---|---|---|\n");
+        }
+        for result in errors.iter().take(MAX_TABLE_ROWS) {
+            if categorized {
+                let _ = write!(
+                    out,
+                    "| {} ",
+                    escape_cell(result.category.as_deref().unwrap_or("uncategorized"))
+                );
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                result.status_code,
+                escape_cell(shown_url(result)),
+                escape_cell(result.title.as_deref().unwrap_or(""))
+            );
+        }
+        push_overflow(&mut out, errors.len());
+    }
+
+    if !report.failures.is_empty() {
+        out.push_str(
+            "\n## Failed URLs\n\n| URL | Depth | Attempts | Reason |\n|---|---|---|---|\n",
+        );
+        for failure in report.failures.iter().take(MAX_TABLE_ROWS) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape_cell(&display_form(&failure.url)),
+                failure.depth,
+                failure.attempts,
+                escape_cell(&failure.reason)
+            );
+        }
+        push_overflow(&mut out, report.failures.len());
+    }
+
+    let broken = broken_links(results);
+    out.push_str("\n## Broken links\n\n");
+    if broken.is_empty() {
+        out.push_str("No links to error pages.\n");
+    } else {
+        out.push_str("| Link | Status | Linked from |\n|---|---|---|\n");
+        for (link, status, sources) in broken.iter().take(MAX_TABLE_ROWS) {
+            let mut linked_from = escape_cell(&display_form(sources[0]));
+            if sources.len() > 1 {
+                let _ = write!(linked_from, " (+{} more)", sources.len() - 1);
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                escape_cell(&display_form(link)),
+                status,
+                linked_from
+            );
+        }
+        push_overflow(&mut out, broken.len());
+    }
+
+    let soft_404s: Vec<&CrawlResult> = results.iter().filter(|r| r.soft_404.is_some()).collect();
+    if !soft_404s.is_empty() {
+        out.push_str("\n## Soft 404s\n\n| URL | Reason | Title |\n|---|---|---|\n");
+        for result in soft_404s.iter().take(MAX_TABLE_ROWS) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                escape_cell(shown_url(result)),
+                result
+                    .soft_404
+                    .map(|reason| reason.label())
+                    .unwrap_or_default(),
+                escape_cell(result.title.as_deref().unwrap_or(""))
+            );
+        }
+        push_overflow(&mut out, soft_404s.len());
+    }
+
+    let insecure: Vec<&CrawlResult> = results
+        .iter()
+        .filter(|r| !r.mixed_content.is_empty() || !r.insecure_links.is_empty())
+        .collect();
+    if !insecure.is_empty() {
+        out.push_str(
+            "\n## Insecure content\n\n| Page | Mixed content | Insecure links | First |\n|---|---|---|---|\n",
+        );
+        for result in insecure.iter().take(MAX_TABLE_ROWS) {
+            let first = result
+                .mixed_content
+                .first()
+                .or(result.insecure_links.first())
+                .map(|url| escape_cell(&display_form(url)))
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape_cell(shown_url(result)),
+                result.mixed_content.len(),
+                result.insecure_links.len(),
+                first
+            );
+        }
+        push_overflow(&mut out, insecure.len());
+    }
+
+    let assets = asset_problems(results);
+    if !assets.is_empty() {
+        out.push_str(
+            "\n## Asset problems\n\n| Asset | Kind | Problem | Loaded from |\n|---|---|---|---|\n",
+        );
+        for (asset, sources) in assets.iter().take(MAX_TABLE_ROWS) {
+            let mut loaded_from = escape_cell(&display_form(sources[0]));
+            if sources.len() > 1 {
+                let _ = write!(loaded_from, " (+{} more)", sources.len() - 1);
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape_cell(&display_form(&asset.url)),
+                asset.kind.label(),
+                asset.problem().unwrap_or_default(),
+                loaded_from
+            );
+        }
+        push_overflow(&mut out, assets.len());
+    }
+
+    if !report.external_domains.is_empty() {
+        out.push_str(
+            "\n## External domains\n\n| Domain | Links | Pages | Linked from |\n|---|---|---|---|\n",
+        );
+        for domain in report.external_domains.iter().take(MAX_TABLE_ROWS) {
+            let mut linked_from = domain
+                .pages
+                .first()
+                .map(|page| escape_cell(&display_form(page)))
+                .unwrap_or_default();
+            if domain.pages.len() > 1 {
+                let _ = write!(linked_from, " (+{} more)", domain.pages.len() - 1);
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape_cell(&domain.domain),
+                domain.links,
+                domain.pages.len(),
+                linked_from
+            );
+        }
+        push_overflow(&mut out, report.external_domains.len());
+    }
+
+    let mut slowest: Vec<&CrawlResult> = results.iter().collect();
+    slowest.sort_by_key(|r| Reverse(r.crawl_time));
+    if !slowest.is_empty() {
+        out.push_str("\n## Slowest pages\n\n| Time | Status | URL |\n|---|---|---|\n");
+        for result in slowest.iter().take(SLOWEST_PAGES) {
+            let _ = writeln!(
+                out,
+                "| {}ms | {} | {} |",
+                result.crawl_time.as_millis(),
+                result.status_code,
+                escape_cell(shown_url(result))
+            );
+        }
+    }
+
+    if !report.certificates.is_empty() {
+        out.push_str("\n## Certificates\n\n| Host | Expires | Issuer |\n|---|---|---|\n");
+        for certificate in &report.certificates {
+            let expires = UNIX_EPOCH + Duration::from_secs(certificate.not_after.max(0) as u64);
+            let mut expires = httpdate::fmt_http_date(expires);
+            if certificate.expiring_soon {
+                expires = format!("**{} (expiring soon)**", expires);
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                escape_cell(&certificate.host),
+                expires,
+                escape_cell(&certificate.issuer)
+            );
+        }
+    }
+
+    out
+}
+
+/// Links to crawled pages that returned an error, with the pages linking to them,
+/// in crawl order.
+pub(crate) fn broken_links(results: &[CrawlResult]) -> Vec<(&str, u16, Vec<&str>)> {
+    let status_of: HashMap<&str, u16> = results
+        .iter()
+        .map(|r| (r.url.as_str(), r.status_code))
+        .collect();
+    let mut broken: Vec<(&str, u16, Vec<&str>)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+
+    for result in results {
+        for link in &result.links {
+            let Some(&status) = status_of.get(link.as_str()).filter(|s| **s >= 400) else {
+                continue;
+            };
+            let slot = *index.entry(link.as_str()).or_insert_with(|| {
+                broken.push((link.as_str(), status, Vec::new()));
+                broken.len() - 1
+            });
+            let sources = &mut broken[slot].2;
+            if !sources.contains(&result.url.as_str()) {
+                sources.push(&result.url);
+            }
+        }
+    }
+    broken
+}
+
+/// Broken or mixed-content assets, in order of first appearance, with the pages
+/// loading them.
+fn asset_problems(results: &[CrawlResult]) -> Vec<(&PageAsset, Vec<&str>)> {
+    let mut problems: Vec<(&PageAsset, Vec<&str>)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for result in results {
+        for asset in result.assets.iter().filter(|a| a.problem().is_some()) {
+            let slot = *index.entry(asset.url.as_str()).or_insert_with(|| {
+                problems.push((asset, Vec::new()));
+                problems.len() - 1
+            });
+            let sources = &mut problems[slot].1;
+            if !sources.contains(&result.url.as_str()) {
+                sources.push(&result.url);
+            }
+        }
+    }
+    problems
+}
+
+/// The URL readers should see: internationalized hosts in Unicode.
+fn shown_url(result: &CrawlResult) -> &str {
+    result.display_url.as_deref().unwrap_or(&result.url)
+}
+
+fn display_form(url: &str) -> String {
+    display_url(url).unwrap_or_else(|| url.to_string())
+}
+
+fn push_overflow(out: &mut String, total: usize) {
+    if total > MAX_TABLE_ROWS {
+        let _ = writeln!(out, "\n…and {} more.", total - MAX_TABLE_ROWS);
+    }
+}
+
+/// Keep table cells on one line and stop `|` from ending the cell.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlFailure;
+    use std::time::Duration;
+
+    fn page(url: &str, status_code: u16, millis: u64, links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code,
+            title: Some(format!("Page | {}", status_code)),
+            links: links.iter().map(|l| l.to_string()).collect(),
+            crawl_time: Duration::from_millis(millis),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_report_tables() {
+        let results = vec![
+            page(
+                "https://example.com/",
+                200,
+                30,
+                &["https://example.com/gone", "https://example.com/a"],
+            ),
+            page(
+                "https://example.com/a",
+                200,
+                90,
+                &["https://example.com/gone"],
+            ),
+            page("https://example.com/gone", 404, 10, &[]),
+        ];
+        let report = CrawlReport {
+            results: &results,
+            failures: vec![CrawlFailure {
+                url: "https://example.com/down".to_string(),
+                depth: 1,
+                reason: "Timed out fetching https://example.com/down after 3 attempt(s)"
+                    .to_string(),
+                attempts: 3,
+                error: None,
+            }],
+            clusters: crate::cluster_results(&results),
+            categories: crate::classify::summarize_categories(&results),
+            anomalies: crate::detect_anomalies(&results),
+            diff: Some(crate::diff_crawls(&results[..1], &results)),
+            ..Default::default()
+        };
+
+        let markdown = render_report(&report);
+        assert!(markdown.contains("- **Pages crawled:** 3\n"));
+        assert!(markdown
+            .contains("Since the previous crawl: 2 added, 0 removed, 0 changed, 1 unchanged."));
+        assert!(markdown.contains("| added | https://example.com/a |  |\n"));
+        assert!(markdown.contains("- **Status codes:** 2xx: 2, 4xx: 1\n"));
+        assert!(markdown.contains("- **Average response time:** 43ms\n"));
+        assert!(markdown.contains("- **Failed URLs:** 1\n"));
+        assert!(markdown.contains("| https://example.com/down | 1 | 3 | Timed out fetching"));
+        assert!(markdown.contains("| 404 | https://example.com/gone | Page \\| 404 |\n"));
+        assert!(markdown
+            .contains("| https://example.com/gone | 404 | https://example.com/ (+1 more) |\n"));
+        let slowest = markdown.split("## Slowest pages").nth(1).unwrap();
+        assert!(slowest.find("/a |").unwrap() < slowest.find("example.com/ |").unwrap());
+    }
+}
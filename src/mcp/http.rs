@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Empty, Full, Limited, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::header::{
+    HeaderMap, ACCEPT, ALLOW, CACHE_CONTROL, CONTENT_TYPE, HOST, ORIGIN, RETRY_AFTER,
+};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, RwLock};
+use url::Url;
+use uuid::Uuid;
+
+use crate::mcp::notifications::ResourceNotifier;
+use crate::mcp::protocol::{handle_message, is_initialize, parse_error};
+use crate::mcp::server::RustCrawlerMcpServer;
+use crate::mcp::storage::SessionStore;
+
+/// Path of the MCP endpoint.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Header carrying the session ID handed out on `initialize`.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Largest POST body read, batches included.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// How many sessions the server keeps and for how long, so clients that go away
+/// without a DELETE don't pile up.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLimits {
+    /// A session unused for this long is closed, unless its GET stream is open.
+    pub idle_ttl: Duration,
+    /// `initialize` is refused with 503 while this many sessions are open.
+    pub max_sessions: usize,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            idle_ttl: Duration::from_secs(30 * 60),
+            max_sessions: 100,
+        }
+    }
+}
+
+/// A client's session: its own crawls and results, and the notifications waiting for
+/// its GET stream.
+struct Session {
+    server: Arc<RustCrawlerMcpServer>,
+    notifications: Arc<Mutex<Option<mpsc::UnboundedReceiver<String>>>>,
+    last_used: Mutex<Instant>,
+}
+
+impl Session {
+    /// Unused for `ttl`; a session whose GET stream is open is in use.
+    fn is_expired(&self, ttl: Duration, now: Instant) -> bool {
+        let streaming = self.notifications.lock().unwrap().is_none();
+        !streaming && now.duration_since(*self.last_used.lock().unwrap()) >= ttl
+    }
+}
+
+struct Transport {
+    sessions: RwLock<HashMap<String, Arc<Session>>>,
+    /// Bound to a loopback address, so only local Host and Origin headers are let in.
+    local_only: bool,
+    /// Shared by every session when the server keeps crawl sessions on disk.
+    store: Option<SessionStore>,
+    limits: SessionLimits,
+}
+
+/// Serve MCP over the Streamable HTTP transport on `listener`, at `MCP_PATH`, until the
+/// process ends. Each `initialize` opens a session with its own crawls and results;
+/// clients send its ID back in the `Mcp-Session-Id` header and end it with a DELETE.
+/// Requests get a JSON reply, or an SSE stream carrying their progress first when the
+/// client accepts `text/event-stream`; resource updates go to the session's GET stream.
+/// With a `store`, all sessions share the crawls kept in it instead. Sessions left idle
+/// are closed as `limits` says.
+pub async fn serve(
+    listener: TcpListener,
+    store: Option<SessionStore>,
+    limits: SessionLimits,
+) -> Result<()> {
+    let address = listener.local_addr()?;
+    let transport = Arc::new(Transport {
+        sessions: RwLock::new(HashMap::new()),
+        local_only: address.ip().is_loopback(),
+        store,
+        limits,
+    });
+    info!("Serving MCP on http://{}{}", address, MCP_PATH);
+
+    // Close idle sessions in the background; ends once `serve` is dropped
+    let sweeper = Arc::downgrade(&transport);
+    tokio::spawn(async move {
+        let period = (limits.idle_ttl / 2).clamp(Duration::from_secs(1), Duration::from_secs(60));
+        let mut ticks = tokio::time::interval(period);
+        loop {
+            ticks.tick().await;
+            let Some(transport) = sweeper.upgrade() else {
+                break;
+            };
+            transport.close_expired().await;
+        }
+    });
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let transport = transport.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let transport = transport.clone();
+                async move { Ok::<_, Infallible>(transport.handle(request).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
+
+impl Transport {
+    async fn handle(&self, request: Request<Incoming>) -> Response<Body> {
+        if request.uri().path() != MCP_PATH {
+            return transport_error(StatusCode::NOT_FOUND, "Not found");
+        }
+        if self.local_only && !is_local_request(request.headers()) {
+            return transport_error(StatusCode::FORBIDDEN, "Host or origin not allowed");
+        }
+        match *request.method() {
+            Method::POST => self.post(request).await,
+            Method::GET => self.get(request).await,
+            Method::DELETE => self.delete(request).await,
+            _ => {
+                let mut response =
+                    transport_error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+                response
+                    .headers_mut()
+                    .insert(ALLOW, "GET, POST, DELETE".parse().unwrap());
+                response
+            }
+        }
+    }
+
+    /// One JSON-RPC message or a batch of them.
+    async fn post(&self, request: Request<Incoming>) -> Response<Body> {
+        let streaming = accepts(request.headers(), "text/event-stream");
+        let session_id = session_id(request.headers());
+        let body = match Limited::new(request.into_body(), MAX_BODY_BYTES)
+            .collect()
+            .await
+        {
+            Ok(body) => body.to_bytes(),
+            Err(e) => {
+                return transport_error(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Failed to read body: {}", e),
+                )
+            }
+        };
+        let (messages, batch) = match serde_json::from_slice(&body) {
+            Ok(Value::Array(messages)) => (messages, true),
+            Ok(message) => (vec![message], false),
+            Err(e) => return json_response(StatusCode::BAD_REQUEST, &parse_error(&e)),
+        };
+
+        let (session_id, session) = if messages.iter().any(is_initialize) {
+            if messages.len() > 1 {
+                return transport_error(
+                    StatusCode::BAD_REQUEST,
+                    "initialize must be sent on its own",
+                );
+            }
+            match self.open_session().await {
+                Some(opened) => opened,
+                None => {
+                    let mut response = transport_error(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Too many open sessions; try again later",
+                    );
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, "60".parse().unwrap());
+                    return response;
+                }
+            }
+        } else {
+            match self.session(session_id).await {
+                Ok(found) => found,
+                Err((status, message)) => return transport_error(status, message),
+            }
+        };
+
+        let mut response = if !messages.iter().any(is_request) {
+            // Notifications and responses are only acknowledged
+            for message in &messages {
+                handle_message(&session.server, message, None).await;
+            }
+            empty_response(StatusCode::ACCEPTED)
+        } else if streaming {
+            let (outgoing, replies) = mpsc::unbounded_channel();
+            let server = session.server.clone();
+            tokio::spawn(async move {
+                for message in &messages {
+                    if let Some(reply) = handle_message(&server, message, Some(&outgoing)).await {
+                        let _ = outgoing.send(reply.to_string());
+                    }
+                }
+            });
+            // Ends once the replies are sent and progress reporters dropped
+            sse_response(receiver_stream(replies))
+        } else {
+            let mut replies = Vec::new();
+            for message in &messages {
+                replies.extend(handle_message(&session.server, message, None).await);
+            }
+            let body = if batch {
+                Value::Array(replies)
+            } else {
+                replies.pop().unwrap_or_default()
+            };
+            json_response(StatusCode::OK, &body)
+        };
+        response
+            .headers_mut()
+            .insert(SESSION_HEADER, session_id.parse().unwrap());
+        response
+    }
+
+    /// The session's stream of resource update notifications.
+    async fn get(&self, request: Request<Incoming>) -> Response<Body> {
+        if !accepts(request.headers(), "text/event-stream") {
+            return transport_error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "GET opens an event stream; accept text/event-stream",
+            );
+        }
+        let (_, session) = match self.session(session_id(request.headers())).await {
+            Ok(found) => found,
+            Err((status, message)) => return transport_error(status, message),
+        };
+        let Some(receiver) = session.notifications.lock().unwrap().take() else {
+            return transport_error(
+                StatusCode::CONFLICT,
+                "The session already has an event stream open",
+            );
+        };
+        sse_response(LentReceiver {
+            receiver: Some(receiver),
+            slot: session.notifications.clone(),
+        })
+    }
+
+    async fn delete(&self, request: Request<Incoming>) -> Response<Body> {
+        let Some(id) = session_id(request.headers()) else {
+            return transport_error(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header");
+        };
+        match self.sessions.write().await.remove(&id) {
+            Some(_) => {
+                info!("Closed MCP session {}", id);
+                empty_response(StatusCode::NO_CONTENT)
+            }
+            None => transport_error(StatusCode::NOT_FOUND, "Unknown session"),
+        }
+    }
+
+    /// A new session, or `None` when `max_sessions` are open even after closing the
+    /// expired ones.
+    async fn open_session(&self) -> Option<(String, Arc<Session>)> {
+        if self.sessions.read().await.len() >= self.limits.max_sessions {
+            self.close_expired().await;
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut server = RustCrawlerMcpServer::new().with_notifier(ResourceNotifier::new(sender));
+        if let Some(store) = &self.store {
+            server = server.with_storage(store.clone());
+        }
+        let session = Arc::new(Session {
+            server: Arc::new(server),
+            notifications: Arc::new(Mutex::new(Some(receiver))),
+            last_used: Mutex::new(Instant::now()),
+        });
+        let id = Uuid::new_v4().to_string();
+        let mut sessions = self.sessions.write().await;
+        if sessions.len() >= self.limits.max_sessions {
+            warn!("Refused a new MCP session: {} are open", sessions.len());
+            return None;
+        }
+        sessions.insert(id.clone(), session.clone());
+        info!("Opened MCP session {}", id);
+        Some((id, session))
+    }
+
+    async fn close_expired(&self) {
+        let now = Instant::now();
+        self.sessions.write().await.retain(|id, session| {
+            let expired = session.is_expired(self.limits.idle_ttl, now);
+            if expired {
+                info!("Closed idle MCP session {}", id);
+            }
+            !expired
+        });
+    }
+
+    async fn session(
+        &self,
+        id: Option<String>,
+    ) -> Result<(String, Arc<Session>), (StatusCode, &'static str)> {
+        let Some(id) = id else {
+            return Err((StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"));
+        };
+        let now = Instant::now();
+        match self.sessions.read().await.get(&id) {
+            // Expired but not swept yet
+            Some(session) if !session.is_expired(self.limits.idle_ttl, now) => {
+                *session.last_used.lock().unwrap() = now;
+                Ok((id, session.clone()))
+            }
+            _ => Err((StatusCode::NOT_FOUND, "Unknown session")),
+        }
+    }
+}
+
+/// A session's notification receiver, lent to its GET stream and handed back when the
+/// client disconnects so it can open another.
+struct LentReceiver {
+    receiver: Option<mpsc::UnboundedReceiver<String>>,
+    slot: Arc<Mutex<Option<mpsc::UnboundedReceiver<String>>>>,
+}
+
+impl Stream for LentReceiver {
+    type Item = String;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<String>> {
+        match self.get_mut().receiver.as_mut() {
+            Some(receiver) => receiver.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Drop for LentReceiver {
+    fn drop(&mut self) {
+        *self.slot.lock().unwrap() = self.receiver.take();
+    }
+}
+
+fn receiver_stream(receiver: mpsc::UnboundedReceiver<String>) -> impl Stream<Item = String> {
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (line, receiver))
+    })
+}
+
+/// A request, as opposed to a notification or a response, which get no reply.
+fn is_request(message: &Value) -> bool {
+    message.get("id").is_some() && message.get("method").is_some()
+}
+
+fn session_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn accepts(headers: &HeaderMap, mime: &str) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|accepted| accepted.split(';').next().unwrap_or("").trim() == mime)
+}
+
+/// Whether the Host header, and the Origin header if any, name this machine, so a web
+/// page can't reach a local server through DNS rebinding.
+fn is_local_request(headers: &HeaderMap) -> bool {
+    let is_local = |url: Option<Url>| {
+        url.as_ref().and_then(Url::host_str).is_some_and(|host| {
+            host == "localhost"
+                || host
+                    .trim_matches(['[', ']'])
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback())
+        })
+    };
+    let host = headers.get(HOST).and_then(|value| value.to_str().ok());
+    let origin = headers.get(ORIGIN).and_then(|value| value.to_str().ok());
+    is_local(host.and_then(|host| Url::parse(&format!("http://{}", host)).ok()))
+        && origin.map_or(true, |origin| is_local(Url::parse(origin).ok()))
+}
+
+fn sse_response(lines: impl Stream<Item = String> + Send + 'static) -> Response<Body> {
+    let frames = lines.map(|line| {
+        Ok(Frame::data(Bytes::from(format!(
+            "event: message\ndata: {}\n\n",
+            line
+        ))))
+    });
+    Response::builder()
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(BodyExt::boxed_unsync(StreamBody::new(frames)))
+        .unwrap()
+}
+
+fn json_response(status: StatusCode, body: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body.to_string())).boxed_unsync())
+        .unwrap()
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Empty::new().boxed_unsync())
+        .unwrap()
+}
+
+/// A failure of the transport itself, as a JSON-RPC error without an ID.
+fn transport_error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(
+        status,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": { "code": -32000, "message": message }
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn start(limits: SessionLimits) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}{}", listener.local_addr().unwrap(), MCP_PATH);
+        tokio::spawn(serve(listener, None, limits));
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_sessions_over_streamable_http() {
+        let endpoint = start(SessionLimits::default()).await;
+        let client = reqwest::Client::new();
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-03-26" }
+        });
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+
+        let response = client
+            .post(&endpoint)
+            .header(ACCEPT, "application/json")
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let reply: Value = response.json().await.unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], "2025-03-26");
+
+        // Requests outside a session are turned away
+        let response = client.post(&endpoint).json(&ping).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, "bogus")
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let initialized = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .json(&initialized)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+        assert_eq!(
+            response.text().await.unwrap(),
+            "event: message\ndata: {\"id\":2,\"jsonrpc\":\"2.0\",\"result\":{}}\n\n"
+        );
+
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .header(ORIGIN, "https://evil.test")
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = client
+            .delete(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_expire_and_make_room() {
+        let endpoint = start(SessionLimits {
+            idle_ttl: Duration::from_millis(300),
+            max_sessions: 1,
+        })
+        .await;
+        let client = reqwest::Client::new();
+        let initialize = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+
+        let response = client
+            .post(&endpoint)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER].clone();
+
+        let response = client
+            .post(&endpoint)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let response = client
+            .post(&endpoint)
+            .header(SESSION_HEADER, &session)
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = client
+            .post(&endpoint)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
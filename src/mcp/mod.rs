@@ -1,5 +1,12 @@
+pub mod http;
+pub mod notifications;
+pub mod prompts;
+pub mod protocol;
 pub mod resources;
 pub mod server;
+pub mod storage;
 pub mod tools;
 
-pub use server::RustCrawlerMcpServer;
+pub use notifications::{ProgressReporter, ResourceNotifier};
+pub use server::{InvalidArguments, RustCrawlerMcpServer};
+pub use storage::SessionStore;
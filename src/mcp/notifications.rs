@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use log::debug;
+use serde_json::Value;
+use tokio::sync::{mpsc, RwLock};
+
+/// Tracks `resources/subscribe` requests and pushes `notifications/resources/updated`
+/// messages to the client's outgoing channel when a subscribed resource changes.
+#[derive(Clone, Default)]
+pub struct ResourceNotifier {
+    sender: Option<mpsc::UnboundedSender<String>>,
+    subscriptions: Arc<RwLock<HashSet<String>>>,
+}
+
+impl ResourceNotifier {
+    /// Notifications are written as JSON-RPC lines to `sender`.
+    pub fn new(sender: mpsc::UnboundedSender<String>) -> Self {
+        Self {
+            sender: Some(sender),
+            subscriptions: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+
+    pub async fn subscribe(&self, uri: &str) {
+        self.subscriptions.write().await.insert(uri.to_string());
+    }
+
+    pub async fn unsubscribe(&self, uri: &str) {
+        self.subscriptions.write().await.remove(uri);
+    }
+
+    pub async fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions.read().await.contains(uri)
+    }
+
+    /// Tell the client `uri` changed, if it subscribed to it.
+    pub async fn resource_updated(&self, uri: &str) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if !self.is_subscribed(uri).await {
+            return;
+        }
+
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": { "uri": uri }
+        });
+        if sender.send(notification.to_string()).is_err() {
+            debug!("Client channel closed, dropping update for {}", uri);
+        }
+    }
+}
+
+/// Sends `notifications/progress` messages for one request while it runs.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    sender: mpsc::UnboundedSender<String>,
+    token: Value,
+}
+
+impl ProgressReporter {
+    /// Report progress on the request that carried `token` in its `_meta.progressToken`,
+    /// writing to `sender`: the client's channel on stdio, the request's own stream over
+    /// HTTP.
+    pub fn new(sender: mpsc::UnboundedSender<String>, token: Value) -> Self {
+        Self { sender, token }
+    }
+
+    /// `progress` must grow with each call; `total` is left out when unknown.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: &str) {
+        let mut params = serde_json::json!({
+            "progressToken": self.token,
+            "progress": progress,
+            "message": message
+        });
+        if let Some(total) = total {
+            params["total"] = total.into();
+        }
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": params
+        });
+        if self.sender.send(notification.to_string()).is_err() {
+            debug!(
+                "Client channel closed, dropping progress for {}",
+                self.token
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_only_subscribed_resources_notify() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let notifier = ResourceNotifier::new(tx);
+
+        notifier.resource_updated("crawl://results/a").await;
+        notifier.subscribe("crawl://results/b").await;
+        notifier.resource_updated("crawl://results/b").await;
+        notifier.unsubscribe("crawl://results/b").await;
+        notifier.resource_updated("crawl://results/b").await;
+
+        let line = rx.try_recv().unwrap();
+        let message: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(message["method"], "notifications/resources/updated");
+        assert_eq!(message["params"]["uri"], "crawl://results/b");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_progress_carries_the_request_token() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let reporter = ProgressReporter::new(tx, 7.into());
+        reporter.report(3, Some(10), "3 page(s) crawled");
+        reporter.report(4, None, "4 page(s) crawled");
+
+        let message: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(message["params"]["progressToken"], 7);
+        assert_eq!(message["params"]["progress"], 3);
+        assert_eq!(message["params"]["total"], 10);
+        let message: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert!(message["params"].get("total").is_none());
+    }
+}
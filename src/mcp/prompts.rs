@@ -0,0 +1,182 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::mcp::server::InvalidArguments;
+use crate::mcp::tools::{MAX_CRAWL_DEPTH, MAX_CRAWL_PAGES};
+
+/// The prompts served by `prompts/list`: one-click workflows built on the tools.
+pub fn prompt_list() -> Vec<Value> {
+    vec![
+        serde_json::json!({
+            "name": "site_audit",
+            "description": "Crawl a site and report its broken links and SEO issues, most urgent first",
+            "arguments": [
+                {
+                    "name": "url",
+                    "description": "Home page of the site to audit",
+                    "required": true
+                },
+                {
+                    "name": "max_pages",
+                    "description": format!("Pages to crawl, at most {} (default: {})", MAX_CRAWL_PAGES, MAX_CRAWL_PAGES),
+                    "required": false
+                }
+            ]
+        }),
+        serde_json::json!({
+            "name": "summarize_site",
+            "description": "Crawl a site and outline its structure: sections, key pages and what the site is for",
+            "arguments": [
+                {
+                    "name": "url",
+                    "description": "Home page of the site to summarize",
+                    "required": true
+                },
+                {
+                    "name": "max_depth",
+                    "description": format!("Link depth to follow, at most {} (default: 2)", MAX_CRAWL_DEPTH),
+                    "required": false
+                }
+            ]
+        }),
+    ]
+}
+
+/// The `prompts/get` result for `name`, with `arguments` filled in.
+pub fn get_prompt(name: &str, arguments: &Value) -> Result<Value> {
+    let (description, text) = match name {
+        "site_audit" => {
+            let (url, max_pages) = match (
+                required_url(arguments),
+                bounded_number(arguments, "max_pages", MAX_CRAWL_PAGES, MAX_CRAWL_PAGES),
+            ) {
+                (Ok(url), Ok(max_pages)) => (url, max_pages),
+                (url, max_pages) => return Err(invalid(name, [url.err(), max_pages.err()])),
+            };
+            (
+                format!("SEO and broken link audit of {}", url),
+                format!(
+                    "Audit the website at {url}.\n\n\
+                     1. Call `crawl_website` with url \"{url}\", background false, max_depth {depth}, \
+                     max_pages {max_pages}, extract_structured_data true and summary_format \"markdown\".\n\
+                     2. Call `query_results` on the session with status [\"4xx\", \"5xx\"] to list the \
+                     broken pages, and read the session's `failures` for URLs that couldn't be fetched.\n\
+                     3. Check every page for SEO issues: a missing or duplicate title, a missing meta \
+                     description, no h1 or several, a canonical pointing elsewhere, noindex, soft 404s, \
+                     mixed content and links to http:// URLs.\n\n\
+                     Report the broken links first, each with the pages linking to it when you can tell, \
+                     then the SEO issues grouped by kind with the affected URLs. End with a short list of \
+                     fixes, most urgent first. Only report what the crawl data shows.",
+                    url = url,
+                    depth = MAX_CRAWL_DEPTH,
+                    max_pages = max_pages
+                ),
+            )
+        }
+        "summarize_site" => {
+            let (url, max_depth) = match (
+                required_url(arguments),
+                bounded_number(arguments, "max_depth", MAX_CRAWL_DEPTH, 2),
+            ) {
+                (Ok(url), Ok(max_depth)) => (url, max_depth),
+                (url, max_depth) => return Err(invalid(name, [url.err(), max_depth.err()])),
+            };
+            (
+                format!("Structure and purpose of {}", url),
+                format!(
+                    "Summarize the website at {url}.\n\n\
+                     1. Call `crawl_website` with url \"{url}\", background false, max_depth {max_depth}, \
+                     max_pages {max_pages}, same_domain true and extract_text true.\n\
+                     2. Read the pages from the tool result, or page through them with `query_results`.\n\n\
+                     Then outline the site: what it is for and who it serves, its main sections as a tree \
+                     following the URL paths, with each section's key pages and their titles, and how the \
+                     home page links into them. Mention sections the crawl only reached partly. Keep it \
+                     under 400 words and base it on the crawled pages only.",
+                    url = url,
+                    max_depth = max_depth,
+                    max_pages = MAX_CRAWL_PAGES
+                ),
+            )
+        }
+        _ => return Err(anyhow::anyhow!("Unknown prompt: {}", name)),
+    };
+
+    Ok(serde_json::json!({
+        "description": description,
+        "messages": [
+            {
+                "role": "user",
+                "content": { "type": "text", "text": text }
+            }
+        ]
+    }))
+}
+
+fn required_url(arguments: &Value) -> Result<String, String> {
+    let url = arguments["url"]
+        .as_str()
+        .filter(|url| !url.trim().is_empty())
+        .ok_or("arguments.url: is required")?;
+    match url::Url::parse(url.trim()) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(parsed.to_string()),
+        _ => Err(format!("arguments.url: not an http(s) URL: {}", url)),
+    }
+}
+
+/// Prompt arguments are strings, so numbers come as e.g. `"10"`.
+fn bounded_number(
+    arguments: &Value,
+    key: &str,
+    max: usize,
+    default: usize,
+) -> Result<usize, String> {
+    let Some(value) = arguments[key].as_str() else {
+        return Ok(default);
+    };
+    match value.trim().parse::<usize>() {
+        Ok(n) if (1..=max).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "arguments.{}: must be a number from 1 to {}, got {}",
+            key, max, value
+        )),
+    }
+}
+
+fn invalid(prompt: &str, errors: impl IntoIterator<Item = Option<String>>) -> anyhow::Error {
+    InvalidArguments {
+        tool: prompt.to_string(),
+        errors: errors.into_iter().flatten().collect(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_fill_in_their_arguments() {
+        let names: Vec<_> = prompt_list()
+            .iter()
+            .map(|prompt| prompt["name"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["site_audit", "summarize_site"]);
+
+        let prompt = get_prompt(
+            "site_audit",
+            &serde_json::json!({ "url": "https://example.com", "max_pages": "5" }),
+        )
+        .unwrap();
+        assert_eq!(prompt["messages"][0]["role"], "user");
+        let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("url \"https://example.com/\""));
+        assert!(text.contains("max_pages 5,"));
+
+        let error =
+            get_prompt("site_audit", &serde_json::json!({ "max_pages": "500" })).unwrap_err();
+        let invalid = error.downcast_ref::<InvalidArguments>().unwrap();
+        assert_eq!(invalid.errors.len(), 2);
+        assert!(get_prompt("summarize_site", &serde_json::json!({ "url": "ftp://x" })).is_err());
+        assert!(get_prompt("bogus", &serde_json::json!({})).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use log::error;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::mcp::notifications::ProgressReporter;
+use crate::mcp::server::{InvalidArguments, RustCrawlerMcpServer};
+
+/// Protocol versions `initialize` accepts, newest first; 2025-03-26 brought the
+/// Streamable HTTP transport.
+pub const PROTOCOL_VERSIONS: [&str; 2] = ["2025-03-26", "2024-11-05"];
+
+/// Answer one JSON-RPC message, whichever transport it came over. Notifications and
+/// responses from the client get no reply. Progress of `tools/call` requests is written
+/// to `outgoing` when the transport has a channel for it.
+pub async fn handle_message(
+    server: &RustCrawlerMcpServer,
+    message: &Value,
+    outgoing: Option<&mpsc::UnboundedSender<String>>,
+) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    message.get("method")?;
+
+    match dispatch(server, message, outgoing).await {
+        Ok(result) => Some(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result
+        })),
+        Err(e) => {
+            error!("Error handling MCP message: {}", e);
+            let error = match e.downcast_ref::<InvalidArguments>() {
+                Some(invalid) => json!({
+                    "code": -32602,
+                    "message": e.to_string(),
+                    "data": { "errors": invalid.errors }
+                }),
+                None => json!({
+                    "code": -32603,
+                    "message": e.to_string()
+                }),
+            };
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": error
+            }))
+        }
+    }
+}
+
+/// Reply to a message that isn't JSON at all.
+pub fn parse_error(e: &serde_json::Error) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": Value::Null,
+        "error": {
+            "code": -32700,
+            "message": format!("Parse error: {}", e)
+        }
+    })
+}
+
+/// Whether `message` is an `initialize` request, which opens an HTTP session.
+pub fn is_initialize(message: &Value) -> bool {
+    message["method"] == "initialize" && message.get("id").is_some()
+}
+
+async fn dispatch(
+    server: &RustCrawlerMcpServer,
+    request: &Value,
+    outgoing: Option<&mpsc::UnboundedSender<String>>,
+) -> Result<Value> {
+    let method = request["method"].as_str().unwrap_or("");
+    let params = &request["params"];
+
+    let result = match method {
+        "initialize" => {
+            // Agree to the client's version when we speak it, else offer our newest
+            let requested = params["protocolVersion"].as_str().unwrap_or("");
+            let version = PROTOCOL_VERSIONS
+                .into_iter()
+                .find(|version| *version == requested)
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            json!({
+                "protocolVersion": version,
+                "capabilities": {
+                    "tools": {},
+                    "resources": {
+                        "subscribe": true
+                    },
+                    "prompts": {}
+                },
+                "serverInfo": {
+                    "name": "rustcrawler",
+                    "version": "0.1.0"
+                }
+            })
+        }
+        "ping" => json!({}),
+        "tools/list" => {
+            json!({
+                "tools": server.get_tools()
+            })
+        }
+        "tools/call" => {
+            let tool_name = params["name"].as_str().unwrap_or("");
+            let arguments = params["arguments"].clone();
+            let progress = params["_meta"]
+                .get("progressToken")
+                .filter(|token| token.is_string() || token.is_number())
+                .zip(outgoing)
+                .map(|(token, outgoing)| ProgressReporter::new(outgoing.clone(), token.clone()));
+
+            let output = server
+                .handle_tool_call_with_progress(tool_name, arguments, progress)
+                .await?;
+            json!({
+                "content": output.content_blocks()?
+            })
+        }
+        "resources/list" => {
+            json!({
+                "resources": server.get_resources()
+            })
+        }
+        "resources/read" => {
+            let uri = params["uri"].as_str().unwrap_or("");
+            let content = server.get_resource(uri).await?;
+            json!({
+                "contents": [
+                    {
+                        "uri": uri,
+                        "mimeType": "application/json",
+                        "text": content
+                    }
+                ]
+            })
+        }
+        "resources/subscribe" => {
+            let uri = params["uri"].as_str().unwrap_or("");
+            server.subscribe(uri).await?;
+            json!({})
+        }
+        "resources/unsubscribe" => {
+            let uri = params["uri"].as_str().unwrap_or("");
+            server.unsubscribe(uri).await;
+            json!({})
+        }
+        "prompts/list" => {
+            json!({
+                "prompts": server.get_prompts()
+            })
+        }
+        "prompts/get" => {
+            let name = params["name"].as_str().unwrap_or("");
+            server.get_prompt(name, &params["arguments"])?
+        }
+        _ => {
+            return Err(anyhow::anyhow!("Unknown method: {}", method));
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_are_answered_and_notifications_are_not() {
+        let server = RustCrawlerMcpServer::new();
+
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2024-11-05" }
+        });
+        let response = handle_message(&server, &initialize, None).await.unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+
+        let initialized = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(handle_message(&server, &initialized, None).await.is_none());
+
+        let unknown = json!({"jsonrpc": "2.0", "id": "a", "method": "bogus"});
+        let response = handle_message(&server, &unknown, None).await.unwrap();
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["code"], -32603);
+    }
+}
@@ -1,47 +1,256 @@
+use crate::crawler::{
+    CrawlFailure, CrawlResult, Crawler, PageAsset, Soft404Reason, StructuredData,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+/// A crawl started by `crawl_website`, as stored by the server and served at
+/// `crawl://results/{session_id}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawlSession {
     pub session_id: String,
-    pub start_url: String,
-    pub start_time: u64,
-    pub end_time: Option<u64>,
     pub status: CrawlStatus,
+    pub start_url: String,
+    /// Unix seconds.
+    pub started_at: u64,
+    /// Unix seconds; `None` while the crawl runs.
+    pub finished_at: Option<u64>,
     pub pages_crawled: usize,
     pub total_links_found: usize,
+    /// Set, like the run and the failures, when the crawl ends.
+    pub crawl_duration_seconds: Option<u64>,
+    pub run_id: Option<String>,
+    /// The crawl's `RunMetadata`, as JSON so stored sessions can be read back.
+    pub run: Option<Value>,
     pub config: CrawlSessionConfig,
     pub results: Vec<CrawlPageResult>,
+    pub failures: Vec<CrawlFailure>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl CrawlSession {
+    /// A running session with no pages yet.
+    pub fn new(
+        session_id: impl Into<String>,
+        start_url: impl Into<String>,
+        config: CrawlSessionConfig,
+    ) -> Self {
+        Self {
+            session_id: session_id.into(),
+            status: CrawlStatus::Running,
+            start_url: start_url.into(),
+            started_at: unix_seconds(),
+            finished_at: None,
+            pages_crawled: 0,
+            total_links_found: 0,
+            crawl_duration_seconds: None,
+            run_id: None,
+            run: None,
+            config,
+            results: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn add_page(&mut self, result: &CrawlResult) {
+        self.pages_crawled += 1;
+        self.total_links_found += result.links.len();
+        self.results.push(CrawlPageResult::from(result));
+    }
+
+    /// Record the end of the crawl run by `crawler`. A cancelled session keeps its status.
+    pub fn finish(&mut self, crawler: &Crawler, crawl_duration_seconds: u64) {
+        if self.status == CrawlStatus::Running {
+            self.status = CrawlStatus::Completed;
+        }
+        self.finished_at = Some(unix_seconds());
+        self.crawl_duration_seconds = Some(crawl_duration_seconds);
+        let run = crawler.get_run_metadata();
+        self.run_id = run.as_ref().map(|run| run.run_id.clone());
+        self.run = run.and_then(|run| serde_json::to_value(run).ok());
+        self.failures = crawler.get_failures();
+    }
+
+    /// The session without its pages and failures, for listings.
+    pub fn summary(&self) -> CrawlSessionSummary {
+        CrawlSessionSummary {
+            session_id: self.session_id.clone(),
+            start_url: self.start_url.clone(),
+            status: self.status,
+            pages_crawled: self.pages_crawled,
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+        }
+    }
+
+    /// How far the crawl has got, served at `crawl://sessions/{session_id}/status`.
+    pub fn progress(&self) -> CrawlProgress {
+        CrawlProgress {
+            session_id: self.session_id.clone(),
+            status: self.status,
+            start_url: self.start_url.clone(),
+            pages_crawled: self.pages_crawled,
+            elapsed_seconds: self
+                .crawl_duration_seconds
+                .unwrap_or_else(|| unix_seconds().saturating_sub(self.started_at)),
+            last_url: self.results.last().map(|page| page.url.clone()),
+        }
+    }
+}
+
+/// `status` of a crawl session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CrawlStatus {
     Running,
     Completed,
-    Failed,
+    /// Stopped by `cancel_crawl`; the session keeps the pages crawled before that.
     Cancelled,
+    /// The server stopped while the crawl ran; only a stored session can end up so.
+    Interrupted,
+    /// A foreground crawl ran out of time and was stopped; the session keeps the
+    /// pages crawled before that.
+    TimedOut,
 }
 
+impl CrawlStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Cancelled => "cancelled",
+            Self::Interrupted => "interrupted",
+            Self::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// One entry of `crawl://sessions`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlSessionSummary {
+    pub session_id: String,
+    pub start_url: String,
+    pub status: CrawlStatus,
+    pub pages_crawled: usize,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlProgress {
+    pub session_id: String,
+    pub status: CrawlStatus,
+    pub start_url: String,
+    pub pages_crawled: usize,
+    /// Until now while the crawl runs, then its duration.
+    pub elapsed_seconds: u64,
+    /// The most recently crawled page.
+    pub last_url: Option<String>,
+}
+
+/// The `crawl_website` arguments a session ran with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CrawlSessionConfig {
     pub max_depth: usize,
     pub max_pages: Option<usize>,
     pub rate_limit: f64,
     pub respect_robots: bool,
     pub follow_redirects: bool,
+    pub keep_html: bool,
+    pub extract_text: bool,
+    pub extract_structured_data: bool,
+    pub languages: Vec<String>,
+    pub same_domain: bool,
+    pub max_concurrency: usize,
+    pub timeout_seconds: u64,
     pub user_agent: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A crawled page of a session: its `CrawlResult`, with a count in place of the links.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CrawlPageResult {
     pub url: String,
+    pub display_url: Option<String>,
     pub status_code: u16,
     pub title: Option<String>,
-    pub content_length: Option<usize>,
-    pub links_found: Vec<String>,
+    pub meta_description: Option<String>,
+    pub canonical: Option<String>,
+    pub h1: Vec<String>,
+    pub h2: Vec<String>,
+    pub open_graph: BTreeMap<String, String>,
+    pub links_found: usize,
     pub crawl_time_ms: u128,
     pub depth: usize,
-    pub timestamp: u64,
+    pub html: Option<String>,
+    pub text: Option<String>,
+    pub metadata: Option<StructuredData>,
+    pub extracted: BTreeMap<String, Vec<String>>,
+    pub html_truncated: bool,
+    pub language: Option<String>,
+    pub run_id: String,
+    pub final_url: String,
+    pub content_type: Option<String>,
+    pub content_length: Option<usize>,
+    pub mime_mismatch: bool,
+    pub body_skipped: bool,
+    pub body_truncated: bool,
+    pub not_modified: bool,
+    pub external_links: Vec<String>,
+    pub assets: Vec<PageAsset>,
+    pub mixed_content: Vec<String>,
+    pub insecure_links: Vec<String>,
+    pub soft_404: Option<Soft404Reason>,
+    pub noindex: bool,
+    pub response_headers: BTreeMap<String, String>,
+}
+
+impl From<&CrawlResult> for CrawlPageResult {
+    fn from(result: &CrawlResult) -> Self {
+        Self {
+            url: result.url.clone(),
+            display_url: result.display_url.clone(),
+            status_code: result.status_code,
+            title: result.title.clone(),
+            meta_description: result.meta_description.clone(),
+            canonical: result.canonical.clone(),
+            h1: result.h1.clone(),
+            h2: result.h2.clone(),
+            open_graph: result.open_graph.clone(),
+            links_found: result.links.len(),
+            crawl_time_ms: result.crawl_time.as_millis(),
+            depth: result.depth,
+            html: result.html.clone(),
+            text: result.text.clone(),
+            metadata: result.metadata.clone(),
+            extracted: result.extracted.clone(),
+            html_truncated: result.html_truncated,
+            language: result.language.clone(),
+            run_id: result.run_id.clone(),
+            final_url: result.final_url.clone(),
+            content_type: result.content_type.clone(),
+            content_length: result.content_length,
+            mime_mismatch: result.mime_mismatch,
+            body_skipped: result.body_skipped,
+            body_truncated: result.body_truncated,
+            not_modified: result.not_modified,
+            external_links: result.external_links.clone(),
+            assets: result.assets.clone(),
+            mixed_content: result.mixed_content.clone(),
+            insecure_links: result.insecure_links.clone(),
+            soft_404: result.soft_404,
+            noindex: result.noindex,
+            response_headers: result.response_headers.clone(),
+        }
+    }
+}
+
+pub(crate) fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,8 +285,7 @@ impl CrawlStatistics {
         self.total_sessions += 1;
         self.total_pages_crawled += session.pages_crawled as u64;
 
-        if let Some(end_time) = session.end_time {
-            let duration = end_time.saturating_sub(session.start_time);
+        if let Some(duration) = session.crawl_duration_seconds {
             self.total_crawl_time_seconds += duration;
         }
 
@@ -102,8 +310,8 @@ impl CrawlStatistics {
 
         // Update top domains
         if let Ok(url) = url::Url::parse(&session.start_url) {
-            if let Some(domain) = url.domain() {
-                *self.top_domains.entry(domain.to_string()).or_insert(0) += 1;
+            if let Some(host) = url.host_str() {
+                *self.top_domains.entry(host.to_string()).or_insert(0) += 1;
             }
         }
 
@@ -141,3 +349,48 @@ pub struct RobotsRule {
     pub crawl_delay: Option<f64>,
     pub sitemap_urls: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_progress_and_statistics() {
+        let mut session =
+            CrawlSession::new("s1", "https://example.com/", CrawlSessionConfig::default());
+        session.started_at -= 5;
+        for (url, status_code) in [
+            ("https://example.com/", 200),
+            ("https://example.com/a", 404),
+        ] {
+            session.results.push(CrawlPageResult {
+                url: url.to_string(),
+                status_code,
+                links_found: 3,
+                ..Default::default()
+            });
+            session.pages_crawled += 1;
+        }
+
+        let progress = session.progress();
+        assert_eq!(progress.status, CrawlStatus::Running);
+        assert_eq!(progress.pages_crawled, 2);
+        assert!(progress.elapsed_seconds >= 5);
+        assert_eq!(progress.last_url.as_deref(), Some("https://example.com/a"));
+        let json = serde_json::to_value(&progress).unwrap();
+        assert_eq!(json["status"], "running");
+        assert!(json.get("results").is_none());
+
+        session.status = CrawlStatus::Completed;
+        session.crawl_duration_seconds = Some(4);
+        let mut stats = CrawlStatistics::default();
+        stats.update_from_session(&session);
+        stats.update_from_session(&session);
+        assert_eq!(stats.total_sessions, 2);
+        assert_eq!(stats.total_pages_crawled, 4);
+        assert_eq!(stats.total_crawl_time_seconds, 8);
+        assert_eq!(stats.status_code_distribution[&404], 2);
+        assert_eq!(stats.top_domains["example.com"], 2);
+        assert_eq!(session.progress().elapsed_seconds, 4);
+    }
+}
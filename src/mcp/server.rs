@@ -6,13 +6,28 @@ use serde_json::Value;
 use tokio::sync::RwLock;
 
 use crate::crawler::{Crawler, CrawlerConfig};
-use crate::mcp::tools::{CrawlTool, GetRobotsTool, GetStatsTool};
+use crate::mcp::notifications::{ProgressReporter, ResourceNotifier};
+use crate::mcp::prompts;
+use crate::mcp::resources::CrawlStatistics;
+use crate::mcp::storage::SessionStore;
+use crate::mcp::tools::{
+    session_list, CancelCrawlTool, CrawlQueueTool, CrawlSessions, CrawlTool, DeleteSessionTool,
+    FetchPageTool, GetCrawlStatusTool, GetRobotsTool, GetStatsTool, ListCrawlSessionsTool,
+    QueryResultsTool, ResultQuery, RunningCrawls, ToolOutput, ValidateUrlsTool, DEFAULT_USER_AGENT,
+    MAX_CRAWL_CONCURRENCY, MAX_CRAWL_DEPTH, MAX_CRAWL_PAGES, MAX_CRAWL_TIMEOUT_SECS,
+    MAX_QUEUE_LIST, MAX_RESULTS_LIMIT, MAX_VALIDATE_URLS, SESSIONS_URI,
+};
+use crate::robots::{RobotsFetchStatus, RobotsInfo};
 
 #[derive(Clone)]
 pub struct RustCrawlerMcpServer {
-    crawler: Arc<RwLock<Option<Crawler>>>,
-    crawl_results: Arc<RwLock<HashMap<String, serde_json::Value>>>,
-    stats: Arc<RwLock<HashMap<String, u64>>>,
+    /// The crawler of the latest session, or the one set by `initialize_crawler`.
+    crawler: Arc<RwLock<Option<Arc<Crawler>>>>,
+    crawl_results: CrawlSessions,
+    stats: Arc<RwLock<CrawlStatistics>>,
+    notifier: ResourceNotifier,
+    running: RunningCrawls,
+    store: Option<SessionStore>,
 }
 
 impl RustCrawlerMcpServer {
@@ -20,21 +35,94 @@ impl RustCrawlerMcpServer {
         Self {
             crawler: Arc::new(RwLock::new(None)),
             crawl_results: Arc::new(RwLock::new(HashMap::new())),
-            stats: Arc::new(RwLock::new(HashMap::new())),
+            stats: Arc::new(RwLock::new(CrawlStatistics::default())),
+            notifier: ResourceNotifier::default(),
+            running: Arc::new(RwLock::new(HashMap::new())),
+            store: None,
         }
     }
 
+    /// Keep sessions and statistics in `store`, starting from what it already holds.
+    /// Servers given the same store see each other's sessions.
+    pub fn with_storage(mut self, store: SessionStore) -> Self {
+        self.crawl_results = store.sessions();
+        self.stats = store.stats();
+        self.store = Some(store);
+        self
+    }
+
+    /// Deliver resource update notifications through `notifier`.
+    pub fn with_notifier(mut self, notifier: ResourceNotifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    pub async fn subscribe(&self, uri: &str) -> Result<()> {
+        if !uri.starts_with("crawl://") {
+            return Err(anyhow::anyhow!("Unknown resource: {}", uri));
+        }
+        self.notifier.subscribe(uri).await;
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, uri: &str) {
+        self.notifier.unsubscribe(uri).await;
+    }
+
+    /// Set the crawler whose robots.txt cache `crawl://robots-cache` reports until the
+    /// next `crawl_website` call; each call builds its own crawler from its arguments.
     pub async fn initialize_crawler(&self, config: CrawlerConfig) -> Result<()> {
         let crawler = Crawler::new(config)?;
         let mut crawler_guard = self.crawler.write().await;
-        *crawler_guard = Some(crawler);
+        *crawler_guard = Some(Arc::new(crawler));
         Ok(())
     }
 
-    pub async fn handle_tool_call(&self, tool_name: &str, arguments: Value) -> Result<String> {
-        // Add timeout for tool calls to prevent hanging
-        let timeout_duration = std::time::Duration::from_secs(30);
-        
+    pub async fn handle_tool_call(&self, tool_name: &str, arguments: Value) -> Result<ToolOutput> {
+        self.handle_tool_call_with_progress(tool_name, arguments, None)
+            .await
+    }
+
+    /// Like `handle_tool_call`, reporting progress through `progress` for the tools
+    /// that can.
+    pub async fn handle_tool_call_with_progress(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        progress: Option<ProgressReporter>,
+    ) -> Result<ToolOutput> {
+        let Some(schema) = self
+            .get_tools()
+            .into_iter()
+            .find(|tool| tool["name"] == tool_name)
+            .map(|mut tool| tool["inputSchema"].take())
+        else {
+            return Err(anyhow::anyhow!("Unknown tool: {}", tool_name));
+        };
+        // Clients may leave out `arguments` for tools without required ones
+        let arguments = if arguments.is_null() {
+            serde_json::json!({})
+        } else {
+            arguments
+        };
+        let mut errors = Vec::new();
+        validate_schema(&schema, &arguments, "arguments", &mut errors);
+        if !errors.is_empty() {
+            return Err(InvalidArguments {
+                tool: tool_name.to_string(),
+                errors,
+            }
+            .into());
+        }
+
+        // Add timeout for tool calls to prevent hanging. Crawls stop themselves within
+        // a budget derived from their arguments, and keep the pages crawled until then.
+        let timeout_duration = if tool_name == "crawl_website" {
+            std::time::Duration::MAX
+        } else {
+            std::time::Duration::from_secs(30)
+        };
+
         let result = tokio::time::timeout(timeout_duration, async {
             match tool_name {
                 "crawl_website" => {
@@ -42,9 +130,46 @@ impl RustCrawlerMcpServer {
                         self.crawler.clone(),
                         self.crawl_results.clone(),
                         self.stats.clone(),
-                    );
+                        self.notifier.clone(),
+                        self.running.clone(),
+                    )
+                    .with_progress(progress)
+                    .with_store(self.store.clone());
                     crawl_tool.execute(arguments).await
                 }
+                "query_results" => {
+                    let query_tool = QueryResultsTool::new(self.crawl_results.clone());
+                    query_tool.execute(arguments).await
+                }
+                "get_crawl_status" => {
+                    let status_tool = GetCrawlStatusTool::new(self.crawl_results.clone());
+                    status_tool.execute(arguments).await
+                }
+                "list_crawl_sessions" => {
+                    let list_tool = ListCrawlSessionsTool::new(self.crawl_results.clone());
+                    list_tool.execute(arguments).await
+                }
+                "cancel_crawl" => {
+                    let cancel_tool =
+                        CancelCrawlTool::new(self.crawl_results.clone(), self.running.clone());
+                    cancel_tool.execute(arguments).await
+                }
+                "delete_session" => {
+                    let delete_tool = DeleteSessionTool::new(
+                        self.crawl_results.clone(),
+                        self.notifier.clone(),
+                        self.store.clone(),
+                    );
+                    delete_tool.execute(arguments).await
+                }
+                "crawl_queue" => {
+                    let queue_tool = CrawlQueueTool::new(self.running.clone());
+                    queue_tool.execute(arguments).await
+                }
+                "fetch_page" => {
+                    let fetch_tool = FetchPageTool::new();
+                    fetch_tool.execute(arguments).await
+                }
                 "get_robots_txt" => {
                     let robots_tool = GetRobotsTool::new();
                     robots_tool.execute(arguments).await
@@ -53,9 +178,14 @@ impl RustCrawlerMcpServer {
                     let stats_tool = GetStatsTool::new(self.stats.clone());
                     stats_tool.execute(arguments).await
                 }
+                "validate_urls" => {
+                    let validate_tool = ValidateUrlsTool::new();
+                    validate_tool.execute(arguments).await
+                }
                 _ => Err(anyhow::anyhow!("Unknown tool: {}", tool_name)),
             }
-        }).await;
+        })
+        .await;
 
         match result {
             Ok(result) => result,
@@ -64,23 +194,65 @@ impl RustCrawlerMcpServer {
     }
 
     pub async fn get_resource(&self, uri: &str) -> Result<String> {
-        if uri.starts_with("crawl://results/") {
-            let session_id = uri.strip_prefix("crawl://results/").unwrap();
+        if let Some(rest) = uri.strip_prefix("crawl://results/") {
+            // A query string selects and pages through the session's results
+            let (session_id, query) = match rest.split_once('?') {
+                Some((session_id, query)) => {
+                    (session_id, Some(ResultQuery::from_query_string(query)?))
+                }
+                None => (rest, None),
+            };
             let results = self.crawl_results.read().await;
 
-            if let Some(result) = results.get(session_id) {
-                Ok(serde_json::to_string_pretty(result)?)
-            } else {
-                Err(anyhow::anyhow!("Crawl session not found: {}", session_id))
+            match (results.get(session_id), query) {
+                (Some(result), Some(query)) => {
+                    Ok(serde_json::to_string_pretty(&query.apply(result)?)?)
+                }
+                (Some(result), None) => Ok(serde_json::to_string_pretty(result)?),
+                (None, _) => Err(anyhow::anyhow!("Crawl session not found: {}", session_id)),
+            }
+        } else if let Some(session_id) = uri
+            .strip_prefix("crawl://sessions/")
+            .and_then(|rest| rest.strip_suffix("/status"))
+        {
+            let results = self.crawl_results.read().await;
+            match results.get(session_id) {
+                Some(session) => Ok(serde_json::to_string_pretty(&session.progress())?),
+                None => Err(anyhow::anyhow!("Crawl session not found: {}", session_id)),
             }
+        } else if uri == SESSIONS_URI {
+            let results = self.crawl_results.read().await;
+            Ok(serde_json::to_string_pretty(&session_list(&results))?)
         } else if uri == "crawl://stats" {
             let stats = self.stats.read().await;
             Ok(serde_json::to_string_pretty(&*stats)?)
+        } else if uri == "crawl://robots-cache" {
+            let crawler = self.crawler.read().await;
+            let entries = match crawler.as_ref() {
+                Some(crawler) => crawler.robots_manager().cache_entries(),
+                None => Vec::new(),
+            };
+            let entries: Vec<Value> = entries
+                .iter()
+                .map(|(domain, info)| robots_cache_entry_json(domain, info))
+                .collect();
+            Ok(serde_json::to_string_pretty(&serde_json::json!({
+                "entries": entries
+            }))?)
         } else {
             Err(anyhow::anyhow!("Unknown resource: {}", uri))
         }
     }
 
+    pub fn get_prompts(&self) -> Vec<Value> {
+        prompts::prompt_list()
+    }
+
+    /// The messages of prompt `name` for `arguments`, a map of strings.
+    pub fn get_prompt(&self, name: &str, arguments: &Value) -> Result<Value> {
+        prompts::get_prompt(name, arguments)
+    }
+
     pub fn get_tools(&self) -> Vec<serde_json::Value> {
         vec![
             serde_json::json!({
@@ -96,16 +268,18 @@ impl RustCrawlerMcpServer {
                         "max_depth": {
                             "type": "integer",
                             "description": "Maximum crawl depth (default: 1)",
-                            "minimum": 1
+                            "minimum": 1,
+                            "maximum": MAX_CRAWL_DEPTH
                         },
                         "max_pages": {
                             "type": "integer",
                             "description": "Maximum number of pages to crawl (default: 10)",
-                            "minimum": 1
+                            "minimum": 1,
+                            "maximum": MAX_CRAWL_PAGES
                         },
                         "rate_limit": {
                             "type": "number",
-                            "description": "Rate limit in requests per second (default: 1)",
+                            "description": "Rate limit in requests per second per host (default: 1)",
                             "minimum": 0
                         },
                         "respect_robots": {
@@ -115,6 +289,71 @@ impl RustCrawlerMcpServer {
                         "follow_redirects": {
                             "type": "boolean",
                             "description": "Whether to follow HTTP redirects (default: true)"
+                        },
+                        "keep_html": {
+                            "type": "boolean",
+                            "description": "Store the raw HTML of each page in the session results, truncated at 256 KB (default: false)"
+                        },
+                        "extract_text": {
+                            "type": "boolean",
+                            "description": "Store each page's readable text, without scripts, styles and navigation, in the session results (default: false)"
+                        },
+                        "extract_structured_data": {
+                            "type": "boolean",
+                            "description": "Store each page's JSON-LD, OpenGraph/Twitter card metadata and schema.org microdata in the session results (default: false)"
+                        },
+                        "languages": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Only keep pages in these languages (ISO 639-1 codes, e.g. [\"en\"])"
+                        },
+                        "same_domain": {
+                            "type": "boolean",
+                            "description": "Only follow links on the start URL's registrable domain (default: false)"
+                        },
+                        "max_concurrency": {
+                            "type": "integer",
+                            "description": "Requests in flight at once (default: 3)",
+                            "minimum": 1,
+                            "maximum": MAX_CRAWL_CONCURRENCY
+                        },
+                        "timeout_seconds": {
+                            "type": "integer",
+                            "description": "Timeout of each request in seconds (default: 10)",
+                            "minimum": 1,
+                            "maximum": MAX_CRAWL_TIMEOUT_SECS
+                        },
+                        "user_agent": {
+                            "type": "string",
+                            "description": format!("User agent sent with every request, robots.txt included (default: {})", DEFAULT_USER_AGENT)
+                        },
+                        "background": {
+                            "type": "boolean",
+                            "description": "Return the session ID immediately and crawl in the background; poll get_crawl_status or subscribe to the session resource for progress. Set to false to wait for the results (default: true)"
+                        },
+                        "summary_format": {
+                            "type": "string",
+                            "enum": ["text", "markdown"],
+                            "description": "Format of the completed crawl's summary when background is false; markdown adds tables of errors, broken links and slowest pages (default: text)"
+                        }
+                    },
+                    "required": ["url"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "fetch_page",
+                "description": "Fetch a single page and return its title, metadata, readable text and links, without starting a crawl session",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "url": {
+                            "type": "string",
+                            "description": "The URL of the page"
+                        },
+                        "respect_robots": {
+                            "type": "boolean",
+                            "description": "Whether to respect robots.txt (default: true)"
                         }
                     },
                     "required": ["url"],
@@ -145,6 +384,150 @@ impl RustCrawlerMcpServer {
                     "additionalProperties": false
                 }
             }),
+            serde_json::json!({
+                "name": "get_crawl_status",
+                "description": "Report the status, pages crawled so far and elapsed time of a crawl session",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID returned by crawl_website"
+                        }
+                    },
+                    "required": ["session_id"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "query_results",
+                "description": "Page through a crawl session's results, optionally filtered by status code and URL substring; for sessions too large to read in one piece",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID returned by crawl_website"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of matching pages to skip (default: 0)",
+                            "minimum": 0
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Number of pages to return (default: 50)",
+                            "minimum": 1,
+                            "maximum": MAX_RESULTS_LIMIT
+                        },
+                        "status": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Only pages with one of these status codes or classes, e.g. [\"404\", \"5xx\"]"
+                        },
+                        "url_contains": {
+                            "type": "string",
+                            "description": "Only pages whose URL contains this text"
+                        }
+                    },
+                    "required": ["session_id"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "list_crawl_sessions",
+                "description": "List every crawl session with its start URL, status, page count and start and finish times (Unix seconds)",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {},
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "cancel_crawl",
+                "description": "Stop a running crawl; the session keeps the pages crawled so far and its status becomes cancelled. Over stdio, requests are handled one at a time, so a crawl_website call with background: false can't be cancelled before it returns",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID returned by crawl_website or list_crawl_sessions"
+                        }
+                    },
+                    "required": ["session_id"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "delete_session",
+                "description": "Delete a crawl session that is not running, with its results; with a storage directory the stored copy is deleted too",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID returned by crawl_website or list_crawl_sessions"
+                        }
+                    },
+                    "required": ["session_id"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "crawl_queue",
+                "description": "Inspect or edit the queue of a running background crawl: list the next queued URLs, drop URLs matching a pattern, or crawl them first",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID returned by crawl_website with background: true"
+                        },
+                        "action": {
+                            "type": "string",
+                            "enum": ["list", "drop", "prioritize"],
+                            "description": "What to do with the queue (default: list)"
+                        },
+                        "pattern": {
+                            "type": "string",
+                            "description": "Regular expression matched against queued URLs; required for drop and prioritize"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Number of URLs to list (default: 20)",
+                            "minimum": 1,
+                            "maximum": MAX_QUEUE_LIST
+                        }
+                    },
+                    "required": ["session_id"],
+                    "additionalProperties": false
+                }
+            }),
+            serde_json::json!({
+                "name": "validate_urls",
+                "description": "Pre-flight check for URLs: parse validity, normalized form, scope relative to a seed, and robots.txt permission",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "urls": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "URLs to validate",
+                            "maxItems": MAX_VALIDATE_URLS
+                        },
+                        "seed": {
+                            "type": "string",
+                            "description": "URL used for scope classification (default: first valid URL)"
+                        },
+                        "check_robots": {
+                            "type": "boolean",
+                            "description": "Whether to check robots.txt permission (default: true)"
+                        }
+                    },
+                    "required": ["urls"],
+                    "additionalProperties": false
+                }
+            }),
         ]
     }
 
@@ -153,7 +536,19 @@ impl RustCrawlerMcpServer {
             serde_json::json!({
                 "uriTemplate": "crawl://results/{session_id}",
                 "name": "Crawl Results",
-                "description": "Access crawl results by session ID",
+                "description": "Access crawl results by session ID; add ?offset=&limit=&status=&url_contains= to page through and filter them",
+                "mimeType": "application/json"
+            }),
+            serde_json::json!({
+                "uriTemplate": "crawl://sessions",
+                "name": "Crawl Sessions",
+                "description": "Every crawl session with its start URL, status, page count and start and finish times",
+                "mimeType": "application/json"
+            }),
+            serde_json::json!({
+                "uriTemplate": "crawl://sessions/{session_id}/status",
+                "name": "Crawl Session Status",
+                "description": "Status, pages crawled so far and elapsed time of a crawl session",
                 "mimeType": "application/json"
             }),
             serde_json::json!({
@@ -162,12 +557,200 @@ impl RustCrawlerMcpServer {
                 "description": "Current crawling statistics and metrics",
                 "mimeType": "application/json"
             }),
+            serde_json::json!({
+                "uriTemplate": "crawl://robots-cache",
+                "name": "Robots Cache",
+                "description": "Cached robots.txt entries with fetch status, crawl-delay, age and rule counts",
+                "mimeType": "application/json"
+            }),
         ]
     }
 }
 
+/// Tool arguments that don't match the tool's `inputSchema`; reported to clients as
+/// JSON-RPC "invalid params" with one message per problem.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid arguments for {tool}: {}", errors.join("; "))]
+pub struct InvalidArguments {
+    pub tool: String,
+    pub errors: Vec<String>,
+}
+
+/// Check `value` against the subset of JSON Schema the tool schemas use: `type`,
+/// `properties`, `required`, `additionalProperties: false`, `enum`, `minimum`,
+/// `maximum`, `items` and `maxItems`. Problems are pushed to `errors`, prefixed with
+/// the path of the offending value.
+fn validate_schema(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema["type"].as_str() {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            _ => true,
+        };
+        if !matches {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                expected,
+                json_type(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(format!(
+                "{}: must be one of {}, got {}",
+                path,
+                allowed.join(", "),
+                value
+            ));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema["minimum"].as_f64().filter(|min| number < *min) {
+            errors.push(format!(
+                "{}: must be at least {}, got {}",
+                path, minimum, value
+            ));
+        }
+        if let Some(maximum) = schema["maximum"].as_f64().filter(|max| number > *max) {
+            errors.push(format!(
+                "{}: must be at most {}, got {}",
+                path, maximum, value
+            ));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in schema["required"].as_array().into_iter().flatten() {
+            if let Some(name) = name.as_str().filter(|name| !object.contains_key(*name)) {
+                errors.push(format!("{}.{}: required", path, name));
+            }
+        }
+        let properties = schema["properties"].as_object();
+        for (name, field) in object {
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(field_schema) => {
+                    validate_schema(field_schema, field, &format!("{}.{}", path, name), errors)
+                }
+                None if schema["additionalProperties"] == false => {
+                    errors.push(format!("{}.{}: unknown argument", path, name))
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(max) = schema["maxItems"]
+            .as_u64()
+            .filter(|max| items.len() as u64 > *max)
+        {
+            errors.push(format!(
+                "{}: at most {} items allowed, got {}",
+                path,
+                max,
+                items.len()
+            ));
+        }
+        if schema["items"].is_object() {
+            for (i, item) in items.iter().enumerate() {
+                validate_schema(&schema["items"], item, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn robots_cache_entry_json(domain: &str, info: &RobotsInfo) -> Value {
+    let (status, http_status, error) = match &info.status {
+        RobotsFetchStatus::NotFetched => ("not_fetched", None, None),
+        RobotsFetchStatus::Available => ("available", None, None),
+        RobotsFetchStatus::NotFound(code) => ("not_found", Some(*code), None),
+        RobotsFetchStatus::ServerError(code) => ("server_error", Some(*code), None),
+        RobotsFetchStatus::Error(e) => ("error", None, Some(e.clone())),
+    };
+    let (allow_rules, disallow_rules) = info.rule_counts();
+
+    serde_json::json!({
+        "domain": domain,
+        "status": status,
+        "http_status": http_status,
+        "error": error,
+        "crawl_delay_seconds": info.crawl_delay.map(|d| d.as_secs_f64()),
+        "age_seconds": info.age().map(|a| a.as_secs()),
+        "expires_in_seconds": info.expires_in().map(|e| e.as_secs()),
+        "allow_rules": allow_rules,
+        "disallow_rules": disallow_rules
+    })
+}
+
 impl Default for RustCrawlerMcpServer {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tool_arguments_are_checked_against_schema() {
+        let server = RustCrawlerMcpServer::new();
+        let arguments = serde_json::json!({
+            "url": 5,
+            "max_depth": 9,
+            "summary_format": "html",
+            "languages": ["en", 1],
+            "bogus": true
+        });
+        let error = server
+            .handle_tool_call("crawl_website", arguments)
+            .await
+            .unwrap_err();
+        let invalid = error.downcast_ref::<InvalidArguments>().unwrap();
+        assert_eq!(
+            invalid.errors,
+            vec![
+                "arguments.bogus: unknown argument",
+                "arguments.languages[1]: expected string, got integer",
+                "arguments.max_depth: must be at most 3, got 9",
+                "arguments.summary_format: must be one of \"text\", \"markdown\", got \"html\"",
+                "arguments.url: expected string, got integer",
+            ]
+        );
+
+        let error = server
+            .handle_tool_call("get_crawl_status", Value::Null)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid arguments for get_crawl_status: arguments.session_id: required"
+        );
+
+        let stats = server
+            .handle_tool_call("get_crawl_stats", Value::Null)
+            .await;
+        assert!(stats.is_ok());
+    }
+}
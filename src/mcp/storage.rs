@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::mcp::resources::{CrawlSession, CrawlStatistics, CrawlStatus};
+use crate::mcp::tools::CrawlSessions;
+
+/// Crawl sessions and statistics kept in a directory, so they outlive the server.
+/// Each session is `sessions/{session_id}.json`, rewritten when the crawl starts and
+/// when it ends; the statistics are `stats.json`.
+///
+/// Writing happens in two steps so no lock is held during disk I/O: take a
+/// `StoreWrite` snapshot while holding the session or statistics lock, release the
+/// lock, then `write` it.
+#[derive(Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+    sessions: CrawlSessions,
+    stats: Arc<RwLock<CrawlStatistics>>,
+    writes: Arc<Writes>,
+}
+
+/// Orders the store's writes: every snapshot is numbered when it's taken, and a file
+/// is never overwritten by a snapshot older than the one already written to it.
+#[derive(Default)]
+struct Writes {
+    next: AtomicU64,
+    /// Number of the newest snapshot written to each file, held while writing.
+    written: Mutex<HashMap<PathBuf, u64>>,
+}
+
+/// A session or the statistics, serialized while their lock was held, or the removal
+/// of a stored session.
+#[must_use = "a snapshot does nothing until it's written"]
+pub struct StoreWrite {
+    writes: Arc<Writes>,
+    path: PathBuf,
+    number: u64,
+    /// The file's new content; `None` removes it.
+    data: Option<serde_json::Result<Vec<u8>>>,
+}
+
+impl SessionStore {
+    /// Open the store in `dir`, creating it if needed, and load what an earlier run
+    /// left there. Sessions that were still running when it stopped are marked
+    /// interrupted; files that can't be read are skipped with a warning.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let sessions_dir = dir.join("sessions");
+        fs::create_dir_all(&sessions_dir).with_context(|| {
+            format!(
+                "Failed to create storage directory {}",
+                sessions_dir.display()
+            )
+        })?;
+
+        let mut sessions = HashMap::new();
+        let entries = fs::read_dir(&sessions_dir)
+            .with_context(|| format!("Failed to read {}", sessions_dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+            match read_json::<CrawlSession>(&path) {
+                Ok(mut session) => {
+                    if session.status == CrawlStatus::Running {
+                        session.status = CrawlStatus::Interrupted;
+                    }
+                    sessions.insert(session.session_id.clone(), session);
+                }
+                Err(e) => warn!("Skipping stored session {}: {:#}", path.display(), e),
+            }
+        }
+
+        let stats_path = dir.join("stats.json");
+        let stats = if stats_path.exists() {
+            read_json(&stats_path).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring stored statistics {}: {:#}",
+                    stats_path.display(),
+                    e
+                );
+                CrawlStatistics::default()
+            })
+        } else {
+            CrawlStatistics::default()
+        };
+
+        info!(
+            "Loaded {} crawl session(s) from {}",
+            sessions.len(),
+            dir.display()
+        );
+        Ok(Self {
+            dir,
+            sessions: Arc::new(RwLock::new(sessions)),
+            stats: Arc::new(RwLock::new(stats)),
+            writes: Arc::default(),
+        })
+    }
+
+    /// The sessions loaded at startup; servers sharing the store share these.
+    pub fn sessions(&self) -> CrawlSessions {
+        self.sessions.clone()
+    }
+
+    pub fn stats(&self) -> Arc<RwLock<CrawlStatistics>> {
+        self.stats.clone()
+    }
+
+    /// Snapshot `session` for writing; take it while holding the sessions lock.
+    pub fn session_snapshot(&self, session: &CrawlSession) -> StoreWrite {
+        self.snapshot(
+            self.session_path(&session.session_id),
+            Some(serde_json::to_vec(session)),
+        )
+    }
+
+    /// Snapshot `stats` for writing; take it while holding the statistics lock.
+    pub fn stats_snapshot(&self, stats: &CrawlStatistics) -> StoreWrite {
+        self.snapshot(self.dir.join("stats.json"), Some(serde_json::to_vec(stats)))
+    }
+
+    /// The deletion of a stored session; a session never stored is fine. Take it
+    /// while holding the sessions lock, so an earlier snapshot can't bring it back.
+    pub fn session_removal(&self, session_id: &str) -> StoreWrite {
+        self.snapshot(self.session_path(session_id), None)
+    }
+
+    fn snapshot(&self, path: PathBuf, data: Option<serde_json::Result<Vec<u8>>>) -> StoreWrite {
+        StoreWrite {
+            writes: self.writes.clone(),
+            number: self.writes.next.fetch_add(1, Ordering::SeqCst),
+            path,
+            data,
+        }
+    }
+
+    fn session_path(&self, session_id: &str) -> PathBuf {
+        self.dir
+            .join("sessions")
+            .join(format!("{}.json", session_id))
+    }
+}
+
+impl StoreWrite {
+    /// Write the snapshot to disk, or remove the file, unless a newer snapshot of the
+    /// same file was written first.
+    pub async fn write(self) -> Result<()> {
+        let mut written = self.writes.written.lock().await;
+        if written
+            .get(&self.path)
+            .is_some_and(|newest| *newest > self.number)
+        {
+            return Ok(());
+        }
+        match self.data {
+            Some(data) => write_file(&self.path, data?).await?,
+            None => match tokio::fs::remove_file(&self.path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to delete {}", self.path.display()))
+                }
+                _ => {}
+            },
+        }
+        written.insert(self.path, self.number);
+        Ok(())
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let data = fs::read(path).context("Failed to read file")?;
+    serde_json::from_slice(&data).context("Failed to parse file")
+}
+
+/// Write through a temporary file so a crash never leaves half a file behind.
+async fn write_file(path: &Path, data: Vec<u8>) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    tokio::fs::write(&tmp, data)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::resources::{CrawlPageResult, CrawlSessionConfig};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_sessions_and_stats_survive_reopening() {
+        let dir = std::env::temp_dir().join(format!("rustcrawler-mcp-{}", Uuid::new_v4()));
+        let store = SessionStore::open(&dir).unwrap();
+        assert!(store.sessions.try_read().unwrap().is_empty());
+
+        let mut running = CrawlSession::new(
+            "running",
+            "https://example.com/",
+            CrawlSessionConfig::default(),
+        );
+        running.results.push(CrawlPageResult {
+            url: "https://example.com/".to_string(),
+            status_code: 200,
+            ..Default::default()
+        });
+        running.pages_crawled = 1;
+        let mut done = running.clone();
+        done.session_id = "done".to_string();
+        done.status = CrawlStatus::Completed;
+        done.crawl_duration_seconds = Some(3);
+        store.session_snapshot(&running).write().await.unwrap();
+        store.session_snapshot(&done).write().await.unwrap();
+        let mut stats = CrawlStatistics::default();
+        stats.update_from_session(&done);
+        store.stats_snapshot(&stats).write().await.unwrap();
+        fs::write(dir.join("sessions").join("garbage.json"), "{").unwrap();
+
+        let store = SessionStore::open(&dir).unwrap();
+        {
+            let sessions = store.sessions.try_read().unwrap();
+            assert_eq!(sessions.len(), 2);
+            assert_eq!(sessions["running"].status, CrawlStatus::Interrupted);
+            assert_eq!(sessions["done"].status, CrawlStatus::Completed);
+            assert_eq!(sessions["done"].results[0].status_code, 200);
+        }
+        assert_eq!(store.stats.try_read().unwrap().total_sessions, 1);
+
+        store.session_removal("done").write().await.unwrap();
+        store.session_removal("never-stored").write().await.unwrap();
+        assert!(!dir.join("sessions").join("done.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_older_snapshots_never_overwrite_newer_ones() {
+        let dir = std::env::temp_dir().join(format!("rustcrawler-mcp-{}", Uuid::new_v4()));
+        let store = SessionStore::open(&dir).unwrap();
+        let path = dir.join("sessions").join("s1.json");
+
+        let mut session =
+            CrawlSession::new("s1", "https://example.com/", CrawlSessionConfig::default());
+        let started = store.session_snapshot(&session);
+        session.status = CrawlStatus::Completed;
+        let finished = store.session_snapshot(&session);
+        finished.write().await.unwrap();
+        started.write().await.unwrap();
+        let stored: CrawlSession = read_json(&path).unwrap();
+        assert_eq!(stored.status, CrawlStatus::Completed);
+
+        // A deletion taken after a snapshot wins even when that snapshot lands later
+        let late = store.session_snapshot(&session);
+        store.session_removal("s1").write().await.unwrap();
+        late.write().await.unwrap();
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,34 +1,167 @@
 use std::collections::HashMap;
+use std::pin::pin;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::Result;
+use futures::{FutureExt, StreamExt};
+use log::warn;
+use regex::Regex;
 use serde_json::Value;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::anomalies::detect_anomalies;
+use crate::classify::summarize_categories;
+use crate::clusters::cluster_results;
 use crate::crawler::{Crawler, CrawlerConfig};
+use crate::markdown::render_report;
+use crate::mcp::notifications::{ProgressReporter, ResourceNotifier};
+use crate::mcp::resources::{
+    CrawlPageResult, CrawlSession, CrawlSessionConfig, CrawlStatistics, CrawlStatus,
+};
+use crate::mcp::storage::{SessionStore, StoreWrite};
+use crate::output::CrawlReport;
+use crate::robots::RobotsManager;
+use crate::urls::{classify_scope, normalize_url, CrawlScope};
+
+/// Upper bound on URLs accepted by a single `validate_urls` call.
+pub const MAX_VALIDATE_URLS: usize = 50;
+
+/// Upper bound on URLs listed by a single `crawl_queue` call.
+pub const MAX_QUEUE_LIST: usize = 100;
+
+/// Deepest crawl `crawl_website` runs, to keep tool calls short.
+pub const MAX_CRAWL_DEPTH: usize = 3;
+
+/// Most pages a single `crawl_website` call crawls.
+pub const MAX_CRAWL_PAGES: usize = 20;
+
+/// Most concurrent requests a `crawl_website` call may ask for.
+pub const MAX_CRAWL_CONCURRENCY: usize = 10;
+
+/// Longest per-request timeout, in seconds, a `crawl_website` call may ask for.
+pub const MAX_CRAWL_TIMEOUT_SECS: u64 = 60;
+
+/// User agent `crawl_website` sends when the call doesn't name one.
+pub const DEFAULT_USER_AGENT: &str = "RustCrawler-MCP/0.1.0";
+
+/// Characters of readable text `fetch_page` returns; the rest is cut off.
+pub const MAX_PAGE_TEXT_CHARS: usize = 20_000;
+
+/// Pages `query_results` returns when the call gives no `limit`.
+pub const DEFAULT_RESULTS_LIMIT: usize = 50;
+
+/// Most pages a single `query_results` call returns.
+pub const MAX_RESULTS_LIMIT: usize = 500;
+
+/// URI of the resource listing every crawl session.
+pub const SESSIONS_URI: &str = "crawl://sessions";
+
+/// Crawlers of sessions that are still running, by session ID.
+pub type RunningCrawls = Arc<RwLock<HashMap<String, Arc<Crawler>>>>;
+
+/// Every crawl session of the server, by session ID.
+pub type CrawlSessions = Arc<RwLock<HashMap<String, CrawlSession>>>;
+
+/// Result of a tool call: a short human-readable summary, plus the structured
+/// data behind it so clients don't have to parse the prose.
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub summary: String,
+    pub data: Option<(String, Value)>,
+}
+
+impl ToolOutput {
+    pub fn text(summary: impl Into<String>) -> Self {
+        Self {
+            summary: summary.into(),
+            data: None,
+        }
+    }
+
+    /// Attach structured data, exposed as an embedded `application/json` resource at `uri`.
+    pub fn with_json(mut self, uri: impl Into<String>, data: Value) -> Self {
+        self.data = Some((uri.into(), data));
+        self
+    }
+
+    /// MCP `content` array for a `tools/call` response.
+    pub fn content_blocks(&self) -> Result<Vec<Value>> {
+        let mut blocks = vec![serde_json::json!({
+            "type": "text",
+            "text": self.summary
+        })];
+
+        if let Some((uri, data)) = &self.data {
+            blocks.push(serde_json::json!({
+                "type": "resource",
+                "resource": {
+                    "uri": uri,
+                    "mimeType": "application/json",
+                    "text": serde_json::to_string_pretty(data)?
+                }
+            }));
+        }
+
+        Ok(blocks)
+    }
+}
 
 pub struct CrawlTool {
-    crawler: Arc<RwLock<Option<Crawler>>>,
-    crawl_results: Arc<RwLock<HashMap<String, Value>>>,
-    stats: Arc<RwLock<HashMap<String, u64>>>,
+    /// Set to each new session's crawler, for `crawl://robots-cache`.
+    latest_crawler: Arc<RwLock<Option<Arc<Crawler>>>>,
+    crawl_results: CrawlSessions,
+    stats: Arc<RwLock<CrawlStatistics>>,
+    notifier: ResourceNotifier,
+    running: RunningCrawls,
+    progress: Option<ProgressReporter>,
+    store: Option<SessionStore>,
+    time_budget: Option<Duration>,
 }
 
 impl CrawlTool {
     pub fn new(
-        crawler: Arc<RwLock<Option<Crawler>>>,
-        crawl_results: Arc<RwLock<HashMap<String, Value>>>,
-        stats: Arc<RwLock<HashMap<String, u64>>>,
+        latest_crawler: Arc<RwLock<Option<Arc<Crawler>>>>,
+        crawl_results: CrawlSessions,
+        stats: Arc<RwLock<CrawlStatistics>>,
+        notifier: ResourceNotifier,
+        running: RunningCrawls,
     ) -> Self {
         Self {
-            crawler,
+            latest_crawler,
             crawl_results,
             stats,
+            notifier,
+            running,
+            progress: None,
+            store: None,
+            time_budget: None,
         }
     }
 
-    pub async fn execute(&self, arguments: Value) -> Result<String> {
+    /// Report pages crawled and queued through `progress` while a foreground crawl
+    /// runs. Background crawls answer right away and are followed through resources.
+    pub fn with_progress(mut self, progress: Option<ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Write sessions and statistics to `store` as crawls start and finish.
+    pub fn with_store(mut self, store: Option<SessionStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Stop foreground crawls after `budget` instead of the time derived from each
+    /// call's page budget, concurrency and request timeout.
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
         let url = arguments["url"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing required parameter: url"))?;
@@ -38,12 +171,40 @@ impl CrawlTool {
         let rate_limit = arguments["rate_limit"].as_f64().unwrap_or(1.0);
         let respect_robots = arguments["respect_robots"].as_bool().unwrap_or(true);
         let follow_redirects = arguments["follow_redirects"].as_bool().unwrap_or(true);
+        let keep_html = arguments["keep_html"].as_bool().unwrap_or(false);
+        let extract_text = arguments["extract_text"].as_bool().unwrap_or(false);
+        let extract_structured_data = arguments["extract_structured_data"]
+            .as_bool()
+            .unwrap_or(false);
+        let same_domain = arguments["same_domain"].as_bool().unwrap_or(false);
+        let max_concurrency = arguments["max_concurrency"]
+            .as_u64()
+            .map_or(3, |n| (n as usize).clamp(1, MAX_CRAWL_CONCURRENCY));
+        let timeout_seconds = arguments["timeout_seconds"]
+            .as_u64()
+            .map_or(10, |n| n.clamp(1, MAX_CRAWL_TIMEOUT_SECS));
+        let user_agent = match arguments["user_agent"].as_str().map(str::trim) {
+            Some("") => return Err(anyhow::anyhow!("user_agent must not be empty")),
+            Some(user_agent) => user_agent.to_string(),
+            None => DEFAULT_USER_AGENT.to_string(),
+        };
+        let markdown_summary = arguments["summary_format"].as_str() == Some("markdown");
+        let languages: Vec<String> = arguments["languages"]
+            .as_array()
+            .map(|langs| {
+                langs
+                    .iter()
+                    .filter_map(|l| l.as_str())
+                    .map(str::to_lowercase)
+                    .collect()
+            })
+            .unwrap_or_default();
 
         // Create crawler configuration - optimized for MCP usage
         let config = CrawlerConfig {
-            max_concurrency: 3, // Reduced concurrency to avoid rate limiting
-            user_agent: "RustCrawler-MCP/0.1.0".to_string(),
-            timeout: std::time::Duration::from_secs(10), // Reduced timeout for faster response
+            max_concurrency,
+            user_agent,
+            timeout: std::time::Duration::from_secs(timeout_seconds),
             max_retries: 2, // Reduced retries for faster response
             rate_limit: if rate_limit > 0.0 {
                 Some(std::time::Duration::from_secs_f64(1.0 / rate_limit))
@@ -51,93 +212,684 @@ impl CrawlTool {
                 Some(std::time::Duration::from_millis(500)) // Default rate limit to be respectful
             },
             proxy: None,
-            max_pages: max_pages.map(|p| p.min(20)), // Limit max pages to prevent long runs
-            max_depth: Some(max_depth.min(3)), // Limit max depth to prevent deep crawls
+            max_pages: max_pages.map(|p| p.min(MAX_CRAWL_PAGES)), // Limit max pages to prevent long runs
+            max_depth: Some(max_depth.min(MAX_CRAWL_DEPTH)), // Limit max depth to prevent deep crawls
             respect_robots,
             follow_redirects,
+            keep_html,
+            keep_html_max_kb: 256, // Keep stored sessions reasonably small
+            extract_text,
+            extract_structured_data,
+            languages: languages.clone(),
+            scope: if same_domain {
+                CrawlScope::SameDomain
+            } else {
+                CrawlScope::All
+            },
+            ..Default::default()
         };
 
-        // Initialize crawler if not already done
-        {
-            let crawler_guard = self.crawler.read().await;
-            if crawler_guard.is_none() {
-                drop(crawler_guard);
-                let crawler = Crawler::new(config)?;
-                let mut crawler_guard = self.crawler.write().await;
-                *crawler_guard = Some(crawler);
-            }
+        let session_config = CrawlSessionConfig {
+            max_depth,
+            max_pages,
+            rate_limit,
+            respect_robots,
+            follow_redirects,
+            keep_html,
+            extract_text,
+            extract_structured_data,
+            languages,
+            same_domain,
+            max_concurrency,
+            timeout_seconds,
+            user_agent: config.user_agent.clone(),
+        };
+
+        if arguments["background"].as_bool().unwrap_or(true) {
+            return self
+                .start_background_crawl(url, config, session_config)
+                .await;
         }
 
-        // Perform crawl
-        let crawler_guard = self.crawler.read().await;
-        let crawler = crawler_guard.as_ref().unwrap();
+        let time_budget = self
+            .time_budget
+            .unwrap_or_else(|| foreground_time_budget(&config));
+
+        // Every session gets its own crawler, so each call's settings take effect
+        let crawler = Arc::new(Crawler::new(config)?);
+        *self.latest_crawler.write().await = Some(crawler.clone());
+        let session_id = Uuid::new_v4().to_string();
+        let session_uri = format!("crawl://results/{}", session_id);
+        let progress_uri = status_uri(&session_id);
+        // Listed and cancellable while it runs, like a background session
+        self.register_session(
+            CrawlSession::new(session_id.clone(), url, session_config),
+            crawler.clone(),
+        )
+        .await;
 
         let start_time = SystemTime::now();
-        let results = crawler.crawl(vec![url.to_string()]).await?;
+        let mut results = Vec::new();
+        let mut stream = pin!(crawler.crawl_stream(vec![url.to_string()]));
+        let deadline = pin!(tokio::time::sleep(time_budget));
+        let mut deadline = deadline.fuse();
+        loop {
+            let result = tokio::select! {
+                result = stream.next() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+                _ = &mut deadline => {
+                    // Stopping ends the stream soon; pages crawled so far are kept
+                    if let Some(session) = self.crawl_results.write().await.get_mut(&session_id) {
+                        if session.status == CrawlStatus::Running {
+                            session.status = CrawlStatus::TimedOut;
+                        }
+                    }
+                    crawler.request_stop();
+                    continue;
+                }
+            };
+            if let Some(session) = self.crawl_results.write().await.get_mut(&session_id) {
+                session.add_page(&result);
+            }
+            self.notifier.resource_updated(&session_uri).await;
+            self.notifier.resource_updated(&progress_uri).await;
+            results.push(result);
+            if let Some(progress) = &self.progress {
+                let queued = crawler.get_queued_count();
+                progress.report(
+                    results.len() as u64,
+                    max_pages.map(|n| n as u64),
+                    &format!("{} page(s) crawled, {} queued", results.len(), queued),
+                );
+            }
+        }
+        self.running.write().await.remove(&session_id);
         let crawl_duration = start_time.elapsed()?.as_secs();
+        let run = crawler.get_run_metadata();
+
+        // Store results
+        let (session_status, run_id, crawl_summary, writes) = {
+            let mut sessions = self.crawl_results.write().await;
+            let session = sessions.get_mut(&session_id).ok_or_else(|| {
+                anyhow::anyhow!("Crawl session {} was deleted while it ran", session_id)
+            })?;
+            session.finish(&crawler, crawl_duration);
+            let mut stats = self.stats.write().await;
+            stats.update_from_session(session);
+            let writes = self.store.as_ref().map(|store| {
+                [
+                    store.session_snapshot(session),
+                    store.stats_snapshot(&stats),
+                ]
+            });
+            (
+                session.status,
+                session.run_id.clone().unwrap_or_default(),
+                serde_json::to_value(&*session)?,
+                writes,
+            )
+        };
+        store_writes(writes.into_iter().flatten()).await;
+        self.notifier.resource_updated(&session_uri).await;
+        self.notifier.resource_updated(&progress_uri).await;
+        self.notifier.resource_updated(SESSIONS_URI).await;
+
+        let stopped = match session_status {
+            CrawlStatus::TimedOut => Some(format!(
+                "Crawl stopped after its time budget of {}s; the pages crawled so far are kept.",
+                time_budget.as_secs()
+            )),
+            CrawlStatus::Cancelled => {
+                Some("Crawl cancelled; the pages crawled so far are kept.".to_string())
+            }
+            _ => None,
+        };
+        let summary = if markdown_summary {
+            let report = CrawlReport {
+                run,
+                results: &results,
+                failures: crawler.get_failures(),
+                clusters: cluster_results(&results),
+                categories: summarize_categories(&results),
+                retry_summary: crawler.get_retry_summary(),
+                timeline: crawler.get_timeline(),
+                certificates: crawler.get_certificates(),
+                external_domains: crawler.get_external_domains(),
+                anomalies: detect_anomalies(&results),
+                diff: None,
+            };
+            format!(
+                "{}{}\nSession ID: `{}`; use resource crawl://results/{} for full results.\n",
+                stopped.map(|note| note + "\n\n").unwrap_or_default(),
+                render_report(&report),
+                session_id,
+                session_id
+            )
+        } else {
+            let outcome = stopped.unwrap_or_else(|| "Crawl completed successfully!".to_string());
+            format!(
+                "{}\n\nSession ID: {}\nRun ID: {}\nPages crawled: {}\nDuration: {}s\n\nUse resource crawl://results/{} to get detailed results.",
+                outcome,
+                session_id,
+                run_id,
+                results.len(),
+                crawl_duration,
+                session_id
+            )
+        };
+        Ok(ToolOutput::text(summary)
+            .with_json(format!("crawl://results/{}", session_id), crawl_summary))
+    }
 
-        // Generate session ID and store results
+    /// Add a starting session to the server's sessions, and its crawler to the running
+    /// ones. The crawler goes in first, so a `cancel_crawl` that finds the session
+    /// running always finds the crawler to stop.
+    async fn register_session(&self, session: CrawlSession, crawler: Arc<Crawler>) {
+        let write = self
+            .store
+            .as_ref()
+            .map(|store| store.session_snapshot(&session));
+        self.running
+            .write()
+            .await
+            .insert(session.session_id.clone(), crawler);
+        self.crawl_results
+            .write()
+            .await
+            .insert(session.session_id.clone(), session);
+        store_writes(write).await;
+        self.notifier.resource_updated(SESSIONS_URI).await;
+    }
+
+    /// Run the crawl in a spawned task, appending pages to the session as they complete
+    /// and notifying subscribers of `crawl://results/{session_id}` and its status
+    /// resource after each one.
+    async fn start_background_crawl(
+        &self,
+        url: &str,
+        config: CrawlerConfig,
+        session_config: CrawlSessionConfig,
+    ) -> Result<ToolOutput> {
+        let crawler = Arc::new(Crawler::new(config)?);
+        *self.latest_crawler.write().await = Some(crawler.clone());
         let session_id = Uuid::new_v4().to_string();
-        let crawl_summary = serde_json::json!({
-            "session_id": session_id,
-            "start_url": url,
-            "pages_crawled": results.len(),
-            "crawl_duration_seconds": crawl_duration,
-            "config": {
-                "max_depth": max_depth,
-                "max_pages": max_pages,
-                "rate_limit": rate_limit,
-                "respect_robots": respect_robots,
-                "follow_redirects": follow_redirects
-            },
-            "results": results.iter().map(|r| {
-                serde_json::json!({
-                    "url": r.url,
-                    "status_code": r.status_code,
-                    "title": r.title,
-                    "links_found": r.links.len(),
-                    "crawl_time_ms": r.crawl_time.as_millis(),
-                    "depth": r.depth
-                })
-            }).collect::<Vec<_>>()
+        let uri = format!("crawl://results/{}", session_id);
+
+        let session = CrawlSession::new(session_id.clone(), url, session_config);
+        let session_json = serde_json::to_value(&session)?;
+        self.register_session(session, crawler.clone()).await;
+
+        let crawl_results = self.crawl_results.clone();
+        let running = self.running.clone();
+        let stats = self.stats.clone();
+        let notifier = self.notifier.clone();
+        let store = self.store.clone();
+        let start_url = url.to_string();
+        let session_uri = uri.clone();
+        let progress_uri = status_uri(&session_id);
+        let job_session_id = session_id.clone();
+
+        tokio::spawn(async move {
+            let start_time = Instant::now();
+            let mut stream = pin!(crawler.crawl_stream(vec![start_url]));
+
+            while let Some(result) = stream.next().await {
+                if let Some(session) = crawl_results.write().await.get_mut(&job_session_id) {
+                    session.add_page(&result);
+                }
+                notifier.resource_updated(&session_uri).await;
+                notifier.resource_updated(&progress_uri).await;
+            }
+
+            running.write().await.remove(&job_session_id);
+            let crawl_duration = start_time.elapsed().as_secs();
+            let mut writes = Vec::new();
+            if let Some(session) = crawl_results.write().await.get_mut(&job_session_id) {
+                session.finish(&crawler, crawl_duration);
+                let mut stats = stats.write().await;
+                stats.update_from_session(session);
+                if let Some(store) = &store {
+                    writes.push(store.session_snapshot(session));
+                    writes.push(store.stats_snapshot(&stats));
+                }
+            }
+            store_writes(writes).await;
+            notifier.resource_updated(&session_uri).await;
+            notifier.resource_updated(&progress_uri).await;
+            notifier.resource_updated(SESSIONS_URI).await;
         });
 
-        // Store results
-        {
-            let mut results_guard = self.crawl_results.write().await;
-            results_guard.insert(session_id.clone(), crawl_summary.clone());
-        }
+        let summary = format!(
+            "Crawl started in the background.\n\nSession ID: {}\n\nPoll get_crawl_status or {} for progress, or subscribe to {} to be notified as pages are added; the session status changes to \"completed\" when the crawl ends.",
+            session_id,
+            status_uri(&session_id),
+            uri
+        );
+        Ok(ToolOutput::text(summary).with_json(uri, session_json))
+    }
+}
 
-        // Update stats
-        {
-            let mut stats_guard = self.stats.write().await;
-            *stats_guard.entry("total_crawls".to_string()).or_insert(0) += 1;
-            *stats_guard
-                .entry("total_pages_crawled".to_string())
-                .or_insert(0) += results.len() as u64;
-            *stats_guard
-                .entry("total_crawl_time_seconds".to_string())
-                .or_insert(0) += crawl_duration;
+/// How long a foreground crawl runs before it's stopped: every wave of
+/// `max_concurrency` pages may use up each attempt's full request timeout, and
+/// requests to a host are spaced out by the rate limit.
+fn foreground_time_budget(config: &CrawlerConfig) -> Duration {
+    let pages = config.max_pages.unwrap_or(MAX_CRAWL_PAGES) as u32;
+    let concurrency = config.max_concurrency.max(1) as u32;
+    let waves = (pages + concurrency - 1) / concurrency;
+    let attempts = config.max_retries as u32 + 1;
+    config.timeout * attempts * waves + config.rate_limit.unwrap_or_default() * pages
+}
+
+/// Write store snapshots taken under a lock, once it's released. Failures are logged,
+/// since the sessions are still served from memory.
+async fn store_writes(writes: impl IntoIterator<Item = StoreWrite>) {
+    for write in writes {
+        if let Err(e) = write.write().await {
+            warn!("Failed to store crawl session data: {:#}", e);
         }
+    }
+}
+
+/// Progress of a crawl session, without its results.
+pub struct GetCrawlStatusTool {
+    crawl_results: CrawlSessions,
+}
+
+impl GetCrawlStatusTool {
+    pub fn new(crawl_results: CrawlSessions) -> Self {
+        Self { crawl_results }
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let session_id = arguments["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: session_id"))?;
+        let sessions = self.crawl_results.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Crawl session not found: {}", session_id))?;
 
-        Ok(format!(
-            "Crawl completed successfully!\n\nSession ID: {}\nPages crawled: {}\nDuration: {}s\n\nUse resource crawl://results/{} to get detailed results.",
+        let progress = session.progress();
+        let mut summary = format!(
+            "Session {}: {}, {} page(s) crawled in {}s",
             session_id,
-            results.len(),
-            crawl_duration,
-            session_id
+            progress.status.as_str(),
+            progress.pages_crawled,
+            progress.elapsed_seconds
+        );
+        if let Some(last_url) = &progress.last_url {
+            summary.push_str(&format!("\nLast page: {}", last_url));
+        }
+        Ok(ToolOutput::text(summary)
+            .with_json(status_uri(session_id), serde_json::to_value(progress)?))
+    }
+}
+
+/// URI of the resource reporting a session's progress.
+pub fn status_uri(session_id: &str) -> String {
+    format!("crawl://sessions/{}/status", session_id)
+}
+
+/// A status code filter: an exact code like `404`, or a class like `4xx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    Code(u16),
+    /// The hundreds digit of the codes it matches.
+    Class(u16),
+}
+
+impl StatusFilter {
+    fn matches(self, status_code: u16) -> bool {
+        match self {
+            StatusFilter::Code(code) => status_code == code,
+            StatusFilter::Class(class) => status_code / 100 == class,
+        }
+    }
+}
+
+impl FromStr for StatusFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let class = s
+            .strip_suffix("xx")
+            .or_else(|| s.strip_suffix("XX"))
+            .and_then(|digit| digit.parse::<u16>().ok())
+            .filter(|digit| (1..=5).contains(digit));
+        if let Some(class) = class {
+            return Ok(StatusFilter::Class(class));
+        }
+        match s.parse::<u16>() {
+            Ok(code) if (100..=599).contains(&code) => Ok(StatusFilter::Code(code)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid status filter '{}': expected a code like 404 or a class like 4xx",
+                s
+            )),
+        }
+    }
+}
+
+/// Filters and a window over a session's pages, from the `query_results` arguments or
+/// the query string of `crawl://results/{session_id}?...`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultQuery {
+    pub offset: usize,
+    /// Pages to return; all matching ones when `None`.
+    pub limit: Option<usize>,
+    /// A page is kept when its status matches any of these, or when there are none.
+    pub status: Vec<StatusFilter>,
+    /// Only pages whose URL contains this.
+    pub url_contains: Option<String>,
+}
+
+impl ResultQuery {
+    /// Parse `offset=0&limit=20&status=404,5xx&url_contains=/blog/`.
+    pub fn from_query_string(query: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("Invalid {} '{}': expected a number", key, value))
+            };
+            match key.as_ref() {
+                "offset" => parsed.offset = number()?,
+                "limit" => parsed.limit = Some(number()?),
+                "status" => {
+                    for filter in value.split(',').filter(|filter| !filter.is_empty()) {
+                        parsed.status.push(filter.parse()?);
+                    }
+                }
+                "url_contains" => parsed.url_contains = Some(value.into_owned()),
+                _ => return Err(anyhow::anyhow!("Unknown query parameter: {}", key)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Read the `query_results` arguments, defaulting `limit` to `DEFAULT_RESULTS_LIMIT`.
+    pub fn from_arguments(arguments: &Value) -> Result<Self> {
+        let status = arguments["status"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|filter| filter.as_str().unwrap_or_default().parse())
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            offset: arguments["offset"].as_u64().unwrap_or(0) as usize,
+            limit: Some(
+                arguments["limit"]
+                    .as_u64()
+                    .map_or(DEFAULT_RESULTS_LIMIT, |limit| limit as usize),
+            ),
+            status,
+            url_contains: arguments["url_contains"].as_str().map(str::to_string),
+        })
+    }
+
+    /// The same query as a resource query string.
+    pub fn to_query_string(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query.append_pair("offset", &self.offset.to_string());
+        if let Some(limit) = self.limit {
+            query.append_pair("limit", &limit.to_string());
+        }
+        if !self.status.is_empty() {
+            let filters: Vec<String> = self
+                .status
+                .iter()
+                .map(|filter| match filter {
+                    StatusFilter::Code(code) => code.to_string(),
+                    StatusFilter::Class(class) => format!("{}xx", class),
+                })
+                .collect();
+            query.append_pair("status", &filters.join(","));
+        }
+        if let Some(url_contains) = &self.url_contains {
+            query.append_pair("url_contains", url_contains);
+        }
+        query.finish()
+    }
+
+    fn matches(&self, page: &CrawlPageResult) -> bool {
+        (self.status.is_empty()
+            || self
+                .status
+                .iter()
+                .any(|filter| filter.matches(page.status_code)))
+            && self
+                .url_contains
+                .as_deref()
+                .map_or(true, |needle| page.url.contains(needle))
+    }
+
+    /// The session with only the requested window of matching pages in `results`, plus
+    /// `matching_results`, `offset`, `limit` and `next_offset` (null on the last window).
+    pub fn apply(&self, session: &CrawlSession) -> Result<Value> {
+        let matching: Vec<&CrawlPageResult> = session
+            .results
+            .iter()
+            .filter(|page| self.matches(page))
+            .collect();
+        let total = matching.len();
+        let window: Vec<&CrawlPageResult> = matching
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        let end = self.offset + window.len();
+
+        let mut queried = serde_json::to_value(session)?;
+        queried["matching_results"] = total.into();
+        queried["offset"] = self.offset.into();
+        queried["limit"] = self.limit.into();
+        queried["next_offset"] = (end < total).then_some(end).into();
+        queried["results"] = serde_json::to_value(window)?;
+        Ok(queried)
+    }
+}
+
+/// Filtered, paginated pages of a stored session, for sessions too large to read whole.
+pub struct QueryResultsTool {
+    crawl_results: CrawlSessions,
+}
+
+impl QueryResultsTool {
+    pub fn new(crawl_results: CrawlSessions) -> Self {
+        Self { crawl_results }
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let session_id = arguments["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: session_id"))?;
+        let query = ResultQuery::from_arguments(&arguments)?;
+        let sessions = self.crawl_results.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Crawl session not found: {}", session_id))?;
+
+        let queried = query.apply(session)?;
+        let returned = queried["results"].as_array().map_or(0, Vec::len);
+        let mut summary = format!(
+            "{} of {} matching page(s) in session {}, from offset {}",
+            returned, queried["matching_results"], session_id, query.offset
+        );
+        if let Some(next_offset) = queried["next_offset"].as_u64() {
+            summary.push_str(&format!(
+                "; call again with offset {} for more",
+                next_offset
+            ));
+        }
+        for page in queried["results"].as_array().into_iter().flatten() {
+            summary.push_str(&format!(
+                "\n- {} {}",
+                page["status_code"],
+                page["url"].as_str().unwrap_or_default()
+            ));
+        }
+        let uri = format!("crawl://results/{}?{}", session_id, query.to_query_string());
+        Ok(ToolOutput::text(summary).with_json(uri, queried))
+    }
+}
+
+/// Every stored session's ID, start URL, status, page count and timestamps, oldest
+/// first; `finished_at` is null while a crawl runs.
+pub fn session_list(sessions: &HashMap<String, CrawlSession>) -> Value {
+    let mut list: Vec<_> = sessions.values().map(CrawlSession::summary).collect();
+    list.sort_by_key(|session| session.started_at);
+    serde_json::json!({ "sessions": list })
+}
+
+/// Lists the stored crawl sessions, so clients can find a session ID again.
+pub struct ListCrawlSessionsTool {
+    crawl_results: CrawlSessions,
+}
+
+impl ListCrawlSessionsTool {
+    pub fn new(crawl_results: CrawlSessions) -> Self {
+        Self { crawl_results }
+    }
+
+    pub async fn execute(&self, _arguments: Value) -> Result<ToolOutput> {
+        let list = session_list(&*self.crawl_results.read().await);
+        let sessions = list["sessions"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut summary = format!("{} crawl session(s)", sessions.len());
+        for session in sessions {
+            summary.push_str(&format!(
+                "\n- {}: {} ({}, {} page(s))",
+                session["session_id"].as_str().unwrap_or_default(),
+                session["start_url"].as_str().unwrap_or("unknown URL"),
+                session["status"].as_str().unwrap_or("unknown"),
+                session["pages_crawled"]
+            ));
+        }
+        Ok(ToolOutput::text(summary).with_json(SESSIONS_URI, list))
+    }
+}
+
+/// Stop a running crawl. Pages crawled so far stay in the session.
+pub struct CancelCrawlTool {
+    crawl_results: CrawlSessions,
+    running: RunningCrawls,
+}
+
+impl CancelCrawlTool {
+    pub fn new(crawl_results: CrawlSessions, running: RunningCrawls) -> Self {
+        Self {
+            crawl_results,
+            running,
+        }
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let session_id = arguments["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: session_id"))?;
+
+        let mut sessions = self.crawl_results.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Crawl session not found: {}", session_id))?;
+        let crawler = self.running.read().await.get(session_id).cloned();
+        let Some(crawler) = crawler.filter(|_| session.status == CrawlStatus::Running) else {
+            return Err(anyhow::anyhow!(
+                "Crawl session {} is not running (status: {})",
+                session_id,
+                session.status.as_str()
+            ));
+        };
+
+        session.status = CrawlStatus::Cancelled;
+        crawler.request_stop();
+
+        let uri = format!("crawl://results/{}", session_id);
+        let summary = format!(
+            "Cancelled crawl session {} after {} page(s). The session keeps the pages crawled so far; use resource {} to get them.",
+            session_id, session.pages_crawled, uri
+        );
+        Ok(ToolOutput::text(summary).with_json(uri, serde_json::to_value(&*session)?))
+    }
+}
+
+/// Forget a crawl session that is no longer running, along with its stored copy.
+/// Aggregate statistics keep counting it.
+pub struct DeleteSessionTool {
+    crawl_results: CrawlSessions,
+    notifier: ResourceNotifier,
+    store: Option<SessionStore>,
+}
+
+impl DeleteSessionTool {
+    pub fn new(
+        crawl_results: CrawlSessions,
+        notifier: ResourceNotifier,
+        store: Option<SessionStore>,
+    ) -> Self {
+        Self {
+            crawl_results,
+            notifier,
+            store,
+        }
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let session_id = arguments["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: session_id"))?;
+
+        let mut sessions = self.crawl_results.write().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Crawl session not found: {}", session_id))?;
+        if session.status == CrawlStatus::Running {
+            return Err(anyhow::anyhow!(
+                "Crawl session {} is still running; cancel it first",
+                session_id
+            ));
+        }
+        let removal = self
+            .store
+            .as_ref()
+            .map(|store| store.session_removal(session_id));
+        let session = sessions.remove(session_id).unwrap();
+        drop(sessions);
+        self.notifier.resource_updated(SESSIONS_URI).await;
+        if let Some(removal) = removal {
+            removal.write().await?;
+        }
+
+        let summary = format!(
+            "Deleted crawl session {} and its {} page(s).",
+            session_id, session.pages_crawled
+        );
+        Ok(ToolOutput::text(summary).with_json(
+            SESSIONS_URI,
+            serde_json::json!({ "deleted": session.summary() }),
         ))
     }
 }
 
 pub struct GetRobotsTool;
 
+impl Default for GetRobotsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GetRobotsTool {
     pub fn new() -> Self {
         Self
     }
 
-    pub async fn execute(&self, arguments: Value) -> Result<String> {
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
         let domain = arguments["domain"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing required parameter: domain"))?;
@@ -147,37 +899,676 @@ impl GetRobotsTool {
         let robots_url = format!("https://{}/robots.txt", domain);
 
         let response = client.get(&robots_url).send().await?;
+        let status = response.status();
 
-        if response.status().is_success() {
+        if status.is_success() {
             let content = response.text().await?;
-            Ok(format!("Robots.txt for {}:\n\n{}", domain, content))
+            let data = serde_json::json!({
+                "domain": domain,
+                "url": robots_url,
+                "found": true,
+                "status_code": status.as_u16(),
+                "content": content
+            });
+            Ok(
+                ToolOutput::text(format!("Robots.txt for {}:\n\n{}", domain, content))
+                    .with_json(robots_url, data),
+            )
         } else {
-            Ok(format!(
+            let data = serde_json::json!({
+                "domain": domain,
+                "url": robots_url,
+                "found": false,
+                "status_code": status.as_u16(),
+                "content": null
+            });
+            Ok(ToolOutput::text(format!(
                 "No robots.txt found for {} (HTTP {})",
-                domain,
-                response.status()
+                domain, status
             ))
+            .with_json(robots_url, data))
+        }
+    }
+}
+
+/// Fetch one page and return what's on it, without a crawl session.
+pub struct FetchPageTool;
+
+impl Default for FetchPageTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FetchPageTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let url = arguments["url"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: url"))?;
+        let respect_robots = arguments["respect_robots"].as_bool().unwrap_or(true);
+
+        // Depth 0 crawls the URL alone, without queueing its links
+        let crawler = Crawler::new(CrawlerConfig {
+            user_agent: "RustCrawler-MCP/0.1.0".to_string(),
+            timeout: std::time::Duration::from_secs(10),
+            max_retries: 1,
+            max_pages: Some(1),
+            max_depth: Some(0),
+            respect_robots,
+            extract_text: true,
+            extract_structured_data: true,
+            ..Default::default()
+        })?;
+        let results = crawler.crawl(vec![url.to_string()]).await?;
+        let Some(page) = results.first() else {
+            let reason = crawler.get_failures().into_iter().next().map_or_else(
+                || "nothing was fetched".to_string(),
+                |failure| failure.reason,
+            );
+            return Err(anyhow::anyhow!("Failed to fetch {}: {}", url, reason));
+        };
+
+        let text = page.text.as_deref().unwrap_or_default();
+        let text_truncated = text.chars().count() > MAX_PAGE_TEXT_CHARS;
+        let text: String = text.chars().take(MAX_PAGE_TEXT_CHARS).collect();
+        let data = serde_json::json!({
+            "url": page.url,
+            "final_url": page.final_url,
+            "status_code": page.status_code,
+            "content_type": page.content_type,
+            "title": page.title,
+            "meta_description": page.meta_description,
+            "canonical": page.canonical,
+            "language": page.language,
+            "h1": page.h1,
+            "h2": page.h2,
+            "open_graph": page.open_graph,
+            "metadata": page.metadata,
+            "noindex": page.noindex,
+            "text": text,
+            "text_truncated": text_truncated,
+            "links": page.links
+        });
+
+        let mut summary = format!(
+            "{} (HTTP {})\nTitle: {}\n{} link(s)",
+            page.final_url,
+            page.status_code,
+            page.title.as_deref().unwrap_or("(none)"),
+            page.links.len()
+        );
+        if let Some(description) = &page.meta_description {
+            summary.push_str(&format!("\nDescription: {}", description));
+        }
+        if !text.is_empty() {
+            summary.push_str(&format!("\n\n{}", text));
+            if text_truncated {
+                summary.push_str("\n[text truncated]");
+            }
+        }
+        Ok(ToolOutput::text(summary).with_json(page.final_url.clone(), data))
+    }
+}
+
+pub struct ValidateUrlsTool;
+
+impl Default for ValidateUrlsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidateUrlsTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let urls: Vec<&str> = arguments["urls"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: urls"))?
+            .iter()
+            .map(|v| v.as_str().unwrap_or_default())
+            .collect();
+
+        if urls.len() > MAX_VALIDATE_URLS {
+            return Err(anyhow::anyhow!(
+                "Too many URLs: {} (maximum is {})",
+                urls.len(),
+                MAX_VALIDATE_URLS
+            ));
+        }
+
+        let check_robots = arguments["check_robots"].as_bool().unwrap_or(true);
+
+        // Scope is classified against the explicit seed, or the first valid URL
+        let seed = match arguments["seed"].as_str() {
+            Some(seed) => Some(
+                url::Url::parse(seed)
+                    .map_err(|e| anyhow::anyhow!("Invalid seed URL '{}': {}", seed, e))?,
+            ),
+            None => urls.iter().find_map(|u| url::Url::parse(u).ok()),
+        };
+
+        let robots_manager = RobotsManager::new(
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()?,
+            "RustCrawler-MCP/0.1.0".to_string(),
+        );
+
+        let mut results = Vec::with_capacity(urls.len());
+        for raw in urls {
+            let parsed = match url::Url::parse(raw) {
+                Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => parsed,
+                Ok(parsed) => {
+                    results.push(serde_json::json!({
+                        "url": raw,
+                        "valid": false,
+                        "error": format!("Unsupported scheme: {}", parsed.scheme())
+                    }));
+                    continue;
+                }
+                Err(e) => {
+                    results.push(serde_json::json!({
+                        "url": raw,
+                        "valid": false,
+                        "error": e.to_string()
+                    }));
+                    continue;
+                }
+            };
+
+            let robots_allowed = if check_robots {
+                robots_manager.check_robots_compliance(&parsed).await.ok()
+            } else {
+                None
+            };
+
+            results.push(serde_json::json!({
+                "url": raw,
+                "valid": true,
+                "normalized": normalize_url(&parsed).as_str(),
+                "scope": seed.as_ref().map(|seed| classify_scope(&parsed, seed).as_str()),
+                "robots_allowed": robots_allowed
+            }));
+        }
+
+        let valid = results.iter().filter(|r| r["valid"] == true).count();
+        let summary = format!(
+            "URL validation results: {} valid, {} invalid",
+            valid,
+            results.len() - valid
+        );
+        Ok(ToolOutput::text(summary).with_json("crawl://validate-urls", Value::Array(results)))
+    }
+}
+
+/// Inspect and edit the queue of a background crawl while it runs.
+pub struct CrawlQueueTool {
+    running: RunningCrawls,
+}
+
+impl CrawlQueueTool {
+    pub fn new(running: RunningCrawls) -> Self {
+        Self { running }
+    }
+
+    pub async fn execute(&self, arguments: Value) -> Result<ToolOutput> {
+        let session_id = arguments["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: session_id"))?;
+        let action = arguments["action"].as_str().unwrap_or("list");
+        let pattern = arguments["pattern"]
+            .as_str()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid pattern '{}': {}", pattern, e))
+            })
+            .transpose()?;
+
+        let crawler = self
+            .running
+            .read()
+            .await
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No running crawl for session: {}", session_id))?;
+        let finished = || anyhow::anyhow!("Crawl session {} has finished", session_id);
+        let uri = format!("crawl://results/{}/queue", session_id);
+
+        match action {
+            "list" => {
+                let limit = arguments["limit"]
+                    .as_u64()
+                    .map_or(20, |limit| limit as usize)
+                    .min(MAX_QUEUE_LIST);
+                let queued = crawler.queued_urls(limit).await.ok_or_else(finished)?;
+                let mut summary = format!("Next {} queued URL(s):\n", queued.len());
+                for entry in &queued {
+                    summary.push_str(&format!("\n[depth {}] {}", entry.depth, entry.url));
+                }
+                Ok(ToolOutput::text(summary).with_json(uri, serde_json::to_value(&queued)?))
+            }
+            "drop" | "prioritize" => {
+                let pattern = pattern
+                    .ok_or_else(|| anyhow::anyhow!("The {} action requires a pattern", action))?;
+                let (count, summary) = if action == "drop" {
+                    let count = crawler.drop_queued(pattern).await.ok_or_else(finished)?;
+                    (count, format!("Dropped {} queued URL(s)", count))
+                } else {
+                    let count = crawler
+                        .prioritize_queued(pattern)
+                        .await
+                        .ok_or_else(finished)?;
+                    (count, format!("Moved {} queued URL(s) to the front", count))
+                };
+                Ok(ToolOutput::text(summary)
+                    .with_json(uri, serde_json::json!({ "action": action, "urls": count })))
+            }
+            _ => Err(anyhow::anyhow!("Unknown queue action: {}", action)),
         }
     }
 }
 
 pub struct GetStatsTool {
-    stats: Arc<RwLock<HashMap<String, u64>>>,
+    stats: Arc<RwLock<CrawlStatistics>>,
 }
 
 impl GetStatsTool {
-    pub fn new(stats: Arc<RwLock<HashMap<String, u64>>>) -> Self {
+    pub fn new(stats: Arc<RwLock<CrawlStatistics>>) -> Self {
         Self { stats }
     }
 
-    pub async fn execute(&self, _arguments: Value) -> Result<String> {
+    pub async fn execute(&self, _arguments: Value) -> Result<ToolOutput> {
         let stats = self.stats.read().await;
 
-        if stats.is_empty() {
-            Ok("No crawl statistics available yet.".to_string())
+        if stats.total_sessions == 0 {
+            Ok(ToolOutput::text("No crawl statistics available yet."))
         } else {
             let stats_json = serde_json::to_string_pretty(&*stats)?;
-            Ok(format!("Current crawl statistics:\n\n{}", stats_json))
+            Ok(
+                ToolOutput::text(format!("Current crawl statistics:\n\n{}", stats_json))
+                    .with_json("crawl://stats", serde_json::to_value(&*stats)?),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_output_content_blocks() {
+        let output = ToolOutput::text("2 pages").with_json(
+            "crawl://results/abc",
+            serde_json::json!({ "pages_crawled": 2 }),
+        );
+        let blocks = output.content_blocks().unwrap();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["type"], "text");
+        assert_eq!(blocks[0]["text"], "2 pages");
+        assert_eq!(blocks[1]["type"], "resource");
+        assert_eq!(blocks[1]["resource"]["mimeType"], "application/json");
+        let data: Value =
+            serde_json::from_str(blocks[1]["resource"]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(data["pages_crawled"], 2);
+
+        assert_eq!(ToolOutput::text("none").content_blocks().unwrap().len(), 1);
+    }
+
+    /// A session of `start_url` with a page for each of `pages`' URL and status.
+    fn session_with_pages(start_url: &str, pages: &[(&str, u16)]) -> CrawlSession {
+        let mut session = CrawlSession::new("s1", start_url, CrawlSessionConfig::default());
+        session.results = pages
+            .iter()
+            .map(|&(url, status_code)| CrawlPageResult {
+                url: url.to_string(),
+                status_code,
+                ..Default::default()
+            })
+            .collect();
+        session.pages_crawled = pages.len();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_fetch_page_returns_one_page() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 1024];
+                let _ = socket.read(&mut request).await;
+                let body = "<html><head><title>Docs</title>\
+                    <meta name=\"description\" content=\"All about it\"></head>\
+                    <body><p>Hello there</p><a href=\"/next\">Next</a></body></html>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let url = format!("http://{}/", addr);
+        let output = FetchPageTool::new()
+            .execute(serde_json::json!({ "url": url, "respect_robots": false }))
+            .await
+            .unwrap();
+        let (_, page) = output.data.unwrap();
+        assert_eq!(page["title"], "Docs");
+        assert_eq!(page["meta_description"], "All about it");
+        assert!(page["text"].as_str().unwrap().contains("Hello there"));
+        assert_eq!(page["links"], serde_json::json!([format!("{}next", url)]));
+        assert!(output.summary.contains("Title: Docs"));
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_crawl_keeps_its_pages() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // The home page answers at once; the page it links to never does
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = vec![0; 4096];
+                    let n = socket.read(&mut request).await.unwrap_or(0);
+                    if !request[..n].starts_with(b"GET / ") {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        return;
+                    }
+                    let body = r#"<html><a href="/slow">Slow</a></html>"#;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        let crawl_results: CrawlSessions = Arc::new(RwLock::new(HashMap::new()));
+        let tool = CrawlTool::new(
+            Arc::new(RwLock::new(None)),
+            crawl_results.clone(),
+            Arc::new(RwLock::new(CrawlStatistics::default())),
+            ResourceNotifier::default(),
+            Arc::new(RwLock::new(HashMap::new())),
+        )
+        .with_time_budget(Duration::from_millis(1500));
+        let output = tool
+            .execute(serde_json::json!({
+                "url": format!("http://{}/", addr),
+                "background": false,
+                "respect_robots": false,
+                "max_depth": 2,
+                "timeout_seconds": 30
+            }))
+            .await
+            .unwrap();
+        assert!(output.summary.contains("time budget"));
+
+        let sessions = crawl_results.read().await;
+        let session = sessions.values().next().unwrap();
+        assert_eq!(session.status, CrawlStatus::TimedOut);
+        assert_eq!(session.pages_crawled, 1);
+    }
+
+    #[tokio::test]
+    async fn test_each_crawl_uses_its_own_settings() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Answers every request with a page titled after its User-Agent
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let n = socket.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]).to_string();
+                let user_agent = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("user-agent: "))
+                    .unwrap_or("");
+                let body = format!("<html><head><title>{}</title></head></html>", user_agent);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let latest_crawler = Arc::new(RwLock::new(None));
+        let tool = CrawlTool::new(
+            latest_crawler.clone(),
+            Arc::new(RwLock::new(HashMap::new())),
+            Arc::new(RwLock::new(CrawlStatistics::default())),
+            ResourceNotifier::default(),
+            Arc::new(RwLock::new(HashMap::new())),
+        );
+        let url = format!("http://{}/", addr);
+        for (user_agent, max_concurrency) in [("first-agent/1.0", 1), ("second-agent/2.0", 5)] {
+            let output = tool
+                .execute(serde_json::json!({
+                    "url": url,
+                    "background": false,
+                    "respect_robots": false,
+                    "max_concurrency": max_concurrency,
+                    "timeout_seconds": 5,
+                    "user_agent": user_agent
+                }))
+                .await
+                .unwrap();
+            let (_, session) = output.data.unwrap();
+            assert_eq!(session["results"][0]["title"], user_agent);
+            assert_eq!(session["config"]["max_concurrency"], max_concurrency);
+            assert_eq!(session["config"]["timeout_seconds"], 5);
         }
+        assert!(latest_crawler.read().await.is_some());
+
+        let blank = tool
+            .execute(serde_json::json!({ "url": url, "user_agent": " " }))
+            .await;
+        assert!(blank.is_err());
+    }
+
+    #[test]
+    fn test_result_query_filters_and_pages() {
+        let session = session_with_pages(
+            "https://example.com/",
+            &[
+                ("https://example.com/", 200),
+                ("https://example.com/blog/a", 404),
+                ("https://example.com/blog/b", 200),
+                ("https://example.com/blog/c", 503),
+                ("https://example.com/blog/d", 410),
+            ],
+        );
+
+        let query =
+            ResultQuery::from_query_string("status=4xx,503&url_contains=/blog/&limit=2").unwrap();
+        let queried = query.apply(&session).unwrap();
+        assert_eq!(queried["session_id"], "s1");
+        assert_eq!(queried["matching_results"], 3);
+        assert_eq!(queried["next_offset"], 2);
+        let urls: Vec<&str> = queried["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["url"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://example.com/blog/a", "https://example.com/blog/c"]
+        );
+
+        let last = ResultQuery {
+            offset: 2,
+            ..query.clone()
+        }
+        .apply(&session)
+        .unwrap();
+        assert_eq!(last["results"][0]["url"], "https://example.com/blog/d");
+        assert!(last["next_offset"].is_null());
+
+        // Tool arguments and the resource query string describe the same query
+        let arguments = serde_json::json!({
+            "limit": 2,
+            "status": ["4xx", "503"],
+            "url_contains": "/blog/"
+        });
+        let from_arguments = ResultQuery::from_arguments(&arguments).unwrap();
+        assert_eq!(from_arguments, query);
+        assert_eq!(
+            ResultQuery::from_query_string(&query.to_query_string()).unwrap(),
+            query
+        );
+
+        assert!(ResultQuery::from_query_string("status=4x").is_err());
+        assert!(ResultQuery::from_query_string("page=2").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_crawl_sessions() {
+        let mut new = session_with_pages("https://b.example/", &[("https://b.example/", 200)]);
+        new.session_id = "new".to_string();
+        new.started_at = 200;
+        let mut old = session_with_pages("https://a.example/", &[]);
+        old.session_id = "old".to_string();
+        old.status = CrawlStatus::Completed;
+        old.started_at = 100;
+        old.finished_at = Some(150);
+        old.pages_crawled = 4;
+        let crawl_results = Arc::new(RwLock::new(HashMap::from([
+            ("new".to_string(), new),
+            ("old".to_string(), old),
+        ])));
+        let output = ListCrawlSessionsTool::new(crawl_results)
+            .execute(serde_json::json!({}))
+            .await
+            .unwrap();
+
+        assert!(output
+            .summary
+            .contains("- old: https://a.example/ (completed, 4 page(s))"));
+        let (uri, list) = output.data.unwrap();
+        assert_eq!(uri, SESSIONS_URI);
+        let sessions = list["sessions"].as_array().unwrap();
+        assert_eq!(sessions[0]["session_id"], "old");
+        assert_eq!(sessions[0]["finished_at"], 150);
+        assert_eq!(sessions[1]["session_id"], "new");
+        assert!(sessions[1]["finished_at"].is_null());
+        assert!(sessions[1].get("results").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_crawl_marks_running_session_cancelled() {
+        let session = session_with_pages(
+            "https://example.com/",
+            &[
+                ("https://example.com/", 200),
+                ("https://example.com/a", 200),
+            ],
+        );
+        let crawl_results = Arc::new(RwLock::new(HashMap::from([("s1".to_string(), session)])));
+        let running: RunningCrawls = Arc::new(RwLock::new(HashMap::from([(
+            "s1".to_string(),
+            Arc::new(Crawler::new(CrawlerConfig::default()).unwrap()),
+        )])));
+        let tool = CancelCrawlTool::new(crawl_results.clone(), running);
+
+        let output = tool
+            .execute(serde_json::json!({ "session_id": "s1" }))
+            .await
+            .unwrap();
+        assert!(output.summary.contains("after 2 page(s)"));
+        assert_eq!(
+            crawl_results.read().await["s1"].status,
+            CrawlStatus::Cancelled
+        );
+
+        let again = tool
+            .execute(serde_json::json!({ "session_id": "s1" }))
+            .await;
+        assert!(again.unwrap_err().to_string().contains("not running"));
+        let missing = tool
+            .execute(serde_json::json!({ "session_id": "s2" }))
+            .await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_foreground_crawl_can_be_cancelled() {
+        use crate::test_server::{test_server, Response};
+
+        // The home page answers at once; the page it links to never does
+        let addr = test_server(|request| async move {
+            if request.path != "/" {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            Response::html(r#"<html><a href="/slow">Slow</a></html>"#)
+        })
+        .await;
+        let crawl_results: CrawlSessions = Arc::new(RwLock::new(HashMap::new()));
+        let running: RunningCrawls = Arc::new(RwLock::new(HashMap::new()));
+        let tool = CrawlTool::new(
+            Arc::new(RwLock::new(None)),
+            crawl_results.clone(),
+            Arc::new(RwLock::new(CrawlStatistics::default())),
+            ResourceNotifier::default(),
+            running.clone(),
+        );
+        let crawl = tokio::spawn(async move {
+            tool.execute(serde_json::json!({
+                "url": format!("http://{}/", addr),
+                "background": false,
+                "respect_robots": false,
+                "max_depth": 2,
+                "timeout_seconds": 30
+            }))
+            .await
+        });
+
+        // The session is listed as running once its first page is in
+        let session_id = loop {
+            let sessions = crawl_results.read().await;
+            if let Some(session) = sessions.values().find(|s| s.pages_crawled == 1) {
+                assert_eq!(session.status, CrawlStatus::Running);
+                break session.session_id.clone();
+            }
+            drop(sessions);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        };
+        CancelCrawlTool::new(crawl_results.clone(), running.clone())
+            .execute(serde_json::json!({ "session_id": session_id }))
+            .await
+            .unwrap();
+
+        let output = tokio::time::timeout(Duration::from_secs(5), crawl)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(output.summary.contains("cancelled"));
+        assert_eq!(
+            crawl_results.read().await[&session_id].status,
+            CrawlStatus::Cancelled
+        );
+        assert!(running.read().await.is_empty());
     }
 }
@@ -1,13 +1,83 @@
 use anyhow::Result;
-use env_logger;
+use clap::{Parser, ValueEnum};
 use log::{error, info};
-use serde_json::{json, Value};
-use std::io::{self, BufRead, Write};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+use rustcrawler::mcp::http::{self, SessionLimits};
+use rustcrawler::mcp::protocol::{handle_message, parse_error};
+use rustcrawler::mcp::{ResourceNotifier, RustCrawlerMcpServer, SessionStore};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "rustcrawler-mcp",
+    version = "0.1.0",
+    about = "RustCrawler's tools served over the Model Context Protocol"
+)]
+struct McpArgs {
+    #[arg(
+        long = "transport",
+        help = "stdio for a single client that launches the server, http to serve clients over Streamable HTTP",
+        value_enum,
+        default_value = "stdio"
+    )]
+    transport: Transport,
+
+    #[arg(
+        long = "host",
+        help = "Address to listen on with --transport http; anything but loopback exposes the crawler to the network",
+        default_value = "127.0.0.1",
+        value_name = "ADDR"
+    )]
+    host: IpAddr,
+
+    #[arg(
+        long = "port",
+        help = "Port to listen on with --transport http",
+        default_value = "8080",
+        value_name = "PORT"
+    )]
+    port: u16,
+
+    #[arg(
+        long = "storage-dir",
+        help = "Keep crawl sessions and statistics in this directory and reload them at startup",
+        value_name = "DIR"
+    )]
+    storage_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "session-ttl",
+        help = "Close HTTP sessions left unused for this many seconds",
+        default_value = "1800",
+        value_name = "SECS"
+    )]
+    session_ttl: u64,
+
+    #[arg(
+        long = "max-sessions",
+        help = "Refuse new HTTP sessions while this many are open",
+        default_value = "100",
+        value_name = "N"
+    )]
+    max_sessions: usize,
+}
 
-use rustcrawler::mcp::RustCrawlerMcpServer;
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Transport {
+    Stdio,
+    Http,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = McpArgs::parse();
+
     // Initialize logging to stderr so it doesn't interfere with MCP communication
     env_logger::Builder::from_default_env()
         .target(env_logger::Target::Stderr)
@@ -15,165 +85,84 @@ async fn main() -> Result<()> {
 
     info!("Starting RustCrawler MCP Server");
 
+    let store = args.storage_dir.map(SessionStore::open).transpose()?;
+
+    match args.transport {
+        Transport::Stdio => serve_stdio(store).await,
+        Transport::Http => {
+            let limits = SessionLimits {
+                idle_ttl: Duration::from_secs(args.session_ttl),
+                max_sessions: args.max_sessions,
+            };
+            http::serve(
+                TcpListener::bind((args.host, args.port)).await?,
+                store,
+                limits,
+            )
+            .await
+        }
+    }
+}
+
+async fn serve_stdio(store: Option<SessionStore>) -> Result<()> {
+    // Responses and notifications share one writer so lines never interleave
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = io::stdout();
+        while let Some(line) = outgoing_rx.recv().await {
+            if let Err(e) = writeln!(stdout, "{}", line) {
+                error!("Error writing response: {}", e);
+                break;
+            }
+            if let Err(e) = stdout.flush() {
+                error!("Error flushing stdout: {}", e);
+                break;
+            }
+        }
+    });
+
     // Create the MCP server
-    let server = RustCrawlerMcpServer::new();
+    let mut server =
+        RustCrawlerMcpServer::new().with_notifier(ResourceNotifier::new(outgoing.clone()));
+    if let Some(store) = store {
+        server = server.with_storage(store);
+    }
 
     info!("RustCrawler MCP Server is ready to accept connections via stdio");
 
     // Handle MCP protocol messages via stdin/stdout
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
 
-    for line in stdin.lock().lines() {
-        let line = match line {
-            Ok(line) => line,
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
             Err(e) => {
                 error!("Error reading from stdin: {}", e);
                 break;
             }
         };
-        
+
         if line.trim().is_empty() {
             continue;
         }
 
-        match handle_mcp_message(&server, &line).await {
-            Ok(response) => {
-                if !response.is_empty() {
-                    if let Err(e) = writeln!(stdout, "{}", response) {
-                        error!("Error writing response: {}", e);
-                        break;
-                    }
-                    if let Err(e) = stdout.flush() {
-                        error!("Error flushing stdout: {}", e);
-                        break;
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Error handling MCP message: {}", e);
-                let request: Result<Value, _> = serde_json::from_str(&line);
-                let id = request
-                    .as_ref()
-                    .ok()
-                    .and_then(|r| r.get("id"))
-                    .cloned()
-                    .unwrap_or(Value::Null);
-                let error_response = json!({
-                    "jsonrpc": "2.0",
-                    "id": id,
-                    "error": {
-                        "code": -32603,
-                        "message": e.to_string()
-                    }
-                });
-                if let Err(e) = writeln!(stdout, "{}", error_response) {
-                    error!("Error writing error response: {}", e);
-                    break;
-                }
-                if let Err(e) = stdout.flush() {
-                    error!("Error flushing stdout after error: {}", e);
-                    break;
-                }
+        let response = match serde_json::from_str(&line) {
+            Ok(message) => handle_message(&server, &message, Some(&outgoing)).await,
+            Err(e) => Some(parse_error(&e)),
+        };
+
+        if let Some(response) = response {
+            if outgoing.send(response.to_string()).is_err() {
+                break;
             }
         }
     }
+    // Flush pending output; background crawls hold their own senders, so don't wait on them
+    drop(outgoing);
+    drop(server);
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(1), writer).await;
 
     info!("RustCrawler MCP Server shutdown");
     Ok(())
 }
-
-async fn handle_mcp_message(server: &RustCrawlerMcpServer, message: &str) -> Result<String> {
-    let request: Value = serde_json::from_str(message)?;
-
-    let id = request["id"].clone();
-    let method = request["method"].as_str().unwrap_or("");
-    let params = &request["params"];
-
-    let result = match method {
-        "initialize" => {
-            json!({
-                "protocolVersion": "2024-11-05",
-                "capabilities": {
-                    "tools": {},
-                    "resources": {},
-                    "prompts": {}
-                },
-                "serverInfo": {
-                    "name": "rustcrawler",
-                    "version": "0.1.0"
-                }
-            })
-        }
-        "tools/list" => {
-            json!({
-                "tools": server.get_tools()
-            })
-        }
-        "tools/call" => {
-            let tool_name = params["name"].as_str().unwrap_or("");
-            let arguments = params["arguments"].clone();
-
-            match server.handle_tool_call(tool_name, arguments).await {
-                Ok(content) => {
-                    json!({
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": content
-                            }
-                        ]
-                    })
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        "resources/list" => {
-            json!({
-                "resources": server.get_resources()
-            })
-        }
-        "resources/read" => {
-            let uri = params["uri"].as_str().unwrap_or("");
-
-            match server.get_resource(uri).await {
-                Ok(content) => {
-                    json!({
-                        "contents": [
-                            {
-                                "uri": uri,
-                                "mimeType": "application/json",
-                                "text": content
-                            }
-                        ]
-                    })
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        "prompts/list" => {
-            json!({
-                "prompts": []
-            })
-        }
-        "notifications/initialized" => {
-            // Acknowledge initialization
-            return Ok(String::new());
-        }
-        _ => {
-            return Err(anyhow::anyhow!("Unknown method: {}", method));
-        }
-    };
-
-    let response = json!({
-        "jsonrpc": "2.0",
-        "id": id,
-        "result": result
-    });
-
-    Ok(response.to_string())
-}
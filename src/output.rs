@@ -0,0 +1,240 @@
+use crate::anomalies::Anomaly;
+use crate::classify::CategorySummary;
+use crate::clusters::PageCluster;
+use crate::crawler::{CrawlFailure, CrawlResult, ExternalDomain};
+#[cfg(feature = "reports")]
+use crate::diff::CrawlDiff;
+#[cfg(feature = "reports")]
+use crate::markdown::render_report;
+use crate::run::RunMetadata;
+use crate::telemetry::{RetrySummary, TimelineEvent};
+use crate::tls::CertificateInfo;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// How crawl results are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// One human-readable line per page, then one per failed URL
+    #[default]
+    Text,
+    /// A single JSON document with results, run metadata, retries and timeline
+    Json,
+    /// One JSON object per page, written as each page completes
+    Ndjson,
+    /// A Markdown summary with tables of errors, broken links and the slowest pages
+    #[cfg(feature = "reports")]
+    Markdown,
+}
+
+/// Everything `--output-format json` writes once the crawl ends; `markdown` summarizes it.
+#[derive(Debug, Default, Serialize)]
+pub struct CrawlReport<'a> {
+    pub run: Option<RunMetadata>,
+    pub results: &'a [CrawlResult],
+    /// URLs that couldn't be crawled.
+    pub failures: Vec<CrawlFailure>,
+    /// Results grouped into logical pages by canonical and hreflang relations.
+    pub clusters: Vec<PageCluster>,
+    /// Page counts per `--classify` category; empty without rules.
+    pub categories: Vec<CategorySummary>,
+    pub retry_summary: RetrySummary,
+    pub timeline: Vec<TimelineEvent>,
+    /// HTTPS certificates seen per host; empty without `--tls-info`.
+    pub certificates: Vec<CertificateInfo>,
+    /// Domains outside the crawl's scope that pages link to, most linked first.
+    pub external_domains: Vec<ExternalDomain>,
+    /// Things worth investigating, most significant first.
+    pub anomalies: Vec<Anomaly>,
+    /// Pages added, removed or changed since the crawl given to `--diff-against`.
+    #[cfg(feature = "reports")]
+    pub diff: Option<CrawlDiff>,
+}
+
+/// Destination for crawl results, registered on a crawler with `Crawler::with_sink`.
+/// The crawler calls `write` for each result as it's yielded, `flush` when the crawl
+/// ends, and `close` with the final report once the caller has built it.
+// `async_trait` marks the boxed futures `#[must_use]` again, which newer clippy flags
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait ResultSink: Send {
+    async fn write(&mut self, result: &CrawlResult) -> Result<()>;
+
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn close(&mut self, report: &CrawlReport<'_>) -> Result<()>;
+
+    /// Whether `close` needs every result in full, e.g. to write them all at once.
+    /// When no sink does, results spilled to disk are reported as summaries.
+    fn needs_full_results(&self) -> bool {
+        false
+    }
+}
+
+/// Writes results to stdout or a file. Text and NDJSON lines go out as each result
+/// arrives; JSON and Markdown are written in one piece by `finish`.
+pub struct ResultWriter {
+    format: OutputFormat,
+    out: Box<dyn Write + Send>,
+}
+
+impl ResultWriter {
+    pub fn new(format: OutputFormat, path: Option<&Path>) -> Result<Self> {
+        let out: Box<dyn Write + Send> =
+            match path {
+                Some(path) => Box::new(BufWriter::new(File::create(path).with_context(|| {
+                    format!("Failed to create output file {}", path.display())
+                })?)),
+                None => Box::new(io::stdout()),
+            };
+        Ok(Self { format, out })
+    }
+
+    /// Write to any sink, e.g. a socket or an in-memory buffer.
+    pub fn from_writer(format: OutputFormat, out: Box<dyn Write + Send>) -> Self {
+        Self { format, out }
+    }
+
+    pub fn write_result(&mut self, result: &CrawlResult) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => writeln!(self.out, "{}", result.format_output())?,
+            OutputFormat::Ndjson => {
+                serde_json::to_writer(&mut self.out, result)?;
+                writeln!(self.out)?;
+                // Flush per line so downstream tools like jq see pages as they complete
+                self.out.flush()?;
+            }
+            OutputFormat::Json => {}
+            #[cfg(feature = "reports")]
+            OutputFormat::Markdown => {}
+        }
+        Ok(())
+    }
+
+    /// Whether `finish` writes the results themselves, not just what's derived from them.
+    pub fn needs_full_results(&self) -> bool {
+        match self.format {
+            OutputFormat::Json => true,
+            #[cfg(feature = "reports")]
+            OutputFormat::Markdown => true,
+            OutputFormat::Text | OutputFormat::Ndjson => false,
+        }
+    }
+
+    pub fn finish(mut self, report: &CrawlReport) -> Result<()> {
+        self.write_report(report)
+    }
+
+    fn write_report(&mut self, report: &CrawlReport) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.out, report)?;
+                writeln!(self.out)?;
+            }
+            #[cfg(feature = "reports")]
+            OutputFormat::Markdown => self.out.write_all(render_report(report).as_bytes())?,
+            OutputFormat::Text => {
+                for failure in &report.failures {
+                    writeln!(self.out, "{}", failure.format_output())?;
+                }
+            }
+            OutputFormat::Ndjson => {}
+        }
+        self.out.flush().context("Failed to flush output")
+    }
+}
+
+#[async_trait]
+impl ResultSink for ResultWriter {
+    async fn write(&mut self, result: &CrawlResult) -> Result<()> {
+        self.write_result(result)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.out.flush().context("Failed to flush output")
+    }
+
+    async fn close(&mut self, report: &CrawlReport<'_>) -> Result<()> {
+        self.write_report(report)
+    }
+
+    fn needs_full_results(&self) -> bool {
+        ResultWriter::needs_full_results(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Shared buffer so the test can read what the writer produced.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn result(url: &str) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code: 200,
+            title: Some("Title".to_string()),
+            crawl_time: Duration::from_millis(12),
+            run_id: "run".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ndjson_writes_one_object_per_line() {
+        let buffer = SharedBuffer::default();
+        let mut writer = ResultWriter::from_writer(OutputFormat::Ndjson, Box::new(buffer.clone()));
+        writer.write_result(&result("https://a.com/")).unwrap();
+        writer.write_result(&result("https://b.com/")).unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["url"], "https://b.com/");
+        assert_eq!(lines[0]["crawl_time_ms"], 12);
+    }
+
+    #[test]
+    fn test_json_report_written_on_finish() {
+        let buffer = SharedBuffer::default();
+        let mut writer = ResultWriter::from_writer(OutputFormat::Json, Box::new(buffer.clone()));
+        let results = vec![result("https://a.com/")];
+        writer.write_result(&results[0]).unwrap();
+        assert!(buffer.0.lock().unwrap().is_empty());
+
+        writer
+            .finish(&CrawlReport {
+                results: &results,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let report: serde_json::Value = serde_json::from_slice(&buffer.0.lock().unwrap()).unwrap();
+        assert_eq!(report["results"][0]["status_code"], 200);
+        assert!(report["timeline"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::crawler::{CrawlResult, Crawler, CrawlerConfig};
+use crate::sitemap;
+use crate::urls::{classify_scope, registrable_domain, seed_without_scheme, CrawlScope, UrlScope};
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::collections::BTreeSet;
+use url::Url;
+
+/// Pages fetched by `--probe` / `--dry-run` before suggesting settings.
+pub const DEFAULT_PROBE_PAGES: usize = 10;
+
+/// Sitemaps fetched at most while estimating site size, so huge indexes stay cheap.
+const MAX_SITEMAP_FETCHES: usize = 20;
+
+/// What a short probe crawl learned about the seed site.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteProfile {
+    pub pages_probed: usize,
+    pub avg_links_per_page: f64,
+    /// Links on the seed host, on its subdomains and elsewhere, across probed pages.
+    pub same_host_links: usize,
+    pub subdomain_links: usize,
+    pub external_links: usize,
+    /// Distinct same-host URLs discovered by the probe.
+    pub distinct_internal_urls: usize,
+    /// Other hosts on the seeds' registrable domains that probed pages link to.
+    pub subdomains: Vec<String>,
+    /// Page URLs listed in the site's sitemaps, if any could be read.
+    pub sitemap_urls: Option<usize>,
+}
+
+/// Suggested limits for the full crawl, with the reasoning behind them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeSuggestion {
+    pub scope: CrawlScope,
+    pub max_depth: usize,
+    pub max_pages: usize,
+    pub reasons: Vec<String>,
+}
+
+impl SiteProfile {
+    /// Profile probed pages relative to the first seed.
+    pub fn from_results(seeds: &[String], results: &[CrawlResult]) -> Self {
+        let Some(seed) = seeds.first().and_then(|seed| Url::parse(seed).ok()) else {
+            return Self::default();
+        };
+        let seed_hosts: BTreeSet<String> = seeds
+            .iter()
+            .filter_map(|seed| Url::parse(seed).ok()?.host_str().map(str::to_string))
+            .collect();
+        let seed_domains: BTreeSet<String> =
+            seed_hosts.iter().map(|h| registrable_domain(h)).collect();
+
+        let mut profile = Self {
+            pages_probed: results.len(),
+            ..Self::default()
+        };
+        let mut internal = BTreeSet::new();
+        let mut subdomains = BTreeSet::new();
+
+        for link in results.iter().flat_map(|result| &result.links) {
+            let Ok(url) = Url::parse(link) else {
+                continue;
+            };
+            let host = url.host_str().unwrap_or_default();
+            if seed_hosts.contains(host) {
+                profile.same_host_links += 1;
+                internal.insert(link.as_str());
+            } else if classify_scope(&url, &seed) == UrlScope::Subdomain
+                || seed_domains.contains(&registrable_domain(host))
+            {
+                profile.subdomain_links += 1;
+                subdomains.insert(host.to_string());
+            } else {
+                profile.external_links += 1;
+            }
+        }
+
+        let total_links =
+            profile.same_host_links + profile.subdomain_links + profile.external_links;
+        if !results.is_empty() {
+            profile.avg_links_per_page = total_links as f64 / results.len() as f64;
+        }
+        profile.distinct_internal_urls = internal.len();
+        profile.subdomains = subdomains.into_iter().collect();
+        profile
+    }
+
+    pub fn suggest(&self) -> ScopeSuggestion {
+        let mut reasons = Vec::new();
+
+        let internal_links = self.same_host_links + self.subdomain_links;
+        let scope = if !self.subdomains.is_empty() && self.subdomain_links * 10 >= internal_links {
+            reasons.push(format!(
+                "{} subdomain(s) carry {} of {} on-site links",
+                self.subdomains.len(),
+                self.subdomain_links,
+                internal_links
+            ));
+            CrawlScope::SameDomain
+        } else {
+            CrawlScope::SameHost
+        };
+        if self.external_links > 0 {
+            reasons.push(format!(
+                "{} off-site link(s) excluded by the suggested scope",
+                self.external_links
+            ));
+        }
+
+        let per_page = if self.pages_probed == 0 {
+            0.0
+        } else {
+            internal_links as f64 / self.pages_probed as f64
+        };
+        let max_depth = if per_page >= 100.0 {
+            2
+        } else if per_page >= 20.0 {
+            3
+        } else {
+            5
+        };
+        reasons.push(format!(
+            "{:.0} on-site links per page suggests depth {}",
+            per_page, max_depth
+        ));
+
+        let max_pages = match self.sitemap_urls {
+            Some(count) if count > 0 => {
+                reasons.push(format!("sitemaps list {} page(s)", count));
+                // Leave headroom for pages the sitemap misses
+                count + count / 10 + 1
+            }
+            _ => {
+                let estimate = (self.distinct_internal_urls * 10).clamp(100, 10_000);
+                reasons.push(format!(
+                    "no sitemap; budget estimated from {} distinct on-site URLs seen",
+                    self.distinct_internal_urls
+                ));
+                estimate
+            }
+        };
+
+        ScopeSuggestion {
+            scope,
+            max_depth,
+            max_pages,
+            reasons,
+        }
+    }
+
+    pub fn format_output(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Pages probed: {}", self.pages_probed),
+            format!("Average links/page: {:.1}", self.avg_links_per_page),
+            format!(
+                "Links: {} same-host, {} subdomain, {} external",
+                self.same_host_links, self.subdomain_links, self.external_links
+            ),
+        ];
+        if !self.subdomains.is_empty() {
+            lines.push(format!("Subdomains seen: {}", self.subdomains.join(", ")));
+        }
+        match self.sitemap_urls {
+            Some(count) => lines.push(format!("Sitemap size: {} URL(s)", count)),
+            None => lines.push("Sitemap size: unknown (no sitemap found)".to_string()),
+        }
+        lines
+    }
+}
+
+impl ScopeSuggestion {
+    /// The suggestion as command-line flags.
+    pub fn to_args(&self) -> String {
+        let scope = match self.scope {
+            CrawlScope::All => "all",
+            CrawlScope::SameHost => "same-host",
+            CrawlScope::SameDomain => "same-domain",
+        };
+        format!(
+            "--scope {} --depth {} --max-pages {}",
+            scope, self.max_depth, self.max_pages
+        )
+    }
+}
+
+/// Give seeds typed without a scheme one: `https://`, unless the host can't be reached
+/// over HTTPS but answers over plain HTTP. Other seeds are returned unchanged.
+pub async fn resolve_seed_schemes(
+    config: &CrawlerConfig,
+    seeds: Vec<String>,
+) -> Result<Vec<String>> {
+    if seeds.iter().all(|seed| seed_without_scheme(seed).is_none()) {
+        return Ok(seeds);
+    }
+    let mut builder = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .user_agent(&config.user_agent);
+    if let Some(proxy) = config.proxy.as_ref().or(config.proxy_list.first()) {
+        builder = builder.proxy(reqwest::Proxy::all(proxy).context("Failed to create proxy")?);
+    }
+    let client = builder.build().context("Failed to build HTTP client")?;
+
+    let mut resolved = Vec::with_capacity(seeds.len());
+    for seed in seeds {
+        let Some(https) = seed_without_scheme(&seed) else {
+            resolved.push(seed);
+            continue;
+        };
+        let mut url = https.clone();
+        if let Err(e) = client.head(https.as_str()).send().await {
+            debug!("{} is not reachable over HTTPS: {}", https, e);
+            let mut http = https.clone();
+            // Only fails for special schemes switching to non-special ones
+            let _ = http.set_scheme("http");
+            if client.head(http.as_str()).send().await.is_ok() {
+                url = http;
+            }
+        }
+        info!("Seed {} has no scheme; using {}", seed, url);
+        resolved.push(url.to_string());
+    }
+    Ok(resolved)
+}
+
+/// Crawl the first `pages` pages from `seeds` and look for sitemaps to profile the site.
+pub async fn probe_site(
+    config: &CrawlerConfig,
+    seeds: &[String],
+    pages: usize,
+) -> Result<SiteProfile> {
+    // Seeds plus a few of their links; `max_pages` is only checked between depth
+    // levels, so capping links per page is what keeps the probe short
+    let probe_config = CrawlerConfig {
+        max_pages: Some(pages),
+        max_depth: Some(1),
+        max_links_per_page: Some(pages),
+        scope: CrawlScope::SameDomain,
+        ..config.clone()
+    };
+    let crawler = Crawler::new(probe_config)?;
+    let results = crawler.crawl(seeds.to_vec()).await?;
+    let mut profile = SiteProfile::from_results(seeds, &results);
+
+    let roots = sitemap::find_sitemaps(crawler.client(), seeds).await;
+    profile.sitemap_urls = sitemap::count_urls(crawler.client(), &roots, MAX_SITEMAP_FETCHES).await;
+
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: "https://example.com/".to_string(),
+            status_code: 200,
+            links: links.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile_and_suggestion() {
+        let seeds = vec!["https://example.com/".to_string()];
+        let results = vec![
+            page(&[
+                "https://example.com/a",
+                "https://example.com/b",
+                "https://blog.example.com/post",
+                "https://other.org/",
+            ]),
+            page(&["https://example.com/a", "https://shop.example.com/"]),
+        ];
+
+        let profile = SiteProfile::from_results(&seeds, &results);
+        assert_eq!(profile.pages_probed, 2);
+        assert_eq!(profile.avg_links_per_page, 3.0);
+        assert_eq!(
+            (
+                profile.same_host_links,
+                profile.subdomain_links,
+                profile.external_links
+            ),
+            (3, 2, 1)
+        );
+        assert_eq!(profile.distinct_internal_urls, 2);
+        assert_eq!(
+            profile.subdomains,
+            vec!["blog.example.com", "shop.example.com"]
+        );
+
+        let suggestion = profile.suggest();
+        assert_eq!(suggestion.scope, CrawlScope::SameDomain);
+        assert_eq!(suggestion.max_depth, 5);
+        assert_eq!(suggestion.max_pages, 100);
+
+        let with_sitemap = SiteProfile {
+            sitemap_urls: Some(1000),
+            subdomains: Vec::new(),
+            ..profile
+        };
+        let suggestion = with_sitemap.suggest();
+        assert_eq!(suggestion.scope, CrawlScope::SameHost);
+        assert_eq!(suggestion.max_pages, 1101);
+        assert_eq!(
+            suggestion.to_args(),
+            "--scope same-host --depth 5 --max-pages 1101"
+        );
+    }
+}
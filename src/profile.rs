@@ -0,0 +1,215 @@
+use crate::cli::CliArgs;
+use anyhow::{bail, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, Parser};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse the command line, filling in settings from the `--config` file if there is
+/// one. Exits with clap's usage error on bad arguments, like `CliArgs::parse`.
+pub fn parse_args() -> Result<CliArgs> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let Some(path) = config_path(&args) else {
+        return Ok(CliArgs::parse_from(args));
+    };
+    let settings = load_profile(&path)?;
+    let merged = merge_profile(&args, &settings)
+        .with_context(|| format!("Invalid config file {}", path.display()))?;
+    Ok(CliArgs::parse_from(merged))
+}
+
+/// The `--config` argument, found before clap runs since the file can supply the
+/// arguments clap requires.
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Read a crawl profile: a TOML file, or YAML (which covers JSON) for any other
+/// extension. Its top level maps option names to values.
+pub fn load_profile(path: &Path) -> Result<serde_json::Map<String, Value>> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let is_toml = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"));
+    let settings: Value = if is_toml {
+        toml::from_str(&data)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?
+    } else {
+        serde_yaml::from_str(&data)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?
+    };
+    match settings {
+        Value::Object(settings) => Ok(settings),
+        Value::Null => Ok(Default::default()),
+        _ => bail!("Config file {} is not a table of options", path.display()),
+    }
+}
+
+/// Arguments equivalent to the profile's settings followed by the command line's,
+/// leaving out settings the command line already gives so that its flags win.
+fn merge_profile(
+    args: &[OsString],
+    settings: &serde_json::Map<String, Value>,
+) -> Result<Vec<OsString>> {
+    let command = CliArgs::command();
+    // Required seeds may come from the file, so errors don't matter here
+    let given = CliArgs::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok();
+    let from_command_line: HashSet<&str> = command
+        .get_arguments()
+        .map(|arg| arg.get_id().as_str())
+        .filter(|id| {
+            given
+                .as_ref()
+                .is_some_and(|matches| matches.value_source(id) == Some(ValueSource::CommandLine))
+        })
+        .collect();
+
+    let mut merged = vec![args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "rustcrawler".into())];
+    for (key, value) in settings {
+        let name = key.replace('_', "-");
+        let Some(arg) = command.get_arguments().find(|arg| {
+            arg.get_long() == Some(name.as_str())
+                || arg.get_id().as_str() == key
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&name.as_str()))
+        }) else {
+            bail!("Unknown option `{}`", key);
+        };
+        let id = arg.get_id().as_str();
+        if id == "config" {
+            bail!("`config` can't be set from a config file");
+        }
+        if from_command_line.contains(id) {
+            continue;
+        }
+        let values = match value {
+            Value::Array(values) => values.iter().map(scalar).collect::<Result<Vec<_>>>(),
+            value => scalar(value).map(|value| vec![value]),
+        }
+        .with_context(|| format!("Invalid value for `{}`", key))?;
+        let Some(long) = arg.get_long() else {
+            // Positional seeds
+            merged.extend(values.into_iter().map(OsString::from));
+            continue;
+        };
+        match arg.get_action() {
+            ArgAction::SetTrue => {
+                if values.iter().any(|value| value == "true") {
+                    merged.push(format!("--{}", long).into());
+                }
+            }
+            ArgAction::Count => {
+                let count: usize = values[0]
+                    .parse()
+                    .with_context(|| format!("`{}` must be a number", key))?;
+                merged.extend((0..count).map(|_| OsString::from(format!("--{}", long))));
+            }
+            _ => merged.extend(
+                values
+                    .into_iter()
+                    .map(|value| OsString::from(format!("--{}={}", long, value))),
+            ),
+        }
+    }
+    merged.extend(args.iter().skip(1).cloned());
+    Ok(merged)
+}
+
+/// A setting's value as it would be typed on the command line.
+fn scalar(value: &Value) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Number(value) => Ok(value.to_string()),
+        _ => bail!("expected a string, number, boolean or list of them"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::urls::CrawlScope;
+
+    fn settings(toml: &str) -> serde_json::Map<String, Value> {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn parse(args: &[&str], profile: &str) -> Result<CliArgs> {
+        let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let merged = merge_profile(&args, &settings(profile))?;
+        Ok(CliArgs::try_parse_from(merged)?)
+    }
+
+    #[test]
+    fn test_profile_settings_and_overrides() {
+        let profile = r#"
+            urls = ["https://example.com/"]
+            depth = 3
+            max_pages = 100
+            scope = "same-domain"
+            include-assets = true
+            respect-robots = false
+            verbose = 2
+            extract = ["price=.price", "sku=[itemprop=sku]"]
+        "#;
+        let args = parse(&["rustcrawler", "--config", "crawl.toml"], profile).unwrap();
+        assert_eq!(args.urls, ["https://example.com/"]);
+        assert_eq!(args.depth, Some(3));
+        assert_eq!(args.max_pages, Some(100));
+        assert_eq!(args.scope, CrawlScope::SameDomain);
+        assert!(args.include_assets);
+        assert!(!args.get_respect_robots());
+        assert_eq!(args.verbose, 2);
+        assert_eq!(args.extract.len(), 2);
+
+        // Command-line flags and seeds replace the file's, lists included
+        let args = parse(
+            &[
+                "rustcrawler",
+                "--config",
+                "crawl.toml",
+                "--depth",
+                "1",
+                "--extract",
+                "title=h1",
+                "https://other.com/",
+            ],
+            profile,
+        )
+        .unwrap();
+        assert_eq!(args.urls, ["https://other.com/"]);
+        assert!(!args.get_respect_robots());
+        assert_eq!(args.depth, Some(1));
+        assert_eq!(args.max_pages, Some(100));
+        assert_eq!(args.extract, ["title=h1"]);
+
+        assert!(parse(&["rustcrawler"], "no_such_option = 1").is_err());
+        assert!(parse(&["rustcrawler"], "depth = { max = 3 }").is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use crate::crawler::{CrawlResult, Crawler};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+/// How often the bar redraws on its own, so the elapsed time and rate keep moving
+/// while no page completes.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Progress bar for `--progress`: pages crawled, queue size, rate and errors, with an
+/// ETA when `max_pages` gives the crawl a known length. Drawn in place on stderr and
+/// hidden when stderr isn't a terminal.
+pub struct CrawlProgress {
+    bar: ProgressBar,
+    /// Pages answered with a 4xx/5xx status.
+    error_pages: usize,
+}
+
+impl CrawlProgress {
+    pub fn new(max_pages: Option<usize>) -> Self {
+        let (bar, template) = match max_pages {
+            Some(max_pages) => (
+                ProgressBar::new(max_pages as u64),
+                "{spinner} [{elapsed_precise}] {wide_bar} {pos}/{len} pages, {per_sec}, ETA {eta} | {msg}",
+            ),
+            None => (
+                ProgressBar::no_length(),
+                "{spinner} [{elapsed_precise}] {pos} pages, {per_sec} | {msg}",
+            ),
+        };
+        bar.set_style(
+            ProgressStyle::with_template(template)
+                .expect("progress templates are valid")
+                .progress_chars("=> "),
+        );
+        bar.enable_steady_tick(TICK);
+        Self {
+            bar,
+            error_pages: 0,
+        }
+    }
+
+    /// Count a page the crawl yielded.
+    pub fn record(&mut self, result: &CrawlResult) {
+        if result.status_code >= 400 {
+            self.error_pages += 1;
+        }
+        self.bar.inc(1);
+    }
+
+    /// Refresh the queue size and error count from the crawler.
+    pub fn update(&self, crawler: &Crawler) {
+        self.bar.set_message(format!(
+            "{} queued, {} error(s)",
+            crawler.get_queued_count(),
+            self.error_pages + crawler.get_failure_count()
+        ));
+    }
+
+    pub fn finish(&self, crawler: &Crawler) {
+        self.update(crawler);
+        self.bar.finish();
+    }
+}
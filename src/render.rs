@@ -0,0 +1,385 @@
+#[cfg(feature = "render")]
+use anyhow::{anyhow, Context};
+use anyhow::{bail, Result};
+#[cfg(feature = "render")]
+use chromiumoxide::browser::{Browser, BrowserConfig};
+#[cfg(feature = "render")]
+use chromiumoxide::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent,
+};
+#[cfg(feature = "render")]
+use chromiumoxide::Page;
+#[cfg(feature = "render")]
+use futures::StreamExt;
+#[cfg(feature = "render")]
+use log::{debug, warn};
+use serde::Serialize;
+#[cfg(feature = "render")]
+use std::collections::HashSet;
+#[cfg(feature = "render")]
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+#[cfg(feature = "render")]
+use std::time::Instant;
+#[cfg(feature = "render")]
+use tokio::sync::Semaphore;
+#[cfg(feature = "render")]
+use tokio::task::JoinHandle;
+
+/// Pages rendered at once; further pages wait for a tab to close.
+#[cfg(feature = "render")]
+const MAX_OPEN_TABS: usize = 4;
+
+/// How long the network must be quiet for `RenderWait::NetworkIdle`.
+pub const NETWORK_IDLE_TIME: Duration = Duration::from_millis(500);
+
+/// How often selector and JavaScript conditions are checked.
+#[cfg(feature = "render")]
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a rendered page waits for after its load event before its DOM is read. A
+/// condition that doesn't hold within the page timeout is given up on, and the DOM is
+/// read as it is then.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderWait {
+    /// A fixed time for scripts to fill the page in
+    Delay(Duration),
+    /// No requests in flight for `NETWORK_IDLE_TIME`
+    NetworkIdle,
+    /// An element matching this CSS selector exists
+    Selector(String),
+    /// This JavaScript expression is truthy
+    Predicate(String),
+}
+
+impl Default for RenderWait {
+    fn default() -> Self {
+        RenderWait::Delay(Duration::from_millis(500))
+    }
+}
+
+#[cfg(feature = "render")]
+impl RenderWait {
+    /// The JavaScript checked for selector and predicate conditions.
+    fn expression(&self) -> Option<String> {
+        match self {
+            RenderWait::Delay(_) | RenderWait::NetworkIdle => None,
+            RenderWait::Selector(selector) => Some(format!(
+                "document.querySelector({}) !== null",
+                serde_json::Value::from(selector.as_str())
+            )),
+            RenderWait::Predicate(expression) => Some(format!("!!({})", expression)),
+        }
+    }
+}
+
+/// Parses `--render-wait`: milliseconds, `network-idle`, `selector:<css>` or
+/// `js:<expression>`.
+impl FromStr for RenderWait {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s == "network-idle" {
+            return Ok(RenderWait::NetworkIdle);
+        }
+        if let Some(selector) = s.strip_prefix("selector:") {
+            if selector.trim().is_empty() {
+                bail!("Render wait 'selector:' needs a CSS selector");
+            }
+            return Ok(RenderWait::Selector(selector.trim().to_string()));
+        }
+        if let Some(expression) = s.strip_prefix("js:") {
+            if expression.trim().is_empty() {
+                bail!("Render wait 'js:' needs a JavaScript expression");
+            }
+            return Ok(RenderWait::Predicate(expression.trim().to_string()));
+        }
+        match s.parse::<u64>() {
+            Ok(ms) => Ok(RenderWait::Delay(Duration::from_millis(ms))),
+            Err(_) => bail!(
+                "Invalid render wait '{}': expected milliseconds, network-idle, selector:<css> or js:<expression>",
+                s
+            ),
+        }
+    }
+}
+
+/// How to start the browser for `--render-js`.
+#[cfg(feature = "render")]
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions<'a> {
+    /// Chrome or Chromium binary; `None` looks for one in the usual places.
+    pub chrome_path: Option<&'a Path>,
+    /// Run without Chromium's sandbox, which it refuses to start as root without.
+    pub no_sandbox: bool,
+    pub user_agent: &'a str,
+    /// Limit on loading a page in the browser, and separately on waiting for `wait`.
+    pub timeout: Duration,
+    /// When a loaded page is done.
+    pub wait: RenderWait,
+}
+
+/// Headless Chromium driven over the DevTools protocol, for pages whose content only
+/// appears once their JavaScript has run.
+#[cfg(feature = "render")]
+pub struct Renderer {
+    browser: Browser,
+    /// Pumps the DevTools connection; the browser stops responding without it.
+    handler: JoinHandle<()>,
+    tabs: Semaphore,
+    timeout: Duration,
+    wait: RenderWait,
+}
+
+#[cfg(feature = "render")]
+impl Renderer {
+    pub async fn launch(options: RenderOptions<'_>) -> Result<Self> {
+        let mut builder = BrowserConfig::builder()
+            .request_timeout(options.timeout)
+            .arg(format!("--user-agent={}", options.user_agent));
+        if let Some(chrome_path) = options.chrome_path {
+            builder = builder.chrome_executable(chrome_path);
+        }
+        if options.no_sandbox {
+            builder = builder.no_sandbox();
+        }
+        let config = builder
+            .build()
+            .map_err(|e| anyhow!("Can't start headless Chromium: {}", e))?;
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .context("Failed to launch headless Chromium")?;
+        let handler = tokio::spawn(async move { while handler.next().await.is_some() {} });
+        Ok(Self {
+            browser,
+            handler,
+            tabs: Semaphore::new(MAX_OPEN_TABS),
+            timeout: options.timeout,
+            wait: options.wait,
+        })
+    }
+
+    /// Load `url` in a new tab and return its DOM as HTML, once the page has loaded and
+    /// its `wait` condition holds.
+    pub async fn render(&self, url: &str) -> Result<String> {
+        let _tab = self.tabs.acquire().await?;
+        let page = tokio::time::timeout(self.timeout, self.browser.new_page("about:blank"))
+            .await
+            .map_err(|_| anyhow!("Timed out rendering {}", url))?
+            .with_context(|| format!("Failed to render {}", url))?;
+        let rendered = async {
+            // Listening before navigating, so network idle sees every request of the page
+            let network = match self.wait {
+                RenderWait::NetworkIdle => Some(NetworkEvents::listen(&page).await?),
+                _ => None,
+            };
+            tokio::time::timeout(self.timeout, async {
+                page.goto(url).await?;
+                page.wait_for_navigation().await
+            })
+            .await
+            .map_err(|_| anyhow!("Timed out rendering {}", url))?
+            .with_context(|| format!("Failed to render {}", url))?;
+
+            let waited = tokio::time::timeout(self.timeout, async {
+                match (&self.wait, network) {
+                    (RenderWait::Delay(delay), _) => tokio::time::sleep(*delay).await,
+                    (_, Some(network)) => network.wait_for_idle().await,
+                    _ => self.wait_for_expression(&page).await,
+                }
+            })
+            .await;
+            if waited.is_err() {
+                warn!(
+                    "Gave up waiting for {:?} on {} after {}s; reading the page as it is",
+                    self.wait,
+                    url,
+                    self.timeout.as_secs()
+                );
+            }
+            page.content()
+                .await
+                .with_context(|| format!("Failed to read the rendered DOM of {}", url))
+        }
+        .await;
+        let _ = page.close().await;
+        rendered
+    }
+
+    /// Check the selector or predicate until it holds.
+    async fn wait_for_expression(&self, page: &Page) {
+        let Some(expression) = self.wait.expression() else {
+            return;
+        };
+        loop {
+            match page.evaluate(expression.as_str()).await {
+                Ok(result) => {
+                    if result.into_value::<bool>().unwrap_or(false) {
+                        return;
+                    }
+                }
+                // A predicate may throw until the page defines what it looks at
+                Err(e) => debug!("Render wait check failed: {}", e),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        // The browser process itself is killed when `browser` drops
+        self.handler.abort();
+    }
+}
+
+/// A page's network events, for telling when it has gone quiet.
+#[cfg(feature = "render")]
+struct NetworkEvents {
+    sent: chromiumoxide::listeners::EventStream<EventRequestWillBeSent>,
+    finished: chromiumoxide::listeners::EventStream<EventLoadingFinished>,
+    failed: chromiumoxide::listeners::EventStream<EventLoadingFailed>,
+}
+
+#[cfg(feature = "render")]
+impl NetworkEvents {
+    async fn listen(page: &Page) -> Result<Self> {
+        Ok(Self {
+            sent: page.event_listener().await?,
+            finished: page.event_listener().await?,
+            failed: page.event_listener().await?,
+        })
+    }
+
+    async fn wait_for_idle(mut self) {
+        let mut requests = InFlightRequests::new(Instant::now());
+        loop {
+            let quiet_at = requests.quiet_at(NETWORK_IDLE_TIME);
+            tokio::select! {
+                Some(event) = self.sent.next() => {
+                    requests.sent(event.request_id.inner(), Instant::now());
+                }
+                Some(event) = self.finished.next() => {
+                    requests.done(event.request_id.inner(), Instant::now());
+                }
+                Some(event) = self.failed.next() => {
+                    requests.done(event.request_id.inner(), Instant::now());
+                }
+                _ = tokio::time::sleep_until(quiet_at.unwrap_or_else(Instant::now).into()), if quiet_at.is_some() => {
+                    return;
+                }
+                else => return,
+            }
+        }
+    }
+}
+
+/// Requests a page has in flight, and when that last changed.
+#[cfg(feature = "render")]
+struct InFlightRequests {
+    ids: HashSet<String>,
+    changed: Instant,
+}
+
+#[cfg(feature = "render")]
+impl InFlightRequests {
+    fn new(now: Instant) -> Self {
+        Self {
+            ids: HashSet::new(),
+            changed: now,
+        }
+    }
+
+    /// A request started; redirects reuse the id of the request they continue.
+    fn sent(&mut self, id: &str, now: Instant) {
+        self.ids.insert(id.to_string());
+        self.changed = now;
+    }
+
+    fn done(&mut self, id: &str, now: Instant) {
+        if self.ids.remove(id) {
+            self.changed = now;
+        }
+    }
+
+    /// When the network will have been quiet for `idle`, unless a request starts
+    /// first; `None` while requests are in flight.
+    fn quiet_at(&self, idle: Duration) -> Option<Instant> {
+        self.ids.is_empty().then(|| self.changed + idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_wait_parses_each_condition() {
+        assert_eq!(
+            "1500".parse::<RenderWait>().unwrap(),
+            RenderWait::Delay(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            "network-idle".parse::<RenderWait>().unwrap(),
+            RenderWait::NetworkIdle
+        );
+        assert_eq!(
+            "selector:#app .item".parse::<RenderWait>().unwrap(),
+            RenderWait::Selector("#app .item".to_string())
+        );
+        assert_eq!(
+            "js: window.ready === true".parse::<RenderWait>().unwrap(),
+            RenderWait::Predicate("window.ready === true".to_string())
+        );
+        for invalid in ["soon", "selector:", "js: ", "-5"] {
+            assert!(invalid.parse::<RenderWait>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_render_wait_expressions() {
+        assert_eq!(RenderWait::NetworkIdle.expression(), None);
+        assert_eq!(RenderWait::default().expression(), None);
+        // The selector is quoted as a string literal, whatever it contains
+        assert_eq!(
+            RenderWait::Selector(r#"a[href="/x"]"#.to_string())
+                .expression()
+                .unwrap(),
+            r#"document.querySelector("a[href=\"/x\"]") !== null"#
+        );
+        assert_eq!(
+            RenderWait::Predicate("window.items.length > 2".to_string())
+                .expression()
+                .unwrap(),
+            "!!(window.items.length > 2)"
+        );
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_network_is_idle_once_requests_settle() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut requests = InFlightRequests::new(start);
+        assert_eq!(requests.quiet_at(NETWORK_IDLE_TIME), Some(at(500)));
+
+        requests.sent("1", at(100));
+        requests.sent("2", at(150));
+        // A redirect keeps its request's id
+        requests.sent("1", at(200));
+        assert_eq!(requests.quiet_at(NETWORK_IDLE_TIME), None);
+        requests.done("1", at(300));
+        assert_eq!(requests.quiet_at(NETWORK_IDLE_TIME), None);
+        requests.done("2", at(400));
+        assert_eq!(requests.quiet_at(NETWORK_IDLE_TIME), Some(at(900)));
+
+        // Events for requests sent before listening don't move the clock
+        requests.done("0", at(600));
+        assert_eq!(requests.quiet_at(NETWORK_IDLE_TIME), Some(at(900)));
+    }
+}
@@ -1,16 +1,334 @@
+use crate::decompress::response_text;
 use anyhow::{Context, Result};
 use dashmap::DashMap;
 use log::{debug, warn};
+use reqwest::header::{HeaderMap, CACHE_CONTROL, EXPIRES};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use url::Url;
 
+/// How long a robots.txt is cached before it is fetched again, unless its cache
+/// headers ask for less.
+pub const DEFAULT_ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shortest time a robots.txt is cached, even when its headers say not to cache it, so
+/// a busy host isn't asked for robots.txt before every page.
+const MIN_ROBOTS_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Longest time a 5xx answer or a failed fetch of robots.txt is cached, so a host that
+/// recovers isn't kept blocked for the whole TTL.
+const UNREACHABLE_ROBOTS_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RobotsFetchStatus {
+    NotFetched,
+    Available,
+    /// A 4xx answer: there are no rules, so everything is allowed.
+    NotFound(u16),
+    /// A 5xx answer: the site counts as unreachable and nothing is allowed until
+    /// robots.txt is fetched again (RFC 9309, section 2.3.1.4).
+    ServerError(u16),
+    /// robots.txt couldn't be fetched or read, e.g. on a DNS or connection error; like
+    /// a 5xx, nothing is allowed until it's fetched again.
+    Error(String),
+}
+
+/// A robots.txt parsed per RFC 9309: rules grouped by user-agent, with `*` wildcards,
+/// `$` end anchors and longest-match precedence between `Allow` and `Disallow`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotsRules {
+    groups: Vec<RobotsGroup>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct RobotsGroup {
+    /// Lowercased user-agent names from the group's `User-agent` lines.
+    agents: Vec<String>,
+    rules: Vec<RobotsRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RobotsRule {
+    allow: bool,
+    /// Path pattern, percent-encoding normalized.
+    pattern: String,
+}
+
+impl RobotsRules {
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        // Consecutive User-agent lines share one group; a rule closes the list
+        let mut collecting_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !collecting_agents {
+                        groups.push(RobotsGroup::default());
+                        collecting_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                directive @ ("allow" | "disallow") => {
+                    collecting_agents = false;
+                    // Rules before the first User-agent line belong to no group
+                    let Some(group) = groups.last_mut() else {
+                        continue;
+                    };
+                    // An empty Disallow allows everything, same as having no rule
+                    if !value.is_empty() {
+                        group.rules.push(RobotsRule {
+                            allow: directive == "allow",
+                            pattern: normalize_percent_encoding(value),
+                        });
+                    }
+                }
+                // Sitemap, Crawl-delay and unknown lines don't end a group
+                _ => {}
+            }
+        }
+
+        Self { groups }
+    }
+
+    /// Number of (allow, disallow) rules across all user-agent groups.
+    pub fn rule_counts(&self) -> (usize, usize) {
+        self.groups
+            .iter()
+            .flat_map(|group| &group.rules)
+            .fold((0, 0), |(allow, disallow), rule| {
+                if rule.allow {
+                    (allow + 1, disallow)
+                } else {
+                    (allow, disallow + 1)
+                }
+            })
+    }
+
+    /// Whether `user_agent` may fetch `url`. The most specific (longest) matching rule
+    /// wins, and `Allow` wins a tie.
+    pub fn is_allowed(&self, user_agent: &str, url: &Url) -> bool {
+        let mut path = normalize_percent_encoding(url.path());
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(&normalize_percent_encoding(query));
+        }
+        if path == "/robots.txt" {
+            return true;
+        }
+
+        let mut best: Option<&RobotsRule> = None;
+        for rule in self
+            .groups_for(user_agent)
+            .into_iter()
+            .flat_map(|group| &group.rules)
+        {
+            if !pattern_matches(&rule.pattern, &path) {
+                continue;
+            }
+            let more_specific = best.map_or(true, |best| {
+                rule.pattern.len() > best.pattern.len()
+                    || (rule.pattern.len() == best.pattern.len() && rule.allow && !best.allow)
+            });
+            if more_specific {
+                best = Some(rule);
+            }
+        }
+        best.map_or(true, |rule| rule.allow)
+    }
+
+    /// Every group naming one of `user_agent`'s product tokens, or the `*` groups when
+    /// none does. The caller combines their rules.
+    fn groups_for(&self, user_agent: &str) -> Vec<&RobotsGroup> {
+        // Product tokens are made of letters, `_` and `-`, e.g. `RustCrawler` in
+        // `Mozilla/5.0 (compatible; RustCrawler/0.1)`
+        let tokens: Vec<String> = user_agent
+            .split(|c: char| !(c.is_ascii_alphabetic() || c == '_' || c == '-'))
+            .filter(|token| !token.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect();
+
+        let named: Vec<&RobotsGroup> = self
+            .groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| tokens.contains(agent)))
+            .collect();
+        if !named.is_empty() {
+            return named;
+        }
+        self.groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+            .collect()
+    }
+}
+
+/// Match a robots.txt path pattern against a path: `*` matches any run of characters,
+/// a trailing `$` anchors the end, and otherwise the pattern only needs to match a prefix.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern.as_bytes(), true),
+        None => (pattern.as_bytes(), false),
+    };
+    let path = path.as_bytes();
+
+    let (mut p, mut s) = (0, 0);
+    // Position after the last `*` seen, and the path position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+    loop {
+        if p == pattern.len() {
+            if !anchored || s == path.len() {
+                return true;
+            }
+        } else if pattern[p] == b'*' {
+            star = Some((p + 1, s));
+            p += 1;
+            continue;
+        } else if s < path.len() && pattern[p] == path[s] {
+            p += 1;
+            s += 1;
+            continue;
+        }
+
+        // Mismatch: let the last `*` swallow one more character and retry
+        match star {
+            Some((after_star, matched)) if matched < path.len() => {
+                star = Some((after_star, matched + 1));
+                p = after_star;
+                s = matched + 1;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Normalize percent-encoding so patterns and paths compare octet for octet: non-ASCII
+/// characters are encoded, escapes of unreserved characters are decoded and hex digits
+/// are uppercased.
+fn normalize_percent_encoding(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let escape = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+        if let (b'%', Some(hex)) = (byte, escape) {
+            let hex = std::str::from_utf8(hex).unwrap_or_default();
+            let decoded = u8::from_str_radix(hex, 16).unwrap_or_default();
+            if decoded.is_ascii_alphanumeric() || b"-._~".contains(&decoded) {
+                out.push(decoded as char);
+            } else {
+                out.push('%');
+                out.push_str(&hex.to_ascii_uppercase());
+            }
+            i += 3;
+            continue;
+        }
+        if byte.is_ascii() {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Page-level indexing directives from `<meta name="robots">` and `X-Robots-Tag`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    pub noindex: bool,
+    pub nofollow: bool,
+}
+
+/// `X-Robots-Tag` directives that take a value after a colon, as opposed to a
+/// user-agent prefix like `googlebot: noindex`.
+const VALUED_DIRECTIVES: &[&str] = &[
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+impl RobotsDirectives {
+    /// Parse a comma-separated directive list, e.g. `noindex, nofollow` or `none`.
+    pub fn parse(content: &str) -> Self {
+        let mut directives = Self::default();
+        for directive in content.split(',') {
+            directives.apply(directive);
+        }
+        directives
+    }
+
+    /// Parse an `X-Robots-Tag` value. Directives after a user-agent prefix
+    /// (`otherbot: noindex`) only count when `user_agent` contains that name.
+    pub fn from_header(value: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_lowercase();
+        let mut directives = Self::default();
+        let mut applies = true;
+        for part in value.split(',') {
+            let mut directive = part;
+            if let Some((prefix, rest)) = part.split_once(':') {
+                let prefix = prefix.trim().to_lowercase();
+                if !VALUED_DIRECTIVES.contains(&prefix.as_str()) {
+                    applies = user_agent.contains(&prefix);
+                    directive = rest;
+                }
+            }
+            if applies {
+                directives.apply(directive);
+            }
+        }
+        directives
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            noindex: self.noindex || other.noindex,
+            nofollow: self.nofollow || other.nofollow,
+        }
+    }
+
+    fn apply(&mut self, directive: &str) {
+        match directive.trim().to_lowercase().as_str() {
+            "noindex" => self.noindex = true,
+            "nofollow" => self.nofollow = true,
+            "none" => {
+                self.noindex = true;
+                self.nofollow = true;
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RobotsInfo {
     pub content: Option<String>,
+    pub rules: RobotsRules,
     pub crawl_delay: Option<Duration>,
     pub last_accessed: Option<Instant>,
+    pub status: RobotsFetchStatus,
+    pub fetched_at: Option<Instant>,
+    /// When the entry goes stale and robots.txt is fetched again; `None` never expires.
+    pub expires_at: Option<SystemTime>,
 }
 
 impl Default for RobotsInfo {
@@ -23,8 +341,12 @@ impl RobotsInfo {
     pub fn new() -> Self {
         Self {
             content: None,
+            rules: RobotsRules::default(),
             crawl_delay: None,
             last_accessed: None,
+            status: RobotsFetchStatus::NotFetched,
+            fetched_at: None,
+            expires_at: None,
         }
     }
 
@@ -33,12 +355,61 @@ impl RobotsInfo {
         let crawl_delay = Self::parse_crawl_delay(&content);
 
         Self {
+            rules: RobotsRules::parse(&content),
             content: Some(content),
             crawl_delay,
             last_accessed: None,
+            status: RobotsFetchStatus::Available,
+            fetched_at: None,
+            expires_at: None,
         }
     }
 
+    fn with_status(status: RobotsFetchStatus) -> Self {
+        Self {
+            status,
+            ..Self::new()
+        }
+    }
+
+    /// Number of (allow, disallow) rules across all user-agent groups.
+    pub fn rule_counts(&self) -> (usize, usize) {
+        self.rules.rule_counts()
+    }
+
+    /// Sitemap URLs listed with `Sitemap:` lines; these apply regardless of user-agent group.
+    pub fn sitemaps(&self) -> Vec<String> {
+        let Some(content) = &self.content else {
+            return Vec::new();
+        };
+
+        content
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case("sitemap"))
+            .map(|(_, url)| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect()
+    }
+
+    /// Time since robots.txt was fetched for this domain.
+    pub fn age(&self) -> Option<Duration> {
+        self.fetched_at.map(|fetched_at| fetched_at.elapsed())
+    }
+
+    /// Time until the entry goes stale, zero once it has.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_at.map(|expires_at| {
+            expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO)
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_in() == Some(Duration::ZERO)
+    }
+
     fn parse_crawl_delay(content: &str) -> Option<Duration> {
         for line in content.lines() {
             let line = line.trim().to_lowercase();
@@ -54,79 +425,16 @@ impl RobotsInfo {
     }
 
     pub fn can_fetch(&self, user_agent: &str, url: &str) -> bool {
-        match &self.content {
-            Some(content) => {
-                // Simple robots.txt parsing
-                self.parse_robots_txt(content, user_agent, url)
-            }
-            None => true, // If we don't have robots.txt, allow crawling
+        if matches!(
+            self.status,
+            RobotsFetchStatus::ServerError(_) | RobotsFetchStatus::Error(_)
+        ) {
+            return false;
         }
-    }
-
-    fn parse_robots_txt(&self, content: &str, user_agent: &str, url: &str) -> bool {
-        let mut in_relevant_section = false;
-
-        for line in content.lines() {
-            let line = line.trim();
-
-            // Skip comments and empty lines
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // Check for User-agent directive
-            if line.to_lowercase().starts_with("user-agent:") {
-                if let Some(agent) = line.split(':').nth(1) {
-                    let current_user_agent = agent.trim().to_lowercase();
-                    in_relevant_section = current_user_agent == "*"
-                        || current_user_agent == user_agent.to_lowercase()
-                        || user_agent.to_lowercase().contains(&current_user_agent);
-                }
-                continue;
-            }
-
-            // Only process rules if we're in a relevant section
-            if !in_relevant_section {
-                continue;
-            }
-
-            // Check for Disallow directive
-            if line.to_lowercase().starts_with("disallow:") {
-                if let Some(path) = line.split(':').nth(1) {
-                    let path = path.trim();
-
-                    // Empty disallow means allow everything
-                    if path.is_empty() {
-                        continue;
-                    }
-
-                    // Check if URL matches the disallowed path
-                    if let Ok(parsed_url) = Url::parse(url) {
-                        let url_path = parsed_url.path();
-                        if path == "/" || url_path.starts_with(path) {
-                            return false; // URL is disallowed
-                        }
-                    }
-                }
-            }
-
-            // Check for Allow directive (takes precedence over Disallow)
-            if line.to_lowercase().starts_with("allow:") {
-                if let Some(path) = line.split(':').nth(1) {
-                    let path = path.trim();
-
-                    // Check if URL matches the allowed path
-                    if let Ok(parsed_url) = Url::parse(url) {
-                        let url_path = parsed_url.path();
-                        if path == "/" || url_path.starts_with(path) {
-                            return true; // URL is explicitly allowed
-                        }
-                    }
-                }
-            }
+        match Url::parse(url) {
+            Ok(url) => self.rules.is_allowed(user_agent, &url),
+            Err(_) => true,
         }
-
-        true // Allow by default
     }
 
     pub fn should_wait(&self) -> Option<Duration> {
@@ -147,11 +455,26 @@ impl RobotsInfo {
     }
 }
 
+/// A cache entry as written by `RobotsManager::save_cache`, with times in Unix seconds.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedRobots {
+    content: Option<String>,
+    status: RobotsFetchStatus,
+    fetched_at: u64,
+    expires_at: u64,
+}
+
 #[derive(Debug)]
 pub struct RobotsManager {
     client: Client,
     robots_cache: Arc<DashMap<String, RobotsInfo>>,
+    /// Held while robots.txt is fetched for a domain.
+    fetch_locks: DashMap<String, Arc<tokio::sync::Mutex<()>>>,
     user_agent: String,
+    /// Longest time a robots.txt is cached for.
+    ttl: Duration,
+    /// File the cache is loaded from and saved to between crawls.
+    cache_file: Option<PathBuf>,
 }
 
 impl RobotsManager {
@@ -159,13 +482,102 @@ impl RobotsManager {
         Self {
             client,
             robots_cache: Arc::new(DashMap::new()),
+            fetch_locks: DashMap::new(),
             user_agent,
+            ttl: DEFAULT_ROBOTS_TTL,
+            cache_file: None,
         }
     }
 
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Load the entries saved in `path` that are still fresh; `save_cache` writes the
+    /// cache back there. A missing file starts an empty cache.
+    pub fn with_cache_file(mut self, path: &Path) -> Result<Self> {
+        let data = match fs::read(path) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read robots.txt cache {}", path.display()))
+            }
+        };
+        if let Some(data) = data {
+            let saved: BTreeMap<String, SavedRobots> = serde_json::from_slice(&data)
+                .with_context(|| format!("Invalid robots.txt cache {}", path.display()))?;
+            let now = SystemTime::now();
+            for (domain, saved) in saved {
+                let expires_at = UNIX_EPOCH + Duration::from_secs(saved.expires_at);
+                if expires_at <= now {
+                    continue;
+                }
+                let mut info = match saved.content {
+                    Some(content) => RobotsInfo::with_content(content),
+                    None => RobotsInfo::with_status(saved.status),
+                };
+                let age = now
+                    .duration_since(UNIX_EPOCH + Duration::from_secs(saved.fetched_at))
+                    .unwrap_or(Duration::ZERO);
+                info.fetched_at = Instant::now().checked_sub(age);
+                info.expires_at = Some(expires_at);
+                self.robots_cache.insert(domain, info);
+            }
+            debug!(
+                "Loaded {} robots.txt cache entries from {}",
+                self.robots_cache.len(),
+                path.display()
+            );
+        }
+        self.cache_file = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Write the fresh cache entries to the cache file, if there is one. Written to a
+    /// temporary file first so a crash mid-write never leaves a torn cache.
+    pub fn save_cache(&self) -> Result<()> {
+        let Some(path) = &self.cache_file else {
+            return Ok(());
+        };
+        let now = SystemTime::now();
+        let unix_secs = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::ZERO)
+                .as_secs()
+        };
+        let saved: BTreeMap<String, SavedRobots> = self
+            .robots_cache
+            .iter()
+            .filter(|entry| entry.status != RobotsFetchStatus::NotFetched && !entry.is_expired())
+            .filter_map(|entry| {
+                let expires_at = entry.expires_at?;
+                let fetched_at = now - entry.age().unwrap_or(Duration::ZERO);
+                Some((
+                    entry.key().clone(),
+                    SavedRobots {
+                        content: entry.content.clone(),
+                        status: entry.status.clone(),
+                        fetched_at: unix_secs(fetched_at),
+                        expires_at: unix_secs(expires_at),
+                    },
+                ))
+            })
+            .collect();
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let data = serde_json::to_vec(&saved).context("Failed to serialize robots.txt cache")?;
+        fs::write(&tmp, data)
+            .with_context(|| format!("Failed to write robots.txt cache {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace robots.txt cache {}", path.display()))
+    }
+
     pub async fn check_robots_compliance(&self, url: &Url) -> Result<bool> {
-        let host = url.host_str().context("URL has no host")?;
-        let domain = format!("{}://{}", url.scheme(), host);
+        let domain = robots_domain(url)?;
 
         // Get or fetch robots.txt for this domain
         let robots_info = self.get_or_fetch_robots(&domain).await?;
@@ -180,9 +592,14 @@ impl RobotsManager {
         Ok(allowed)
     }
 
+    /// Crawl-delay from the cached robots.txt for `url`'s domain, if any.
+    pub fn crawl_delay(&self, url: &Url) -> Option<Duration> {
+        let domain = robots_domain(url).ok()?;
+        self.robots_cache.get(&domain)?.crawl_delay
+    }
+
     pub async fn should_delay(&self, url: &Url) -> Result<Option<Duration>> {
-        let host = url.host_str().context("URL has no host")?;
-        let domain = format!("{}://{}", url.scheme(), host);
+        let domain = robots_domain(url)?;
 
         if let Some(robots_info) = self.robots_cache.get_mut(&domain) {
             Ok(robots_info.should_wait())
@@ -192,8 +609,7 @@ impl RobotsManager {
     }
 
     pub async fn update_last_access(&self, url: &Url) -> Result<()> {
-        let host = url.host_str().context("URL has no host")?;
-        let domain = format!("{}://{}", url.scheme(), host);
+        let domain = robots_domain(url)?;
 
         if let Some(mut robots_info) = self.robots_cache.get_mut(&domain) {
             robots_info.update_last_accessed();
@@ -202,57 +618,168 @@ impl RobotsManager {
         Ok(())
     }
 
+    /// Snapshot of every cached robots.txt entry keyed by domain, sorted by domain.
+    pub fn cache_entries(&self) -> Vec<(String, RobotsInfo)> {
+        let mut entries: Vec<_> = self
+            .robots_cache
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
     async fn get_or_fetch_robots(&self, domain: &str) -> Result<RobotsInfo> {
         // Check cache first
-        if let Some(robots_info) = self.robots_cache.get(domain) {
-            return Ok(robots_info.clone());
+        if let Some(robots_info) = self.fresh_entry(domain) {
+            return Ok(robots_info);
         }
 
-        // Fetch robots.txt
-        let robots_info = self.fetch_robots(domain).await?;
+        // One fetch per domain at a time: pages of a new host queued together wait for
+        // the first one's fetch instead of each fetching robots.txt
+        let lock = self
+            .fetch_locks
+            .entry(domain.to_string())
+            .or_default()
+            .clone();
+        let _fetching = lock.lock().await;
+        if let Some(robots_info) = self.fresh_entry(domain) {
+            return Ok(robots_info);
+        }
+        let last_accessed = self
+            .robots_cache
+            .get(domain)
+            .and_then(|robots_info| robots_info.last_accessed);
+
+        // Fetch robots.txt, keeping the crawl-delay clock of a stale entry
+        let (mut robots_info, lifetime) = self.fetch_robots(domain).await?;
+        robots_info.fetched_at = Some(Instant::now());
+        robots_info.expires_at = Some(SystemTime::now() + lifetime);
+        robots_info.last_accessed = last_accessed;
         self.robots_cache
             .insert(domain.to_string(), robots_info.clone());
 
         Ok(robots_info)
     }
 
-    async fn fetch_robots(&self, domain: &str) -> Result<RobotsInfo> {
+    fn fresh_entry(&self, domain: &str) -> Option<RobotsInfo> {
+        self.robots_cache
+            .get(domain)
+            .filter(|robots_info| !robots_info.is_expired())
+            .map(|robots_info| robots_info.clone())
+    }
+
+    /// The robots.txt for `domain` and how long to cache it.
+    async fn fetch_robots(&self, domain: &str) -> Result<(RobotsInfo, Duration)> {
         let robots_url = format!("{}/robots.txt", domain);
         debug!("Fetching robots.txt from: {}", robots_url);
 
         match self.client.get(&robots_url).send().await {
             Ok(response) => {
+                let lifetime = cache_lifetime(response.headers(), self.ttl, SystemTime::now());
                 if response.status().is_success() {
-                    match response.text().await {
+                    match response_text(response).await {
                         Ok(content) => {
                             debug!("Successfully fetched robots.txt for {}", domain);
-                            Ok(RobotsInfo::with_content(content))
+                            Ok((RobotsInfo::with_content(content), lifetime))
                         }
                         Err(e) => {
-                            warn!("Error reading robots.txt content for {}: {}", domain, e);
-                            Ok(RobotsInfo::new())
+                            warn!(
+                                "Error reading robots.txt for {}: {}; not crawling the host until it's fetched again",
+                                domain, e
+                            );
+                            Ok((
+                                RobotsInfo::with_status(RobotsFetchStatus::Error(e.to_string())),
+                                lifetime.min(UNREACHABLE_ROBOTS_LIFETIME),
+                            ))
                         }
                     }
+                } else if response.status().is_server_error() {
+                    warn!(
+                        "robots.txt for {} answered {}; not crawling the host until it's fetched again",
+                        domain,
+                        response.status()
+                    );
+                    Ok((
+                        RobotsInfo::with_status(RobotsFetchStatus::ServerError(
+                            response.status().as_u16(),
+                        )),
+                        lifetime.min(UNREACHABLE_ROBOTS_LIFETIME),
+                    ))
                 } else {
                     debug!(
                         "robots.txt not found for {} (status: {})",
                         domain,
                         response.status()
                     );
-                    Ok(RobotsInfo::new()) // No robots.txt means crawling is allowed
+                    // No robots.txt means crawling is allowed
+                    Ok((
+                        RobotsInfo::with_status(RobotsFetchStatus::NotFound(
+                            response.status().as_u16(),
+                        )),
+                        lifetime,
+                    ))
                 }
             }
             Err(e) => {
-                warn!("Error fetching robots.txt for {}: {}", domain, e);
-                Ok(RobotsInfo::new()) // Allow crawling if fetch fails
+                warn!(
+                    "Error fetching robots.txt for {}: {}; not crawling the host until it's fetched again",
+                    domain, e
+                );
+                Ok((
+                    RobotsInfo::with_status(RobotsFetchStatus::Error(e.to_string())),
+                    self.ttl.min(UNREACHABLE_ROBOTS_LIFETIME),
+                ))
             }
         }
     }
 }
 
+/// How long to cache a robots.txt response: its `Cache-Control` `max-age` or `Expires`,
+/// at most `ttl` and at least `MIN_ROBOTS_LIFETIME`. `no-store` and `no-cache` get the
+/// minimum; without cache headers the entry lives for `ttl`.
+fn cache_lifetime(headers: &HeaderMap, ttl: Duration, now: SystemTime) -> Duration {
+    let cache_control = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase());
+    let mut lifetime = None;
+    for directive in cache_control {
+        if directive == "no-store" || directive == "no-cache" {
+            lifetime = Some(Duration::ZERO);
+            break;
+        }
+        if let Some(seconds) = directive.strip_prefix("max-age=") {
+            // An invalid max-age makes the response stale right away
+            let seconds = seconds.trim_matches('"').parse().unwrap_or(0);
+            lifetime = Some(Duration::from_secs(seconds));
+        }
+    }
+    let lifetime = lifetime.or_else(|| {
+        let expires = headers.get(EXPIRES)?.to_str().ok()?;
+        Some(match httpdate::parse_http_date(expires) {
+            Ok(at) => at.duration_since(now).unwrap_or(Duration::ZERO),
+            Err(_) => Duration::ZERO,
+        })
+    });
+    lifetime
+        .map_or(ttl, |lifetime| lifetime.min(ttl))
+        .max(MIN_ROBOTS_LIFETIME.min(ttl))
+}
+
+/// `scheme://host[:port]` that robots.txt applies to; the port is kept so sites on
+/// non-default ports get their own robots.txt.
+fn robots_domain(url: &Url) -> Result<String> {
+    url.host_str().context("URL has no host")?;
+    Ok(url.origin().ascii_serialization())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_server::{test_server, Response};
 
     #[tokio::test]
     async fn test_robots_info_creation() {
@@ -275,4 +802,265 @@ mod tests {
         assert_eq!(manager.user_agent, "test-agent");
         assert_eq!(manager.robots_cache.len(), 0);
     }
+
+    #[test]
+    fn test_robots_info_rule_counts() {
+        let info = RobotsInfo::with_content(
+            "User-agent: *\nDisallow: /private\nDisallow: /tmp\nAllow: /private/public\n"
+                .to_string(),
+        );
+        assert_eq!(info.status, RobotsFetchStatus::Available);
+        assert_eq!(info.rule_counts(), (1, 2));
+        assert_eq!(RobotsInfo::new().rule_counts(), (0, 0));
+    }
+
+    #[test]
+    fn test_robots_info_sitemaps() {
+        let info = RobotsInfo::with_content(
+            "Sitemap: https://example.com/sitemap.xml\nUser-agent: *\nDisallow: /tmp\nsitemap:https://example.com/news.xml\n"
+                .to_string(),
+        );
+        assert_eq!(
+            info.sitemaps(),
+            vec![
+                "https://example.com/sitemap.xml",
+                "https://example.com/news.xml"
+            ]
+        );
+    }
+
+    fn allowed(robots: &str, user_agent: &str, path: &str) -> bool {
+        let url = Url::parse(&format!("https://example.com{}", path)).unwrap();
+        RobotsRules::parse(robots).is_allowed(user_agent, &url)
+    }
+
+    #[test]
+    fn test_robots_rules_wildcards_and_anchors() {
+        let fish = "User-agent: *\nDisallow: /fish\n";
+        assert!(!allowed(fish, "bot", "/fish"));
+        assert!(!allowed(fish, "bot", "/fish.html"));
+        assert!(!allowed(fish, "bot", "/fishheads/yummy.html"));
+        assert!(allowed(fish, "bot", "/Fish.asp"));
+        assert!(allowed(fish, "bot", "/catfish"));
+
+        let php = "User-agent: *\nDisallow: /*.php$\nDisallow: /fish*.html\n";
+        assert!(!allowed(php, "bot", "/folder/filename.php"));
+        assert!(allowed(php, "bot", "/filename.php?parameters"));
+        assert!(allowed(php, "bot", "/filename.php/"));
+        assert!(!allowed(php, "bot", "/fishheads/catfish.html?x=1"));
+        assert!(allowed(php, "bot", "/fish.htm"));
+
+        // Non-ASCII patterns match the percent-encoded path
+        assert!(!allowed(
+            "User-agent: *\nDisallow: /café\n",
+            "bot",
+            "/caf%c3%a9/menu"
+        ));
+        // robots.txt itself is always allowed
+        assert!(allowed(
+            "User-agent: *\nDisallow: /\n",
+            "bot",
+            "/robots.txt"
+        ));
+    }
+
+    #[test]
+    fn test_robots_rules_longest_match_wins() {
+        let robots = "User-agent: *\nAllow: /p\nDisallow: /\nAllow: /folder\nDisallow: /folder\nAllow: /page\nDisallow: /*.htm\n";
+        assert!(allowed(robots, "bot", "/page"));
+        assert!(allowed(robots, "bot", "/folder/page"));
+        assert!(!allowed(robots, "bot", "/page.htm"));
+        assert!(!allowed(robots, "bot", "/other"));
+    }
+
+    #[test]
+    fn test_robots_rules_user_agent_groups() {
+        let robots = "\
+Disallow: /orphan
+
+User-agent: *
+Disallow: /
+
+User-agent: RustCrawler
+User-agent: otherbot
+Disallow: /private # comment
+Sitemap: https://example.com/sitemap.xml
+Allow: /private/public
+
+user-agent: rustcrawler
+disallow: /tmp
+";
+        let ua = "Mozilla/5.0 (compatible; RustCrawler/0.1.0)";
+        assert!(allowed(robots, ua, "/orphan"));
+        assert!(allowed(robots, ua, "/about"));
+        assert!(!allowed(robots, ua, "/private/x"));
+        assert!(allowed(robots, ua, "/private/public/x"));
+        // Both groups naming the crawler apply
+        assert!(!allowed(robots, ua, "/tmp/x"));
+        assert!(!allowed(robots, "OtherBot/2.0", "/private"));
+        assert!(allowed(robots, "OtherBot/2.0", "/tmp"));
+        // Anything else falls back to `*`
+        assert!(!allowed(robots, "somebot", "/about"));
+        assert_eq!(RobotsRules::parse(robots).rule_counts(), (1, 3));
+    }
+
+    #[test]
+    fn test_robots_directives() {
+        let meta = RobotsDirectives::parse("NOINDEX, follow");
+        assert!(meta.noindex && !meta.nofollow);
+        assert_eq!(
+            RobotsDirectives::parse("none"),
+            RobotsDirectives {
+                noindex: true,
+                nofollow: true
+            }
+        );
+
+        let header = "unavailable_after: 25 Jun 2030 15:00:00 PST, otherbot: noindex, nofollow";
+        assert_eq!(
+            RobotsDirectives::from_header(header, "rustcrawler/0.1.0"),
+            RobotsDirectives::default()
+        );
+        assert!(RobotsDirectives::from_header(header, "OtherBot/2.0").nofollow);
+        assert!(RobotsDirectives::from_header("nofollow", "rustcrawler/0.1.0").nofollow);
+    }
+
+    #[test]
+    fn test_cache_lifetime() {
+        let ttl = Duration::from_secs(3600);
+        // Whole seconds, as HTTP dates have no fraction
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let lifetime = |headers: &[(&'static str, &str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in headers {
+                map.append(*name, value.parse().unwrap());
+            }
+            cache_lifetime(&map, ttl, now).as_secs()
+        };
+        assert_eq!(lifetime(&[]), 3600);
+        assert_eq!(lifetime(&[("cache-control", "public, max-age=600")]), 600);
+        // Capped at the TTL, and never below the minimum
+        assert_eq!(lifetime(&[("cache-control", "max-age=86400")]), 3600);
+        assert_eq!(lifetime(&[("cache-control", "max-age=5")]), 60);
+        assert_eq!(lifetime(&[("cache-control", "no-cache")]), 60);
+        let expires = httpdate::fmt_http_date(now + Duration::from_secs(1200));
+        assert_eq!(lifetime(&[("expires", &expires)]), 1200);
+        // max-age wins over Expires
+        assert_eq!(
+            lifetime(&[("expires", &expires), ("cache-control", "max-age=300")]),
+            300
+        );
+        assert_eq!(lifetime(&[("expires", "0")]), 60);
+        // A TTL below the minimum still applies
+        assert_eq!(
+            cache_lifetime(&HeaderMap::new(), Duration::ZERO, now),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_robots_cache_ttl_and_file() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let addr = test_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Response::new("200 OK").body("User-agent: *\nDisallow: /private\n") }
+        })
+        .await;
+        let base = format!("http://{}", addr);
+        let private = Url::parse(&format!("{}/private", base)).unwrap();
+
+        // A zero TTL refetches on every check
+        let uncached =
+            RobotsManager::new(Client::new(), "test-agent".to_string()).with_ttl(Duration::ZERO);
+        assert!(!uncached.check_robots_compliance(&private).await.unwrap());
+        assert!(!uncached.check_robots_compliance(&private).await.unwrap());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        let path =
+            std::env::temp_dir().join(format!("rustcrawler-robots-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let manager = RobotsManager::new(Client::new(), "test-agent".to_string())
+            .with_cache_file(&path)
+            .unwrap();
+        assert!(!manager.check_robots_compliance(&private).await.unwrap());
+        assert!(!manager.check_robots_compliance(&private).await.unwrap());
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        manager.save_cache().unwrap();
+
+        // A later run answers from the file without fetching
+        let reloaded = RobotsManager::new(Client::new(), "test-agent".to_string())
+            .with_cache_file(&path)
+            .unwrap();
+        assert!(!reloaded.check_robots_compliance(&private).await.unwrap());
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        let (_, info) = &reloaded.cache_entries()[0];
+        assert_eq!(info.status, RobotsFetchStatus::Available);
+        assert!(info.expires_in().unwrap() > Duration::from_secs(86000));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_robots_server_and_network_errors_block_and_client_errors_allow() {
+        async fn answering(status: &'static str) -> String {
+            let addr = test_server(move |_| async move { Response::new(status) }).await;
+            format!("http://{}", addr)
+        }
+        let unavailable = answering("503 Service Unavailable").await;
+        let missing = answering("404 Not Found").await;
+        let unreachable = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let manager = RobotsManager::new(Client::new(), "test-agent".to_string());
+
+        let page = Url::parse(&format!("{}/page", unavailable)).unwrap();
+        assert!(!manager.check_robots_compliance(&page).await.unwrap());
+        let entry = manager.fresh_entry(&unavailable).unwrap();
+        assert_eq!(entry.status, RobotsFetchStatus::ServerError(503));
+        assert!(entry.expires_in().unwrap() <= UNREACHABLE_ROBOTS_LIFETIME);
+
+        // Nothing listens there any more, so the connection is refused
+        let page = Url::parse(&format!("{}/page", unreachable)).unwrap();
+        assert!(!manager.check_robots_compliance(&page).await.unwrap());
+        let entry = manager.fresh_entry(&unreachable).unwrap();
+        assert!(matches!(entry.status, RobotsFetchStatus::Error(_)));
+        assert!(entry.expires_in().unwrap() <= UNREACHABLE_ROBOTS_LIFETIME);
+
+        let page = Url::parse(&format!("{}/page", missing)).unwrap();
+        assert!(manager.check_robots_compliance(&page).await.unwrap());
+        let entry = manager.fresh_entry(&missing).unwrap();
+        assert_eq!(entry.status, RobotsFetchStatus::NotFound(404));
+        assert!(entry.expires_in().unwrap() > UNREACHABLE_ROBOTS_LIFETIME);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_checks_fetch_robots_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let addr = test_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async {
+                // Slow enough that every check arrives while the first fetch is out
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Response::new("200 OK").body("User-agent: *\nDisallow: /private\n")
+            }
+        })
+        .await;
+        let base = format!("http://{}", addr);
+
+        let manager = RobotsManager::new(Client::new(), "test-agent".to_string());
+        let urls: Vec<Url> = (0..20)
+            .map(|i| Url::parse(&format!("{}/page{}", base, i)).unwrap())
+            .collect();
+        let checks = urls.iter().map(|url| manager.check_robots_compliance(url));
+        for allowed in futures::future::join_all(checks).await {
+            assert!(allowed.unwrap());
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,219 @@
+use crate::decompress::response_text;
+use crate::robots::RobotsInfo;
+use anyhow::{Context, Result};
+use log::debug;
+use reqwest::Client;
+use std::collections::HashSet;
+use url::Url;
+
+/// Entries of a sitemap or sitemap index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    /// Page URLs from `<url><loc>` entries.
+    pub urls: Vec<String>,
+    /// Child sitemaps from `<sitemap><loc>` entries (sitemap indexes).
+    pub sitemaps: Vec<String>,
+}
+
+/// Pull `<loc>` entries out of a sitemap or sitemap index. This is a tolerant scan
+/// rather than a full XML parse, since real-world sitemaps are often slightly invalid.
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let mut sitemap = Sitemap::default();
+    let mut in_index_entry = false;
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = rest[..end].trim();
+        rest = &rest[end + 1..];
+
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        // Element name without attributes or a namespace prefix like `sm:`
+        let name = tag
+            .split_whitespace()
+            .next()
+            .and_then(|name| name.rsplit(':').next())
+            .unwrap_or_default();
+
+        match (closing, name) {
+            (_, "sitemap") => in_index_entry = !closing,
+            (false, "url") => in_index_entry = false,
+            (false, "loc") => {
+                let Some(close) = rest.find("</") else {
+                    break;
+                };
+                let loc = xml_unescape(rest[..close].trim());
+                if !loc.is_empty() {
+                    if in_index_entry {
+                        sitemap.sitemaps.push(loc);
+                    } else {
+                        sitemap.urls.push(loc);
+                    }
+                }
+                rest = &rest[close..];
+            }
+            _ => {}
+        }
+    }
+
+    sitemap
+}
+
+fn xml_unescape(text: &str) -> String {
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|inner| inner.strip_suffix("]]>"))
+        .unwrap_or(text);
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub async fn fetch_sitemap(client: &Client, url: &str) -> Result<Sitemap> {
+    debug!("Fetching sitemap {}", url);
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to fetch sitemap {}", url))?
+        .error_for_status()
+        .with_context(|| format!("Sitemap {} returned an error", url))?;
+    let body = response_text(response)
+        .await
+        .with_context(|| format!("Failed to read sitemap {}", url))?;
+    Ok(parse_sitemap(&body))
+}
+
+/// Sitemaps of the seeds' origins: those listed in robots.txt, or `/sitemap.xml` when
+/// robots.txt lists none.
+pub async fn find_sitemaps(client: &Client, seeds: &[String]) -> Vec<String> {
+    let mut roots = Vec::new();
+    for seed in seeds.iter().filter_map(|seed| Url::parse(seed).ok()) {
+        let origin = seed.origin().ascii_serialization();
+        let robots_url = format!("{}/robots.txt", origin);
+        let listed = match client.get(&robots_url).send().await {
+            Ok(response) if response.status().is_success() => response
+                .text()
+                .await
+                .map(|content| RobotsInfo::with_content(content).sitemaps())
+                .unwrap_or_default(),
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                debug!("Failed to fetch {}: {}", robots_url, e);
+                Vec::new()
+            }
+        };
+        if listed.is_empty() {
+            roots.push(format!("{}/sitemap.xml", origin));
+        } else {
+            roots.extend(listed);
+        }
+    }
+    roots.dedup();
+    roots
+}
+
+/// Page URLs reachable from `roots` in sitemap order, without duplicates, following
+/// sitemap indexes but fetching at most `max_fetches` sitemaps. Returns `None` if no
+/// sitemap could be read.
+pub async fn collect_urls(
+    client: &Client,
+    roots: &[String],
+    max_fetches: usize,
+) -> Option<Vec<String>> {
+    let mut queue: Vec<String> = roots.iter().rev().cloned().collect();
+    let mut seen_sitemaps = HashSet::new();
+    let mut seen_urls = HashSet::new();
+    let mut urls = None;
+
+    while let Some(url) = queue.pop() {
+        // Indexes listing each other, or themselves, would otherwise loop
+        if !seen_sitemaps.insert(url.clone()) {
+            continue;
+        }
+        if seen_sitemaps.len() > max_fetches {
+            debug!("Stopped reading sitemaps after {}", max_fetches);
+            break;
+        }
+        match fetch_sitemap(client, &url).await {
+            Ok(sitemap) => {
+                let urls = urls.get_or_insert_with(Vec::new);
+                for page in sitemap.urls {
+                    if seen_urls.insert(page.clone()) {
+                        urls.push(page);
+                    }
+                }
+                // Depth-first, children in listed order
+                queue.extend(sitemap.sitemaps.into_iter().rev());
+            }
+            Err(e) => debug!("{:#}", e),
+        }
+    }
+
+    urls
+}
+
+/// Count page URLs reachable from `roots`, following sitemap indexes but fetching at
+/// most `max_fetches` sitemaps. Returns `None` if no sitemap could be read.
+pub async fn count_urls(client: &Client, roots: &[String], max_fetches: usize) -> Option<usize> {
+    let mut queue: Vec<String> = roots.to_vec();
+    let mut fetched = 0;
+    let mut total = None;
+
+    while let Some(url) = queue.pop() {
+        if fetched == max_fetches {
+            debug!("Stopped counting sitemap URLs after {} sitemaps", fetched);
+            break;
+        }
+        fetched += 1;
+        match fetch_sitemap(client, &url).await {
+            Ok(sitemap) => {
+                *total.get_or_insert(0) += sitemap.urls.len();
+                queue.extend(sitemap.sitemaps);
+            }
+            Err(e) => debug!("{:#}", e),
+        }
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_urlset_and_index() {
+        let urlset = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://example.com/?a=1&amp;b=2</loc><lastmod>2024-01-01</lastmod></url>
+              <url><loc> https://example.com/about </loc></url>
+            </urlset>"#;
+        assert_eq!(
+            parse_sitemap(urlset).urls,
+            vec!["https://example.com/?a=1&b=2", "https://example.com/about"]
+        );
+
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <sitemap><loc>https://example.com/posts.xml</loc></sitemap>
+              <sitemap><loc><![CDATA[https://example.com/pages.xml]]></loc></sitemap>
+            </sitemapindex>"#;
+        let sitemap = parse_sitemap(index);
+        assert!(sitemap.urls.is_empty());
+        assert_eq!(
+            sitemap.sitemaps,
+            vec![
+                "https://example.com/posts.xml",
+                "https://example.com/pages.xml"
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::crawler::CrawlResult;
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Results of a crawl in crawl order, kept under a memory budget. Once the results held
+/// in memory pass `max_bytes`, the oldest are written to a temporary NDJSON file and
+/// only their summaries stay in memory; `into_results` reads them back for export.
+pub struct ResultStore {
+    /// Full results, except `..spilled`, which are summaries of results on disk.
+    results: Vec<CrawlResult>,
+    spilled: usize,
+    /// Approximate size of the full results still in memory.
+    bytes_in_memory: usize,
+    max_bytes: Option<usize>,
+    spill: Option<SpillFile>,
+}
+
+struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl ResultStore {
+    /// `None` keeps every result in memory.
+    pub fn new(max_bytes: Option<usize>) -> Self {
+        Self {
+            results: Vec::new(),
+            spilled: 0,
+            bytes_in_memory: 0,
+            max_bytes,
+            spill: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Results spilled to disk so far.
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+
+    pub fn push(&mut self, result: CrawlResult) -> Result<()> {
+        self.bytes_in_memory += approx_size(&result);
+        self.results.push(result);
+
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+        while self.bytes_in_memory > max_bytes && self.spilled < self.results.len() {
+            if self.spill.is_none() {
+                let path = std::env::temp_dir()
+                    .join(format!("rustcrawler-results-{}.ndjson", Uuid::new_v4()));
+                info!(
+                    "Results passed {} bytes in memory; spilling older results to {}",
+                    max_bytes,
+                    path.display()
+                );
+                let file = File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                self.spill = Some(SpillFile {
+                    path,
+                    writer: BufWriter::new(file),
+                });
+            }
+            let spill = self.spill.as_mut().expect("spill file was just created");
+            let result = &mut self.results[self.spilled];
+            serde_json::to_writer(&mut spill.writer, &*result)?;
+            writeln!(spill.writer)?;
+            self.bytes_in_memory -= approx_size(result);
+            summarize(result);
+            self.spilled += 1;
+        }
+        Ok(())
+    }
+
+    /// Every result in crawl order. Spilled results are summaries: their `links`,
+    /// `external_links`, `assets`, `html`, `text` and `response_headers` are empty;
+    /// everything else is intact.
+    pub fn summaries(&self) -> &[CrawlResult] {
+        &self.results
+    }
+
+    /// Every result, leaving spilled ones as summaries.
+    pub fn into_summaries(self) -> Vec<CrawlResult> {
+        self.results
+    }
+
+    /// Every result in full, reading spilled results back from disk.
+    pub fn into_results(mut self) -> Result<Vec<CrawlResult>> {
+        let Some(mut spill) = self.spill.take() else {
+            return Ok(self.results);
+        };
+        spill.writer.flush()?;
+        debug!("Reading {} spilled results back", self.spilled);
+        let file = File::open(&spill.path)
+            .with_context(|| format!("Failed to open {}", spill.path.display()))?;
+        for (slot, line) in self.results.iter_mut().zip(BufReader::new(file).lines()) {
+            *slot = serde_json::from_str(&line?)
+                .with_context(|| format!("Corrupt spill file {}", spill.path.display()))?;
+        }
+        Ok(self.results)
+    }
+}
+
+/// Drop the fields that make a result big; reports only need them at export time.
+fn summarize(result: &mut CrawlResult) {
+    result.links = Vec::new();
+    result.external_links = Vec::new();
+    result.assets = Vec::new();
+    result.html = None;
+    result.text = None;
+    result.response_headers = Default::default();
+}
+
+/// Rough heap footprint of a result, dominated by retained HTML and links.
+fn approx_size(result: &CrawlResult) -> usize {
+    let strings = result.url.len()
+        + result.final_url.len()
+        + result.run_id.len()
+        + result.title.as_ref().map_or(0, String::len)
+        + result.html.as_ref().map_or(0, String::len)
+        + result.content_type.as_ref().map_or(0, String::len)
+        + result.canonical.as_ref().map_or(0, String::len)
+        + result.display_url.as_ref().map_or(0, String::len)
+        + result.meta_description.as_ref().map_or(0, String::len)
+        + result.text.as_ref().map_or(0, String::len)
+        + result.content_hash.as_ref().map_or(0, String::len)
+        + result.simhash.as_ref().map_or(0, String::len)
+        + result.duplicate_of.as_ref().map_or(0, String::len);
+    let headings: usize = result
+        .h1
+        .iter()
+        .chain(&result.h2)
+        .map(|h| h.len() + 24)
+        .sum();
+    let open_graph: usize = result
+        .open_graph
+        .iter()
+        .map(|(name, value)| name.len() + value.len() + 48)
+        .sum();
+    // Structured data is arbitrary JSON; its serialized size is close enough
+    let metadata = result.metadata.as_ref().map_or(0, |metadata| {
+        serde_json::to_vec(metadata).map_or(0, |json| json.len() * 2)
+    });
+    let extracted: usize = result
+        .extracted
+        .iter()
+        .map(|(name, values)| name.len() + 48 + values.iter().map(|v| v.len() + 24).sum::<usize>())
+        .sum();
+    let links: usize = result
+        .links
+        .iter()
+        .chain(&result.external_links)
+        .chain(&result.mixed_content)
+        .chain(&result.insecure_links)
+        .map(|link| link.len() + 24)
+        .sum();
+    let assets: usize = result.assets.iter().map(|asset| asset.url.len() + 64).sum();
+    let redirects: usize = result
+        .redirect_chain
+        .iter()
+        .map(|hop| hop.url.len() + 32)
+        .sum();
+    let headers: usize = result
+        .response_headers
+        .iter()
+        .map(|(name, value)| name.len() + value.len() + 48)
+        .sum();
+    let hreflang: usize = result
+        .hreflang
+        .iter()
+        .map(|variant| variant.url.len() + variant.hreflang.len() + 48)
+        .sum();
+    std::mem::size_of::<CrawlResult>()
+        + strings
+        + links
+        + assets
+        + redirects
+        + headers
+        + hreflang
+        + headings
+        + open_graph
+        + metadata
+        + extracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result(i: usize) -> CrawlResult {
+        CrawlResult {
+            url: format!("https://example.com/{}", i),
+            status_code: 200,
+            title: Some(format!("Page {}", i)),
+            links: vec![format!("https://example.com/{}", i + 1)],
+            crawl_time: Duration::from_millis(i as u64),
+            html: Some("<p>x</p>".repeat(100)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_spills_oldest_results_and_merges_them_back() {
+        let mut store = ResultStore::new(Some(3 * approx_size(&result(0))));
+        for i in 0..10 {
+            store.push(result(i)).unwrap();
+        }
+        assert_eq!(store.len(), 10);
+        assert!(store.spilled() >= 7);
+        let summaries = store.summaries();
+        assert!(summaries[0].html.is_none() && summaries[0].links.is_empty());
+        assert_eq!(summaries[0].title.as_deref(), Some("Page 0"));
+        assert!(summaries[9].html.is_some());
+
+        let path = store.spill.as_ref().unwrap().path.clone();
+        let results = store.into_results().unwrap();
+        assert_eq!(results.len(), 10);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.url, format!("https://example.com/{}", i));
+            assert_eq!(result.links.len(), 1);
+            assert!(result.html.is_some());
+        }
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::crawler::CrawlResult;
+use crate::output::{CrawlReport, ResultSink};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Pages written between commits; a crash loses at most this many.
+const COMMIT_EVERY: usize = 100;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id TEXT PRIMARY KEY,
+        started_at_ms INTEGER,
+        finished_at_ms INTEGER,
+        pages INTEGER,
+        metadata TEXT
+    );
+    CREATE TABLE IF NOT EXISTS pages (
+        run_id TEXT NOT NULL,
+        url TEXT NOT NULL,
+        final_url TEXT,
+        status_code INTEGER NOT NULL,
+        title TEXT,
+        depth INTEGER NOT NULL,
+        crawl_time_ms INTEGER NOT NULL,
+        content_type TEXT,
+        content_length INTEGER,
+        language TEXT,
+        canonical TEXT,
+        category TEXT,
+        noindex INTEGER NOT NULL,
+        content_hash TEXT,
+        result TEXT NOT NULL,
+        PRIMARY KEY (run_id, url)
+    );
+    CREATE TABLE IF NOT EXISTS links (
+        run_id TEXT NOT NULL,
+        from_url TEXT NOT NULL,
+        to_url TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS links_from ON links (run_id, from_url);
+    CREATE INDEX IF NOT EXISTS links_to ON links (run_id, to_url);
+    CREATE INDEX IF NOT EXISTS pages_content_hash ON pages (content_hash);
+";
+
+/// Writes crawl results into a SQLite database as the crawl progresses (`--sqlite`):
+/// one `pages` row per result with the full result as JSON in `result`, one `links`
+/// row per outgoing link, and a `runs` row with the run metadata. Each run keeps its
+/// own rows, so one database can hold successive crawls of a site.
+pub struct SqliteSink {
+    conn: Connection,
+    /// Pages written since the last commit.
+    pending: usize,
+    runs: HashSet<String>,
+    closed: bool,
+}
+
+impl SqliteSink {
+    /// Open or create the database at `path`, adding any missing tables.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create SQLite tables")?;
+        conn.execute_batch("BEGIN")?;
+        Ok(Self {
+            conn,
+            pending: 0,
+            runs: HashSet::new(),
+            closed: false,
+        })
+    }
+
+    fn insert(&mut self, result: &CrawlResult) -> Result<()> {
+        if self.runs.insert(result.run_id.clone()) {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO runs (run_id, started_at_ms) VALUES (?1, ?2)",
+                params![result.run_id, unix_millis()],
+            )?;
+        }
+
+        // Resumed crawls yield saved results again; keep one copy of each
+        self.conn.execute(
+            "INSERT OR REPLACE INTO pages (run_id, url, final_url, status_code, title, depth,
+                 crawl_time_ms, content_type, content_length, language, canonical, category,
+                 noindex, content_hash, result)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                result.run_id,
+                result.url,
+                result.final_url,
+                result.status_code,
+                result.title,
+                result.depth as i64,
+                result.crawl_time.as_millis() as i64,
+                result.content_type,
+                result.content_length.map(|length| length as i64),
+                result.language,
+                result.canonical,
+                result.category,
+                result.noindex,
+                result.content_hash,
+                serde_json::to_string(result)?,
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM links WHERE run_id = ?1 AND from_url = ?2",
+            params![result.run_id, result.url],
+        )?;
+        let mut insert_link = self
+            .conn
+            .prepare_cached("INSERT INTO links (run_id, from_url, to_url) VALUES (?1, ?2, ?3)")?;
+        for link in &result.links {
+            insert_link.execute(params![result.run_id, result.url, link])?;
+        }
+        drop(insert_link);
+
+        self.pending += 1;
+        if self.pending >= COMMIT_EVERY {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        self.conn
+            .execute_batch("COMMIT; BEGIN")
+            .context("Failed to commit to SQLite database")?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ResultSink for SqliteSink {
+    async fn write(&mut self, result: &CrawlResult) -> Result<()> {
+        anyhow::ensure!(!self.closed, "SQLite database is already closed");
+        self.insert(result)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.closed || self.pending == 0 {
+            return Ok(());
+        }
+        self.commit()
+    }
+
+    async fn close(&mut self, report: &CrawlReport<'_>) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        if let Some(run) = &report.run {
+            self.conn.execute(
+                "INSERT INTO runs (run_id, started_at_ms, finished_at_ms, pages, metadata)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (run_id) DO UPDATE SET started_at_ms = excluded.started_at_ms,
+                     finished_at_ms = excluded.finished_at_ms, pages = excluded.pages,
+                     metadata = excluded.metadata",
+                params![
+                    run.run_id,
+                    run.started_at_ms as i64,
+                    unix_millis(),
+                    report.results.len() as i64,
+                    serde_json::to_string(run)?,
+                ],
+            )?;
+        }
+        self.conn
+            .execute_batch("COMMIT")
+            .context("Failed to commit to SQLite database")?;
+        self.closed = true;
+        Ok(())
+    }
+}
+
+/// Results of the most recently started run in the database at `path`.
+pub fn load_latest_run(path: &Path) -> Result<Vec<CrawlResult>> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+    let run_id: String = conn
+        .query_row(
+            "SELECT run_id FROM runs ORDER BY started_at_ms DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("No crawl runs in {}", path.display()))?;
+    let mut query = conn.prepare("SELECT result FROM pages WHERE run_id = ?1 ORDER BY rowid")?;
+    let rows = query.query_map(params![run_id], |row| row.get::<_, String>(0))?;
+    rows.map(|row| {
+        let row = row?;
+        serde_json::from_str(&row).with_context(|| format!("Corrupt result in run {}", run_id))
+    })
+    .collect()
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerConfig;
+    use crate::run::RunMetadata;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn result(run_id: &str, url: &str, links: &[&str]) -> CrawlResult {
+        CrawlResult {
+            url: url.to_string(),
+            status_code: 200,
+            title: Some("Title".to_string()),
+            links: links.iter().map(|link| link.to_string()).collect(),
+            crawl_time: Duration::from_millis(5),
+            run_id: run_id.to_string(),
+            final_url: url.to_string(),
+            content_type: Some("text/html".to_string()),
+            content_length: Some(42),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_links_and_run_are_stored() {
+        let path = std::env::temp_dir().join(format!("rustcrawler-{}.db", Uuid::new_v4()));
+        let run = RunMetadata::new(&CrawlerConfig::default());
+        let results = vec![
+            result(
+                &run.run_id,
+                "https://a.com/",
+                &["https://a.com/x", "https://b.com/"],
+            ),
+            result(&run.run_id, "https://a.com/x", &["https://a.com/"]),
+        ];
+
+        let mut sink = SqliteSink::open(&path).unwrap();
+        for result in &results {
+            sink.write(result).await.unwrap();
+        }
+        // A resumed crawl yields the first page again
+        sink.write(&results[0]).await.unwrap();
+        sink.close(&CrawlReport {
+            run: Some(run.clone()),
+            results: &results,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        drop(sink);
+
+        let conn = Connection::open(&path).unwrap();
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM pages"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM links"), 3);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM links WHERE to_url = 'https://a.com/'"),
+            1
+        );
+        let (pages, metadata): (i64, String) = conn
+            .query_row("SELECT pages, metadata FROM runs", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(pages, 2);
+        assert!(metadata.contains(&run.run_id));
+        drop(conn);
+        let latest = load_latest_run(&path).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert!(latest.iter().all(|page| page.run_id == run.run_id));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// How a URL's fetch ended after the retry loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryDisposition {
+    /// Succeeded after one or more retries.
+    Recovered,
+    /// Still returned a retryable status (5xx/429) after the last attempt.
+    GaveUp,
+    /// Every attempt failed at the network level.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryRecord {
+    pub url: String,
+    pub attempts: usize,
+    pub retries: usize,
+    pub backoff: Duration,
+    pub disposition: RetryDisposition,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetrySummary {
+    pub urls_retried: usize,
+    pub total_retries: usize,
+    pub total_backoff: Duration,
+    pub recovered: usize,
+    pub gave_up: usize,
+    pub failed: usize,
+    /// Hosts with the most retries, highest first.
+    pub top_hosts: Vec<(String, usize)>,
+}
+
+impl RetrySummary {
+    const TOP_HOSTS: usize = 5;
+
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a RetryRecord>) -> Self {
+        Self::from_records_by_host(records, str::to_string)
+    }
+
+    /// Like `from_records`, with hosts grouped under `host_key` (e.g. to merge aliases).
+    pub fn from_records_by_host<'a>(
+        records: impl IntoIterator<Item = &'a RetryRecord>,
+        host_key: impl Fn(&str) -> String,
+    ) -> Self {
+        let mut summary = Self::default();
+        let mut host_retries: HashMap<String, usize> = HashMap::new();
+
+        for record in records {
+            if record.retries > 0 {
+                summary.urls_retried += 1;
+            }
+            summary.total_retries += record.retries;
+            summary.total_backoff += record.backoff;
+            match record.disposition {
+                RetryDisposition::Recovered => summary.recovered += 1,
+                RetryDisposition::GaveUp => summary.gave_up += 1,
+                RetryDisposition::Failed => summary.failed += 1,
+            }
+
+            if let Some(host) = Url::parse(&record.url)
+                .ok()
+                .and_then(|u| u.host_str().map(&host_key))
+            {
+                *host_retries.entry(host).or_insert(0) += record.retries;
+            }
+        }
+
+        let mut top_hosts: Vec<_> = host_retries
+            .into_iter()
+            .filter(|(_, retries)| *retries > 0)
+            .collect();
+        top_hosts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_hosts.truncate(Self::TOP_HOSTS);
+        summary.top_hosts = top_hosts;
+
+        summary
+    }
+}
+
+/// A significant moment during a crawl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    CrawlStarted {
+        seeds: usize,
+    },
+    /// First 429/503 seen from a host.
+    HostThrottled {
+        host: String,
+        status: u16,
+    },
+    BudgetHalfReached {
+        pages: usize,
+        max_pages: usize,
+    },
+    DepthCompleted {
+        depth: usize,
+        pages: usize,
+    },
+    CrawlFinished {
+        pages: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    /// Wall-clock time in milliseconds since the Unix epoch.
+    pub timestamp_ms: u128,
+    /// Milliseconds since the timeline was created.
+    pub elapsed_ms: u128,
+    #[serde(flatten)]
+    pub kind: TimelineEventKind,
+}
+
+impl TimelineEvent {
+    pub fn format_output(&self) -> String {
+        let description = match &self.kind {
+            TimelineEventKind::CrawlStarted { seeds } => {
+                format!("crawl started with {} seed(s)", seeds)
+            }
+            TimelineEventKind::HostThrottled { host, status } => {
+                format!("host {} throttled (HTTP {})", host, status)
+            }
+            TimelineEventKind::BudgetHalfReached { pages, max_pages } => {
+                format!("page budget 50% reached ({}/{})", pages, max_pages)
+            }
+            TimelineEventKind::DepthCompleted { depth, pages } => {
+                format!("depth {} completed ({} pages so far)", depth, pages)
+            }
+            TimelineEventKind::CrawlFinished { pages } => {
+                format!("crawl finished ({} pages)", pages)
+            }
+        };
+        format!("+{}ms {}", self.elapsed_ms, description)
+    }
+}
+
+/// Append-only, thread-safe record of crawl events.
+#[derive(Debug)]
+pub struct Timeline {
+    started: Instant,
+    events: Mutex<Vec<TimelineEvent>>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn record(&self, kind: TimelineEventKind) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let event = TimelineEvent {
+            timestamp_ms,
+            elapsed_ms: self.started.elapsed().as_millis(),
+            kind,
+        };
+        self.events.lock().unwrap().push(event);
+    }
+
+    pub fn events(&self) -> Vec<TimelineEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(url: &str, retries: usize, disposition: RetryDisposition) -> RetryRecord {
+        RetryRecord {
+            url: url.to_string(),
+            attempts: retries + 1,
+            retries,
+            backoff: Duration::from_secs(retries as u64),
+            disposition,
+        }
+    }
+
+    #[test]
+    fn test_retry_summary_aggregates_by_host() {
+        let records = vec![
+            record("https://a.com/1", 2, RetryDisposition::Recovered),
+            record("https://a.com/2", 3, RetryDisposition::GaveUp),
+            record("https://b.com/", 1, RetryDisposition::Recovered),
+            record("https://c.com/", 0, RetryDisposition::Failed),
+        ];
+
+        let summary = RetrySummary::from_records(&records);
+        assert_eq!(summary.urls_retried, 3);
+        assert_eq!(summary.total_retries, 6);
+        assert_eq!(summary.total_backoff, Duration::from_secs(6));
+        assert_eq!(summary.recovered, 2);
+        assert_eq!(summary.gave_up, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(
+            summary.top_hosts,
+            vec![("a.com".to_string(), 5), ("b.com".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_timeline_records_in_order() {
+        let timeline = Timeline::new();
+        timeline.record(TimelineEventKind::CrawlStarted { seeds: 2 });
+        timeline.record(TimelineEventKind::CrawlFinished { pages: 10 });
+
+        let events = timeline.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, TimelineEventKind::CrawlStarted { seeds: 2 });
+        assert!(events[0].elapsed_ms <= events[1].elapsed_ms);
+        assert!(events[1]
+            .format_output()
+            .ends_with("crawl finished (10 pages)"));
+
+        let json = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(json["event"], "crawl_started");
+        assert_eq!(json["seeds"], 2);
+    }
+}
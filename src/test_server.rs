@@ -0,0 +1,154 @@
+//! A small HTTP server for tests, answering each request from a `routes` function.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// The parts of a request the routes look at.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub method: String,
+    /// The request target: a path, or a full URL when sent through a proxy.
+    pub path: String,
+    /// Header names are lowercase.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// What the server writes back. The connection is closed afterwards, and a
+/// `Content-Length` matching the body is added unless one was set.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    status: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    endless: bool,
+    hold: Option<Duration>,
+}
+
+impl Response {
+    /// An empty response with `status`, e.g. `"302 Found"`.
+    pub fn new(status: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            endless: false,
+            hold: None,
+        }
+    }
+
+    /// A 200 HTML page.
+    pub fn html(body: impl Into<Vec<u8>>) -> Self {
+        Self::new("200 OK")
+            .header("Content-Type", "text/html")
+            .body(body)
+    }
+
+    /// Add a header; a name can be given more than once.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Send no `Content-Length` and keep writing after the body until the client leaves.
+    pub fn endless(mut self) -> Self {
+        self.endless = true;
+        self
+    }
+
+    /// Keep the connection open for `duration` after writing, e.g. after a body shorter
+    /// than its `Content-Length`.
+    pub fn hold(mut self, duration: Duration) -> Self {
+        self.hold = Some(duration);
+        self
+    }
+
+    fn head(&self) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {}\r\n", self.status);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        let has_length = self
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-length"));
+        if !self.endless && !has_length {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        head.into_bytes()
+    }
+}
+
+/// Serve `routes` on a local port until the test ends, one connection per request,
+/// each handled in its own task so slow routes don't hold back the others.
+pub(crate) async fn test_server<F, Fut>(routes: F) -> SocketAddr
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let routes = std::sync::Arc::new(routes);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let routes = routes.clone();
+            tokio::spawn(async move {
+                let mut buffer = vec![0; 4096];
+                let n = socket.read(&mut buffer).await.unwrap_or(0);
+                let Some(request) = parse_request(&buffer[..n]) else {
+                    return;
+                };
+                let response = routes(request).await;
+                let mut data = response.head();
+                data.extend_from_slice(&response.body);
+                if socket.write_all(&data).await.is_err() {
+                    return;
+                }
+                if response.endless {
+                    let chunk = [b'x'; 1024];
+                    while socket.write_all(&chunk).await.is_ok() {}
+                }
+                if let Some(duration) = response.hold {
+                    tokio::time::sleep(duration).await;
+                }
+            });
+        }
+    });
+    addr
+}
+
+fn parse_request(data: &[u8]) -> Option<Request> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines();
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Some(Request {
+        method,
+        path,
+        headers,
+    })
+}
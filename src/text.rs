@@ -0,0 +1,132 @@
+//! Cleanup for short text pulled out of pages (titles and the like) so it fits on
+//! one line of CLI output or in a single export cell.
+
+/// Appended to text cut by `truncate_chars`.
+pub const TRUNCATION_MARKER: &str = "…";
+
+/// Collapse whitespace, decode leftover entities and cut to `max_chars` (0 = no limit).
+/// Returns `None` when nothing but whitespace remains.
+pub fn clean_title(raw: &str, max_chars: usize) -> Option<String> {
+    // The HTML parser already decodes entities once; decoding again catches
+    // double-escaped titles like `Q&amp;amp;A` that CMSes commonly emit.
+    let decoded = decode_entities(raw);
+    let collapsed = collapse_whitespace(&decoded);
+    if collapsed.is_empty() {
+        return None;
+    }
+    Some(truncate_chars(&collapsed, max_chars))
+}
+
+/// Replace every run of whitespace (including newlines and non-breaking spaces)
+/// with a single space and trim both ends.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Decode numeric character references and the common named entities.
+/// Unknown or malformed references are left as-is.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // Entity names are short; don't scan far for the terminating semicolon
+        let decoded = rest
+            .char_indices()
+            .take(12)
+            .find(|&(_, c)| c == ';')
+            .and_then(|(end, _)| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "middot" => '·',
+        "bull" => '•',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Cut `text` to at most `max_chars` characters, marker included (0 = no limit).
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if max_chars == 0 || text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let keep = max_chars.saturating_sub(TRUNCATION_MARKER.chars().count());
+    let mut truncated: String = text.chars().take(keep).collect();
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push_str(TRUNCATION_MARKER);
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title_collapses_and_decodes() {
+        assert_eq!(
+            clean_title("\n  Home\n\t Page &amp; More&#33; ", 0).as_deref(),
+            Some("Home Page & More!")
+        );
+        assert_eq!(clean_title(" \n\u{a0} ", 0), None);
+        assert_eq!(decode_entities("AT&T &bogus; &#x41;"), "AT&T &bogus; A");
+    }
+
+    #[test]
+    fn test_truncate_chars_adds_marker() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("a long title here", 8), "a long…");
+        assert_eq!(truncate_chars("ñandú ñandú", 6), "ñandú…");
+        assert_eq!(truncate_chars("anything", 0), "anything");
+    }
+}
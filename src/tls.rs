@@ -0,0 +1,153 @@
+#[cfg(feature = "tls-info")]
+use anyhow::{Context, Result};
+use serde::Serialize;
+#[cfg(feature = "tls-info")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "tls-info")]
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "tls-info")]
+use x509_parser::extensions::GeneralName;
+#[cfg(feature = "tls-info")]
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The certificate an HTTPS host presented, recorded with `--tls-info`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CertificateInfo {
+    pub host: String,
+    pub subject: String,
+    pub issuer: String,
+    /// Unix timestamps in seconds bounding the validity period.
+    pub not_before: i64,
+    pub not_after: i64,
+    /// DNS names and IP addresses from the subject alternative name extension.
+    pub sans: Vec<String>,
+    /// SHA-256 of the DER-encoded certificate, as lowercase hex.
+    pub sha256_fingerprint: String,
+    /// Expired, or expiring within the configured warning window, when recorded.
+    pub expiring_soon: bool,
+}
+
+impl CertificateInfo {
+    /// Parse the leaf certificate `host` presented, in DER form.
+    #[cfg(feature = "tls-info")]
+    pub fn from_der(host: &str, der: &[u8]) -> Result<Self> {
+        let (_, cert) = X509Certificate::from_der(der)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Invalid certificate from {}", host))?;
+
+        let mut sans = Vec::new();
+        if let Ok(Some(extension)) = cert.subject_alternative_name() {
+            for name in &extension.value.general_names {
+                match name {
+                    GeneralName::DNSName(dns) => sans.push(dns.to_string()),
+                    GeneralName::IPAddress(bytes) => {
+                        if let Some(ip) = ip_from_bytes(bytes) {
+                            sans.push(ip);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut sha256_fingerprint = String::with_capacity(64);
+        for byte in Sha256::digest(der) {
+            let _ = write!(sha256_fingerprint, "{:02x}", byte);
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            not_before: cert.validity().not_before.timestamp(),
+            not_after: cert.validity().not_after.timestamp(),
+            sans,
+            sha256_fingerprint,
+            expiring_soon: false,
+        })
+    }
+
+    /// Whole days left until the certificate expires; negative once it has.
+    pub fn days_until_expiry(&self, now: SystemTime) -> i64 {
+        (self.not_after - unix_seconds(now)).div_euclid(SECONDS_PER_DAY)
+    }
+
+    /// Whether the certificate has expired or will within `days`.
+    pub fn expires_within(&self, days: u64, now: SystemTime) -> bool {
+        self.days_until_expiry(now) < days as i64
+    }
+
+    pub fn format_output(&self, now: SystemTime) -> String {
+        let days = self.days_until_expiry(now);
+        let expiry = if days < 0 {
+            format!("expired {} day(s) ago", -days)
+        } else {
+            format!("expires in {} day(s)", days)
+        };
+        format!("{}: {} (issuer: {})", self.host, expiry, self.issuer)
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+#[cfg(feature = "tls-info")]
+fn ip_from_bytes(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn certificate(not_after: i64) -> CertificateInfo {
+        CertificateInfo {
+            host: "example.com".to_string(),
+            subject: "CN=example.com".to_string(),
+            issuer: "CN=Test CA".to_string(),
+            not_before: 0,
+            not_after,
+            sans: vec!["example.com".to_string()],
+            sha256_fingerprint: String::new(),
+            expiring_soon: false,
+        }
+    }
+
+    #[test]
+    fn test_expiry_windows() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000 * 86_400);
+        let soon = certificate(1_010 * 86_400 + 60);
+        assert_eq!(soon.days_until_expiry(now), 10);
+        assert!(soon.expires_within(30, now));
+        assert!(!soon.expires_within(10, now));
+        assert_eq!(
+            soon.format_output(now),
+            "example.com: expires in 10 day(s) (issuer: CN=Test CA)"
+        );
+
+        let expired = certificate(998 * 86_400);
+        assert_eq!(expired.days_until_expiry(now), -2);
+        assert!(expired.expires_within(0, now));
+    }
+
+    #[cfg(feature = "tls-info")]
+    #[test]
+    fn test_from_der_rejects_garbage() {
+        assert!(CertificateInfo::from_der("example.com", b"not a certificate").is_err());
+    }
+}
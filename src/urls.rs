@@ -0,0 +1,482 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use url::{Host, Position, Url};
+
+/// Where a URL sits relative to a seed URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UrlScope {
+    SameHost,
+    Subdomain,
+    External,
+}
+
+impl UrlScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UrlScope::SameHost => "same_host",
+            UrlScope::Subdomain => "subdomain",
+            UrlScope::External => "external",
+        }
+    }
+}
+
+/// `https://` form of a seed typed without a scheme, like `example.com/path` or
+/// `localhost:8080`. `None` when `input` has a scheme or doesn't start with a host.
+pub fn seed_without_scheme(input: &str) -> Option<Url> {
+    if input.contains("://") {
+        return None;
+    }
+    let url = Url::parse(&format!("https://{}", input)).ok()?;
+    let looks_like_host = match url.host()? {
+        Host::Domain(domain) => domain.contains('.') || domain == "localhost",
+        Host::Ipv4(_) | Host::Ipv6(_) => true,
+    };
+    (looks_like_host && url.username().is_empty()).then_some(url)
+}
+
+/// ASCII (punycode) form of a host as typed by a user, e.g. `Bücher.de` becomes
+/// `xn--bcher-kva.de`, matching what `Url::parse` produces for URLs. Hosts that
+/// aren't valid IDNs are only lowercased.
+pub fn ascii_host(host: &str) -> String {
+    match Host::parse(host) {
+        Ok(Host::Domain(domain)) => domain,
+        _ => host.to_lowercase(),
+    }
+}
+
+/// `url` with its host in Unicode for display, e.g. `https://bücher.de/` for
+/// `https://xn--bcher-kva.de/`. Labels that mix Latin, Greek or Cyrillic letters stay
+/// in punycode, as browsers do, so lookalike hosts can't pass for the real one.
+/// `None` when the display form is the same as `url`.
+pub fn display_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return None;
+    }
+    let labels: Vec<String> = host
+        .split('.')
+        .map(|label| {
+            let (unicode, result) = idna::domain_to_unicode(label);
+            if result.is_ok() && !mixes_scripts(&unicode) {
+                unicode
+            } else {
+                label.to_string()
+            }
+        })
+        .collect();
+    let display_host = labels.join(".");
+    if display_host == host {
+        return None;
+    }
+    // Swap the host in the serialized URL; `Url` itself only holds ASCII hosts
+    Some(format!(
+        "{}{}{}",
+        &parsed[..Position::BeforeHost],
+        display_host,
+        &parsed[Position::AfterHost..]
+    ))
+}
+
+/// Whether a label mixes letters of Latin, Greek and Cyrillic, the scripts whose
+/// lookalikes are used for spoofing. Latin alongside CJK scripts is normal.
+fn mixes_scripts(label: &str) -> bool {
+    let mut scripts = [false; 3];
+    for c in label.chars() {
+        match c {
+            'a'..='z' | '\u{00c0}'..='\u{024f}' => scripts[0] = true,
+            '\u{0370}'..='\u{03ff}' => scripts[1] = true,
+            '\u{0400}'..='\u{04ff}' => scripts[2] = true,
+            _ => {}
+        }
+    }
+    scripts.iter().filter(|&&seen| seen).count() > 1
+}
+
+/// Knobs that change what counts as "the same URL".
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOptions {
+    /// Keep `#/route` and `#!route` fragments used by client-side routers.
+    pub spa_fragments: bool,
+}
+
+/// Canonical form used for deduplication: drops the fragment and an empty query.
+/// Scheme/host lowercasing and default port removal are already done by `Url::parse`.
+pub fn normalize_url(url: &Url) -> Url {
+    normalize_url_with(url, &NormalizeOptions::default())
+}
+
+pub fn normalize_url_with(url: &Url, options: &NormalizeOptions) -> Url {
+    let mut normalized = url.clone();
+    let keep_fragment = options.spa_fragments && url.fragment().is_some_and(is_spa_route);
+    if !keep_fragment {
+        normalized.set_fragment(None);
+    }
+    if normalized.query() == Some("") {
+        normalized.set_query(None);
+    }
+    normalized
+}
+
+/// Lowercase the path of `url` in place, for hosts that serve paths case-insensitively.
+pub fn lowercase_path(url: &mut Url) {
+    let lowered = url.path().to_lowercase();
+    if lowered != url.path() {
+        url.set_path(&lowered);
+    }
+}
+
+/// Fragment routes ("#/about", "#!/about") as opposed to in-page anchors ("#section").
+pub fn is_spa_route(fragment: &str) -> bool {
+    fragment.starts_with('/') || fragment.starts_with('!')
+}
+
+/// Classify `url` relative to `seed` by host.
+pub fn classify_scope(url: &Url, seed: &Url) -> UrlScope {
+    classify_scope_with(url, seed, &HostAliases::default())
+}
+
+/// Classify `url` relative to `seed`, treating aliased hosts as the same site.
+pub fn classify_scope_with(url: &Url, seed: &Url, aliases: &HostAliases) -> UrlScope {
+    let (Some(host), Some(seed_host)) = (url.host_str(), seed.host_str()) else {
+        return UrlScope::External;
+    };
+    let host = aliases.canonical(host);
+    let seed_host = aliases.canonical(seed_host);
+
+    if host == seed_host {
+        UrlScope::SameHost
+    } else if host.ends_with(&format!(".{}", seed_host)) {
+        UrlScope::Subdomain
+    } else {
+        UrlScope::External
+    }
+}
+
+/// Hosts that serve the same site (e.g. `www.example.com` and `example.com`),
+/// mapped to one canonical name for scoping, per-host limits and reporting.
+#[derive(Debug, Default)]
+pub struct HostAliases {
+    aliases: DashMap<String, String>,
+    /// Treat `www.<host>` as `<host>` without listing every pair.
+    www_apex: bool,
+}
+
+impl HostAliases {
+    /// Each group lists hosts of one site; the first is the canonical name.
+    pub fn new(groups: &[Vec<String>], www_apex: bool) -> Self {
+        let aliases = Self {
+            aliases: DashMap::new(),
+            www_apex,
+        };
+        for group in groups {
+            if let Some((canonical, rest)) = group.split_first() {
+                for alias in rest {
+                    aliases.add(alias, canonical);
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Record that `alias` serves the same site as `canonical`.
+    /// Returns false if it was already known (or would create a cycle).
+    pub fn add(&self, alias: &str, canonical: &str) -> bool {
+        let alias = ascii_host(alias);
+        let canonical = self.canonical(canonical);
+        if alias == canonical || self.canonical(&alias) == canonical {
+            return false;
+        }
+        self.aliases.insert(alias, canonical);
+        true
+    }
+
+    pub fn canonical(&self, host: &str) -> String {
+        let mut host = ascii_host(host);
+        if self.www_apex {
+            if let Some(apex) = host.strip_prefix("www.") {
+                host = apex.to_string();
+            }
+        }
+        // Follow a short chain in case an alias was added for a host that was itself aliased
+        for _ in 0..4 {
+            match self.aliases.get(&host) {
+                Some(target) if *target != host => host = target.clone(),
+                _ => break,
+            }
+        }
+        host
+    }
+
+    pub fn same_site(&self, a: &str, b: &str) -> bool {
+        self.canonical(a) == self.canonical(b)
+    }
+
+    /// Explicit and detected aliases as sorted `(alias, canonical)` pairs.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<_> = self
+            .aliases
+            .iter()
+            .map(|entry| (entry.key().clone(), self.canonical(entry.value())))
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+/// Which discovered links a crawl follows, relative to its seed URLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlScope {
+    /// Follow every HTTP(S) link
+    #[default]
+    All,
+    /// Only the seed hosts (and their aliases)
+    SameHost,
+    /// Anything under the seeds' registrable domains, e.g. blog.example.com for example.com
+    SameDomain,
+}
+
+/// Second-level labels that ccTLDs commonly register under ("example.co.uk").
+const COMMON_SECOND_LEVEL: &[&str] = &["ac", "co", "com", "edu", "gov", "net", "org", "ne", "or"];
+
+/// Approximate registrable domain ("eTLD+1") without a public suffix list: the last
+/// two labels, or three for `co.uk`-style ccTLD suffixes. IP addresses are returned as-is.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && COMMON_SECOND_LEVEL.contains(second) => 3,
+        _ => 2,
+    };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+/// Decides whether a discovered URL is within the crawl's `CrawlScope`.
+#[derive(Debug)]
+pub struct ScopeFilter {
+    scope: CrawlScope,
+    allowed: HashSet<String>,
+    aliases: Arc<HostAliases>,
+}
+
+impl ScopeFilter {
+    pub fn new(scope: CrawlScope, seeds: &[String], aliases: Arc<HostAliases>) -> Self {
+        let mut filter = Self {
+            scope,
+            allowed: HashSet::new(),
+            aliases,
+        };
+        filter.allowed = seeds
+            .iter()
+            .filter_map(|seed| Url::parse(seed).ok())
+            .filter_map(|seed| seed.host_str().map(|host| filter.key(host)))
+            .collect();
+        filter
+    }
+
+    fn key(&self, host: &str) -> String {
+        let host = self.aliases.canonical(host);
+        match self.scope {
+            CrawlScope::SameDomain => registrable_domain(&host),
+            _ => host,
+        }
+    }
+
+    pub fn allows(&self, url: &str) -> bool {
+        self.scope == CrawlScope::All || self.is_internal(url)
+    }
+
+    /// Whether `url` belongs to the seeds' site: one of their hosts (or aliases), or
+    /// with `CrawlScope::SameDomain` anything under their registrable domains.
+    pub fn is_internal(&self, url: &str) -> bool {
+        Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| self.key(host)))
+            .is_some_and(|key| self.allowed.contains(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url_strips_fragment_and_empty_query() {
+        let url = Url::parse("HTTPS://Example.com:443/a?#top").unwrap();
+        assert_eq!(normalize_url(&url).as_str(), "https://example.com/a");
+
+        let url = Url::parse("https://example.com/a?b=1#top").unwrap();
+        assert_eq!(normalize_url(&url).as_str(), "https://example.com/a?b=1");
+    }
+
+    #[test]
+    fn test_seed_without_scheme() {
+        let seed = |input: &str| seed_without_scheme(input).map(|url| url.to_string());
+        assert_eq!(
+            seed("example.com/path").as_deref(),
+            Some("https://example.com/path")
+        );
+        assert_eq!(
+            seed("localhost:8080").as_deref(),
+            Some("https://localhost:8080/")
+        );
+        assert_eq!(seed("127.0.0.1/a").as_deref(), Some("https://127.0.0.1/a"));
+        assert_eq!(seed("https://example.com/"), None);
+        assert_eq!(seed("not-a-valid-url"), None);
+        assert_eq!(seed("mailto:someone@example.com"), None);
+    }
+
+    #[test]
+    fn test_normalize_url_keeps_spa_routes_when_enabled() {
+        let options = NormalizeOptions {
+            spa_fragments: true,
+        };
+
+        let route = Url::parse("https://example.com/#/products/1").unwrap();
+        assert_eq!(
+            normalize_url_with(&route, &options).as_str(),
+            "https://example.com/#/products/1"
+        );
+        assert_eq!(normalize_url(&route).as_str(), "https://example.com/");
+
+        let hashbang = Url::parse("https://example.com/#!/about").unwrap();
+        assert_eq!(
+            normalize_url_with(&hashbang, &options).as_str(),
+            "https://example.com/#!/about"
+        );
+
+        let anchor = Url::parse("https://example.com/page#section").unwrap();
+        assert_eq!(
+            normalize_url_with(&anchor, &options).as_str(),
+            "https://example.com/page"
+        );
+    }
+
+    #[test]
+    fn test_lowercase_path_leaves_query_alone() {
+        let mut url = Url::parse("https://Example.com/About/Team?Ref=Home").unwrap();
+        lowercase_path(&mut url);
+        assert_eq!(url.as_str(), "https://example.com/about/team?Ref=Home");
+    }
+
+    #[test]
+    fn test_classify_scope() {
+        let seed = Url::parse("https://example.com/").unwrap();
+        let same = Url::parse("https://example.com/about").unwrap();
+        let sub = Url::parse("https://blog.example.com/").unwrap();
+        let external = Url::parse("https://notexample.com/").unwrap();
+
+        assert_eq!(classify_scope(&same, &seed), UrlScope::SameHost);
+        assert_eq!(classify_scope(&sub, &seed), UrlScope::Subdomain);
+        assert_eq!(classify_scope(&external, &seed), UrlScope::External);
+    }
+
+    #[test]
+    fn test_host_aliases() {
+        let aliases = HostAliases::new(
+            &[vec!["example.com".to_string(), "Example.net".to_string()]],
+            true,
+        );
+        assert_eq!(aliases.canonical("www.example.com"), "example.com");
+        assert_eq!(aliases.canonical("example.net"), "example.com");
+        assert!(aliases.same_site("WWW.Example.com", "example.net"));
+        assert!(!aliases.same_site("blog.example.com", "example.com"));
+
+        // Aliases are detected at runtime; a reverse mapping would be a cycle
+        assert!(aliases.add("example.org", "www.example.com"));
+        assert!(!aliases.add("example.com", "example.org"));
+        assert_eq!(aliases.canonical("example.org"), "example.com");
+
+        let seed = Url::parse("https://example.com/").unwrap();
+        let www = Url::parse("https://www.example.com/about").unwrap();
+        assert_eq!(classify_scope(&www, &seed), UrlScope::Subdomain);
+        assert_eq!(
+            classify_scope_with(&www, &seed, &aliases),
+            UrlScope::SameHost
+        );
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("blog.example.com"), "example.com");
+        assert_eq!(registrable_domain("www.bbc.co.uk"), "bbc.co.uk");
+        assert_eq!(registrable_domain("example.com."), "example.com");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("127.0.0.1"), "127.0.0.1");
+    }
+
+    #[test]
+    fn test_scope_filter() {
+        let seeds = vec!["https://www.example.com/blog".to_string()];
+        let aliases = Arc::new(HostAliases::default());
+
+        let same_host = ScopeFilter::new(CrawlScope::SameHost, &seeds, aliases.clone());
+        assert!(same_host.allows("http://www.example.com/about"));
+        assert!(!same_host.allows("https://cdn.example.com/app.js"));
+
+        let same_domain = ScopeFilter::new(CrawlScope::SameDomain, &seeds, aliases.clone());
+        assert!(same_domain.allows("https://cdn.example.com/app.js"));
+        assert!(!same_domain.allows("https://twitter.com/example"));
+
+        let all = ScopeFilter::new(CrawlScope::All, &seeds, aliases);
+        assert!(all.allows("https://twitter.com/example"));
+        // Following everything doesn't make everything internal
+        assert!(!all.is_internal("https://twitter.com/example"));
+        assert!(!all.is_internal("https://cdn.example.com/app.js"));
+        assert!(all.is_internal("https://www.example.com/"));
+    }
+
+    #[test]
+    fn test_idn_hosts_fetch_as_punycode() {
+        // Case folding and ideographic full stops map to the same ASCII host
+        for input in [
+            "https://bücher.de/a",
+            "https://BÜCHER.DE/a",
+            "https://bücher\u{3002}de/a",
+        ] {
+            let url = normalize_url(&Url::parse(input).unwrap());
+            assert_eq!(url.as_str(), "https://xn--bcher-kva.de/a");
+        }
+        assert_eq!(ascii_host("Bücher.de"), "xn--bcher-kva.de");
+        assert_eq!(ascii_host("127.0.0.1"), "127.0.0.1");
+
+        let aliases = HostAliases::new(
+            &[vec!["bücher.de".to_string(), "www.bücher.de".to_string()]],
+            false,
+        );
+        assert_eq!(
+            aliases.canonical("www.xn--bcher-kva.de"),
+            "xn--bcher-kva.de"
+        );
+        let seeds = vec!["https://bücher.de/".to_string()];
+        let filter = ScopeFilter::new(CrawlScope::SameHost, &seeds, Arc::new(aliases));
+        assert!(filter.allows("https://xn--bcher-kva.de/katalog"));
+    }
+
+    #[test]
+    fn test_display_url() {
+        assert_eq!(
+            display_url("https://xn--bcher-kva.de/k?q=1#top").as_deref(),
+            Some("https://bücher.de/k?q=1#top")
+        );
+        // Non-Latin scripts mixed with Latin letters, as in many Japanese domains
+        let japanese = Url::parse("https://ソニーstore.jp/").unwrap();
+        assert_eq!(
+            display_url(japanese.as_str()).as_deref(),
+            Some("https://ソニーstore.jp/")
+        );
+        // Cyrillic "е" in an otherwise Latin label stays punycode
+        let spoof = Url::parse("https://\u{0435}xample.com/").unwrap();
+        assert_eq!(display_url(spoof.as_str()), None);
+        assert_eq!(display_url("https://example.com/"), None);
+        assert_eq!(display_url("http://127.0.0.1:8765/"), None);
+    }
+}